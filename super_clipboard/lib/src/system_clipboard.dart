@@ -3,6 +3,8 @@ import 'writer.dart';
 import 'writer_data_provider.dart';
 import 'events.dart';
 import 'package:super_native_extensions/raw_clipboard.dart' as raw;
+export 'package:super_native_extensions/raw_clipboard.dart'
+    show ClipboardSelection;
 
 class SystemClipboard implements ClipboardWriter {
   /// Returns the shared clipboard instance if available on the current platform.
@@ -19,10 +21,15 @@ class SystemClipboard implements ClipboardWriter {
   static final _instance = SystemClipboard._();
 
   /// Writes the content of the [items] to the clipboard.
+  ///
+  /// [raw.ClipboardSelection.primary] is only supported on Linux.
   @override
-  Future<void> write(Iterable<DataWriterItem> items) async {
+  Future<void> write(
+    Iterable<DataWriterItem> items, {
+    raw.ClipboardSelection selection = raw.ClipboardSelection.clipboard,
+  }) async {
     await items.withHandles((handles) async {
-      await raw.ClipboardWriter.instance.write(handles);
+      await raw.ClipboardWriter.instance.write(handles, selection: selection);
     });
   }
 
@@ -32,8 +39,13 @@ class SystemClipboard implements ClipboardWriter {
   /// For web the preferred way to get clipboard contents is through
   /// [ClipboardEvents.registerPasteEventListener], which is triggered when user pastes something
   /// into the page and does not require any user confirmation.
-  Future<ClipboardReader> read() async {
-    final reader = await raw.ClipboardReader.instance.newClipboardReader();
+  ///
+  /// [raw.ClipboardSelection.primary] is only supported on Linux.
+  Future<ClipboardReader> read({
+    raw.ClipboardSelection selection = raw.ClipboardSelection.clipboard,
+  }) async {
+    final reader = await raw.ClipboardReader.instance
+        .newClipboardReader(selection: selection);
     final readerItems = await reader.getItems();
     final itemInfo = await raw.DataReaderItem.getItemInfo(readerItems);
     final items = <ClipboardDataReader>[];
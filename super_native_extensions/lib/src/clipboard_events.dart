@@ -20,6 +20,18 @@ enum TextEvent {
   selectAll,
 }
 
+/// Sent when clipboard content changes, either by this or another application.
+class ClipboardChangedEvent {
+  ClipboardChangedEvent({required this.changeCount});
+
+  /// Increases with every change. Can be used to skip reading content that
+  /// has already been seen.
+  final int changeCount;
+
+  @override
+  String toString() => 'ClipboardChangedEvent(changeCount: $changeCount)';
+}
+
 abstract class ClipboardEvents {
   static final ClipboardEvents instance = ClipboardEventsImpl();
 
@@ -42,4 +54,12 @@ abstract class ClipboardEvents {
   void registerTextEventListener(bool Function(TextEvent) listener);
 
   void unregisterTextEventListener(bool Function(TextEvent) listener);
+
+  /// Registers listener for clipboard content changes. Currently only
+  /// supported on Linux.
+  void registerClipboardChangedListener(
+      void Function(ClipboardChangedEvent) listener);
+
+  void unregisterClipboardChangedListener(
+      void Function(ClipboardChangedEvent) listener);
 }
@@ -4,6 +4,16 @@ import 'native/clipboard_reader.dart'
     if (dart.library.js_interop) 'web/clipboard_reader.dart';
 import 'reader.dart';
 
+/// Clipboard selection to read from or write to.
+enum ClipboardSelection {
+  /// Regular clipboard.
+  clipboard,
+
+  /// X11/Wayland primary selection used for middle-click paste. Only
+  /// supported on Linux.
+  primary,
+}
+
 abstract class ClipboardReader {
   static final ClipboardReader instance = ClipboardReaderImpl();
 
@@ -15,5 +25,9 @@ abstract class ClipboardReader {
   /// of the reader. On top of it the content is cached lazily.
   ///
   /// If you need updated information create a new reader.
-  Future<DataReader> newClipboardReader();
+  ///
+  /// Reading [ClipboardSelection.primary] throws on platforms other than Linux.
+  Future<DataReader> newClipboardReader({
+    ClipboardSelection selection = ClipboardSelection.clipboard,
+  });
 }
@@ -1,3 +1,4 @@
+import 'clipboard_reader.dart';
 import 'data_provider.dart';
 
 import 'native/clipboard_writer.dart'
@@ -6,5 +7,10 @@ import 'native/clipboard_writer.dart'
 abstract class ClipboardWriter {
   static final ClipboardWriter instance = ClipboardWriterImpl();
 
-  Future<void> write(List<DataProviderHandle> providers);
+  /// Writes [providers] to given clipboard [selection]. Writing
+  /// [ClipboardSelection.primary] throws on platforms other than Linux.
+  Future<void> write(
+    List<DataProviderHandle> providers, {
+    ClipboardSelection selection = ClipboardSelection.clipboard,
+  });
 }
@@ -155,4 +155,12 @@ abstract class DragContext {
     required ui.Offset position,
     TargetedWidgetSnapshot? combinedDragImage,
   });
+
+  /// Starts recording events of drag and drop sessions in all isolates.
+  /// Recording in progress is restarted.
+  Future<void> startTraceRecording();
+
+  /// Stops recording and returns the recorded events. The trace is a plain
+  /// value (maps and lists) that can be stored and attached to bug reports.
+  Future<Object?> stopTraceRecording();
 }
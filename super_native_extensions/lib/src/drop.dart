@@ -150,6 +150,186 @@ class ItemPreviewRequest {
   final Duration fadeOutDuration;
 }
 
+/// Result of drop policy evaluation.
+enum DropPolicyAction {
+  /// Let [DropContextDelegate.onDropUpdate] decide the operation.
+  ask,
+  reject,
+  copy,
+  move,
+  link,
+}
+
+class DropFormatPolicy {
+  const DropFormatPolicy({
+    required this.format,
+    required this.action,
+  });
+
+  final String format;
+  final DropPolicyAction action;
+}
+
+/// Action used instead of the format action while exactly these modifiers
+/// are pressed.
+class DropModifierOverride {
+  const DropModifierOverride({
+    this.alt = false,
+    this.meta = false,
+    this.shift = false,
+    this.control = false,
+    required this.action,
+  });
+
+  final bool alt;
+  final bool meta;
+  final bool shift;
+  final bool control;
+  final DropPolicyAction action;
+}
+
+class DropPolicyRegion {
+  const DropPolicyRegion({
+    this.rect,
+    required this.formats,
+    this.modifierOverrides = const [],
+    this.fallback = DropPolicyAction.reject,
+  });
+
+  /// Area in view coordinates. `null` covers the whole view.
+  final ui.Rect? rect;
+
+  /// Accepted formats in order of preference. If the action of preferred
+  /// format is not allowed by the drag source, next format is tried.
+  final List<DropFormatPolicy> formats;
+
+  /// Only consulted when one of the formats is accepted.
+  final List<DropModifierOverride> modifierOverrides;
+
+  /// Action used when no item provides any of the accepted formats.
+  final DropPolicyAction fallback;
+}
+
+/// Declarative rules for deciding drop operation natively, without waiting
+/// for [DropContextDelegate.onDropUpdate]. Regions are checked in order and
+/// the first one containing the drop location applies. Outside of all
+/// regions, or when no accepted format resolves to an allowed operation,
+/// the delegate is asked. The delegate still receives drop updates decided
+/// by the policy with [DropEvent.acceptedOperation] set, but its result is
+/// ignored.
+class DropPolicy {
+  const DropPolicy({
+    required this.regions,
+  });
+
+  final List<DropPolicyRegion> regions;
+}
+
+/// Spring-loading configuration. When the pointer stays in place during
+/// drag, [DropContextDelegate.onDropDwell] is called.
+class DwellConfiguration {
+  const DwellConfiguration({
+    required this.delay,
+    this.repeatInterval,
+    this.movementThreshold = 5.0,
+  });
+
+  /// How long the pointer must stay in place before the first event.
+  final Duration delay;
+
+  /// Interval between repeated events while the pointer keeps hovering.
+  /// Must be at least 50 milliseconds. If `null` the event fires only once.
+  final Duration? repeatInterval;
+
+  /// Distance in logical pixels the pointer can move without restarting
+  /// the timer.
+  final double movementThreshold;
+}
+
+class DropDwellEvent extends BaseDropEvent {
+  DropDwellEvent({
+    required super.sessionId,
+    required this.locationInView,
+    required this.count,
+  });
+
+  @override
+  String toString() => {
+        'sessionId': sessionId,
+        'locationInView': locationInView,
+        'count': count,
+      }.toString();
+
+  /// Location where the pointer started dwelling.
+  final ui.Offset locationInView;
+
+  /// Starts at 1 and increases with every repeated event.
+  final int count;
+}
+
+/// Scrollable area of the drop target. Auto-scroll is active while the
+/// pointer is within [edgeWidth] of any of the edges.
+class AutoScrollRegion {
+  const AutoScrollRegion({
+    required this.id,
+    required this.rect,
+    this.edgeWidth = 40.0,
+    this.maxVelocity = 1000.0,
+  });
+
+  /// Passed back in [AutoScrollTickEvent.regionId].
+  final int id;
+
+  /// Area in view coordinates.
+  final ui.Rect rect;
+
+  final double edgeWidth;
+
+  /// Velocity in logical pixels per second when the pointer is at the edge.
+  /// Decreases linearly to zero at the inner side of the edge band.
+  final double maxVelocity;
+}
+
+/// While the pointer is inside edge band of a region,
+/// [DropContextDelegate.onAutoScrollTick] is called periodically, even if
+/// the pointer doesn't move.
+class AutoScrollConfiguration {
+  const AutoScrollConfiguration({
+    required this.regions,
+    this.tickInterval = const Duration(milliseconds: 16),
+  });
+
+  /// Regions are checked in order, first one with non-zero velocity wins.
+  final List<AutoScrollRegion> regions;
+
+  final Duration tickInterval;
+}
+
+class AutoScrollTickEvent extends BaseDropEvent {
+  AutoScrollTickEvent({
+    required super.sessionId,
+    required this.regionId,
+    required this.velocity,
+    required this.interval,
+  });
+
+  @override
+  String toString() => {
+        'sessionId': sessionId,
+        'regionId': regionId,
+        'velocity': velocity,
+        'interval': interval,
+      }.toString();
+
+  final int regionId;
+
+  /// Logical pixels per second, negative towards top and left edges.
+  final ui.Offset velocity;
+
+  /// Time since the previous tick.
+  final Duration interval;
+}
+
 abstract class DropContextDelegate {
   Future<DropOperation> onDropUpdate(DropEvent event);
   Future<void> onPerformDrop(DropEvent event);
@@ -158,6 +338,14 @@ abstract class DropContextDelegate {
 
   /// macOS and iOS only.
   Future<ItemPreview?> onGetItemPreview(ItemPreviewRequest request);
+
+  /// Called when pointer stays in place as configured by
+  /// [DropContext.setDwellConfiguration].
+  Future<void> onDropDwell(DropDwellEvent event) async {}
+
+  /// Called periodically as configured by
+  /// [DropContext.setAutoScrollConfiguration].
+  Future<void> onAutoScrollTick(AutoScrollTickEvent event) async {}
 }
 
 abstract class DropContext {
@@ -176,6 +364,17 @@ abstract class DropContext {
 
   Future<void> registerDropFormats(List<String> formats);
 
+  /// Sets policy for deciding drop operation natively. Passing `null`
+  /// removes current policy.
+  Future<void> setDropPolicy(DropPolicy? policy);
+
+  /// Enables spring-loading. Passing `null` disables it.
+  Future<void> setDwellConfiguration(DwellConfiguration? configuration);
+
+  /// Enables auto-scroll ticks. Passing `null` disables them.
+  Future<void> setAutoScrollConfiguration(
+      AutoScrollConfiguration? configuration);
+
   DropContextDelegate? delegate;
 
   static DropContext? _instance;
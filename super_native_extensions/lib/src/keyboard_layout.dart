@@ -22,6 +22,128 @@ abstract class KeyboardLayoutManager {
 
   /// Event fired when current system keyboard layout changes.
   Listenable get onLayoutChanged;
+
+  /// Returns keyboard layouts installed in the system. Supported on Linux,
+  /// macOS (keyboard input sources, including input methods) and Windows.
+  Future<List<KeyboardLayoutInfo>> getKeyboardLayouts();
+
+  /// Returns mapping for installed keyboard layout with given
+  /// [KeyboardLayoutInfo.id] without activating it, or `null` if there is
+  /// no such layout.
+  Future<KeyboardLayout?> getKeyboardLayoutForId(int id);
+
+  /// Makes keyboard layout with given [KeyboardLayoutInfo.id] the active
+  /// system layout. On Windows, where keyboard layout is per thread, the
+  /// layout is activated for the application process.
+  Future<void> selectKeyboardLayout(int id);
+
+  /// Returns mapping for keyboard layout built from XKB keymap, which does
+  /// not need to be installed in the system. Currently only supported on
+  /// Linux.
+  Future<KeyboardLayout> getKeyboardLayoutForKeymap(KeymapDescription keymap);
+}
+
+/// Describes a keyboard layout installed in the system.
+class KeyboardLayoutInfo {
+  KeyboardLayoutInfo({
+    required this.id,
+    required this.name,
+    required this.language,
+    required this.selected,
+  });
+
+  /// Platform specific layout identifier (XKB group index on Linux, HKL on
+  /// Windows, index of enabled keyboard input source on macOS).
+  final int id;
+
+  /// Human readable layout name.
+  final String name;
+
+  /// Language (or layout code where language is not known) of the layout.
+  final String? language;
+
+  /// Whether this layout is currently active.
+  final bool selected;
+
+  @override
+  String toString() =>
+      'KeyboardLayoutInfo(id: $id, name: $name, language: $language, '
+      'selected: $selected)';
+}
+
+/// XKB keymap used to build [KeyboardLayout] independently of the layouts
+/// installed in the system. The keymap must contain a single layout (group);
+/// multiple layouts such as `layout: 'us,de'` are rejected.
+abstract class KeymapDescription {
+  const KeymapDescription();
+
+  /// Keymap described by RMLVO names, i.e. `layout: 'de'`,
+  /// `variant: 'nodeadkeys'`.
+  const factory KeymapDescription.names({
+    String? rules,
+    String? model,
+    required String layout,
+    String? variant,
+    String? options,
+  }) = _KeymapNames;
+
+  /// Compiled keymap in XKB text format.
+  const factory KeymapDescription.text(String keymap) = _KeymapText;
+
+  /// Path to a file containing compiled keymap in XKB text format.
+  const factory KeymapDescription.file(String path) = _KeymapFile;
+
+  dynamic serialize();
+}
+
+class _KeymapNames extends KeymapDescription {
+  const _KeymapNames({
+    this.rules,
+    this.model,
+    required this.layout,
+    this.variant,
+    this.options,
+  });
+
+  final String? rules;
+  final String? model;
+  final String layout;
+  final String? variant;
+  final String? options;
+
+  @override
+  dynamic serialize() => {
+        'type': 'names',
+        'rules': rules,
+        'model': model,
+        'layout': layout,
+        'variant': variant,
+        'options': options,
+      };
+}
+
+class _KeymapText extends KeymapDescription {
+  const _KeymapText(this.keymap);
+
+  final String keymap;
+
+  @override
+  dynamic serialize() => {
+        'type': 'text',
+        'keymap': keymap,
+      };
+}
+
+class _KeymapFile extends KeymapDescription {
+  const _KeymapFile(this.path);
+
+  final String path;
+
+  @override
+  dynamic serialize() => {
+        'type': 'file',
+        'path': path,
+      };
 }
 
 /// Represents a keyboard layout. Allows converting between platform specific
@@ -84,9 +206,43 @@ class KeyboardLayout {
     }
   }
 
+  /// Returns whether the key for given [PhysicalKeyboardKey] is a dead key
+  /// when pressed with given modifiers. Dead keys produce no character on
+  /// their own but modify the character of the next key (see [compose]).
+  /// The logical key of a dead key is the character it represents (i.e. ´
+  /// for dead acute).
+  bool isDeadKey(
+    PhysicalKeyboardKey physicalKey, {
+    bool shift = false,
+    bool alt = false,
+  }) {
+    final key = _physicalToKey[physicalKey.usbHidUsage];
+    if (key == null) {
+      return false;
+    }
+    return switch ((shift, alt)) {
+      (false, false) => key.dead,
+      (true, false) => key.deadShift,
+      (false, true) => key.deadAlt,
+      (true, true) => key.deadAltShift,
+    };
+  }
+
+  /// Returns the character produced by typing [base] after [deadKey] or
+  /// `null` if the keys do not compose in this keyboard layout.
+  String? compose(LogicalKeyboardKey deadKey, LogicalKeyboardKey base) {
+    return _compose[(deadKey.keyId, base.keyId)];
+  }
+
   final Map<int, model.KeyboardKey> _platformToKey;
   final Map<int, model.KeyboardKey> _physicalToKey;
   final Map<int, model.KeyboardKey> _logicalToKey;
+  final Map<(int, int), String> _compose;
 
-  KeyboardLayout(this._platformToKey, this._physicalToKey, this._logicalToKey);
+  KeyboardLayout(
+    this._platformToKey,
+    this._physicalToKey,
+    this._logicalToKey, {
+    List<model.ComposeSequence> compose = const [],
+  }) : _compose = {for (final c in compose) (c.deadKey, c.base): c.result};
 }
@@ -7,6 +7,10 @@ class KeyboardKey {
     this.logicalAlt,
     this.logicalAltShift,
     this.logicalMeta,
+    this.dead = false,
+    this.deadShift = false,
+    this.deadAlt = false,
+    this.deadAltShift = false,
   });
 
   final int platform;
@@ -17,6 +21,13 @@ class KeyboardKey {
   final int? logicalAltShift;
   final int? logicalMeta;
 
+  /// Whether the key is a dead key. For dead keys the logical value is the
+  /// character that the dead key represents (i.e. ´ for dead acute).
+  final bool dead;
+  final bool deadShift;
+  final bool deadAlt;
+  final bool deadAltShift;
+
   static KeyboardKey deserialize(dynamic value) {
     final map = value as Map;
     return KeyboardKey(
@@ -26,23 +37,60 @@ class KeyboardKey {
         logicalShift: map['logicalShift'],
         logicalAlt: map['logicalAlt'],
         logicalAltShift: map['logicalAltShift'],
-        logicalMeta: map['logicalMeta']);
+        logicalMeta: map['logicalMeta'],
+        dead: map['dead'] ?? false,
+        deadShift: map['deadShift'] ?? false,
+        deadAlt: map['deadAlt'] ?? false,
+        deadAltShift: map['deadAltShift'] ?? false);
+  }
+}
+
+/// Character produced by typing [base] after dead key.
+class ComposeSequence {
+  ComposeSequence({
+    required this.deadKey,
+    required this.base,
+    required this.result,
+  });
+
+  /// Logical value of the dead key.
+  final int deadKey;
+
+  /// Logical value of the key typed after the dead key.
+  final int base;
+  final String result;
+
+  static ComposeSequence deserialize(dynamic value) {
+    final map = value as Map;
+    return ComposeSequence(
+      deadKey: map['deadKey'],
+      base: map['base'],
+      result: map['result'],
+    );
   }
 }
 
 class KeyboardLayout {
   KeyboardLayout({
     required this.keys,
+    this.compose = const [],
   });
 
   final List<KeyboardKey> keys;
 
+  /// Compose sequences for dead keys in this layout.
+  final List<ComposeSequence> compose;
+
   static KeyboardLayout? deserialize(dynamic value) {
     if (value == null) {
       return null;
     }
     final map = value as Map;
     final keys = map['keys'] as List;
-    return KeyboardLayout(keys: keys.map(KeyboardKey.deserialize).toList());
+    final compose = map['compose'] as List? ?? [];
+    return KeyboardLayout(
+      keys: keys.map(KeyboardKey.deserialize).toList(),
+      compose: compose.map(ComposeSequence.deserialize).toList(),
+    );
   }
 }
@@ -4,6 +4,7 @@ import 'dart:ui' as ui;
 import 'package:flutter/foundation.dart';
 import 'package:flutter/widgets.dart';
 
+import 'image_data.dart';
 import 'menu_model.dart';
 import 'mutex.dart';
 
@@ -154,6 +155,50 @@ abstract class MenuContext {
     MenuSerializationOptions options,
   );
 
+  /// When enabled, desktop context menus request menu configuration from
+  /// [delegate] and show its preview image next to the menu. Supported on
+  /// Linux.
+  Future<void> setContextMenuPreviewEnabled(bool enabled);
+
+  /// Returns problems that prevent platform menus from representing the
+  /// menu properly. Empty list means the menu is valid.
+  Future<List<MenuProblem>> validateMenu(
+    Menu menu,
+    MenuSerializationOptions options,
+  );
+
+  /// When enabled, [registerMenu] fails for menus with problems instead of
+  /// only logging them.
+  Future<void> setStrictMenuValidation(bool strict);
+
+  /// Updates actions of registered menu in place. Actions are matched by
+  /// unique id; actions that are not part of the menu are ignored.
+  Future<void> updateMenuElements(MenuHandle menu, List<MenuAction> actions);
+
+  /// Installs registered menu as application menu bar. Passing `null` removes
+  /// current menu bar. Supported on Linux, where the menu bar is placed
+  /// above the Flutter view. If the view is a direct child of the window (as
+  /// in the default runner), it is moved into a vertical box first.
+  Future<void> setMenuBar(MenuHandle? menu);
+
+  /// Shows registered menu from system tray icon. Passing `null` removes
+  /// the tray icon. Supported on Linux through `GtkStatusIcon` (XEmbed system
+  /// tray). GNOME and most Wayland compositors don't show such icons
+  /// without an extension providing the legacy tray.
+  Future<void> setTrayMenu(
+    MenuHandle? menu, {
+    ImageData? icon,
+    String? tooltip,
+  });
+
+  /// Returns display string for the activator using the platform
+  /// conventions (i.e. "⌘⇧P" on macOS, "Ctrl+Shift+P" elsewhere), for menus
+  /// that are not rendered natively. Returns `null` if the activator trigger
+  /// can not be represented or the platform provides no formatting (web).
+  static Future<String?> formatActivator(SingleActivator activator) {
+    return formatActivatorImpl(activator);
+  }
+
   static Future<MenuContext> instance() {
     return _mutex.protect(() async {
       if (_instance == null) {
@@ -1,5 +1,6 @@
 import 'dart:async';
 
+import 'image_data.dart';
 import 'menu.dart';
 import 'menu_model.dart';
 
@@ -28,4 +29,41 @@ class FlutterMenuContext extends MenuContext {
   Future<MenuResult> showContextMenu(DesktopContextMenuRequest request) async {
     return request.fallback();
   }
+
+  @override
+  Future<void> setContextMenuPreviewEnabled(bool enabled) async {}
+
+  @override
+  Future<List<MenuProblem>> validateMenu(
+    Menu menu,
+    MenuSerializationOptions options,
+  ) async {
+    // Flutter menus can represent any menu.
+    return [];
+  }
+
+  @override
+  Future<void> setStrictMenuValidation(bool strict) async {}
+
+  @override
+  Future<void> updateMenuElements(
+    MenuHandle menu,
+    List<MenuAction> actions,
+  ) async {
+    // Flutter menus are built from the menu model when shown.
+  }
+
+  @override
+  Future<void> setMenuBar(MenuHandle? menu) async {
+    throw UnsupportedError('Menu bar is not supported on this platform');
+  }
+
+  @override
+  Future<void> setTrayMenu(
+    MenuHandle? menu, {
+    ImageData? icon,
+    String? tooltip,
+  }) async {
+    throw UnsupportedError('Tray menu is not supported on this platform');
+  }
 }
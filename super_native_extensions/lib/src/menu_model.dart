@@ -116,6 +116,25 @@ enum MenuActionState {
   radioOff,
 }
 
+/// Activator made of multiple keystrokes, such as Ctrl+K Ctrl+C. The first
+/// keystroke is described by this activator, [chord] lists the keystrokes
+/// that follow it.
+///
+/// Only used for displaying and validating menu activators. Flutter
+/// shortcuts matching this activator only consider the first keystroke.
+class ChordActivator extends SingleActivator {
+  const ChordActivator(
+    super.trigger, {
+    super.control,
+    super.shift,
+    super.alt,
+    super.meta,
+    required this.chord,
+  });
+
+  final List<SingleActivator> chord;
+}
+
 class MenuAction extends MenuElement {
   MenuAction({
     super.title,
@@ -148,4 +167,31 @@ class MenuResult {
   final bool itemSelected;
 }
 
+enum MenuProblemKind {
+  /// Radio action that has no adjacent radio actions to form a group with.
+  radioActionWithoutGroup,
+  duplicateUniqueId,
+
+  /// Activator trigger that platform menus can not map to a key.
+  unsupportedActivatorTrigger,
+  emptySubmenu,
+}
+
+class MenuProblem {
+  MenuProblem({
+    required this.kind,
+    required this.path,
+    required this.uniqueId,
+  });
+
+  final MenuProblemKind kind;
+
+  /// Child indices leading from the root menu to the offending element.
+  final List<int> path;
+  final int uniqueId;
+
+  @override
+  String toString() => 'MenuProblem($kind, path: $path, uniqueId: $uniqueId)';
+}
+
 int _nextId = 1;
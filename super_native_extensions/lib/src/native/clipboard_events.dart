@@ -91,6 +91,14 @@ class ClipboardEventsImpl extends ClipboardEvents {
         handled |= listener(TextEvent.selectAll);
       }
       return handled;
+    } else if (call.method == 'clipboardChanged') {
+      final event = ClipboardChangedEvent(
+        changeCount: call.arguments['changeCount'] as int,
+      );
+      for (final listener in List.of(_clipboardChangedListeners)) {
+        listener(event);
+      }
+      return null;
     }
   }
 
@@ -101,6 +109,7 @@ class ClipboardEventsImpl extends ClipboardEvents {
   final _copyEventListeners = <void Function(ClipboardWriteEvent reader)>[];
   final _cutEventListeners = <void Function(ClipboardWriteEvent reader)>[];
   final _textEventListeners = <bool Function(TextEvent)>[];
+  final _clipboardChangedListeners = <void Function(ClipboardChangedEvent)>[];
 
   @override
   void registerPasteEventListener(
@@ -148,6 +157,18 @@ class ClipboardEventsImpl extends ClipboardEvents {
     _textEventListeners.remove(listener);
   }
 
+  @override
+  void registerClipboardChangedListener(
+      void Function(ClipboardChangedEvent) listener) {
+    _clipboardChangedListeners.add(listener);
+  }
+
+  @override
+  void unregisterClipboardChangedListener(
+      void Function(ClipboardChangedEvent) listener) {
+    _clipboardChangedListeners.remove(listener);
+  }
+
   final _channel = NativeMethodChannel('ClipboardEventManager',
       context: superNativeExtensionsContext);
 }
@@ -9,8 +9,11 @@ import '../reader_manager.dart';
 
 class ClipboardReaderImpl extends ClipboardReader {
   @override
-  Future<DataReader> newClipboardReader() async {
-    final handle = await _channel.invokeMethod('newClipboardReader');
+  Future<DataReader> newClipboardReader({
+    ClipboardSelection selection = ClipboardSelection.clipboard,
+  }) async {
+    final handle =
+        await _channel.invokeMethod('newClipboardReader', selection.name);
     return DataReader(handle: DataReaderHandle.deserialize(handle));
   }
 
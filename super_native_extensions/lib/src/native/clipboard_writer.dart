@@ -2,6 +2,7 @@ import 'package:flutter/services.dart';
 import 'package:irondash_message_channel/irondash_message_channel.dart';
 
 import 'context.dart';
+import '../clipboard_reader.dart';
 import '../data_provider.dart';
 import '../clipboard_writer.dart';
 
@@ -11,8 +12,14 @@ class ClipboardWriterImpl extends ClipboardWriter {
   }
 
   @override
-  Future<void> write(List<DataProviderHandle> providers) async {
-    await _channel.invokeMethod('writeToClipboard', providers.map((e) => e.id));
+  Future<void> write(
+    List<DataProviderHandle> providers, {
+    ClipboardSelection selection = ClipboardSelection.clipboard,
+  }) async {
+    await _channel.invokeMethod('writeToClipboard', {
+      'providerIds': providers.map((e) => e.id).toList(growable: false),
+      'selection': selection.name,
+    });
     for (final provider in providers) {
       _activeProviders[provider.id] = provider;
     }
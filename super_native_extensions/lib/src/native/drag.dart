@@ -246,4 +246,14 @@ class DragContextImpl extends DragContext {
       _dataProviders[item.dataProvider.id] = item.dataProvider;
     }
   }
+
+  @override
+  Future<void> startTraceRecording() async {
+    await _channel.invokeMethod('startTraceRecording');
+  }
+
+  @override
+  Future<Object?> stopTraceRecording() async {
+    return await _channel.invokeMethod('stopTraceRecording');
+  }
 }
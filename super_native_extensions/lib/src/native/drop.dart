@@ -21,6 +21,77 @@ class Session {
   final mutex = Mutex();
 }
 
+extension on DropPolicy {
+  dynamic serialize() => {
+        'regions': regions
+            .map((region) => {
+                  'rect': region.rect?.serialize(),
+                  'formats': region.formats
+                      .map((f) => {
+                            'format': f.format,
+                            'action': f.action.name,
+                          })
+                      .toList(growable: false),
+                  'modifierOverrides': region.modifierOverrides
+                      .map((o) => {
+                            'alt': o.alt,
+                            'meta': o.meta,
+                            'shift': o.shift,
+                            'control': o.control,
+                            'action': o.action.name,
+                          })
+                      .toList(growable: false),
+                  'fallback': region.fallback.name,
+                })
+            .toList(growable: false),
+      };
+}
+
+extension on DwellConfiguration {
+  dynamic serialize() => {
+        'delay': delay.inSecondsDouble,
+        'repeatInterval': repeatInterval?.inSecondsDouble,
+        'movementThreshold': movementThreshold,
+      };
+}
+
+extension on AutoScrollConfiguration {
+  dynamic serialize() => {
+        'regions': regions
+            .map((region) => {
+                  'id': region.id,
+                  'rect': region.rect.serialize(),
+                  'edgeWidth': region.edgeWidth,
+                  'maxVelocity': region.maxVelocity,
+                })
+            .toList(growable: false),
+        'tickInterval': tickInterval.inSecondsDouble,
+      };
+}
+
+extension AutoScrollTickEventExt on AutoScrollTickEvent {
+  static AutoScrollTickEvent deserialize(dynamic event) {
+    final map = event as Map;
+    return AutoScrollTickEvent(
+      sessionId: map['sessionId'],
+      regionId: map['regionId'],
+      velocity: OffsetExt.deserialize(map['velocity']),
+      interval: DurationExt.fromSeconds(map['interval']),
+    );
+  }
+}
+
+extension DropDwellEventExt on DropDwellEvent {
+  static DropDwellEvent deserialize(dynamic event) {
+    final map = event as Map;
+    return DropDwellEvent(
+      sessionId: map['sessionId'],
+      locationInView: OffsetExt.deserialize(map['locationInView']),
+      count: map['count'],
+    );
+  }
+}
+
 extension ItemPreviewExt on ItemPreview {
   Future<dynamic> serialize() async => {
         'destinationRect': destinationRect.serialize(),
@@ -190,6 +261,16 @@ class DropContextImpl extends DropContext {
           return {'preview': null};
         }
       }, () => {'preview': null});
+    } else if (call.method == 'onDropDwell') {
+      return handleError(() async {
+        final event = DropDwellEventExt.deserialize(call.arguments);
+        return await delegate?.onDropDwell(event);
+      }, () => null);
+    } else if (call.method == 'onAutoScrollTick') {
+      return handleError(() async {
+        final event = AutoScrollTickEventExt.deserialize(call.arguments);
+        return await delegate?.onAutoScrollTick(event);
+      }, () => null);
     } else {
       return null;
     }
@@ -199,4 +280,26 @@ class DropContextImpl extends DropContext {
   Future<void> registerDropFormats(List<String> formats) {
     return _channel.invokeMethod("registerDropFormats", {'formats': formats});
   }
+
+  @override
+  Future<void> setDropPolicy(DropPolicy? policy) async {
+    await _channel.invokeMethod('setDropPolicy', {
+      'policy': policy?.serialize(),
+    });
+  }
+
+  @override
+  Future<void> setDwellConfiguration(DwellConfiguration? configuration) async {
+    await _channel.invokeMethod('setDwellConfiguration', {
+      'configuration': configuration?.serialize(),
+    });
+  }
+
+  @override
+  Future<void> setAutoScrollConfiguration(
+      AutoScrollConfiguration? configuration) async {
+    await _channel.invokeMethod('setAutoScrollConfiguration', {
+      'configuration': configuration?.serialize(),
+    });
+  }
 }
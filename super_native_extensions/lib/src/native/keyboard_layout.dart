@@ -41,6 +41,11 @@ class KeyboardLayoutManagerImpl extends KeyboardLayoutManager {
   }
 
   void _update(model.KeyboardLayout? layout) {
+    _currentLayout = _layoutFromModel(layout);
+    _supported = layout != null;
+  }
+
+  static KeyboardLayout _layoutFromModel(model.KeyboardLayout? layout) {
     final platformToKey = <int, model.KeyboardKey>{};
     final physicalToKey = <int, model.KeyboardKey>{};
     final logicalToKey = <int, model.KeyboardKey>{};
@@ -65,8 +70,12 @@ class KeyboardLayoutManagerImpl extends KeyboardLayoutManager {
       }
     }
 
-    _currentLayout = KeyboardLayout(platformToKey, physicalToKey, logicalToKey);
-    _supported = layout != null;
+    return KeyboardLayout(
+      platformToKey,
+      physicalToKey,
+      logicalToKey,
+      compose: layout?.compose ?? const [],
+    );
   }
 
   @override
@@ -82,6 +91,40 @@ class KeyboardLayoutManagerImpl extends KeyboardLayoutManager {
   @override
   bool get supported => _supported;
 
+  @override
+  Future<List<KeyboardLayoutInfo>> getKeyboardLayouts() async {
+    final layouts = await _channel.invokeMethod('getKeyboardLayouts') as List;
+    return layouts
+        .cast<Map>()
+        .map((l) => KeyboardLayoutInfo(
+              id: l['id'],
+              name: l['name'],
+              language: l['language'],
+              selected: l['selected'],
+            ))
+        .toList(growable: false);
+  }
+
+  @override
+  Future<KeyboardLayout?> getKeyboardLayoutForId(int id) async {
+    final layout = model.KeyboardLayout.deserialize(
+        await _channel.invokeMethod('getKeyboardLayoutForId', id));
+    return layout != null ? _layoutFromModel(layout) : null;
+  }
+
+  @override
+  Future<void> selectKeyboardLayout(int id) async {
+    await _channel.invokeMethod('selectKeyboardLayout', id);
+  }
+
+  @override
+  Future<KeyboardLayout> getKeyboardLayoutForKeymap(
+      KeymapDescription keymap) async {
+    final layout = model.KeyboardLayout.deserialize(await _channel
+        .invokeMethod('getKeyboardLayoutForKeymap', keymap.serialize()));
+    return _layoutFromModel(layout);
+  }
+
   static final _mutex = Mutex();
 
   final _channel = NativeMethodChannel('KeyboardLayoutManager',
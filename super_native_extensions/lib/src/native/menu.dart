@@ -74,13 +74,18 @@ extension on MenuSeparator {
       };
 }
 
-extension on SingleActivator {
+extension ActivatorExt on SingleActivator {
   dynamic serialize() => {
         'trigger': trigger.keyLabel,
         'alt': alt,
         'meta': meta,
         'shift': shift,
         'control': control,
+        'chord': switch (this) {
+          ChordActivator(chord: final chord) =>
+            chord.map((e) => e.serialize()).toList(),
+          _ => null,
+        },
       };
 }
 
@@ -128,6 +133,11 @@ extension on MenuElement {
 final _channel =
     NativeMethodChannel('MenuManager', context: superNativeExtensionsContext);
 
+Future<String?> formatActivatorImpl(SingleActivator activator) async {
+  return await _channel.invokeMethod('formatActivator', activator.serialize())
+      as String?;
+}
+
 class NativeMenuHandle extends MenuHandle {
   NativeMenuHandle({
     required this.handle,
@@ -217,20 +227,24 @@ class MenuContextImpl extends MenuContext {
     );
   }
 
+  MenuSerializationOptions _platformOptions(MenuSerializationOptions options) {
+    final platformIconSize = _platformIconSize();
+    return MenuSerializationOptions(
+      iconTheme: options.iconTheme.copyWith(size: platformIconSize),
+      destructiveIconTheme: options.destructiveIconTheme.copyWith(
+        size: platformIconSize,
+      ),
+      devicePixelRatio: options.devicePixelRatio,
+    );
+  }
+
   @override
   Future<MenuHandle> registerMenu(
     Menu menu,
     // ignore: avoid_renaming_method_parameters, no_leading_underscores_for_local_identifiers
     MenuSerializationOptions _options,
   ) async {
-    final platformIconSize = _platformIconSize();
-    final options = MenuSerializationOptions(
-      iconTheme: _options.iconTheme.copyWith(size: platformIconSize),
-      destructiveIconTheme: _options.destructiveIconTheme.copyWith(
-        size: platformIconSize,
-      ),
-      devicePixelRatio: _options.devicePixelRatio,
-    );
+    final options = _platformOptions(_options);
     // The cast is necessary for correct extension method to be called.
     // ignore: unnecessary_cast
     final serialized = await (menu as MenuElement).serialize(options);
@@ -250,6 +264,71 @@ class MenuContextImpl extends MenuContext {
     return res;
   }
 
+  @override
+  Future<void> setContextMenuPreviewEnabled(bool enabled) async {
+    await _channel.invokeMethod('setContextMenuPreviewEnabled', enabled);
+  }
+
+  @override
+  Future<List<MenuProblem>> validateMenu(
+    Menu menu,
+    MenuSerializationOptions options,
+  ) async {
+    // ignore: unnecessary_cast
+    final serialized =
+        await (menu as MenuElement).serialize(_platformOptions(options));
+    final res = await _channel.invokeMethod('validateMenu', serialized) as Map;
+    return (res['problems'] as List)
+        .cast<Map>()
+        .map((p) => MenuProblem(
+              kind: MenuProblemKind.values.byName(p['kind']),
+              path: (p['path'] as List).cast<int>(),
+              uniqueId: p['uniqueId'],
+            ))
+        .toList(growable: false);
+  }
+
+  @override
+  Future<void> setStrictMenuValidation(bool strict) async {
+    await _channel.invokeMethod('setStrictMenuValidation', strict);
+  }
+
+  @override
+  Future<void> updateMenuElements(
+    MenuHandle menu,
+    List<MenuAction> actions,
+  ) async {
+    final handle = menu as NativeMenuHandle;
+    final elements = await Future.wait(actions.map((action) async {
+      final serialized = await action.serialize(handle.serializationOptions);
+      return serialized['content'];
+    }));
+    await _channel.invokeMethod('updateMenuElements', {
+      'menuHandle': handle.handle,
+      'elements': elements,
+    });
+  }
+
+  @override
+  Future<void> setMenuBar(MenuHandle? menu) async {
+    await _channel.invokeMethod('setMenuBar', {
+      'menuHandle': (menu as NativeMenuHandle?)?.handle,
+    });
+  }
+
+  @override
+  Future<void> setTrayMenu(
+    MenuHandle? menu, {
+    ImageData? icon,
+    String? tooltip,
+  }) async {
+    await _channel.invokeMethod('setTrayMenu', {
+      'menuHandle': (menu as NativeMenuHandle?)?.handle,
+      'icon': icon?.serialize(),
+      'tooltip': tooltip,
+    });
+  }
+
   void _updatePreviewImage(
       int configurationId, WidgetSnapshot previewImage) async {
     final imageData = await ImageData.fromImage(previewImage.image);
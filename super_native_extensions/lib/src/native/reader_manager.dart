@@ -119,6 +119,57 @@ class ReaderManagerImpl extends ReaderManager {
     return (completer.future, progress);
   }
 
+  @override
+  (Future<ItemDataStream?>, ReadProgress) getItemDataStream(
+    DataReaderItemHandle handle, {
+    required String format,
+  }) {
+    if (handle._reader._disposed) {
+      throw StateError("Attempting to get data from disposed reader.");
+    }
+    final progress = ReadProgressImpl(readerManager: this);
+    final completer = Completer<ItemDataStream?>();
+    _progressMap[progress.id] = progress;
+    _channel.invokeMethod("getItemDataStream", {
+      "itemHandle": handle._itemHandle,
+      "readerHandle": handle._readerHandle,
+      "format": format,
+      "progressId": progress.id,
+    }).then((value) {
+      final response = value as Map?;
+      if (response == null) {
+        _completeProgress(progress.id);
+        completer.complete(null);
+      } else {
+        // Progress is reported while the stream is being read.
+        completer.complete(_ItemDataStream(
+          readerManager: this,
+          handle: response['streamHandle'],
+          length: response['length'],
+          progressId: progress.id,
+        ));
+      }
+    }, onError: (error) {
+      _completeProgress(progress.id);
+      completer.completeError(error);
+    });
+    return (completer.future, progress);
+  }
+
+  Future<Uint8List?> itemDataStreamRead({
+    required int handle,
+  }) {
+    return _channel.invokeMethod('itemDataStreamRead', handle);
+  }
+
+  Future<void> itemDataStreamClose({
+    required int handle,
+    required int progressId,
+  }) async {
+    _completeProgress(progressId);
+    await _channel.invokeMethod('itemDataStreamClose', handle);
+  }
+
   (Future<VirtualFile>, ReadProgress) virtualFileCreate(
     DataReaderItemHandle handle, {
     required String format,
@@ -336,6 +387,33 @@ class _VirtualFile extends VirtualFile {
   final int? length;
 }
 
+class _ItemDataStream extends ItemDataStream {
+  _ItemDataStream({
+    required this.readerManager,
+    required this.handle,
+    required this.progressId,
+    this.length,
+  });
+
+  @override
+  void close() {
+    readerManager.itemDataStreamClose(handle: handle, progressId: progressId);
+  }
+
+  @override
+  Future<Uint8List> readNext() async {
+    return (await readerManager.itemDataStreamRead(handle: handle)) ??
+        Uint8List(0);
+  }
+
+  final ReaderManagerImpl readerManager;
+  final int handle;
+  final int progressId;
+
+  @override
+  final int? length;
+}
+
 class _VirtualFileReceiver extends VirtualFileReceiver {
   _VirtualFileReceiver({
     required this.readerManager,
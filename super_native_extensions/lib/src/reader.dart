@@ -77,6 +77,13 @@ class DataReaderItem {
     return ReaderManager.instance.getItemData(_handle, format: format);
   }
 
+  /// Reads data for given format in chunks. See [ItemDataStream].
+  (Future<ItemDataStream?>, ReadProgress) getDataStreamForFormat(
+    String format,
+  ) {
+    return ReaderManager.instance.getItemDataStream(_handle, format: format);
+  }
+
   static Future<List<DataReaderItemInfo>> getItemInfo(
     Iterable<DataReaderItem> items, {
     Duration? timeout,
@@ -135,6 +142,26 @@ class DataReaderItem {
   List<String>? _availableFormats;
 }
 
+/// Item data that is read in chunks, for data that may be too large to be
+/// read at once.
+///
+/// Supported on Windows for drop data objects that support asynchronous
+/// transfer, and on Linux for clipboard readers running on X11, where
+/// incremental (INCR) selection transfers are received chunk by chunk. On
+/// other platforms, and for readers that can only provide the data at once,
+/// [DataReaderItem.getDataStreamForFormat] fails with an unsupported
+/// operation error.
+abstract class ItemDataStream {
+  /// Returns total length if available.
+  int? get length;
+
+  /// Reads next chunk of the data. Returns empty list when all data has been read.
+  Future<Uint8List> readNext();
+
+  /// Closes the stream.
+  void close();
+}
+
 abstract class VirtualFile {
   /// Returns the file name or `null` if not available.
   String? get fileName;
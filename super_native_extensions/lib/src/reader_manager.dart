@@ -23,6 +23,14 @@ abstract class ReaderManager {
     required String format,
   });
 
+  /// Opens a stream for reading item data in chunks. Resolves to `null` if
+  /// the item doesn't have the format. Fails with `unsupportedOperation`
+  /// error if the platform can not provide the data incrementally.
+  (Future<ItemDataStream?>, ReadProgress) getItemDataStream(
+    DataReaderItemHandle handle, {
+    required String format,
+  });
+
   /// Loads as many item infos as possible within the given timeout.
   Future<List<DataReaderItemInfo>> getItemInfo(
     Iterable<DataReaderItemHandle> handles, {
@@ -184,4 +184,12 @@ class ClipboardEventsImpl extends ClipboardEvents {
 
   @override
   void unregisterTextEventListener(bool Function(TextEvent) listener) {}
+
+  @override
+  void registerClipboardChangedListener(
+      void Function(ClipboardChangedEvent) listener) {}
+
+  @override
+  void unregisterClipboardChangedListener(
+      void Function(ClipboardChangedEvent) listener) {}
 }
@@ -12,7 +12,12 @@ import 'reader_manager.dart';
 
 class ClipboardReaderImpl extends ClipboardReader {
   @override
-  Future<DataReader> newClipboardReader() async {
+  Future<DataReader> newClipboardReader({
+    ClipboardSelection selection = ClipboardSelection.clipboard,
+  }) async {
+    if (selection != ClipboardSelection.clipboard) {
+      throw UnsupportedError('Primary selection is not supported on web.');
+    }
     final items = await window.navigator.clipboard.read().toDart;
     final handle = $DataReaderHandle(
       items.toDart
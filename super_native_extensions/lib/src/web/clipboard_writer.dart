@@ -4,6 +4,7 @@ import 'dart:js_interop_unsafe';
 import 'package:flutter/foundation.dart';
 import 'package:web/web.dart' as web;
 
+import '../clipboard_reader.dart';
 import '../clipboard_writer.dart';
 import '../data_provider.dart';
 
@@ -53,7 +54,13 @@ class ClipboardWriterImpl extends ClipboardWriter {
   }
 
   @override
-  Future<void> write(List<DataProviderHandle> providers) async {
+  Future<void> write(
+    List<DataProviderHandle> providers, {
+    ClipboardSelection selection = ClipboardSelection.clipboard,
+  }) async {
+    if (selection != ClipboardSelection.clipboard) {
+      throw UnsupportedError('Primary selection is not supported on web.');
+    }
     for (final handle in _currentPayload) {
       await handle.dispose();
     }
@@ -306,4 +306,14 @@ class DragContextImpl extends DragContext {
       combinedDragImage,
     );
   }
+
+  @override
+  Future<void> startTraceRecording() async {
+    throw UnsupportedError('Drag trace recording is not supported on web');
+  }
+
+  @override
+  Future<Object?> stopTraceRecording() async {
+    throw UnsupportedError('Drag trace recording is not supported on web');
+  }
 }
@@ -260,6 +260,22 @@ class DropContextImpl extends DropContext {
   @override
   Future<void> registerDropFormats(List<String> formats) async {}
 
+  @override
+  Future<void> setDropPolicy(DropPolicy? policy) async {
+    // Drops on web are local, delegate is always asked.
+  }
+
+  @override
+  Future<void> setDwellConfiguration(DwellConfiguration? configuration) async {
+    // Not supported on web.
+  }
+
+  @override
+  Future<void> setAutoScrollConfiguration(
+      AutoScrollConfiguration? configuration) async {
+    // Not supported on web.
+  }
+
   DropEvent _createLocalDropEvent({
     required DragConfiguration configuration,
     required Offset position,
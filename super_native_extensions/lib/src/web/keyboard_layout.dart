@@ -16,4 +16,21 @@ class KeyboardLayoutManagerImpl extends KeyboardLayoutManager {
 
   @override
   bool get supported => false;
+
+  @override
+  Future<List<KeyboardLayoutInfo>> getKeyboardLayouts() async => [];
+
+  @override
+  Future<KeyboardLayout?> getKeyboardLayoutForId(int id) async => null;
+
+  @override
+  Future<void> selectKeyboardLayout(int id) async {
+    throw UnsupportedError('Keyboard layout selection is not supported on web');
+  }
+
+  @override
+  Future<KeyboardLayout> getKeyboardLayoutForKeymap(
+      KeymapDescription keymap) async {
+    throw UnsupportedError('XKB keymaps are not supported on web');
+  }
 }
@@ -1,6 +1,7 @@
 import 'dart:js_interop';
 import 'dart:ui' as ui;
 
+import 'package:flutter/widgets.dart';
 import 'package:web/web.dart' as web;
 import '../drag_interaction/long_press_session.dart';
 import '../menu_flutter.dart';
@@ -29,3 +30,5 @@ class MenuContextImpl extends FlutterMenuContext {
     );
   }
 }
+
+Future<String?> formatActivatorImpl(SingleActivator activator) async => null;
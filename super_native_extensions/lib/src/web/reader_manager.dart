@@ -115,6 +115,14 @@ class ReaderManagerImpl extends ReaderManager {
     return res;
   }
 
+  @override
+  (Future<ItemDataStream?>, ReadProgress) getItemDataStream(
+    DataReaderItemHandle handle, {
+    required String format,
+  }) {
+    throw UnsupportedError('getItemDataStream is not supported on web');
+  }
+
   @override
   VirtualFile createVirtualFileFromUri(Uri uri) {
     throw UnsupportedError('createVirtualFileFromUri is not supported on web');
@@ -5,9 +5,10 @@ fn main() {
     let target_system = std::env::var("CARGO_CFG_TARGET_OS").unwrap();
     let target_arch = std::env::var("CARGO_CFG_TARGET_ARCH").unwrap();
 
-    // keyboard map is currently only supported on desktop.
+    gen_keyboard_map::generate_logical_keys().unwrap();
+
     match target_system.as_str() {
-        "macos" | "linux" | "windows" => {
+        "macos" | "linux" | "windows" | "android" | "ios" => {
             gen_keyboard_map::generate_keyboard_map(&target_system).unwrap();
         }
         _ => {}
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use super_native_extensions::fuzzing::decode_dib;
+
+// Headerless bitmaps from the Windows clipboard can not be detected by
+// signature.
+fuzz_target!(|data: &[u8]| {
+    let _ = decode_dib(data);
+});
@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use super_native_extensions::fuzzing::decode_image;
+
+// Covers PNG, JPEG, BMP and TIFF; format is detected from signature.
+fuzz_target!(|data: &[u8]| {
+    let _ = decode_image(data);
+});
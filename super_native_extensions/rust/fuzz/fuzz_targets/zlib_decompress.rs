@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use super_native_extensions::fuzzing::zlib_decompress;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = zlib_decompress(data, 1 << 24);
+});
@@ -99,25 +99,87 @@ pub fn generate_keyboard_map(platform_name: &str) -> anyhow::Result<()> {
         }
     }
 
+    // Reverse lookup from US layout logical value to key name. When multiple
+    // keys produce the same value the one with lowest platform code wins.
+    let mut key_names = BTreeMap::<i64, &str>::new();
+    for v in key_data.values() {
+        if let Some(fallback) = v.fallback {
+            key_names.entry(fallback).or_insert(&v.name);
+        }
+    }
+
     let gen_path = out_dir.join("generated_keyboard_map.rs");
     let mut file = File::create(gen_path)?;
     writeln!(file, "#[allow(dead_code)]")?;
     writeln!(
         file,
-        "struct KeyMapEntry {{ platform: i64, physical: i64, logical: Option<i64>, fallback: Option<i64> }}"
+        "pub struct KeyMapEntry {{ pub name: &'static str, pub platform: i64, pub physical: i64, pub logical: Option<i64>, pub fallback: Option<i64> }}"
     )?;
     writeln!(file)?;
-    writeln!(file, "fn get_key_map() -> Vec<KeyMapEntry> {{")?;
+    writeln!(file, "pub fn get_key_map() -> Vec<KeyMapEntry> {{")?;
     writeln!(file, "    vec![")?;
     for v in key_data.values() {
         writeln!(
             file,
-            "        KeyMapEntry {{ platform: {}, physical: {}, logical: {:?}, fallback: {:?} }}, // {}",
-            v.platform, v.physical, v.logical, v.fallback, v.name,
+            "        KeyMapEntry {{ name: {:?}, platform: {}, physical: {}, logical: {:?}, fallback: {:?} }},",
+            v.name, v.platform, v.physical, v.logical, v.fallback,
         )?;
     }
     writeln!(file, "    ]")?;
     writeln!(file, "}}")?;
+    writeln!(file)?;
+    writeln!(file, "/// Key names sorted by US layout logical value.")?;
+    writeln!(file, "pub const KEY_NAMES_BY_LOGICAL: &[(i64, &str)] = &[")?;
+    for (value, name) in key_names {
+        writeln!(file, "    ({value}, {name:?}),")?;
+    }
+    writeln!(file, "];")?;
+
+    Ok(())
+}
+
+/// Generates table of all logical keys, used to parse and display menu
+/// accelerators on every platform.
+pub fn generate_logical_keys() -> anyhow::Result<()> {
+    let root_dir: PathBuf = std::env::var("CARGO_MANIFEST_DIR")?.into();
+    let out_dir: PathBuf = std::env::var("OUT_DIR")?.into();
+
+    let logical = fs::read_to_string(root_dir.join("keyboard_map/logical_key_data.g.json"))?;
+    let logical_keys: HashMap<String, LogicalKeyEntry> = serde_json::from_str(&logical)?;
+    let logical_keys: BTreeMap<i64, LogicalKeyEntry> =
+        logical_keys.into_values().map(|v| (v.value, v)).collect();
+
+    let gen_path = out_dir.join("generated_logical_keys.rs");
+    let mut file = File::create(gen_path)?;
+    writeln!(file, "#[allow(dead_code)]")?;
+    writeln!(
+        file,
+        "pub struct LogicalKeyEntry {{ name: &'static str, value: i64, key_label: Option<&'static str>, gtk: Option<i64> }}"
+    )?;
+    writeln!(file)?;
+    writeln!(file, "const LOGICAL_KEYS: &[LogicalKeyEntry] = &[")?;
+    for v in logical_keys.values() {
+        // Prefer main keyboard keysyms over keypad ones (i.e. F1 over KP_F1).
+        let gtk = v
+            .names
+            .as_ref()
+            .and_then(|names| names.get("gtk"))
+            .zip(v.values.as_ref().and_then(|values| values.get("gtk")))
+            .and_then(|(names, values)| {
+                names
+                    .iter()
+                    .zip(values)
+                    .find(|(name, _)| !name.starts_with("KP_"))
+                    .or_else(|| names.iter().zip(values).next())
+                    .map(|(_, value)| *value)
+            });
+        writeln!(
+            file,
+            "    LogicalKeyEntry {{ name: {:?}, value: {}, key_label: {:?}, gtk: {:?} }},",
+            v.name, v.value, v.key_label, gtk,
+        )?;
+    }
+    writeln!(file, "];")?;
 
     Ok(())
 }
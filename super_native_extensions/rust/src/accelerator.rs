@@ -0,0 +1,516 @@
+//! Keyboard accelerators for menu activators.
+//!
+//! Keys are identified by logical key names from `LogicalKeyboardKey`
+//! (`keyboard_map/logical_key_data.g.json`) so that all menu implementations
+//! understand the same set of keys and display them consistently.
+//!
+//! Only Linux (GTK) and macOS menus render activators natively. Windows and
+//! Android have no native menus; menus there are drawn by Flutter, which
+//! gets activator labels through `formatActivator`. iOS context menus have
+//! no way of showing key equivalents, so activators are ignored there.
+//! Menu validation applies to all platforms.
+
+use std::fmt::Write;
+
+use crate::api_model::Activator;
+
+include!(concat!(env!("OUT_DIR"), "/generated_logical_keys.rs"));
+
+/// Alternative key names accepted when parsing accelerator strings.
+const KEY_ALIASES: &[(&str, &str)] = &[
+    ("esc", "Escape"),
+    ("del", "Delete"),
+    ("ins", "Insert"),
+    ("return", "Enter"),
+    ("pgup", "PageUp"),
+    ("pgdn", "PageDown"),
+    ("up", "ArrowUp"),
+    ("down", "ArrowDown"),
+    ("left", "ArrowLeft"),
+    ("right", "ArrowRight"),
+    ("plus", "Add"),
+];
+
+/// NSEvent function key characters used as macOS key equivalents.
+const MAC_FUNCTION_KEYS: &[(&str, u32)] = &[
+    ("ArrowUp", 0xF700),
+    ("ArrowDown", 0xF701),
+    ("ArrowLeft", 0xF702),
+    ("ArrowRight", 0xF703),
+    ("Insert", 0xF727),
+    ("Delete", 0xF728),
+    ("Home", 0xF729),
+    ("End", 0xF72B),
+    ("PageUp", 0xF72C),
+    ("PageDown", 0xF72D),
+    ("PrintScreen", 0xF72E),
+    ("ScrollLock", 0xF72F),
+    ("Pause", 0xF730),
+    ("ContextMenu", 0xF735),
+    ("Clear", 0xF739),
+    ("Select", 0xF741),
+    ("Execute", 0xF742),
+    ("Undo", 0xF743),
+    ("Redo", 0xF744),
+    ("Find", 0xF745),
+    ("Help", 0xF746),
+    ("Backspace", 0x0008),
+    ("Tab", 0x0009),
+    ("Enter", 0x000D),
+    ("NumpadEnter", 0x0003),
+    ("Escape", 0x001B),
+];
+
+const MAC_KEY_SYMBOLS: &[(&str, &str)] = &[
+    ("Enter", "↩"),
+    ("NumpadEnter", "⌤"),
+    ("Backspace", "⌫"),
+    ("Delete", "⌦"),
+    ("Escape", "⎋"),
+    ("Tab", "⇥"),
+    ("ArrowUp", "↑"),
+    ("ArrowDown", "↓"),
+    ("ArrowLeft", "←"),
+    ("ArrowRight", "→"),
+    ("PageUp", "⇞"),
+    ("PageDown", "⇟"),
+    ("Home", "↖"),
+    ("End", "↘"),
+    ("Clear", "⌧"),
+    ("CapsLock", "⇪"),
+];
+
+const KEY_DISPLAY_NAMES: &[(&str, &str)] = &[
+    ("Escape", "Esc"),
+    ("Delete", "Del"),
+    ("ArrowUp", "Up"),
+    ("ArrowDown", "Down"),
+    ("ArrowLeft", "Left"),
+    ("ArrowRight", "Right"),
+];
+
+fn lookup<T: Copy>(table: &[(&str, T)], name: &str) -> Option<T> {
+    table.iter().find(|(n, _)| *n == name).map(|(_, v)| *v)
+}
+
+/// Inserts spaces into camel case key name, i.e. "PageUp" -> "Page Up".
+fn split_camel_case(name: &str) -> String {
+    let mut res = String::new();
+    let mut prev = None::<char>;
+    for c in name.chars() {
+        if c.is_uppercase() && prev.is_some_and(|p| p.is_lowercase() || p.is_ascii_digit()) {
+            res.push(' ');
+        }
+        res.push(c);
+        prev = Some(c);
+    }
+    res
+}
+
+#[derive(Clone, Copy)]
+pub enum LogicalKey {
+    Key(&'static LogicalKeyEntry),
+    /// Printable character that has no dedicated logical key, such as
+    /// characters from non-US keyboard layouts.
+    Character(char),
+}
+
+impl std::fmt::Debug for LogicalKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogicalKey::Key(entry) => write!(f, "LogicalKey({})", entry.name),
+            LogicalKey::Character(c) => write!(f, "LogicalKey({c:?})"),
+        }
+    }
+}
+
+impl PartialEq for LogicalKey {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (LogicalKey::Key(a), LogicalKey::Key(b)) => a.value == b.value,
+            (LogicalKey::Character(a), LogicalKey::Character(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for LogicalKey {}
+
+impl LogicalKey {
+    /// Looks up key by its name ("PageUp"), `LogicalKeyboardKey` label
+    /// ("Page Up"), printable character ("A") or macOS key symbol ("⇞").
+    pub fn from_name(name: &str) -> Option<Self> {
+        let mut chars = name.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            if let Some((key, _)) = MAC_KEY_SYMBOLS.iter().find(|(_, symbol)| *symbol == name) {
+                return Self::from_name(key);
+            }
+            let label = c.to_lowercase().to_string();
+            return Some(
+                LOGICAL_KEYS
+                    .iter()
+                    .find(|e| e.key_label == Some(label.as_str()))
+                    .map(LogicalKey::Key)
+                    .unwrap_or(LogicalKey::Character(c)),
+            );
+        }
+        let normalized: String = name
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .flat_map(|c| c.to_lowercase())
+            .collect();
+        let normalized = KEY_ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == normalized)
+            .map(|(_, name)| name.to_lowercase())
+            .unwrap_or(normalized);
+        LOGICAL_KEYS
+            .iter()
+            .find(|e| e.name.to_lowercase() == normalized)
+            .map(LogicalKey::Key)
+    }
+
+    /// Logical key name, or the character for keys without one.
+    pub fn name(&self) -> String {
+        match self {
+            LogicalKey::Key(entry) => entry.name.into(),
+            LogicalKey::Character(c) => c.to_string(),
+        }
+    }
+
+    /// `LogicalKeyboardKey.keyId` value, if known.
+    pub fn value(&self) -> Option<i64> {
+        match self {
+            LogicalKey::Key(entry) => Some(entry.value),
+            LogicalKey::Character(_) => None,
+        }
+    }
+
+    /// Character produced by the key. `None` for non printable keys.
+    pub fn character(&self) -> Option<char> {
+        match self {
+            LogicalKey::Key(entry) => {
+                let mut chars = entry.key_label?.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Some(c),
+                    _ => None,
+                }
+            }
+            LogicalKey::Character(c) => Some(*c),
+        }
+    }
+
+    fn entry_name(&self) -> Option<&'static str> {
+        match self {
+            LogicalKey::Key(entry) => Some(entry.name),
+            LogicalKey::Character(_) => None,
+        }
+    }
+
+    /// GDK keyval for the key.
+    pub fn gtk_keyval(&self) -> Option<u32> {
+        if let Some(c) = self.character() {
+            // Latin-1 keysyms match code points, others use the Unicode range.
+            let c = c as u32;
+            return Some(if c < 0x100 { c } else { 0x01000000 | c });
+        }
+        match self {
+            LogicalKey::Key(entry) => entry.gtk.map(|v| v as u32),
+            LogicalKey::Character(_) => None,
+        }
+    }
+
+    /// Key equivalent character for NSMenuItem.
+    pub fn mac_key_equivalent(&self) -> Option<char> {
+        let name = self.entry_name().unwrap_or_default();
+        if let Some(n) = name.strip_prefix('F').and_then(|n| n.parse::<u32>().ok()) {
+            if (1..=35).contains(&n) {
+                return char::from_u32(0xF704 + n - 1);
+            }
+        }
+        if let Some(code) = lookup(MAC_FUNCTION_KEYS, name) {
+            return char::from_u32(code);
+        }
+        self.character().and_then(|c| c.to_lowercase().next())
+    }
+
+    pub fn display(&self, style: DisplayStyle) -> String {
+        if let Some(c) = self.character().filter(|c| !c.is_whitespace()) {
+            return c.to_uppercase().collect();
+        }
+        let name = self.entry_name().unwrap_or_default();
+        if style == DisplayStyle::Mac {
+            if let Some(symbol) = lookup(MAC_KEY_SYMBOLS, name) {
+                return symbol.into();
+            }
+        }
+        lookup(KEY_DISPLAY_NAMES, name)
+            .map(|n| n.into())
+            .unwrap_or_else(|| split_camel_case(name))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    pub alt: bool,
+    pub meta: bool,
+    pub shift: bool,
+    pub control: bool,
+}
+
+impl Modifiers {
+    /// Sets modifier by name. Returns `false` if the name is not recognized.
+    fn set(&mut self, name: &str) -> bool {
+        match name.trim().to_lowercase().as_str() {
+            "ctrl" | "control" | "⌃" => self.control = true,
+            "alt" | "option" | "opt" | "⌥" => self.alt = true,
+            "shift" | "⇧" => self.shift = true,
+            "meta" | "cmd" | "command" | "super" | "win" | "⌘" => self.meta = true,
+            // Command on Apple platforms, Control elsewhere.
+            "cmdorctrl" | "commandorcontrol" | "primary" => {
+                if cfg!(any(target_os = "macos", target_os = "ios")) {
+                    self.meta = true
+                } else {
+                    self.control = true
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn display(&self, style: DisplayStyle, res: &mut String) {
+        if style == DisplayStyle::Mac {
+            // Apple ordering of modifier symbols.
+            for (set, symbol) in [
+                (self.control, '⌃'),
+                (self.alt, '⌥'),
+                (self.shift, '⇧'),
+                (self.meta, '⌘'),
+            ] {
+                if set {
+                    res.push(symbol);
+                }
+            }
+        } else {
+            let meta = match style {
+                DisplayStyle::Windows => "Win",
+                _ => "Meta",
+            };
+            for (set, name) in [
+                (self.control, "Ctrl"),
+                (self.alt, "Alt"),
+                (self.shift, "Shift"),
+                (self.meta, meta),
+            ] {
+                if set {
+                    write!(res, "{name}+").unwrap();
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisplayStyle {
+    /// Modifier symbols without separators, i.e. "⌘⇧P".
+    Mac,
+    /// "Ctrl+Shift+P", meta key shown as "Win".
+    Windows,
+    /// "Ctrl+Shift+P", meta key shown as "Meta". Also used on Android.
+    Linux,
+}
+
+impl DisplayStyle {
+    pub fn current() -> Self {
+        if cfg!(any(target_os = "macos", target_os = "ios")) {
+            DisplayStyle::Mac
+        } else if cfg!(target_os = "windows") {
+            DisplayStyle::Windows
+        } else {
+            DisplayStyle::Linux
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keystroke {
+    pub key: LogicalKey,
+    pub modifiers: Modifiers,
+}
+
+impl Keystroke {
+    fn from_activator(activator: &Activator) -> Option<Self> {
+        Some(Self {
+            key: LogicalKey::from_name(&activator.trigger)?,
+            modifiers: Modifiers {
+                alt: activator.alt,
+                meta: activator.meta,
+                shift: activator.shift,
+                control: activator.control,
+            },
+        })
+    }
+
+    /// Parses keystroke such as "Ctrl+Shift+P", "Ctrl++" or "⌘⇧P".
+    fn parse(s: &str) -> Option<Self> {
+        let mut modifiers = Modifiers::default();
+        let mut s = s.trim();
+        while let Some(c) = s.chars().next().filter(|c| "⌃⌥⇧⌘".contains(*c)) {
+            modifiers.set(&c.to_string());
+            s = &s[c.len_utf8()..];
+        }
+        let (prefix, key) = if let Some(prefix) = s.strip_suffix("++") {
+            (prefix, "+")
+        } else if s == "+" {
+            ("", "+")
+        } else {
+            s.rsplit_once('+').unwrap_or(("", s))
+        };
+        if !prefix.is_empty() && !prefix.split('+').all(|m| modifiers.set(m)) {
+            return None;
+        }
+        Some(Self {
+            key: LogicalKey::from_name(key.trim())?,
+            modifiers,
+        })
+    }
+
+    pub fn display(&self, style: DisplayStyle) -> String {
+        let mut res = String::new();
+        self.modifiers.display(style, &mut res);
+        res.push_str(&self.key.display(style));
+        res
+    }
+}
+
+/// Sequence of one or more keystrokes. Multiple keystrokes form a chord,
+/// such as Ctrl+K Ctrl+C.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Accelerator {
+    strokes: Vec<Keystroke>,
+}
+
+impl Accelerator {
+    /// Returns `None` if any trigger of the activator is not a known key.
+    pub fn from_activator(activator: &Activator) -> Option<Self> {
+        let strokes = std::iter::once(activator)
+            .chain(activator.chord.iter().flatten())
+            .map(Keystroke::from_activator)
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self { strokes })
+    }
+
+    /// Parses accelerator string. Keystrokes are separated by whitespace,
+    /// i.e. "Ctrl+K Ctrl+C". Key names may contain spaces ("Ctrl+Page Up").
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut strokes = Vec::new();
+        let mut pending = String::new();
+        for token in s.split_whitespace() {
+            if !pending.is_empty() {
+                pending.push(' ');
+            }
+            pending.push_str(token);
+            if let Some(stroke) = Keystroke::parse(&pending) {
+                strokes.push(stroke);
+                pending.clear();
+            }
+        }
+        if strokes.is_empty() || !pending.is_empty() {
+            None
+        } else {
+            Some(Self { strokes })
+        }
+    }
+
+    pub fn strokes(&self) -> &[Keystroke] {
+        &self.strokes
+    }
+
+    pub fn first(&self) -> &Keystroke {
+        &self.strokes[0]
+    }
+
+    pub fn is_chord(&self) -> bool {
+        self.strokes.len() > 1
+    }
+
+    pub fn display(&self, style: DisplayStyle) -> String {
+        self.strokes
+            .iter()
+            .map(|s| s.display(style))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api_model::Activator;
+
+    use super::{Accelerator, DisplayStyle, LogicalKey};
+
+    fn activator(trigger: &str, shift: bool) -> Activator {
+        Activator {
+            trigger: trigger.into(),
+            alt: false,
+            meta: false,
+            shift,
+            control: true,
+            chord: None,
+        }
+    }
+
+    #[test]
+    fn test_keys() {
+        assert_eq!(
+            LogicalKey::from_name("Page Up"),
+            LogicalKey::from_name("pageup")
+        );
+        assert_eq!(
+            LogicalKey::from_name("F1").unwrap().gtk_keyval(),
+            Some(0xFFBE)
+        );
+        assert_eq!(
+            LogicalKey::from_name("F1").unwrap().mac_key_equivalent(),
+            Some('\u{F704}')
+        );
+        assert_eq!(LogicalKey::from_name("A").unwrap().name(), "KeyA");
+        assert_eq!(LogicalKey::from_name("A").unwrap().gtk_keyval(), Some(0x61));
+        assert_eq!(
+            LogicalKey::from_name("ž").unwrap().gtk_keyval(),
+            Some(0x0100017E)
+        );
+        assert_eq!(
+            LogicalKey::from_name("Media Play Pause")
+                .unwrap()
+                .display(DisplayStyle::Linux),
+            "Media Play Pause"
+        );
+        assert!(LogicalKey::from_name("Hyper Space").is_none());
+    }
+
+    #[test]
+    fn test_accelerator() {
+        let mut chord = activator("K", false);
+        chord.chord = Some(vec![activator("C", false)]);
+        let accelerator = Accelerator::from_activator(&chord).unwrap();
+        assert!(accelerator.is_chord());
+        assert_eq!(accelerator.display(DisplayStyle::Linux), "Ctrl+K Ctrl+C");
+        assert_eq!(Accelerator::parse("ctrl+k ctrl+c"), Some(accelerator));
+
+        let accelerator = Accelerator::from_activator(&activator("Arrow Up", true)).unwrap();
+        assert_eq!(accelerator.display(DisplayStyle::Mac), "⌃⇧↑");
+        assert_eq!(accelerator.display(DisplayStyle::Windows), "Ctrl+Shift+Up");
+        assert_eq!(
+            Accelerator::parse("Ctrl+Shift+Arrow Up").as_ref(),
+            Some(&accelerator)
+        );
+        assert_eq!(Accelerator::parse("⌃⇧↑"), Some(accelerator));
+
+        let plus = Accelerator::parse("Cmd++").unwrap();
+        assert_eq!(plus.display(DisplayStyle::Mac), "⌘+");
+        assert!(Accelerator::parse("Hyper+K").is_none());
+        assert!(Accelerator::from_activator(&activator("Unknown Key", false)).is_none());
+    }
+}
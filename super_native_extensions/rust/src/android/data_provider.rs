@@ -18,7 +18,7 @@ use url::Url;
 
 use crate::{
     android::{CONTEXT, JAVA_VM},
-    api_model::{DataProvider, DataRepresentation},
+    api_model::{ClipboardSelection, DataProvider, DataRepresentation},
     context::Context,
     data_provider_manager::{DataProviderHandle, PlatformDataProviderDelegate},
     error::{NativeExtensionsError, NativeExtensionsResult},
@@ -280,7 +280,11 @@ impl PlatformDataProvider {
 
     pub async fn write_to_clipboard(
         providers: Vec<(Rc<PlatformDataProvider>, Arc<DataProviderHandle>)>,
+        selection: ClipboardSelection,
     ) -> NativeExtensionsResult<()> {
+        if selection != ClipboardSelection::Clipboard {
+            return Err(NativeExtensionsError::UnsupportedOperation);
+        }
         let handles: Vec<_> = providers.iter().map(|p| p.1.clone()).collect();
         let providers: Vec<_> = providers.into_iter().map(|p| p.0).collect();
 
@@ -15,6 +15,7 @@ use jni::{
 };
 
 use crate::{
+    accelerator::Modifiers,
     android::{CONTEXT, DRAG_DROP_HELPER, JAVA_VM},
     api_model::{DropOperation, Point},
     drop_manager::{
@@ -88,6 +89,11 @@ impl PlatformDropContext {
         self._assign_weak_self(weak_self).ok_log();
     }
 
+    /// Android drag events don't carry keyboard modifiers.
+    pub fn current_modifiers(&self) -> Modifiers {
+        Modifiers::default()
+    }
+
     pub fn register_drop_formats(&self, _formats: &[String]) -> NativeExtensionsResult<()> {
         Ok(())
     }
@@ -3,7 +3,7 @@ use std::rc::{Rc, Weak};
 use irondash_message_channel::IsolateId;
 
 use crate::{
-    api_model::{ImageData, Menu, ShowContextMenuRequest, ShowContextMenuResponse},
+    api_model::{ImageData, Menu, MenuAction, ShowContextMenuRequest, ShowContextMenuResponse},
     error::{NativeExtensionsError, NativeExtensionsResult},
     menu_manager::{PlatformMenuContextDelegate, PlatformMenuContextId, PlatformMenuDelegate},
 };
@@ -21,6 +21,10 @@ impl PlatformMenu {
     ) -> NativeExtensionsResult<Rc<Self>> {
         Ok(Rc::new(Self {}))
     }
+
+    pub fn update_elements(&self, _actions: Vec<MenuAction>) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
 }
 
 impl PlatformMenuContext {
@@ -32,6 +36,19 @@ impl PlatformMenuContext {
         Ok(Self {})
     }
 
+    pub fn set_menu_bar(&self, _menu: Option<Rc<PlatformMenu>>) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
+    pub fn set_tray_menu(
+        &self,
+        _menu: Option<Rc<PlatformMenu>>,
+        _icon: Option<ImageData>,
+        _tooltip: Option<String>,
+    ) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
     pub fn update_preview_image(
         &self,
         _configuration_id: i64,
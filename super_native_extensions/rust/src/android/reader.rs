@@ -18,8 +18,9 @@ use url::Url;
 
 use crate::{
     android::{CLIP_DATA_HELPER, CONTEXT, JAVA_VM},
+    api_model::ClipboardSelection,
     error::{NativeExtensionsError, NativeExtensionsResult},
-    reader_manager::{ReadProgress, VirtualFileReader},
+    reader_manager::{ItemDataStream, ReadProgress, VirtualFileReader},
     util::DropNotifier,
 };
 
@@ -217,7 +218,10 @@ impl PlatformDataReader {
         }))
     }
 
-    pub fn new_clipboard_reader() -> NativeExtensionsResult<Rc<Self>> {
+    pub fn new_clipboard_reader(selection: ClipboardSelection) -> NativeExtensionsResult<Rc<Self>> {
+        if selection != ClipboardSelection::Clipboard {
+            return Err(NativeExtensionsError::UnsupportedOperation);
+        }
         let (mut env, context) = Self::get_env_and_context()?;
         let clipboard_service = env
             .get_static_field(
@@ -269,6 +273,15 @@ impl PlatformDataReader {
         Ok(false)
     }
 
+    pub async fn create_data_stream_for_item(
+        &self,
+        _item: i64,
+        _format: &str,
+        _progress: Arc<ReadProgress>,
+    ) -> NativeExtensionsResult<Option<Rc<dyn ItemDataStream>>> {
+        Ok(None)
+    }
+
     pub async fn create_virtual_file_reader_for_item(
         &self,
         _item: i64,
@@ -1,4 +1,4 @@
-use std::rc::Rc;
+use std::{fmt::Display, rc::Rc};
 
 use irondash_message_channel::{IntoValue, TryFromValue, Value};
 
@@ -56,6 +56,13 @@ impl Rect {
             height: self.height + 2.0 * y,
         }
     }
+
+    pub fn contains(&self, point: &Point) -> bool {
+        point.x >= self.x
+            && point.x < self.x + self.width
+            && point.y >= self.y
+            && point.y < self.y + self.height
+    }
 }
 
 #[derive(Clone, Debug, Default, PartialEq, TryFromValue, IntoValue)]
@@ -169,6 +176,18 @@ pub enum VirtualFileStorage {
 
 //
 
+/// Selection used by clipboard reader and writer. `Primary` (X11/Wayland
+/// primary selection used for middle-click paste) is only supported on Linux.
+#[derive(Debug, TryFromValue, IntoValue, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[irondash(rename_all = "camelCase")]
+pub enum ClipboardSelection {
+    #[default]
+    Clipboard,
+    Primary,
+}
+
+//
+
 #[derive(TryFromValue, Debug)]
 #[irondash(rename_all = "camelCase")]
 pub struct TargettedImage {
@@ -248,6 +267,9 @@ pub struct ShowContextMenuRequest {
     pub writing_tools_configuration: Option<WritingToolsConfiguration>,
     #[irondash(skip)]
     pub menu: Option<Rc<PlatformMenu>>,
+    /// Configuration used to show menu preview, if enabled (Linux only).
+    #[irondash(skip)]
+    pub configuration: Option<MenuConfiguration>,
 }
 
 #[derive(IntoValue)]
@@ -283,6 +305,9 @@ pub struct Activator {
     pub meta: bool,
     pub shift: bool,
     pub control: bool,
+    /// Keystrokes following this one for multi-stroke chords such as
+    /// Ctrl+K Ctrl+C.
+    pub chord: Option<Vec<Activator>>,
 }
 
 #[derive(TryFromValue, Debug, PartialEq, Eq)]
@@ -347,3 +372,39 @@ pub enum MenuElement {
     Deferred(DeferredMenuElement),
     Separator(MenuSeparator),
 }
+
+#[derive(IntoValue, Debug, Clone, Copy, PartialEq, Eq)]
+#[irondash(rename_all = "camelCase")]
+pub enum MenuProblemKind {
+    /// Radio action that has no adjacent radio actions to form a group with.
+    RadioActionWithoutGroup,
+    DuplicateUniqueId,
+    /// Activator trigger that platform menus can not map to a key.
+    UnsupportedActivatorTrigger,
+    EmptySubmenu,
+}
+
+#[derive(IntoValue, Debug, Clone, PartialEq, Eq)]
+#[irondash(rename_all = "camelCase")]
+pub struct MenuProblem {
+    pub kind: MenuProblemKind,
+    /// Child indices leading from the root menu to the offending element.
+    pub path: Vec<i64>,
+    pub unique_id: i64,
+}
+
+impl Display for MenuProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let description = match self.kind {
+            MenuProblemKind::RadioActionWithoutGroup => "radio action without group",
+            MenuProblemKind::DuplicateUniqueId => "duplicate unique id",
+            MenuProblemKind::UnsupportedActivatorTrigger => "unsupported activator trigger",
+            MenuProblemKind::EmptySubmenu => "empty submenu",
+        };
+        write!(
+            f,
+            "{description} (id {}) at {:?}",
+            self.unique_id, self.path
+        )
+    }
+}
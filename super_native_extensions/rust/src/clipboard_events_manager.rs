@@ -6,7 +6,7 @@ use std::{
 
 use async_trait::async_trait;
 use irondash_message_channel::{
-    AsyncMethodHandler, AsyncMethodInvoker, IsolateId, Late, MethodCall, PlatformError,
+    AsyncMethodHandler, AsyncMethodInvoker, IntoValue, IsolateId, Late, MethodCall, PlatformError,
     PlatformResult, RegisteredAsyncMethodHandler, Value,
 };
 use log::warn;
@@ -32,6 +32,16 @@ pub trait ClipboardEventManagerDelegate {
     async fn on_copy(&self, isolate_id: IsolateId) -> bool;
     async fn on_paste(&self, isolate_id: IsolateId) -> bool;
     async fn on_select_all(&self, isolate_id: IsolateId) -> bool;
+
+    /// Called when clipboard content has been changed (by this or another
+    /// application). `change_count` increases monotonically.
+    fn on_clipboard_changed(&self, isolate_id: IsolateId, change_count: i64);
+}
+
+#[derive(IntoValue)]
+#[irondash(rename_all = "camelCase")]
+struct ClipboardChangedEvent {
+    change_count: i64,
 }
 
 pub trait GetClipboardEventManager {
@@ -103,6 +113,17 @@ impl ClipboardEventManagerDelegate for ClipboardEventManager {
             .await;
         res.ok_log().unwrap_or(false)
     }
+
+    fn on_clipboard_changed(&self, isolate_id: IsolateId, change_count: i64) {
+        self.invoker.call_method_sync(
+            isolate_id,
+            "clipboardChanged",
+            ClipboardChangedEvent { change_count },
+            |r| {
+                r.ok_log();
+            },
+        );
+    }
 }
 
 #[async_trait(?Send)]
@@ -7,7 +7,7 @@ use irondash_message_channel::{
 };
 
 use crate::{
-    context::Context, platform_impl::platform::PlatformDataReader,
+    api_model::ClipboardSelection, context::Context, platform_impl::platform::PlatformDataReader,
     reader_manager::GetDataReaderManager,
 };
 
@@ -34,7 +34,9 @@ impl AsyncMethodHandler for ClipboardReader {
     async fn on_method_call(&self, call: MethodCall) -> PlatformResult {
         match call.method.as_str() {
             "newClipboardReader" => {
-                let reader = PlatformDataReader::new_clipboard_reader()?;
+                let selection: Option<ClipboardSelection> = call.args.try_into()?;
+                let reader =
+                    PlatformDataReader::new_clipboard_reader(selection.unwrap_or_default())?;
                 Ok(Context::get()
                     .data_reader_manager()
                     .register_platform_reader(reader, call.isolate)
@@ -6,15 +6,26 @@ use std::{
 use async_trait::async_trait;
 use irondash_message_channel::{
     AsyncMethodHandler, AsyncMethodInvoker, IntoPlatformResult, IsolateId, Late, MethodCall,
-    PlatformError, PlatformResult, RegisteredAsyncMethodHandler, Value,
+    PlatformError, PlatformResult, RegisteredAsyncMethodHandler, TryFromValue, Value,
 };
 
 use crate::{
-    api_model::DataProviderId, context::Context, data_provider_manager::GetDataProviderManager,
-    error::NativeExtensionsResult, log::OkLog, platform_impl::platform::PlatformDataProvider,
+    api_model::{ClipboardSelection, DataProviderId},
+    context::Context,
+    data_provider_manager::GetDataProviderManager,
+    error::NativeExtensionsResult,
+    log::OkLog,
+    platform_impl::platform::PlatformDataProvider,
     util::DropNotifier,
 };
 
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct WriteToClipboardRequest {
+    provider_ids: Vec<DataProviderId>,
+    selection: Option<ClipboardSelection>,
+}
+
 pub struct ClipboardWriter {
     weak_self: Late<Weak<Self>>,
     invoker: Late<AsyncMethodInvoker>,
@@ -39,11 +50,11 @@ impl ClipboardWriter {
     async fn write_to_clipboard(
         &self,
         isolate_id: IsolateId,
-        provider_ids: Vec<DataProviderId>,
+        request: WriteToClipboardRequest,
     ) -> NativeExtensionsResult<()> {
         let mut providers = Vec::<_>::new();
         let data_provider_manager = Context::get().data_provider_manager();
-        for provider_id in provider_ids {
+        for provider_id in request.provider_ids {
             let provider = data_provider_manager.get_platform_data_provider(provider_id)?;
             let weak_self = self.weak_self.clone();
             let notifier = DropNotifier::new(move || {
@@ -53,7 +64,8 @@ impl ClipboardWriter {
             });
             providers.push((provider, Arc::new(notifier.into())));
         }
-        PlatformDataProvider::write_to_clipboard(providers).await?;
+        PlatformDataProvider::write_to_clipboard(providers, request.selection.unwrap_or_default())
+            .await?;
         Ok(())
     }
 }
@@ -72,10 +84,19 @@ impl GetClipboardWriter for Context {
 impl AsyncMethodHandler for ClipboardWriter {
     async fn on_method_call(&self, call: MethodCall) -> PlatformResult {
         match call.method.as_str() {
-            "writeToClipboard" => self
-                .write_to_clipboard(call.isolate, call.args.try_into()?)
-                .await
-                .into_platform_result(),
+            "writeToClipboard" => {
+                let request = match call.args {
+                    // List of provider ids without selection.
+                    Value::List(_) => WriteToClipboardRequest {
+                        provider_ids: call.args.try_into()?,
+                        selection: None,
+                    },
+                    args => args.try_into()?,
+                };
+                self.write_to_clipboard(call.isolate, request)
+                    .await
+                    .into_platform_result()
+            }
             _ => Err(PlatformError {
                 code: "invalid_method".into(),
                 message: Some(format!("Unknown Method: {}", call.method)),
@@ -10,17 +10,17 @@ pub struct Context {
     outermost: bool,
 }
 
-#[cfg(not(target_os = "windows"))]
+#[cfg(any(not(target_os = "windows"), feature = "mock"))]
 struct Initializer {}
 
-#[cfg(not(target_os = "windows"))]
+#[cfg(any(not(target_os = "windows"), feature = "mock"))]
 impl Initializer {
     fn new() -> Self {
         Self {}
     }
 }
 
-#[cfg(target_os = "windows")]
+#[cfg(all(target_os = "windows", not(feature = "mock")))]
 type Initializer = crate::platform_impl::platform::OleInitializer;
 
 pub struct ContextInternal {
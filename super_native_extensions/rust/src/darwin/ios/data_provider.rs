@@ -22,11 +22,14 @@ use objc2_ui_kit::UIPasteboard;
 use once_cell::sync::Lazy;
 
 use crate::{
-    api_model::{DataProvider, DataProviderValueId, DataRepresentation, VirtualFileStorage},
+    api_model::{
+        ClipboardSelection, DataProvider, DataProviderValueId, DataRepresentation,
+        VirtualFileStorage,
+    },
     data_provider_manager::{
         DataProviderHandle, PlatformDataProviderDelegate, VirtualFileResult, VirtualSessionHandle,
     },
-    error::NativeExtensionsResult,
+    error::{NativeExtensionsError, NativeExtensionsResult},
     log::OkLog,
     platform_impl::platform::common::to_nserror,
     util::Movable,
@@ -170,7 +173,11 @@ impl PlatformDataProvider {
 
     pub async fn write_to_clipboard(
         providers: Vec<(Rc<PlatformDataProvider>, Arc<DataProviderHandle>)>,
+        selection: ClipboardSelection,
     ) -> NativeExtensionsResult<()> {
+        if selection != ClipboardSelection::Clipboard {
+            return Err(NativeExtensionsError::UnsupportedOperation);
+        }
         for provider in &providers {
             provider.0.precache().await;
         }
@@ -23,6 +23,7 @@ use objc2_ui_kit::{
 };
 
 use crate::{
+    accelerator::Modifiers,
     api_model::{DropOperation, Size},
     drop_manager::{
         BaseDropEvent, DropEvent, DropItem, DropItemId, DropSessionId, ItemPreview,
@@ -390,6 +391,11 @@ impl PlatformDropContext {
         })
     }
 
+    /// Drop sessions on iOS don't carry keyboard modifiers.
+    pub fn current_modifiers(&self) -> Modifiers {
+        Modifiers::default()
+    }
+
     pub fn register_drop_formats(&self, _formats: &[String]) -> NativeExtensionsResult<()> {
         Ok(())
     }
@@ -1,6 +1,11 @@
 use std::rc::Weak;
 
-use crate::keyboard_layout_manager::{KeyboardLayout, KeyboardLayoutDelegate};
+use crate::{
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    keyboard_layout_manager::{
+        KeyboardLayout, KeyboardLayoutDelegate, KeyboardLayoutInfo, KeymapDescription,
+    },
+};
 
 pub struct PlatformKeyboardLayout {}
 
@@ -13,5 +18,24 @@ impl PlatformKeyboardLayout {
         None
     }
 
+    pub fn get_layouts(&self) -> NativeExtensionsResult<Vec<KeyboardLayoutInfo>> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
+    pub fn get_layout(&self, _id: i64) -> NativeExtensionsResult<Option<KeyboardLayout>> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
+    pub fn get_layout_for_keymap(
+        &self,
+        _description: KeymapDescription,
+    ) -> NativeExtensionsResult<KeyboardLayout> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
+    pub fn select_layout(&self, _id: i64) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
     pub fn assign_weak_self(&self, _weak: Weak<PlatformKeyboardLayout>) {}
 }
@@ -31,7 +31,7 @@ use objc2_ui_kit::{
 
 use crate::{
     api_model::{
-        ImageData, Menu, MenuActionState, MenuConfiguration, MenuElement, MenuImage,
+        ImageData, Menu, MenuAction, MenuActionState, MenuConfiguration, MenuElement, MenuImage,
         ShowContextMenuRequest, ShowContextMenuResponse,
     },
     error::{NativeExtensionsError, NativeExtensionsResult},
@@ -92,6 +92,10 @@ impl PlatformMenu {
         Ok(Rc::new(res))
     }
 
+    pub fn update_elements(&self, _actions: Vec<MenuAction>) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
     unsafe fn convert_string(str: &Option<String>) -> Option<Id<NSString>> {
         str.as_ref().map(|str| NSString::from_str(str))
     }
@@ -394,6 +398,19 @@ impl PlatformMenuContext {
         !self.sessions.borrow().is_empty()
     }
 
+    pub fn set_menu_bar(&self, _menu: Option<Rc<PlatformMenu>>) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
+    pub fn set_tray_menu(
+        &self,
+        _menu: Option<Rc<PlatformMenu>>,
+        _icon: Option<ImageData>,
+        _tooltip: Option<String>,
+    ) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
     pub fn update_preview_image(
         &self,
         configuration_id: i64,
@@ -30,13 +30,14 @@ use objc2::{
 use objc2_ui_kit::{UIDragItem, UIPasteboard};
 
 use crate::{
+    api_model::ClipboardSelection,
     error::{NativeExtensionsError, NativeExtensionsResult},
     log::OkLog,
     platform_impl::platform::{
         common::{path_from_url, uti_conforms_to, NSURLSecurtyScopeAccess},
         progress_bridge::bridge_progress,
     },
-    reader_manager::{ReadProgress, VirtualFileReader},
+    reader_manager::{ItemDataStream, ReadProgress, VirtualFileReader},
     util::{get_target_path, Movable},
     value_promise::Promise,
 };
@@ -172,7 +173,10 @@ impl PlatformDataReader {
         future.await
     }
 
-    pub fn new_clipboard_reader() -> NativeExtensionsResult<Rc<Self>> {
+    pub fn new_clipboard_reader(selection: ClipboardSelection) -> NativeExtensionsResult<Rc<Self>> {
+        if selection != ClipboardSelection::Clipboard {
+            return Err(NativeExtensionsError::UnsupportedOperation);
+        }
         let res = Rc::new(Self {
             source: ReaderSource::Pasteboard(unsafe { UIPasteboard::generalPasteboard() }),
         });
@@ -235,6 +239,15 @@ impl PlatformDataReader {
         self.can_copy_virtual_file_for_item(item, format).await
     }
 
+    pub async fn create_data_stream_for_item(
+        &self,
+        _item: i64,
+        _format: &str,
+        _progress: Arc<ReadProgress>,
+    ) -> NativeExtensionsResult<Option<Rc<dyn ItemDataStream>>> {
+        Ok(None)
+    }
+
     pub async fn create_virtual_file_reader_for_item(
         &self,
         item: i64,
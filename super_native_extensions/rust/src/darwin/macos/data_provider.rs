@@ -28,11 +28,11 @@ use objc2_foundation::{NSArray, NSError, NSProgress, NSProgressKindFile, NSStrin
 use once_cell::sync::Lazy;
 
 use crate::{
-    api_model::{DataProvider, DataProviderValueId, DataRepresentation},
+    api_model::{ClipboardSelection, DataProvider, DataProviderValueId, DataRepresentation},
     data_provider_manager::{
         DataProviderHandle, PlatformDataProviderDelegate, VirtualFileResult, VirtualSessionHandle,
     },
-    error::NativeExtensionsResult,
+    error::{NativeExtensionsError, NativeExtensionsResult},
     log::OkLog,
     platform_impl::platform::common::{path_from_url, to_nserror},
     value_promise::ValuePromiseResult,
@@ -119,7 +119,11 @@ impl PlatformDataProvider {
 
     pub async fn write_to_clipboard(
         providers: Vec<(Rc<PlatformDataProvider>, Arc<DataProviderHandle>)>,
+        selection: ClipboardSelection,
     ) -> NativeExtensionsResult<()> {
+        if selection != ClipboardSelection::Clipboard {
+            return Err(NativeExtensionsError::UnsupportedOperation);
+        }
         let items: Vec<_> = providers
             .into_iter()
             .map(|p| p.0.create_writer(p.1, true, false))
@@ -16,12 +16,13 @@ use objc2::{
     sel, ClassType,
 };
 use objc2_app_kit::{
-    NSDragOperation, NSDraggingInfo, NSDraggingItem, NSDraggingItemEnumerationOptions,
-    NSFilePromiseReceiver, NSPasteboardItem, NSView,
+    NSDragOperation, NSDraggingInfo, NSDraggingItem, NSDraggingItemEnumerationOptions, NSEvent,
+    NSEventModifierFlags, NSFilePromiseReceiver, NSPasteboardItem, NSView,
 };
 use objc2_foundation::{ns_string, NSArray, NSDictionary, NSMutableArray, NSRect, NSString};
 
 use crate::{
+    accelerator::Modifiers,
     api_model::DropOperation,
     drop_manager::{
         BaseDropEvent, DropEvent, DropItem, DropSessionId, ItemPreviewRequest,
@@ -285,6 +286,18 @@ impl PlatformDropContext {
         self.weak_self.set(weak_self);
     }
 
+    /// Keyboard modifiers currently pressed.
+    pub fn current_modifiers(&self) -> Modifiers {
+        let flags = unsafe { NSEvent::modifierFlags_class() }.0;
+        let has = |flag: NSEventModifierFlags| flags & flag.0 == flag.0;
+        Modifiers {
+            alt: has(NSEventModifierFlags::NSEventModifierFlagOption),
+            meta: has(NSEventModifierFlags::NSEventModifierFlagCommand),
+            shift: has(NSEventModifierFlags::NSEventModifierFlagShift),
+            control: has(NSEventModifierFlags::NSEventModifierFlagControl),
+        }
+    }
+
     pub fn register_drop_formats(&self, types: &[String]) -> NativeExtensionsResult<()> {
         let types: Vec<_> = types.iter().map(|ty| NSString::from_str(ty)).collect();
         let our_types = NSArray::from_vec(types);
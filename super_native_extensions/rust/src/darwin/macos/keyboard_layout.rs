@@ -6,22 +6,34 @@ use std::{
 };
 
 use core_foundation::{
-    base::CFRelease,
+    array::{CFArray, CFArrayRef},
+    base::{CFRelease, CFType, TCFType},
+    boolean::{CFBoolean, CFBooleanRef},
     data::{CFDataGetBytePtr, CFDataRef},
-    dictionary::CFDictionaryRef,
-    string::CFStringRef,
+    dictionary::{CFDictionary, CFDictionaryRef},
+    string::{CFString, CFStringRef},
 };
 use irondash_message_channel::Late;
 
-use crate::keyboard_layout_manager::{Key, KeyboardLayout, KeyboardLayoutDelegate};
+use crate::{
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    keyboard_layout_manager::{
+        ComposeSequence, Key, KeyboardLayout, KeyboardLayoutDelegate, KeyboardLayoutInfo,
+        KeymapDescription,
+    },
+    keyboard_map::{get_key_map, KeyMapEntry},
+};
 
 use super::keyboard_layout_sys::{
-    altKey, cmdKey, kTISNotifySelectedKeyboardInputSourceChanged, kTISPropertyUnicodeKeyLayoutData,
-    kUCKeyActionDisplay, kUCKeyTranslateNoDeadKeysMask, shiftKey, CFNotificationCenterAddObserver,
+    altKey, cmdKey, kTISCategoryKeyboardInputSource, kTISNotifySelectedKeyboardInputSourceChanged,
+    kTISPropertyInputSourceCategory, kTISPropertyInputSourceIsSelectCapable,
+    kTISPropertyInputSourceIsSelected, kTISPropertyInputSourceLanguages, kTISPropertyLocalizedName,
+    kTISPropertyUnicodeKeyLayoutData, kUCKeyActionDisplay, kUCKeyActionDown,
+    kUCKeyTranslateNoDeadKeysMask, shiftKey, CFNotificationCenterAddObserver,
     CFNotificationCenterGetDistributedCenter, CFNotificationCenterRef,
     CFNotificationCenterRemoveObserver, CFNotificationSuspensionBehaviorCoalesce, CFObject,
-    LMGetKbdType, TISCopyCurrentASCIICapableKeyboardLayoutInputSource, TISGetInputSourceProperty,
-    UCKeyTranslate,
+    LMGetKbdType, TISCopyCurrentASCIICapableKeyboardLayoutInputSource, TISCreateInputSourceList,
+    TISGetInputSourceProperty, TISSelectInputSource, UCKeyTranslate,
 };
 
 pub struct PlatformKeyboardLayout {
@@ -31,8 +43,6 @@ pub struct PlatformKeyboardLayout {
     delegate: Weak<dyn KeyboardLayoutDelegate>,
 }
 
-include!(concat!(env!("OUT_DIR"), "/generated_keyboard_map.rs"));
-
 impl PlatformKeyboardLayout {
     pub fn new(delegate: Weak<dyn KeyboardLayoutDelegate>) -> Self {
         Self {
@@ -53,21 +63,26 @@ impl PlatformKeyboardLayout {
     }
 
     fn create_keyboard_layout(&self) -> KeyboardLayout {
-        let key_map = get_key_map();
         unsafe {
             let input_source = TISCopyCurrentASCIICapableKeyboardLayoutInputSource();
             let layout_data: CFObject =
                 TISGetInputSourceProperty(input_source, kTISPropertyUnicodeKeyLayoutData);
+            let layout = self.keyboard_layout_from_data(layout_data);
+            CFRelease(input_source);
+            layout
+        }
+    }
 
-            let keys: Vec<Key> = key_map
-                .iter()
-                .map(|a| self.key_from_entry(a, layout_data))
-                .collect();
+    unsafe fn keyboard_layout_from_data(&self, layout_data: CFObject) -> KeyboardLayout {
+        let key_map = get_key_map();
+        let keys: Vec<Key> = key_map
+            .iter()
+            .map(|a| self.key_from_entry(a, layout_data))
+            .collect();
 
-            CFRelease(input_source);
+        let compose = Self::compose_sequences(&key_map, &keys, layout_data);
 
-            KeyboardLayout { keys }
-        }
+        KeyboardLayout { keys, compose }
     }
 
     unsafe fn key_from_entry(&self, entry: &KeyMapEntry, layout_data: CFObject) -> Key {
@@ -80,6 +95,10 @@ impl PlatformKeyboardLayout {
                 logical_alt: None,
                 logical_alt_shift: None,
                 logical_meta: None,
+                dead: false,
+                dead_shift: false,
+                dead_alt: false,
+                dead_alt_shift: false,
             },
             None => {
                 let mut logical_key = None::<i64>;
@@ -196,6 +215,10 @@ impl PlatformKeyboardLayout {
                 //     logical_key_alt_shift,
                 // );
 
+                let is_dead = |modifiers: u32| {
+                    Self::dead_key_state(layout, entry.platform as u16, modifiers).is_some()
+                };
+
                 Key {
                     platform: entry.platform,
                     physical: entry.physical,
@@ -204,9 +227,226 @@ impl PlatformKeyboardLayout {
                     logical_alt: logical_key_alt,
                     logical_alt_shift: logical_key_alt_shift,
                     logical_meta: logical_key_cmd,
+                    dead: is_dead(0),
+                    dead_shift: is_dead((shiftKey >> 8) & 0xFF),
+                    dead_alt: is_dead((altKey >> 8) & 0xFF),
+                    dead_alt_shift: is_dead((shiftKey >> 8) & 0xFF | (altKey >> 8) & 0xFF),
+                }
+            }
+        }
+    }
+
+    /// Returns dead key state after pressing the key if it is a dead key.
+    unsafe fn dead_key_state(layout: *const u8, key_code: u16, modifiers: u32) -> Option<u32> {
+        let mut dead_key_state: u32 = 0;
+        let mut unichars = [0u16; 4];
+        let mut unichar_count: c_ulong = 0;
+        UCKeyTranslate(
+            layout as *mut _,
+            key_code,
+            kUCKeyActionDown,
+            modifiers,
+            LMGetKbdType(),
+            0,
+            &mut dead_key_state as *mut _,
+            unichars.len() as c_ulong,
+            &mut unichar_count as *mut _,
+            unichars.as_mut_ptr(),
+        );
+        if dead_key_state != 0 {
+            Some(dead_key_state)
+        } else {
+            None
+        }
+    }
+
+    /// Returns characters produced by the key when pressed in given dead key state.
+    unsafe fn translate_with_state(
+        layout: *const u8,
+        key_code: u16,
+        modifiers: u32,
+        mut dead_key_state: u32,
+    ) -> Option<String> {
+        let mut unichars = [0u16; 4];
+        let mut unichar_count: c_ulong = 0;
+        UCKeyTranslate(
+            layout as *mut _,
+            key_code,
+            kUCKeyActionDown,
+            modifiers,
+            LMGetKbdType(),
+            0,
+            &mut dead_key_state as *mut _,
+            unichars.len() as c_ulong,
+            &mut unichar_count as *mut _,
+            unichars.as_mut_ptr(),
+        );
+        if unichar_count > 0 {
+            Some(String::from_utf16_lossy(
+                &unichars[..unichar_count as usize],
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Resolves every dead key in the layout followed by every character key
+    /// (without modifiers or with shift).
+    unsafe fn compose_sequences(
+        key_map: &[KeyMapEntry],
+        keys: &[Key],
+        layout_data: CFObject,
+    ) -> Vec<ComposeSequence> {
+        let layout = CFDataGetBytePtr(layout_data as CFDataRef);
+        let shift = (shiftKey >> 8) & 0xFF;
+        let alt = (altKey >> 8) & 0xFF;
+
+        let mut dead_keys = Vec::new();
+        let mut base_keys = Vec::new();
+        for (entry, key) in key_map.iter().zip(keys) {
+            if entry.logical.is_some() {
+                continue;
+            }
+            let levels = [
+                (key.logical, key.dead, 0),
+                (key.logical_shift, key.dead_shift, shift),
+                (key.logical_alt, key.dead_alt, alt),
+                (key.logical_alt_shift, key.dead_alt_shift, shift | alt),
+            ];
+            for (logical, dead, modifiers) in levels {
+                let Some(logical) = logical else { continue };
+                let key_code = entry.platform as u16;
+                if dead {
+                    if let Some(state) = Self::dead_key_state(layout, key_code, modifiers) {
+                        dead_keys.push((logical, state));
+                    }
+                } else if modifiers & alt == 0 {
+                    base_keys.push((logical, key_code, modifiers));
                 }
             }
         }
+
+        let mut res = Vec::new();
+        for (dead_key, state) in &dead_keys {
+            for (base, key_code, modifiers) in &base_keys {
+                let result = Self::translate_with_state(layout, *key_code, *modifiers, *state);
+                // Keys that don't compose produce dead key character followed
+                // by the key character.
+                if let Some(result) = result.filter(|r| r.chars().count() == 1) {
+                    res.push(ComposeSequence {
+                        dead_key: *dead_key,
+                        base: *base,
+                        result,
+                    });
+                }
+            }
+        }
+        res
+    }
+
+    /// Enabled keyboard input sources (layouts and input methods) that can
+    /// be selected by the user. Layouts are identified by index in this list.
+    fn input_sources() -> Vec<CFType> {
+        unsafe {
+            let filter = CFDictionary::from_CFType_pairs(&[
+                (
+                    CFString::wrap_under_get_rule(kTISPropertyInputSourceCategory),
+                    CFString::wrap_under_get_rule(kTISCategoryKeyboardInputSource).as_CFType(),
+                ),
+                (
+                    CFString::wrap_under_get_rule(kTISPropertyInputSourceIsSelectCapable),
+                    CFBoolean::true_value().as_CFType(),
+                ),
+            ]);
+            let list = TISCreateInputSourceList(filter.as_concrete_TypeRef(), 0);
+            if list.is_null() {
+                return Vec::new();
+            }
+            CFArray::<CFType>::wrap_under_create_rule(list)
+                .iter()
+                .map(|source| source.clone())
+                .collect()
+        }
+    }
+
+    fn input_source(id: i64) -> Option<CFType> {
+        let index = usize::try_from(id).ok()?;
+        Self::input_sources().into_iter().nth(index)
+    }
+
+    unsafe fn source_property(source: &CFType, key: CFStringRef) -> CFObject {
+        TISGetInputSourceProperty(source.as_CFTypeRef() as CFObject, key as CFObject)
+    }
+
+    pub fn get_layouts(&self) -> NativeExtensionsResult<Vec<KeyboardLayoutInfo>> {
+        let layouts = Self::input_sources()
+            .iter()
+            .enumerate()
+            .map(|(index, source)| unsafe {
+                let name = Self::source_property(source, kTISPropertyLocalizedName);
+                let languages = Self::source_property(source, kTISPropertyInputSourceLanguages);
+                let selected = Self::source_property(source, kTISPropertyInputSourceIsSelected);
+                KeyboardLayoutInfo {
+                    id: index as i64,
+                    name: (!name.is_null())
+                        .then(|| CFString::wrap_under_get_rule(name as CFStringRef).to_string())
+                        .unwrap_or_default(),
+                    // Most used language is listed first.
+                    language: if languages.is_null() {
+                        None
+                    } else {
+                        CFArray::<CFString>::wrap_under_get_rule(languages as CFArrayRef)
+                            .get(0)
+                            .map(|l| l.to_string())
+                    },
+                    selected: !selected.is_null()
+                        && CFBoolean::wrap_under_get_rule(selected as CFBooleanRef).into(),
+                }
+            })
+            .collect();
+        Ok(layouts)
+    }
+
+    /// Input methods have no key layout of their own; for these the layout
+    /// is built from the ASCII capable layout used together with the input
+    /// method.
+    pub fn get_layout(&self, id: i64) -> NativeExtensionsResult<Option<KeyboardLayout>> {
+        let Some(source) = Self::input_source(id) else {
+            return Ok(None);
+        };
+        unsafe {
+            let layout_data = TISGetInputSourceProperty(
+                source.as_CFTypeRef() as CFObject,
+                kTISPropertyUnicodeKeyLayoutData,
+            );
+            if layout_data.is_null() {
+                Ok(Some(self.create_keyboard_layout()))
+            } else {
+                Ok(Some(self.keyboard_layout_from_data(layout_data)))
+            }
+        }
+    }
+
+    pub fn get_layout_for_keymap(
+        &self,
+        _description: KeymapDescription,
+    ) -> NativeExtensionsResult<KeyboardLayout> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
+    /// Layout change is reported through the input source change
+    /// notification.
+    pub fn select_layout(&self, id: i64) -> NativeExtensionsResult<()> {
+        let source = Self::input_source(id).ok_or_else(|| {
+            NativeExtensionsError::OtherError(format!("invalid keyboard layout id {id}"))
+        })?;
+        let status = unsafe { TISSelectInputSource(source.as_CFTypeRef() as CFObject) };
+        if status != 0 {
+            return Err(NativeExtensionsError::OtherError(format!(
+                "failed to select input source ({status})"
+            )));
+        }
+        Ok(())
     }
 
     pub fn assign_weak_self(&self, weak: Weak<PlatformKeyboardLayout>) {
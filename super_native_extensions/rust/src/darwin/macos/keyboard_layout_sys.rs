@@ -1,6 +1,11 @@
 use std::{ffi::c_void, os::raw::c_ulong};
 
-use core_foundation::{array::CFIndex, dictionary::CFDictionaryRef, string::CFStringRef};
+use core_foundation::{
+    array::{CFArrayRef, CFIndex},
+    base::{Boolean, OSStatus},
+    dictionary::CFDictionaryRef,
+    string::CFStringRef,
+};
 
 pub type CFObject = *mut c_void;
 pub type CFNotificationCenterRef = CFObject;
@@ -13,6 +18,18 @@ extern "C" {
     pub fn TISGetInputSourceProperty(input_source: CFObject, property_key: CFObject)
         -> *mut c_void;
 
+    pub static kTISPropertyInputSourceCategory: CFStringRef;
+    pub static kTISCategoryKeyboardInputSource: CFStringRef;
+    pub static kTISPropertyInputSourceIsSelectCapable: CFStringRef;
+    pub static kTISPropertyInputSourceIsSelected: CFStringRef;
+    pub static kTISPropertyLocalizedName: CFStringRef;
+    pub static kTISPropertyInputSourceLanguages: CFStringRef;
+    pub fn TISCreateInputSourceList(
+        properties: CFDictionaryRef,
+        include_all_installed: Boolean,
+    ) -> CFArrayRef;
+    pub fn TISSelectInputSource(input_source: CFObject) -> OSStatus;
+
     pub fn LMGetKbdType() -> u32;
 
     pub fn UCKeyTranslate(
@@ -62,6 +79,8 @@ extern "C" {
     );
 }
 
+#[allow(non_upper_case_globals)]
+pub const kUCKeyActionDown: u16 = 0;
 #[allow(non_upper_case_globals)]
 pub const kUCKeyActionDisplay: u16 = 3;
 #[allow(non_upper_case_globals)]
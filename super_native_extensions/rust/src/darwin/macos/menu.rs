@@ -22,11 +22,12 @@ use objc2_foundation::{
 };
 
 use crate::{
+    accelerator::{Accelerator, Modifiers},
     api_model::{
-        Activator, ImageData, Menu, MenuElement, MenuImage, ShowContextMenuRequest,
+        ImageData, Menu, MenuAction, MenuElement, MenuImage, ShowContextMenuRequest,
         ShowContextMenuResponse, WritingToolsReplacementRequest,
     },
-    error::NativeExtensionsResult,
+    error::{NativeExtensionsError, NativeExtensionsResult},
     log::OkLog,
     menu_manager::{PlatformMenuContextDelegate, PlatformMenuContextId, PlatformMenuDelegate},
 };
@@ -53,57 +54,18 @@ impl std::fmt::Debug for PlatformMenu {
 }
 
 impl PlatformMenu {
-    fn activator_label_to_string(activator: &Activator) -> String {
-        let label = activator.trigger.to_lowercase();
-        let value = match label.as_str() {
-            // these must match labels from LogicalKeyboardKey
-            "f1" => 0xF704,
-            "f2" => 0xF705,
-            "f3" => 0xF706,
-            "f4" => 0xF707,
-            "f5" => 0xF708,
-            "f6" => 0xF709,
-            "f7" => 0xF70A,
-            "f8" => 0xF70B,
-            "f9" => 0xF70C,
-            "f10" => 0xF70D,
-            "f11" => 0xF70E,
-            "f12" => 0xF70F,
-            "home" => 0xF729,
-            "end" => 0xF72B,
-            "insert" => 0xF727,
-            "delete" => 0xF728,
-            "backspace" => 0x0008,
-            "page up" => 0xF72C,
-            "page down" => 0xF72D,
-            "space" => 0x0020,
-            "tab" => 0x0009,
-            "enter" => 0x000d,
-            "arrow up" => 0xF700,
-            "arrow down" => 0xF701,
-            "arrow left" => 0xF702,
-            "arrow right" => 0xF703,
-            _ => label.chars().next().unwrap_or(0 as char) as u32,
-        };
-        let mut res = String::new();
-        if value > 0 {
-            res.push(std::char::from_u32(value).unwrap());
-        }
-        res
-    }
-
-    fn accelerator_label_to_modifier_flags(activator: &Activator) -> NSEventModifierFlags {
+    fn modifier_flags(modifiers: &Modifiers) -> NSEventModifierFlags {
         let mut res: NSUInteger = 0;
-        if activator.alt {
+        if modifiers.alt {
             res |= NSEventModifierFlags::NSEventModifierFlagOption.0;
         }
-        if activator.meta {
+        if modifiers.meta {
             res |= NSEventModifierFlags::NSEventModifierFlagCommand.0;
         }
-        if activator.control {
+        if modifiers.control {
             res |= NSEventModifierFlags::NSEventModifierFlagControl.0;
         }
-        if activator.shift {
+        if modifiers.shift {
             res |= NSEventModifierFlags::NSEventModifierFlagShift.0;
         }
 
@@ -205,13 +167,18 @@ impl PlatformMenu {
                     item.setImage(Some(&image));
                 }
 
-                if let Some(activator) = &menu_action.activator {
-                    let str = Self::activator_label_to_string(activator);
-                    if !str.is_empty() {
-                        item.setKeyEquivalent(&NSString::from_str(&str));
-                        item.setKeyEquivalentModifierMask(
-                            Self::accelerator_label_to_modifier_flags(activator),
-                        );
+                // Key equivalents are single keystroke only; chords are not
+                // shown in native menus.
+                let accelerator = menu_action
+                    .activator
+                    .as_ref()
+                    .and_then(Accelerator::from_activator)
+                    .filter(|a| !a.is_chord());
+                if let Some(accelerator) = accelerator {
+                    let stroke = accelerator.first();
+                    if let Some(key_equivalent) = stroke.key.mac_key_equivalent() {
+                        item.setKeyEquivalent(&NSString::from_str(&key_equivalent.to_string()));
+                        item.setKeyEquivalentModifierMask(Self::modifier_flags(&stroke.modifiers));
                     }
                 }
 
@@ -298,6 +265,10 @@ impl PlatformMenu {
         };
         Ok(Rc::new(Self { menu, selected }))
     }
+
+    pub fn update_elements(&self, _actions: Vec<MenuAction>) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
 }
 
 impl PlatformMenuContext {
@@ -316,6 +287,19 @@ impl PlatformMenuContext {
         })
     }
 
+    pub fn set_menu_bar(&self, _menu: Option<Rc<PlatformMenu>>) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
+    pub fn set_tray_menu(
+        &self,
+        _menu: Option<Rc<PlatformMenu>>,
+        _icon: Option<ImageData>,
+        _tooltip: Option<String>,
+    ) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
     pub fn update_preview_image(
         &self,
         _configuration_id: i64,
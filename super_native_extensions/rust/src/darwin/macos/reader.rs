@@ -30,10 +30,11 @@ use objc2_foundation::{
 };
 
 use crate::{
+    api_model::ClipboardSelection,
     error::{NativeExtensionsError, NativeExtensionsResult},
     log::OkLog,
     platform_impl::platform::common::{format_from_url, path_from_url, uti_conforms_to},
-    reader_manager::{ReadProgress, VirtualFileReader},
+    reader_manager::{ItemDataStream, ReadProgress, VirtualFileReader},
 };
 
 use super::PlatformDataProvider;
@@ -413,7 +414,10 @@ impl PlatformDataReader {
         Ok(res)
     }
 
-    pub fn new_clipboard_reader() -> NativeExtensionsResult<Rc<Self>> {
+    pub fn new_clipboard_reader(selection: ClipboardSelection) -> NativeExtensionsResult<Rc<Self>> {
+        if selection != ClipboardSelection::Clipboard {
+            return Err(NativeExtensionsError::UnsupportedOperation);
+        }
         Ok(Self::from_pasteboard(unsafe {
             NSPasteboard::generalPasteboard()
         }))
@@ -448,6 +452,15 @@ impl PlatformDataReader {
         Ok(false)
     }
 
+    pub async fn create_data_stream_for_item(
+        &self,
+        _item: i64,
+        _format: &str,
+        _progress: Arc<ReadProgress>,
+    ) -> NativeExtensionsResult<Option<Rc<dyn ItemDataStream>>> {
+        Ok(None)
+    }
+
     pub async fn create_virtual_file_reader_for_item(
         &self,
         _item: i64,
@@ -139,7 +139,7 @@ impl DataProviderManager {
             .ok_or(NativeExtensionsError::DataSourceNotFound)
     }
 
-    fn register_provider(
+    pub(crate) fn register_provider(
         &self,
         source: DataProvider,
         isolate_id: IsolateId,
@@ -18,6 +18,7 @@ use crate::{
     api_model::{DataProviderId, DragConfiguration, DragItem, DragRequest, DropOperation, Point},
     context::Context,
     data_provider_manager::{DataProviderHandle, GetDataProviderManager},
+    drag_trace::{DragTrace, DragTraceEvent, DragTraceRecorder},
     drop_manager::GetDropManager,
     error::{NativeExtensionsError, NativeExtensionsResult},
     log::{OkLog, OkLogUnexpected},
@@ -107,6 +108,7 @@ pub struct DragManager {
     invoker: Late<AsyncMethodInvoker>,
     contexts: RefCell<HashMap<PlatformDragContextId, Rc<PlatformDragContext>>>,
     next_session_id: Cell<i64>,
+    trace_recorder: Rc<DragTraceRecorder>,
 }
 
 pub trait GetDragManager {
@@ -138,6 +140,7 @@ impl DragManager {
             invoker: Late::new(),
             contexts: RefCell::new(HashMap::new()),
             next_session_id: Cell::new(0),
+            trace_recorder: Rc::new(DragTraceRecorder::new()),
         }
         .register("DragManager")
     }
@@ -162,10 +165,27 @@ impl DragManager {
         Ok(())
     }
 
+    #[cfg(test)]
+    pub fn new_context_for_tests(&self, isolate: IsolateId) {
+        self.new_context(isolate, DragContextInitRequest { engine_handle: 0 })
+            .unwrap();
+    }
+
     pub fn get_platform_drag_contexts(&self) -> Vec<Rc<PlatformDragContext>> {
         self.contexts.borrow().values().cloned().collect()
     }
 
+    pub fn get_platform_drag_context(
+        &self,
+        id: PlatformDragContextId,
+    ) -> NativeExtensionsResult<Rc<PlatformDragContext>> {
+        self.contexts
+            .borrow()
+            .get(&id)
+            .cloned()
+            .ok_or(NativeExtensionsError::PlatformContextNotFound)
+    }
+
     fn build_data_provider_map(
         &self,
         isolate: IsolateId,
@@ -299,17 +319,12 @@ impl DragManager {
         Ok(result)
     }
 
-    async fn start_drag(
+    pub(crate) async fn start_drag(
         &self,
         isolate: IsolateId,
         request: DragRequest,
     ) -> NativeExtensionsResult<DragSessionId> {
-        let context = self
-            .contexts
-            .borrow()
-            .get(&isolate)
-            .cloned()
-            .ok_or(NativeExtensionsError::PlatformContextNotFound)?;
+        let context = self.get_platform_drag_context(isolate)?;
         let session_id = DragSessionId(self.next_session_id.next_id());
         let provider_map = self.build_data_provider_map(isolate, &request.configuration.items)?;
         context
@@ -323,12 +338,7 @@ impl DragManager {
         isolate: IsolateId,
         request: LocalDataRequest,
     ) -> NativeExtensionsResult<Option<Vec<Value>>> {
-        let context = self
-            .contexts
-            .borrow()
-            .get(&isolate)
-            .cloned()
-            .ok_or(NativeExtensionsError::PlatformContextNotFound)?;
+        let context = self.get_platform_drag_context(isolate)?;
         match context.get_local_data_for_session_id(request.session_id) {
             Ok(value) => Ok(Some(value)),
             Err(NativeExtensionsError::DragSessionNotFound) => Ok(None),
@@ -346,6 +356,20 @@ impl DragManager {
     fn needs_combined_drag_image(&self) -> NativeExtensionsResult<bool> {
         Ok(PlatformDragContext::needs_combined_drag_image())
     }
+
+    /// Recorder for drag and drop sessions. Shared with `DropManager`.
+    pub fn trace_recorder(&self) -> Rc<DragTraceRecorder> {
+        self.trace_recorder.clone()
+    }
+
+    fn start_trace_recording(&self) -> NativeExtensionsResult<()> {
+        self.trace_recorder.start();
+        Ok(())
+    }
+
+    fn stop_trace_recording(&self) -> NativeExtensionsResult<DragTrace> {
+        Ok(self.trace_recorder.stop())
+    }
 }
 
 #[async_trait(?Send)]
@@ -372,6 +396,8 @@ impl AsyncMethodHandler for DragManager {
             "getLocalData" => self
                 .get_local_data(call.isolate, call.args.try_into()?)
                 .into_platform_result(),
+            "startTraceRecording" => self.start_trace_recording().into_platform_result(),
+            "stopTraceRecording" => self.stop_trace_recording().into_platform_result(),
             _ => Ok(Value::Null),
         }
     }
@@ -492,6 +518,12 @@ impl PlatformDragContextDelegate for DragManager {
             session_id: DragSessionId,
             screen_location: Point,
         }
+        self.trace_recorder.record(|time| DragTraceEvent::DragMove {
+            time,
+            context_id: id.0,
+            session_id,
+            screen_location: screen_location.clone(),
+        });
         self.invoker.call_method_sync(
             id,
             "dragSessionDidMove",
@@ -517,7 +549,12 @@ impl PlatformDragContextDelegate for DragManager {
             session_id: DragSessionId,
             drop_operation: DropOperation,
         }
-
+        self.trace_recorder.record(|time| DragTraceEvent::DragEnd {
+            time,
+            context_id: id.0,
+            session_id,
+            operation,
+        });
         self.invoker.call_method_sync(
             id,
             "dragSessionDidEnd",
@@ -531,3 +568,62 @@ impl PlatformDragContextDelegate for DragManager {
         );
     }
 }
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use irondash_message_channel::{IsolateId, Value};
+
+    use crate::{
+        api_model::DropOperation,
+        context::Context,
+        error::NativeExtensionsError,
+        test_util::{block_on, drag_request, register_text_provider},
+    };
+
+    use super::{GetDragManager, LocalDataRequest};
+
+    #[test]
+    fn test_start_drag() {
+        let context = Context::new();
+        let drag_manager = context.drag_manager();
+        drag_manager.new_context_for_tests(IsolateId(1));
+
+        let providers = [
+            register_text_provider(IsolateId(1), "Hello"),
+            register_text_provider(IsolateId(1), "World"),
+        ];
+        let request = drag_request(&providers, &[DropOperation::Copy]);
+        let session_id = block_on(drag_manager.start_drag(IsolateId(1), request)).unwrap();
+        let platform_context = drag_manager
+            .get_platform_drag_context(IsolateId(1))
+            .unwrap();
+        assert_eq!(platform_context.session_ids(), vec![session_id]);
+        assert_eq!(
+            drag_manager
+                .get_local_data(IsolateId(1), LocalDataRequest { session_id })
+                .unwrap(),
+            Some(vec![Value::I64(0), Value::I64(1)])
+        );
+
+        platform_context
+            .end_session(session_id, DropOperation::Copy)
+            .unwrap();
+        assert_eq!(
+            drag_manager
+                .get_local_data(IsolateId(1), LocalDataRequest { session_id })
+                .unwrap(),
+            None
+        );
+
+        let request = drag_request(&[999.into()], &[DropOperation::Copy]);
+        assert!(matches!(
+            block_on(drag_manager.start_drag(IsolateId(1), request)),
+            Err(NativeExtensionsError::DataSourceNotFound)
+        ));
+        let request = drag_request(&providers, &[DropOperation::Copy]);
+        assert!(matches!(
+            block_on(drag_manager.start_drag(IsolateId(2), request)),
+            Err(NativeExtensionsError::PlatformContextNotFound)
+        ));
+    }
+}
@@ -0,0 +1,256 @@
+use std::{
+    cell::{Cell, RefCell},
+    time::Instant,
+};
+
+use irondash_message_channel::{IntoValue, TryFromValue};
+
+use crate::{
+    api_model::{DropOperation, Point, Size},
+    drag_manager::DragSessionId,
+    drop_manager::{DropEvent, DropItemId, DropSessionId},
+};
+
+/// Sequence of drag and drop events recorded while tracing was enabled.
+/// Converts to and from `Value` so that it can be stored on Dart side and
+/// later replayed.
+#[derive(IntoValue, TryFromValue, Debug, Clone, Default, PartialEq)]
+#[irondash(rename_all = "camelCase")]
+pub struct DragTrace {
+    pub events: Vec<DragTraceEvent>,
+}
+
+/// Single recorded event. `time` is number of seconds since recording started,
+/// measured when the event was dispatched. `context_id` is the isolate of
+/// drag or drop context that produced the event.
+#[derive(IntoValue, TryFromValue, Debug, Clone, PartialEq)]
+#[irondash(tag = "type", rename_all = "camelCase")]
+pub enum DragTraceEvent {
+    #[irondash(rename_all = "camelCase")]
+    DragMove {
+        time: f64,
+        context_id: i64,
+        session_id: DragSessionId,
+        screen_location: Point,
+    },
+    #[irondash(rename_all = "camelCase")]
+    DragEnd {
+        time: f64,
+        context_id: i64,
+        session_id: DragSessionId,
+        operation: DropOperation,
+    },
+    /// Drop update together with the operation returned from Dart.
+    #[irondash(rename_all = "camelCase")]
+    DropUpdate {
+        time: f64,
+        context_id: i64,
+        session_id: DropSessionId,
+        location_in_view: Point,
+        allowed_operations: Vec<DropOperation>,
+        item_formats: Vec<Vec<String>>,
+        operation: DropOperation,
+    },
+    #[irondash(rename_all = "camelCase")]
+    PerformDrop {
+        time: f64,
+        context_id: i64,
+        session_id: DropSessionId,
+        location_in_view: Point,
+        allowed_operations: Vec<DropOperation>,
+        item_formats: Vec<Vec<String>>,
+        accepted_operation: Option<DropOperation>,
+        success: bool,
+    },
+    #[irondash(rename_all = "camelCase")]
+    DropLeave {
+        time: f64,
+        context_id: i64,
+        session_id: DropSessionId,
+    },
+    #[irondash(rename_all = "camelCase")]
+    DropEnded {
+        time: f64,
+        context_id: i64,
+        session_id: DropSessionId,
+    },
+    /// Item preview request and whether Dart provided a preview.
+    #[irondash(rename_all = "camelCase")]
+    ItemPreview {
+        time: f64,
+        context_id: i64,
+        session_id: DropSessionId,
+        item_id: DropItemId,
+        size: Size,
+        fade_out_delay: f64,
+        fade_out_duration: f64,
+        has_preview: bool,
+    },
+}
+
+impl DragTraceEvent {
+    pub fn time(&self) -> f64 {
+        match self {
+            DragTraceEvent::DragMove { time, .. } => *time,
+            DragTraceEvent::DragEnd { time, .. } => *time,
+            DragTraceEvent::DropUpdate { time, .. } => *time,
+            DragTraceEvent::PerformDrop { time, .. } => *time,
+            DragTraceEvent::DropLeave { time, .. } => *time,
+            DragTraceEvent::DropEnded { time, .. } => *time,
+            DragTraceEvent::ItemPreview { time, .. } => *time,
+        }
+    }
+}
+
+/// Formats of all items in the drop event.
+pub fn item_formats(event: &DropEvent) -> Vec<Vec<String>> {
+    event.items.iter().map(|i| i.formats.clone()).collect()
+}
+
+struct Recording {
+    start: Instant,
+    generation: u64,
+    /// Reserved slots are `None` until filled.
+    events: Vec<Option<DragTraceEvent>>,
+}
+
+/// Place in the trace reserved for an event when it was dispatched. Events
+/// that are only complete once Dart replies are filled in later, but still
+/// ordered by dispatch time.
+pub struct TraceSlot {
+    generation: u64,
+    index: usize,
+    time: f64,
+}
+
+/// Collects `DragTraceEvent`s while recording is in progress. Shared by
+/// `DragManager` and `DropManager`.
+pub struct DragTraceRecorder {
+    state: RefCell<Option<Recording>>,
+    generation: Cell<u64>,
+}
+
+impl DragTraceRecorder {
+    pub fn new() -> Self {
+        Self {
+            state: RefCell::new(None),
+            generation: Cell::new(0),
+        }
+    }
+
+    /// Starts new recording, discarding events recorded so far.
+    pub fn start(&self) {
+        let generation = self.generation.get() + 1;
+        self.generation.set(generation);
+        self.state.replace(Some(Recording {
+            start: Instant::now(),
+            generation,
+            events: Vec::new(),
+        }));
+    }
+
+    /// Stops recording and returns the recorded trace. Slots that were not
+    /// filled are left out.
+    pub fn stop(&self) -> DragTrace {
+        let events = self
+            .state
+            .take()
+            .map(|recording| recording.events.into_iter().flatten().collect())
+            .unwrap_or_default();
+        DragTrace { events }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.state.borrow().is_some()
+    }
+
+    /// Seconds since recording started.
+    pub fn time(&self) -> f64 {
+        self.state
+            .borrow()
+            .as_ref()
+            .map(|recording| recording.start.elapsed().as_secs_f64())
+            .unwrap_or(0.0)
+    }
+
+    /// Appends event produced by `event` if recording is in progress. The
+    /// closure is given current time.
+    pub fn record<F: FnOnce(f64) -> DragTraceEvent>(&self, event: F) {
+        let time = self.time();
+        if let Some(recording) = self.state.borrow_mut().as_mut() {
+            recording.events.push(Some(event(time)));
+        }
+    }
+
+    /// Reserves place for event at current time. Returns `None` if recording
+    /// is not in progress.
+    pub fn reserve(&self) -> Option<TraceSlot> {
+        let time = self.time();
+        let mut state = self.state.borrow_mut();
+        let recording = state.as_mut()?;
+        recording.events.push(None);
+        Some(TraceSlot {
+            generation: recording.generation,
+            index: recording.events.len() - 1,
+            time,
+        })
+    }
+
+    /// Fills reserved slot with event produced by `event`. The closure is
+    /// given the time at which the slot was reserved. Does nothing if the
+    /// recording the slot belongs to is no longer in progress.
+    pub fn fill<F: FnOnce(f64) -> DragTraceEvent>(&self, slot: TraceSlot, event: F) {
+        if let Some(recording) = self.state.borrow_mut().as_mut() {
+            if recording.generation == slot.generation {
+                recording.events[slot.index] = Some(event(slot.time));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{api_model::DropOperation, drag_manager::DragSessionId};
+
+    use super::{DragTraceEvent, DragTraceRecorder};
+
+    fn drag_end(time: f64) -> DragTraceEvent {
+        DragTraceEvent::DragEnd {
+            time,
+            context_id: 1,
+            session_id: DragSessionId::from(1),
+            operation: DropOperation::Copy,
+        }
+    }
+
+    #[test]
+    fn test_recorder() {
+        let recorder = DragTraceRecorder::new();
+        recorder.record(drag_end);
+        assert!(recorder.reserve().is_none());
+        assert!(!recorder.is_recording());
+
+        recorder.start();
+        let first = recorder.reserve().unwrap();
+        // Never filled.
+        recorder.reserve().unwrap();
+        recorder.record(|_| drag_end(10.0));
+        // Filled out of order, but keeps the place of dispatch.
+        recorder.fill(first, drag_end);
+        let trace = recorder.stop();
+        assert_eq!(trace.events.len(), 2);
+        assert!(trace.events[0].time() < 10.0);
+        assert_eq!(trace.events[1].time(), 10.0);
+
+        // Slot from previous recording is ignored.
+        recorder.start();
+        let stale = recorder.reserve().unwrap();
+        recorder.start();
+        recorder.reserve();
+        recorder.fill(stale, drag_end);
+        assert!(recorder.stop().events.is_empty());
+
+        assert!(!recorder.is_recording());
+        assert!(recorder.stop().events.is_empty());
+    }
+}
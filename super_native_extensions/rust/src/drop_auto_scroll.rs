@@ -0,0 +1,292 @@
+use std::{
+    cell::RefCell,
+    rc::{Rc, Weak},
+    time::Duration,
+};
+
+use irondash_message_channel::TryFromValue;
+use irondash_run_loop::{Handle, RunLoop};
+
+use crate::{
+    api_model::{Point, Rect},
+    drop_manager::{AutoScrollTickEvent, DropSessionId},
+    error::{NativeExtensionsError, NativeExtensionsResult},
+};
+
+/// Scrollable area of the drop target. Auto-scroll is active while the
+/// pointer is within `edge_width` of any of the edges.
+#[derive(TryFromValue, Debug, Clone)]
+#[irondash(rename_all = "camelCase")]
+pub struct AutoScrollRegion {
+    /// Passed back to Dart in auto-scroll ticks.
+    pub id: i64,
+    /// Area in view coordinates.
+    pub rect: Rect,
+    pub edge_width: f64,
+    /// Velocity in logical pixels per second when the pointer is at the edge.
+    /// Decreases linearly to zero at the inner side of the edge band.
+    pub max_velocity: f64,
+}
+
+impl AutoScrollRegion {
+    /// Returns scroll velocity for given location or `None` if the location
+    /// is not inside any of the edge bands.
+    fn velocity(&self, location: &Point) -> Option<Point> {
+        if !self.rect.contains(location) || self.edge_width <= 0.0 {
+            return None;
+        }
+        let axis = |position: f64, start: f64, length: f64| {
+            let from_start = position - start;
+            let from_end = start + length - position;
+            if from_start < self.edge_width && from_start <= from_end {
+                -self.max_velocity * (1.0 - from_start / self.edge_width)
+            } else if from_end < self.edge_width {
+                self.max_velocity * (1.0 - from_end / self.edge_width)
+            } else {
+                0.0
+            }
+        };
+        let velocity = Point {
+            x: axis(location.x, self.rect.x, self.rect.width),
+            y: axis(location.y, self.rect.y, self.rect.height),
+        };
+        if velocity.x == 0.0 && velocity.y == 0.0 {
+            None
+        } else {
+            Some(velocity)
+        }
+    }
+}
+
+#[derive(TryFromValue, Debug, Clone)]
+#[irondash(rename_all = "camelCase")]
+pub struct AutoScrollConfiguration {
+    /// Regions are checked in order, first one with non-zero velocity wins.
+    pub regions: Vec<AutoScrollRegion>,
+    /// Seconds between auto-scroll ticks.
+    pub tick_interval: f64,
+}
+
+impl AutoScrollConfiguration {
+    /// Rejects values that can't be turned into timers or velocities.
+    pub fn validate(&self) -> NativeExtensionsResult<()> {
+        let tick_valid = self.tick_interval.is_finite() && self.tick_interval > 0.0;
+        let regions_valid = self
+            .regions
+            .iter()
+            .all(|r| r.edge_width >= 0.0 && r.max_velocity.is_finite());
+        if tick_valid && regions_valid {
+            Ok(())
+        } else {
+            Err(NativeExtensionsError::InvalidData)
+        }
+    }
+
+    fn velocity(&self, location: &Point) -> Option<(i64, Point)> {
+        self.regions
+            .iter()
+            .find_map(|r| r.velocity(location).map(|v| (r.id, v)))
+    }
+}
+
+struct AutoScrollState {
+    session_id: DropSessionId,
+    location: Point,
+    timer: Option<Handle>,
+}
+
+/// Emits periodic auto-scroll ticks while the pointer stays inside an edge
+/// band. Uses a timer because platforms (i.e. GTK) don't send motion events
+/// while the pointer is not moving.
+pub struct AutoScrollTracker {
+    weak_self: Weak<Self>,
+    configuration: AutoScrollConfiguration,
+    state: RefCell<Option<AutoScrollState>>,
+    on_tick: Box<dyn Fn(AutoScrollTickEvent)>,
+}
+
+impl AutoScrollTracker {
+    pub fn new<F: Fn(AutoScrollTickEvent) + 'static>(
+        configuration: AutoScrollConfiguration,
+        on_tick: F,
+    ) -> Rc<Self> {
+        Rc::new_cyclic(|weak_self| Self {
+            weak_self: weak_self.clone(),
+            configuration,
+            state: RefCell::new(None),
+            on_tick: Box::new(on_tick),
+        })
+    }
+
+    /// Called for every drop update. Starts ticking when the location enters
+    /// an edge band and stops when it leaves.
+    pub fn update(&self, session_id: DropSessionId, location: &Point) {
+        if self.configuration.velocity(location).is_none() {
+            self.state.replace(None);
+            return;
+        }
+        let mut state = self.state.borrow_mut();
+        match state.as_mut() {
+            Some(state) if state.session_id == session_id => {
+                state.location = location.clone();
+            }
+            _ => {
+                state.replace(AutoScrollState {
+                    session_id,
+                    location: location.clone(),
+                    timer: Some(self.schedule()),
+                });
+            }
+        }
+    }
+
+    /// Stops auto-scroll ticks of given session.
+    pub fn end_session(&self, session_id: DropSessionId) {
+        let matches = self
+            .state
+            .borrow()
+            .as_ref()
+            .is_some_and(|state| state.session_id == session_id);
+        if matches {
+            self.state.replace(None);
+        }
+    }
+
+    fn schedule(&self) -> Handle {
+        let weak_self = self.weak_self.clone();
+        let interval = Duration::from_secs_f64(self.configuration.tick_interval);
+        RunLoop::current().schedule(interval, move || {
+            if let Some(this) = weak_self.upgrade() {
+                this.tick();
+            }
+        })
+    }
+
+    fn tick(&self) {
+        let event = {
+            let mut state = self.state.borrow_mut();
+            let Some(state) = state.as_mut() else {
+                return;
+            };
+            let Some((region_id, velocity)) = self.configuration.velocity(&state.location) else {
+                return;
+            };
+            state.timer = Some(self.schedule());
+            AutoScrollTickEvent {
+                session_id: state.session_id,
+                region_id,
+                velocity,
+                interval: self.configuration.tick_interval,
+            }
+        };
+        (self.on_tick)(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::{
+        api_model::{Point, Rect},
+        drop_manager::{AutoScrollTickEvent, DropSessionId},
+        test_util::{run_until, sleep},
+    };
+
+    use super::{AutoScrollConfiguration, AutoScrollRegion, AutoScrollTracker};
+
+    fn region() -> AutoScrollRegion {
+        AutoScrollRegion {
+            id: 1,
+            rect: Rect::xywh(0.0, 0.0, 100.0, 200.0),
+            edge_width: 20.0,
+            max_velocity: 100.0,
+        }
+    }
+
+    fn configuration(tick_interval: f64) -> AutoScrollConfiguration {
+        AutoScrollConfiguration {
+            regions: vec![region()],
+            tick_interval,
+        }
+    }
+
+    fn session(id: i64) -> DropSessionId {
+        id.into()
+    }
+
+    #[test]
+    fn test_velocity() {
+        let region = region();
+        let velocity = |x, y| region.velocity(&Point { x, y });
+        assert_eq!(velocity(50.0, 100.0), None);
+        assert_eq!(velocity(150.0, 100.0), None);
+        assert_eq!(velocity(0.0, 100.0), Some(Point { x: -100.0, y: 0.0 }));
+        assert_eq!(velocity(90.0, 100.0), Some(Point { x: 50.0, y: 0.0 }));
+        assert_eq!(velocity(50.0, 195.0), Some(Point { x: 0.0, y: 75.0 }));
+        assert_eq!(velocity(10.0, 10.0), Some(Point { x: -50.0, y: -50.0 }));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(configuration(0.05).validate().is_ok());
+        assert!(configuration(0.0).validate().is_err());
+        assert!(configuration(-0.05).validate().is_err());
+        assert!(configuration(f64::NAN).validate().is_err());
+        assert!(configuration(f64::INFINITY).validate().is_err());
+        let mut negative_edge = configuration(0.05);
+        negative_edge.regions[0].edge_width = -1.0;
+        assert!(negative_edge.validate().is_err());
+        let mut infinite_velocity = configuration(0.05);
+        infinite_velocity.regions[0].max_velocity = f64::INFINITY;
+        assert!(infinite_velocity.validate().is_err());
+    }
+
+    #[test]
+    fn test_ticks() {
+        let events = Rc::new(RefCell::new(Vec::<AutoScrollTickEvent>::new()));
+        let events_clone = events.clone();
+        let tracker = AutoScrollTracker::new(configuration(0.1), move |event| {
+            events_clone.borrow_mut().push(event);
+        });
+        let velocities = || {
+            events
+                .borrow()
+                .iter()
+                .map(|e| (e.session_id, e.velocity.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        // Ticks keep coming while the pointer stays in the edge band, without
+        // further updates.
+        tracker.update(session(1), &Point { x: 0.0, y: 100.0 });
+        run_until(sleep(0.05));
+        assert!(velocities().is_empty());
+        run_until(sleep(0.2));
+        let left = Point { x: -100.0, y: 0.0 };
+        assert_eq!(
+            velocities(),
+            vec![(session(1), left.clone()), (session(1), left)]
+        );
+        assert_eq!(events.borrow()[0].region_id, 1);
+        assert_eq!(events.borrow()[0].interval, 0.1);
+
+        // Moving within the band updates velocity of following ticks.
+        events.borrow_mut().clear();
+        tracker.update(session(1), &Point { x: 90.0, y: 100.0 });
+        run_until(sleep(0.1));
+        assert_eq!(velocities(), vec![(session(1), Point { x: 50.0, y: 0.0 })]);
+
+        // Leaving the band stops ticking.
+        events.borrow_mut().clear();
+        tracker.update(session(1), &Point { x: 50.0, y: 100.0 });
+        run_until(sleep(0.25));
+        assert!(velocities().is_empty());
+
+        // So does ending the session.
+        tracker.update(session(2), &Point { x: 0.0, y: 100.0 });
+        tracker.end_session(session(2));
+        run_until(sleep(0.25));
+        assert!(velocities().is_empty());
+    }
+}
@@ -0,0 +1,249 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::{Rc, Weak},
+    time::Duration,
+};
+
+use irondash_message_channel::TryFromValue;
+use irondash_run_loop::{Handle, RunLoop};
+
+use crate::{
+    api_model::Point,
+    drop_manager::{DropDwellEvent, DropSessionId},
+    error::{NativeExtensionsError, NativeExtensionsResult},
+};
+
+/// Shortest allowed interval between repeated dwell events.
+const MIN_REPEAT_INTERVAL: f64 = 0.05;
+
+/// Spring-loading configuration for a drop context.
+#[derive(TryFromValue, Debug, Clone)]
+#[irondash(rename_all = "camelCase")]
+pub struct DwellConfiguration {
+    /// Seconds the pointer must stay in place before the first dwell event.
+    pub delay: f64,
+    /// Seconds between repeated dwell events while the pointer keeps
+    /// hovering. `None` fires only once.
+    pub repeat_interval: Option<f64>,
+    /// Distance in logical pixels the pointer can move without restarting
+    /// the timer.
+    pub movement_threshold: f64,
+}
+
+impl DwellConfiguration {
+    /// Rejects values that can't be turned into timers.
+    pub fn validate(&self) -> NativeExtensionsResult<()> {
+        let delay_valid = self.delay.is_finite() && self.delay >= 0.0;
+        let repeat_valid = self
+            .repeat_interval
+            .is_none_or(|interval| interval.is_finite() && interval >= MIN_REPEAT_INTERVAL);
+        let threshold_valid = self.movement_threshold >= 0.0;
+        if delay_valid && repeat_valid && threshold_valid {
+            Ok(())
+        } else {
+            Err(NativeExtensionsError::InvalidData)
+        }
+    }
+}
+
+struct DwellState {
+    session_id: DropSessionId,
+    anchor: Point,
+    count: Cell<i64>,
+    timer: RefCell<Option<Handle>>,
+}
+
+impl DwellState {
+    /// Returns whether the pointer is still dwelling at the anchor.
+    fn is_dwelling(&self, session_id: DropSessionId, location: &Point, threshold: f64) -> bool {
+        let dx = location.x - self.anchor.x;
+        let dy = location.y - self.anchor.y;
+        self.session_id == session_id && (dx * dx + dy * dy).sqrt() <= threshold
+    }
+}
+
+/// Tracks pointer position of drop sessions and fires dwell events when the
+/// pointer stays in place. Timers run on the run loop, independently of
+/// how often the platform sends drop updates.
+pub struct DwellTracker {
+    weak_self: Weak<Self>,
+    configuration: DwellConfiguration,
+    state: RefCell<Option<DwellState>>,
+    on_dwell: Box<dyn Fn(DropDwellEvent)>,
+}
+
+impl DwellTracker {
+    pub fn new<F: Fn(DropDwellEvent) + 'static>(
+        configuration: DwellConfiguration,
+        on_dwell: F,
+    ) -> Rc<Self> {
+        Rc::new_cyclic(|weak_self| Self {
+            weak_self: weak_self.clone(),
+            configuration,
+            state: RefCell::new(None),
+            on_dwell: Box::new(on_dwell),
+        })
+    }
+
+    /// Called for every drop update. Restarts the timer if pointer moved
+    /// beyond the threshold or the session has changed.
+    pub fn update(&self, session_id: DropSessionId, location: &Point) {
+        let dwelling = self.state.borrow().as_ref().is_some_and(|state| {
+            state.is_dwelling(session_id, location, self.configuration.movement_threshold)
+        });
+        if !dwelling {
+            self.state.replace(Some(DwellState {
+                session_id,
+                anchor: location.clone(),
+                count: Cell::new(0),
+                timer: RefCell::new(None),
+            }));
+            self.schedule(self.configuration.delay);
+        }
+    }
+
+    /// Cancels pending dwell events of given session.
+    pub fn end_session(&self, session_id: DropSessionId) {
+        let matches = self
+            .state
+            .borrow()
+            .as_ref()
+            .is_some_and(|state| state.session_id == session_id);
+        if matches {
+            self.state.replace(None);
+        }
+    }
+
+    fn schedule(&self, delay: f64) {
+        if let Some(state) = self.state.borrow().as_ref() {
+            let weak_self = self.weak_self.clone();
+            let timer = RunLoop::current().schedule(Duration::from_secs_f64(delay), move || {
+                if let Some(this) = weak_self.upgrade() {
+                    this.fire();
+                }
+            });
+            state.timer.replace(Some(timer));
+        }
+    }
+
+    fn fire(&self) {
+        let event = match self.state.borrow().as_ref() {
+            Some(state) => {
+                state.count.set(state.count.get() + 1);
+                DropDwellEvent {
+                    session_id: state.session_id,
+                    location_in_view: state.anchor.clone(),
+                    count: state.count.get(),
+                }
+            }
+            None => return,
+        };
+        if let Some(repeat_interval) = self.configuration.repeat_interval {
+            self.schedule(repeat_interval);
+        }
+        (self.on_dwell)(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::{Cell, RefCell},
+        rc::Rc,
+    };
+
+    use crate::{
+        api_model::Point,
+        drop_manager::{DropDwellEvent, DropSessionId},
+        test_util::{run_until, sleep},
+    };
+
+    use super::{DwellConfiguration, DwellState, DwellTracker};
+
+    fn point(x: f64, y: f64) -> Point {
+        Point { x, y }
+    }
+
+    fn session(id: i64) -> DropSessionId {
+        id.into()
+    }
+
+    fn configuration(delay: f64, repeat_interval: Option<f64>) -> DwellConfiguration {
+        DwellConfiguration {
+            delay,
+            repeat_interval,
+            movement_threshold: 5.0,
+        }
+    }
+
+    #[test]
+    fn test_is_dwelling() {
+        let state = DwellState {
+            session_id: 1i64.into(),
+            anchor: Point { x: 10.0, y: 10.0 },
+            count: Cell::new(0),
+            timer: RefCell::new(None),
+        };
+        assert!(state.is_dwelling(1i64.into(), &Point { x: 13.0, y: 14.0 }, 5.0));
+        assert!(!state.is_dwelling(1i64.into(), &Point { x: 14.0, y: 14.0 }, 5.0));
+        assert!(!state.is_dwelling(2i64.into(), &Point { x: 10.0, y: 10.0 }, 5.0));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(configuration(0.0, None).validate().is_ok());
+        assert!(configuration(0.5, Some(0.5)).validate().is_ok());
+        assert!(configuration(-1.0, None).validate().is_err());
+        assert!(configuration(f64::NAN, None).validate().is_err());
+        assert!(configuration(f64::INFINITY, None).validate().is_err());
+        assert!(configuration(0.5, Some(0.0)).validate().is_err());
+        assert!(configuration(0.5, Some(f64::INFINITY)).validate().is_err());
+        let mut negative_threshold = configuration(0.5, None);
+        negative_threshold.movement_threshold = -1.0;
+        assert!(negative_threshold.validate().is_err());
+    }
+
+    #[test]
+    fn test_dwell_timers() {
+        let events = Rc::new(RefCell::new(Vec::<DropDwellEvent>::new()));
+        let events_clone = events.clone();
+        let tracker = DwellTracker::new(configuration(0.05, Some(0.2)), move |event| {
+            events_clone.borrow_mut().push(event);
+        });
+        let counts = || {
+            events
+                .borrow()
+                .iter()
+                .map(|e| (e.session_id, e.count))
+                .collect::<Vec<_>>()
+        };
+
+        // First event after the delay, then repeats while the pointer stays
+        // within the threshold.
+        tracker.update(1i64.into(), &point(10.0, 10.0));
+        run_until(sleep(0.1));
+        assert_eq!(counts(), vec![(session(1), 1)]);
+        tracker.update(1i64.into(), &point(12.0, 12.0));
+        run_until(sleep(0.2));
+        assert_eq!(counts(), vec![(session(1), 1), (session(1), 2)]);
+        assert_eq!(events.borrow()[1].location_in_view, point(10.0, 10.0));
+
+        // Moving beyond the threshold restarts the count.
+        events.borrow_mut().clear();
+        tracker.update(1i64.into(), &point(30.0, 10.0));
+        run_until(sleep(0.1));
+        assert_eq!(counts(), vec![(session(1), 1)]);
+
+        // So does a new session at the same location.
+        events.borrow_mut().clear();
+        tracker.update(2i64.into(), &point(30.0, 10.0));
+        run_until(sleep(0.1));
+        assert_eq!(counts(), vec![(session(2), 1)]);
+
+        // Ended session doesn't fire anymore.
+        events.borrow_mut().clear();
+        tracker.end_session(2i64.into());
+        run_until(sleep(0.3));
+        assert!(counts().is_empty());
+    }
+}
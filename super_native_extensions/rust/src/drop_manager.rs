@@ -17,6 +17,10 @@ use crate::{
     api_model::{DropOperation, ImageData, Point, Rect, Size},
     context::Context,
     drag_manager::{GetDragManager, PlatformDragContextId},
+    drag_trace::{item_formats, DragTraceEvent, DragTraceRecorder},
+    drop_auto_scroll::{AutoScrollConfiguration, AutoScrollTracker},
+    drop_dwell::{DwellConfiguration, DwellTracker},
+    drop_policy::DropPolicy,
     error::{NativeExtensionsError, NativeExtensionsResult},
     log::{OkLog, OkLogUnexpected},
     platform_impl::platform::{PlatformDataReader, PlatformDragContext, PlatformDropContext},
@@ -31,6 +35,9 @@ pub struct DropManager {
     weak_self: Late<Weak<Self>>,
     invoker: Late<AsyncMethodInvoker>,
     contexts: RefCell<HashMap<PlatformDropContextId, Rc<PlatformDropContext>>>,
+    policies: RefCell<HashMap<PlatformDropContextId, DropPolicy>>,
+    dwell_trackers: RefCell<HashMap<PlatformDropContextId, Rc<DwellTracker>>>,
+    auto_scroll_trackers: RefCell<HashMap<PlatformDropContextId, Rc<AutoScrollTracker>>>,
 }
 
 pub trait GetDropManager {
@@ -55,6 +62,24 @@ struct RegisterDropFormatsRequest {
     formats: Vec<String>,
 }
 
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct SetDropPolicyRequest {
+    policy: Option<DropPolicy>,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct SetDwellConfigurationRequest {
+    configuration: Option<DwellConfiguration>,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct SetAutoScrollConfigurationRequest {
+    configuration: Option<AutoScrollConfiguration>,
+}
+
 #[derive(Debug, TryFromValue, IntoValue, Clone, Copy, PartialEq, Hash, Eq)]
 pub struct DropSessionId(i64);
 
@@ -104,6 +129,30 @@ pub struct BaseDropEvent {
     pub session_id: DropSessionId,
 }
 
+/// Sent when pointer stays in place during drag for configured time.
+#[derive(IntoValue, Debug, Clone, PartialEq)]
+#[irondash(rename_all = "camelCase")]
+pub struct DropDwellEvent {
+    pub session_id: DropSessionId,
+    /// Location where the pointer started dwelling.
+    pub location_in_view: Point,
+    /// Starts at 1 and increases with every repeated event.
+    pub count: i64,
+}
+
+/// Sent periodically while the pointer is inside edge band of an auto-scroll
+/// region, regardless of whether it moves.
+#[derive(IntoValue, Debug, Clone, PartialEq)]
+#[irondash(rename_all = "camelCase")]
+pub struct AutoScrollTickEvent {
+    pub session_id: DropSessionId,
+    pub region_id: i64,
+    /// Logical pixels per second, negative towards top and left edges.
+    pub velocity: Point,
+    /// Seconds since the previous tick.
+    pub interval: f64,
+}
+
 #[derive(IntoValue)]
 #[irondash(rename_all = "camelCase")]
 pub struct ItemPreviewRequest {
@@ -169,6 +218,9 @@ impl DropManager {
             weak_self: Late::new(),
             invoker: Late::new(),
             contexts: RefCell::new(HashMap::new()),
+            policies: RefCell::new(HashMap::new()),
+            dwell_trackers: RefCell::new(HashMap::new()),
+            auto_scroll_trackers: RefCell::new(HashMap::new()),
         }
         .register("DropManager")
     }
@@ -178,15 +230,107 @@ impl DropManager {
         isolate: IsolateId,
         request: RegisterDropFormatsRequest,
     ) -> NativeExtensionsResult<()> {
-        let context = self
-            .contexts
-            .borrow()
-            .get(&isolate)
-            .cloned()
-            .ok_or(NativeExtensionsError::PlatformContextNotFound)?;
+        let context = self.get_platform_drop_context(isolate)?;
         context.register_drop_formats(&request.formats)
     }
 
+    fn set_drop_policy(
+        &self,
+        isolate: IsolateId,
+        request: SetDropPolicyRequest,
+    ) -> NativeExtensionsResult<()> {
+        match request.policy {
+            Some(policy) => self.policies.borrow_mut().insert(isolate, policy),
+            None => self.policies.borrow_mut().remove(&isolate),
+        };
+        Ok(())
+    }
+
+    fn set_dwell_configuration(
+        &self,
+        isolate: IsolateId,
+        request: SetDwellConfigurationRequest,
+    ) -> NativeExtensionsResult<()> {
+        match request.configuration {
+            Some(configuration) => {
+                configuration.validate()?;
+                let weak_self = self.weak_self.clone();
+                let tracker = DwellTracker::new(configuration, move |event| {
+                    if let Some(this) = weak_self.upgrade() {
+                        this.invoker
+                            .call_method_sync(isolate, "onDropDwell", event, |r| {
+                                r.ok_log();
+                            });
+                    }
+                });
+                self.dwell_trackers.borrow_mut().insert(isolate, tracker);
+            }
+            None => {
+                self.dwell_trackers.borrow_mut().remove(&isolate);
+            }
+        }
+        Ok(())
+    }
+
+    fn dwell_tracker(&self, id: PlatformDropContextId) -> Option<Rc<DwellTracker>> {
+        self.dwell_trackers.borrow().get(&id).cloned()
+    }
+
+    fn set_auto_scroll_configuration(
+        &self,
+        isolate: IsolateId,
+        request: SetAutoScrollConfigurationRequest,
+    ) -> NativeExtensionsResult<()> {
+        match request.configuration {
+            Some(configuration) => {
+                configuration.validate()?;
+                let weak_self = self.weak_self.clone();
+                let tracker = AutoScrollTracker::new(configuration, move |event| {
+                    if let Some(this) = weak_self.upgrade() {
+                        this.invoker
+                            .call_method_sync(isolate, "onAutoScrollTick", event, |r| {
+                                r.ok_log();
+                            });
+                    }
+                });
+                self.auto_scroll_trackers
+                    .borrow_mut()
+                    .insert(isolate, tracker);
+            }
+            None => {
+                self.auto_scroll_trackers.borrow_mut().remove(&isolate);
+            }
+        }
+        Ok(())
+    }
+
+    fn auto_scroll_tracker(&self, id: PlatformDropContextId) -> Option<Rc<AutoScrollTracker>> {
+        self.auto_scroll_trackers.borrow().get(&id).cloned()
+    }
+
+    /// Stops dwell and auto-scroll timers of given session.
+    fn end_tracked_session(&self, id: PlatformDropContextId, session_id: DropSessionId) {
+        if let Some(tracker) = self.dwell_tracker(id) {
+            tracker.end_session(session_id);
+        }
+        if let Some(tracker) = self.auto_scroll_tracker(id) {
+            tracker.end_session(session_id);
+        }
+    }
+
+    /// Returns operation for the event if it can be decided by drop policy
+    /// registered for given context.
+    fn evaluate_drop_policy(
+        &self,
+        id: PlatformDropContextId,
+        event: &DropEvent,
+    ) -> Option<DropOperation> {
+        let policies = self.policies.borrow();
+        let policy = policies.get(&id)?;
+        let modifiers = self.get_platform_drop_context(id).ok()?.current_modifiers();
+        policy.evaluate(event, &modifiers)
+    }
+
     fn new_context(
         &self,
         isolate: IsolateId,
@@ -207,10 +351,42 @@ impl DropManager {
         Ok(())
     }
 
+    fn trace_recorder(&self) -> Rc<DragTraceRecorder> {
+        Context::get().drag_manager().trace_recorder()
+    }
+
+    #[cfg(test)]
+    pub fn new_context_for_tests(&self, isolate: IsolateId) {
+        self.new_context(isolate, DropContextInitRequest { engine_handle: 0 })
+            .unwrap();
+    }
+
+    #[cfg(test)]
+    pub fn set_drop_policy_for_tests(&self, isolate: IsolateId, policy: DropPolicy) {
+        self.set_drop_policy(
+            isolate,
+            SetDropPolicyRequest {
+                policy: Some(policy),
+            },
+        )
+        .unwrap();
+    }
+
     pub fn get_platform_drop_contexts(&self) -> Vec<Rc<PlatformDropContext>> {
         self.contexts.borrow().values().cloned().collect()
     }
 
+    pub fn get_platform_drop_context(
+        &self,
+        id: PlatformDropContextId,
+    ) -> NativeExtensionsResult<Rc<PlatformDropContext>> {
+        self.contexts
+            .borrow()
+            .get(&id)
+            .cloned()
+            .ok_or(NativeExtensionsError::PlatformContextNotFound)
+    }
+
     async fn get_preview_for_item(
         &self,
         id: PlatformDropContextId,
@@ -243,12 +419,24 @@ impl AsyncMethodHandler for DropManager {
             "registerDropFormats" => self
                 .register_drop_formats(call.isolate, call.args.try_into()?)
                 .into_platform_result(),
+            "setDropPolicy" => self
+                .set_drop_policy(call.isolate, call.args.try_into()?)
+                .into_platform_result(),
+            "setDwellConfiguration" => self
+                .set_dwell_configuration(call.isolate, call.args.try_into()?)
+                .into_platform_result(),
+            "setAutoScrollConfiguration" => self
+                .set_auto_scroll_configuration(call.isolate, call.args.try_into()?)
+                .into_platform_result(),
             _ => Ok(Value::Null),
         }
     }
 
     fn on_isolate_destroyed(&self, isolate: IsolateId) {
         self.contexts.borrow_mut().remove(&isolate);
+        self.policies.borrow_mut().remove(&isolate);
+        self.dwell_trackers.borrow_mut().remove(&isolate);
+        self.auto_scroll_trackers.borrow_mut().remove(&isolate);
     }
 }
 
@@ -260,11 +448,58 @@ impl PlatformDropContextDelegate for DropManager {
     fn send_drop_update(
         &self,
         id: PlatformDropContextId,
-        event: DropEvent,
+        mut event: DropEvent,
         res: Box<dyn FnOnce(Result<DropOperation, MethodCallError>)>,
     ) {
+        if let Some(tracker) = self.dwell_tracker(id) {
+            tracker.update(event.session_id, &event.location_in_view);
+        }
+        if let Some(tracker) = self.auto_scroll_tracker(id) {
+            tracker.update(event.session_id, &event.location_in_view);
+        }
+        let policy_operation = self.evaluate_drop_policy(id, &event);
+        let res: Box<dyn FnOnce(Result<DropOperation, MethodCallError>)> = match policy_operation {
+            Some(operation) => {
+                // Reply right away so that the platform can update drag feedback
+                // without waiting for Dart. Dart still receives the update with
+                // accepted operation set, but its answer is ignored.
+                res(Ok(operation));
+                event.accepted_operation = Some(operation);
+                Box::new(|r| {
+                    r.ok_log();
+                })
+            }
+            None => res,
+        };
+        let recorder = self.trace_recorder();
+        // Drop updates are sent on every pointer move, don't copy the event
+        // unless it is going to be recorded. The slot is reserved now so that
+        // the event keeps its place even if Dart replies late.
+        let Some(slot) = recorder.reserve() else {
+            self.invoker
+                .call_method_sync_cv(id, "onDropUpdate", event, res);
+            return;
+        };
+        let session_id = event.session_id;
+        let location_in_view = event.location_in_view.clone();
+        let allowed_operations = event.allowed_operations.clone();
+        let item_formats = item_formats(&event);
         self.invoker
-            .call_method_sync_cv(id, "onDropUpdate", event, res);
+            .call_method_sync_cv(id, "onDropUpdate", event, move |r| {
+                let operation = policy_operation
+                    .or_else(|| r.as_ref().ok().copied())
+                    .unwrap_or(DropOperation::None);
+                recorder.fill(slot, |time| DragTraceEvent::DropUpdate {
+                    time,
+                    context_id: id.0,
+                    session_id,
+                    location_in_view,
+                    allowed_operations,
+                    item_formats,
+                    operation,
+                });
+                res(r)
+            });
     }
 
     fn send_perform_drop(
@@ -273,8 +508,28 @@ impl PlatformDropContextDelegate for DropManager {
         event: DropEvent,
         res: Box<dyn FnOnce(Result<(), MethodCallError>)>,
     ) {
+        self.end_tracked_session(id, event.session_id);
+        let recorder = self.trace_recorder();
+        let slot = recorder.reserve();
+        let session_id = event.session_id;
+        let location_in_view = event.location_in_view.clone();
+        let allowed_operations = event.allowed_operations.clone();
+        let accepted_operation = event.accepted_operation;
+        let item_formats = item_formats(&event);
         self.invoker
-            .call_method_sync_cv(id, "onPerformDrop", event, |r| {
+            .call_method_sync_cv(id, "onPerformDrop", event, move |r| {
+                if let Some(slot) = slot {
+                    recorder.fill(slot, |time| DragTraceEvent::PerformDrop {
+                        time,
+                        context_id: id.0,
+                        session_id,
+                        location_in_view,
+                        allowed_operations,
+                        item_formats,
+                        accepted_operation,
+                        success: r.is_ok(),
+                    });
+                }
                 // Delay result callback one run loop turn. This is necessary because
                 // AsyncMethodHandler::on_message executes messages using RunLoop::spawn,
                 // whcih means that calls such as PlatformReader::get_data_for_item are delayed
@@ -287,6 +542,13 @@ impl PlatformDropContextDelegate for DropManager {
     }
 
     fn send_drop_leave(&self, id: PlatformDropContextId, event: BaseDropEvent) {
+        self.end_tracked_session(id, event.session_id);
+        self.trace_recorder()
+            .record(|time| DragTraceEvent::DropLeave {
+                time,
+                context_id: id.0,
+                session_id: event.session_id,
+            });
         self.invoker
             .call_method_sync(id, "onDropLeave", event, |r| {
                 r.ok_log();
@@ -294,6 +556,13 @@ impl PlatformDropContextDelegate for DropManager {
     }
 
     fn send_drop_ended(&self, id: PlatformDropContextId, event: BaseDropEvent) {
+        self.end_tracked_session(id, event.session_id);
+        self.trace_recorder()
+            .record(|time| DragTraceEvent::DropEnded {
+                time,
+                context_id: id.0,
+                session_id: event.session_id,
+            });
         self.invoker
             .call_method_sync(id, "onDropEnded", event, |r| {
                 r.ok_log();
@@ -318,13 +587,34 @@ impl PlatformDropContextDelegate for DropManager {
         let res = Arc::new(Promise::new());
         let res_clone = res.clone();
         let weak_self = self.weak_self.clone();
+        let recorder = self.trace_recorder();
+        let slot = recorder.reserve();
         spawn(async move {
             let this = weak_self.upgrade();
             if let Some(this) = this {
+                let session_id = request.session_id;
+                let item_id = request.item_id;
+                let size = request.size.clone();
+                let fade_out_delay = request.fade_out_delay;
+                let fade_out_duration = request.fade_out_duration;
                 let draggable = this
                     .get_preview_for_item(id, request)
                     .await
                     .ok_log_unexpected();
+                if let Some(slot) = slot {
+                    recorder.fill(slot, |time| DragTraceEvent::ItemPreview {
+                        time,
+                        context_id: id.0,
+                        session_id,
+                        item_id,
+                        size,
+                        fade_out_delay,
+                        fade_out_duration,
+                        has_preview: draggable
+                            .as_ref()
+                            .is_some_and(|response| response.preview.is_some()),
+                    });
+                }
                 match draggable {
                     Some(draggable) => res_clone.set(PromiseResult::Ok { value: draggable }),
                     None => res_clone.set(PromiseResult::Cancelled),
@@ -336,3 +626,173 @@ impl PlatformDropContextDelegate for DropManager {
         res
     }
 }
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use irondash_message_channel::{AsyncMethodHandler, IsolateId};
+    use irondash_run_loop::{util::FutureCompleter, RunLoop};
+
+    use crate::{
+        api_model::{DropOperation, Point, Size},
+        context::Context,
+        drag_manager::GetDragManager,
+        drag_trace::DragTraceEvent,
+        drop_policy::{DropFormatPolicy, DropPolicy, DropPolicyAction, DropPolicyRegion},
+        error::NativeExtensionsError,
+        test_util::{drag_request, register_text_provider, run_until},
+    };
+
+    use super::{GetDropManager, ItemPreviewRequest, PlatformDropContextDelegate};
+
+    #[test]
+    fn test_drop_session() {
+        let context = Context::new();
+        let drag_manager = context.drag_manager();
+        let drop_manager = context.drop_manager();
+        drag_manager.new_context_for_tests(IsolateId(1));
+        drop_manager.new_context_for_tests(IsolateId(2));
+
+        let provider = register_text_provider(IsolateId(1), "Hello");
+        let session_id = run_until(async move {
+            let request = drag_request(&[provider], &[DropOperation::Copy]);
+            Context::get()
+                .drag_manager()
+                .start_drag(IsolateId(1), request)
+                .await
+                .unwrap()
+        });
+        let drag_context = drag_manager
+            .get_platform_drag_context(IsolateId(1))
+            .unwrap();
+        let drop_context = drop_manager
+            .get_platform_drop_context(IsolateId(2))
+            .unwrap();
+
+        // There is no Dart isolate to answer the drop update, which counts
+        // as rejecting the drop.
+        drop_context
+            .drag_over(&drag_context, session_id, Point { x: 1.0, y: 1.0 })
+            .unwrap();
+        assert_eq!(drop_context.last_operation(), Some(DropOperation::None));
+        let drop_context_clone = drop_context.clone();
+        let operation = run_until(async move {
+            drop_context_clone
+                .perform_drop(Point { x: 1.0, y: 1.0 })
+                .await
+                .unwrap()
+        });
+        assert_eq!(operation, DropOperation::None);
+        assert_eq!(drop_context.last_operation(), None);
+
+        drop_manager.on_isolate_destroyed(IsolateId(2));
+        assert!(matches!(
+            drop_manager.get_platform_drop_context(IsolateId(2)),
+            Err(NativeExtensionsError::PlatformContextNotFound)
+        ));
+    }
+
+    #[test]
+    fn test_trace_recording() {
+        let context = Context::new();
+        let drag_manager = context.drag_manager();
+        let drop_manager = context.drop_manager();
+        drag_manager.new_context_for_tests(IsolateId(1));
+        drop_manager.new_context_for_tests(IsolateId(2));
+        drop_manager.set_drop_policy_for_tests(
+            IsolateId(2),
+            DropPolicy {
+                regions: vec![DropPolicyRegion {
+                    rect: None,
+                    formats: vec![DropFormatPolicy {
+                        format: "text/plain".into(),
+                        action: DropPolicyAction::Copy,
+                    }],
+                    modifier_overrides: Vec::new(),
+                    fallback: DropPolicyAction::Reject,
+                }],
+            },
+        );
+
+        let provider = register_text_provider(IsolateId(1), "Hello");
+        let session_id = run_until(async move {
+            let request = drag_request(&[provider], &[DropOperation::Copy]);
+            Context::get()
+                .drag_manager()
+                .start_drag(IsolateId(1), request)
+                .await
+                .unwrap()
+        });
+        let drag_context = drag_manager
+            .get_platform_drag_context(IsolateId(1))
+            .unwrap();
+        let drop_context = drop_manager
+            .get_platform_drop_context(IsolateId(2))
+            .unwrap();
+
+        let recorder = drag_manager.trace_recorder();
+        recorder.start();
+        drop_context
+            .drag_over(&drag_context, session_id, Point { x: 1.0, y: 1.0 })
+            .unwrap();
+        let drop_session_id = match &recorder.stop().events[..] {
+            [DragTraceEvent::DropUpdate { session_id, .. }] => *session_id,
+            events => panic!("unexpected events {events:?}"),
+        };
+
+        recorder.start();
+        // Preview is requested before the drop update, but only resolved
+        // after the update has been answered.
+        let preview = PlatformDropContextDelegate::get_preview_for_item(
+            &*drop_manager,
+            IsolateId(2),
+            ItemPreviewRequest {
+                session_id: drop_session_id,
+                item_id: 0.into(),
+                size: Size {
+                    width: 10.0,
+                    height: 10.0,
+                },
+                fade_out_delay: 0.0,
+                fade_out_duration: 0.0,
+            },
+        );
+        drop_context
+            .drag_over(&drag_context, session_id, Point { x: 2.0, y: 2.0 })
+            .unwrap();
+        let drop_context_clone = drop_context.clone();
+        run_until(async move {
+            while preview.try_take().is_none() {
+                let (future, completer) = FutureCompleter::new();
+                RunLoop::current()
+                    .schedule_next(move || completer.complete(()))
+                    .detach();
+                future.await;
+            }
+            drop_context_clone
+                .perform_drop(Point { x: 2.0, y: 2.0 })
+                .await
+                .unwrap()
+        });
+        let events = recorder.stop().events;
+        assert!(events.windows(2).all(|e| e[0].time() <= e[1].time()));
+        assert!(matches!(
+            &events[..],
+            [
+                DragTraceEvent::ItemPreview {
+                    has_preview: false,
+                    ..
+                },
+                DragTraceEvent::DropUpdate {
+                    operation: DropOperation::Copy,
+                    ..
+                },
+                DragTraceEvent::PerformDrop {
+                    accepted_operation: Some(DropOperation::Copy),
+                    success: false,
+                    ..
+                },
+                DragTraceEvent::DropEnded { .. },
+            ]
+        ));
+    }
+}
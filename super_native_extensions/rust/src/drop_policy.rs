@@ -0,0 +1,234 @@
+use irondash_message_channel::TryFromValue;
+
+use crate::{
+    accelerator::Modifiers,
+    api_model::{DropOperation, Rect},
+    drop_manager::DropEvent,
+};
+
+/// Result of drop policy evaluation.
+#[derive(TryFromValue, Debug, Clone, Copy, PartialEq, Eq)]
+#[irondash(rename_all = "camelCase")]
+pub enum DropPolicyAction {
+    /// Let Dart decide the operation in `onDropUpdate`.
+    Ask,
+    Reject,
+    Copy,
+    Move,
+    Link,
+}
+
+impl DropPolicyAction {
+    /// Returns operation for the action or `None` if Dart should be asked.
+    fn operation(self) -> Option<DropOperation> {
+        match self {
+            DropPolicyAction::Ask => None,
+            DropPolicyAction::Reject => Some(DropOperation::None),
+            DropPolicyAction::Copy => Some(DropOperation::Copy),
+            DropPolicyAction::Move => Some(DropOperation::Move),
+            DropPolicyAction::Link => Some(DropOperation::Link),
+        }
+    }
+}
+
+#[derive(TryFromValue, Debug, Clone)]
+#[irondash(rename_all = "camelCase")]
+pub struct DropFormatPolicy {
+    pub format: String,
+    pub action: DropPolicyAction,
+}
+
+/// Action used instead of the format action while exactly these modifiers
+/// are pressed.
+#[derive(TryFromValue, Debug, Clone)]
+#[irondash(rename_all = "camelCase")]
+pub struct DropModifierOverride {
+    pub alt: bool,
+    pub meta: bool,
+    pub shift: bool,
+    pub control: bool,
+    pub action: DropPolicyAction,
+}
+
+impl DropModifierOverride {
+    fn modifiers(&self) -> Modifiers {
+        Modifiers {
+            alt: self.alt,
+            meta: self.meta,
+            shift: self.shift,
+            control: self.control,
+        }
+    }
+}
+
+#[derive(TryFromValue, Debug, Clone)]
+#[irondash(rename_all = "camelCase")]
+pub struct DropPolicyRegion {
+    /// Area in view coordinates. `None` covers the whole view.
+    pub rect: Option<Rect>,
+    /// Accepted formats in order of preference.
+    pub formats: Vec<DropFormatPolicy>,
+    /// Only consulted when one of the formats is accepted.
+    pub modifier_overrides: Vec<DropModifierOverride>,
+    /// Action used when no item provides any of the accepted formats.
+    pub fallback: DropPolicyAction,
+}
+
+/// Declarative rules for deciding drop operation without a round trip to
+/// Dart. Regions are checked in order and the first one containing the drop
+/// location applies. Outside of all regions Dart is asked.
+#[derive(TryFromValue, Debug, Clone)]
+#[irondash(rename_all = "camelCase")]
+pub struct DropPolicy {
+    pub regions: Vec<DropPolicyRegion>,
+}
+
+impl DropPolicy {
+    /// Returns operation for the drop event or `None` if Dart should decide.
+    /// Accepted formats provided by the items are tried in order of preference
+    /// until one resolves to an operation allowed by the drag source. If none
+    /// does, Dart is asked.
+    pub fn evaluate(&self, event: &DropEvent, modifiers: &Modifiers) -> Option<DropOperation> {
+        let region = self.regions.iter().find(|r| {
+            r.rect
+                .as_ref()
+                .map(|rect| rect.contains(&event.location_in_view))
+                .unwrap_or(true)
+        })?;
+        let allowed = |action: DropPolicyAction| {
+            action.operation().filter(|operation| {
+                *operation == DropOperation::None || event.allowed_operations.contains(operation)
+            })
+        };
+        let override_action = region
+            .modifier_overrides
+            .iter()
+            .find(|o| o.modifiers() == *modifiers)
+            .map(|o| o.action);
+        let mut formats = region
+            .formats
+            .iter()
+            .filter(|f| {
+                event
+                    .items
+                    .iter()
+                    .any(|item| item.formats.contains(&f.format))
+            })
+            .peekable();
+        if formats.peek().is_none() {
+            return allowed(region.fallback);
+        }
+        for format in formats {
+            let action = override_action.unwrap_or(format.action);
+            if action == DropPolicyAction::Ask {
+                return None;
+            }
+            if let Some(operation) = allowed(action) {
+                return Some(operation);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use irondash_message_channel::Value;
+
+    use crate::{
+        accelerator::Modifiers,
+        api_model::{DropOperation, Point, Rect},
+        drop_manager::{DropEvent, DropItem},
+    };
+
+    use super::{
+        DropFormatPolicy, DropModifierOverride, DropPolicy, DropPolicyAction, DropPolicyRegion,
+    };
+
+    fn event(x: f64, formats: &[&str]) -> DropEvent {
+        DropEvent {
+            session_id: 0i64.into(),
+            location_in_view: Point { x, y: 5.0 },
+            allowed_operations: vec![DropOperation::Copy, DropOperation::Link],
+            accepted_operation: None,
+            items: vec![DropItem {
+                item_id: 0.into(),
+                formats: formats.iter().map(|f| f.to_string()).collect(),
+                local_data: Value::Null,
+            }],
+            reader: None,
+        }
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let format = |format: &str, action| DropFormatPolicy {
+            format: format.into(),
+            action,
+        };
+        let policy = DropPolicy {
+            regions: vec![
+                DropPolicyRegion {
+                    rect: Some(Rect::xywh(0.0, 0.0, 10.0, 10.0)),
+                    formats: vec![
+                        format("text/uri-list", DropPolicyAction::Move),
+                        format("text/plain", DropPolicyAction::Copy),
+                    ],
+                    modifier_overrides: vec![DropModifierOverride {
+                        alt: true,
+                        meta: false,
+                        shift: false,
+                        control: false,
+                        action: DropPolicyAction::Link,
+                    }],
+                    fallback: DropPolicyAction::Reject,
+                },
+                DropPolicyRegion {
+                    rect: Some(Rect::xywh(10.0, 0.0, 10.0, 10.0)),
+                    formats: vec![format("text/plain", DropPolicyAction::Ask)],
+                    modifier_overrides: Vec::new(),
+                    fallback: DropPolicyAction::Reject,
+                },
+            ],
+        };
+        let none = Modifiers::default();
+        let alt = Modifiers {
+            alt: true,
+            ..Default::default()
+        };
+        let shift_alt = Modifiers { shift: true, ..alt };
+        let text = ["text/plain"];
+        assert_eq!(
+            policy.evaluate(&event(5.0, &text), &none),
+            Some(DropOperation::Copy)
+        );
+        assert_eq!(
+            policy.evaluate(&event(5.0, &text), &alt),
+            Some(DropOperation::Link)
+        );
+        assert_eq!(
+            policy.evaluate(&event(5.0, &text), &shift_alt),
+            Some(DropOperation::Copy)
+        );
+        // Move is preferred but not allowed, next matching format is used.
+        assert_eq!(
+            policy.evaluate(&event(5.0, &["text/uri-list", "text/plain"]), &none),
+            Some(DropOperation::Copy)
+        );
+        // No matching format resolves to allowed operation, Dart is asked.
+        assert_eq!(
+            policy.evaluate(&event(5.0, &["text/uri-list"]), &none),
+            None
+        );
+        assert_eq!(
+            policy.evaluate(&event(5.0, &["image/png"]), &alt),
+            Some(DropOperation::None)
+        );
+        assert_eq!(policy.evaluate(&event(15.0, &text), &none), None);
+        assert_eq!(
+            policy.evaluate(&event(15.0, &["image/png"]), &none),
+            Some(DropOperation::None)
+        );
+        assert_eq!(policy.evaluate(&event(25.0, &text), &none), None);
+    }
+}
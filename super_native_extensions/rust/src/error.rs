@@ -1,6 +1,8 @@
 use std::{fmt::Display, io};
 
-use irondash_message_channel::{MethodCallError, PlatformError, Value};
+use irondash_message_channel::{IntoValue, MethodCallError, PlatformError, Value};
+
+use crate::api_model::MenuProblem;
 
 #[derive(Debug)]
 pub enum NativeExtensionsError {
@@ -20,11 +22,40 @@ pub enum NativeExtensionsError {
     EngineContextError(irondash_engine_context::Error),
     PlatformMenuNotFound,
     InvalidMenuElement,
+    InvalidMenu(Vec<MenuProblem>),
     InvalidMenuConfigurationId,
+    ImageCodecError(String),
+    /// Error while reading data of particular item and format.
+    ItemFormatError {
+        item: i64,
+        format: String,
+        source: Box<NativeExtensionsError>,
+    },
     #[cfg(windows)]
     WindowsError(windows::core::HRESULT, String),
 }
 
+/// Machine readable error description sent to Dart as `PlatformError.detail`.
+#[derive(IntoValue, Debug, Default, Clone, PartialEq)]
+#[irondash(rename_all = "camelCase")]
+pub struct ErrorDetail {
+    /// Error variant, i.e. "ioError". Same string that was previously sent
+    /// as the whole detail. For wrapped errors this is the kind of the
+    /// underlying error.
+    pub kind: String,
+    /// Kind of the error wrapping the underlying error, i.e. "itemFormatError".
+    pub wrapper: Option<String>,
+    pub os_error_code: Option<i64>,
+    /// `std::io::ErrorKind` name, i.e. "NotFound".
+    pub io_error_kind: Option<String>,
+    pub hresult: Option<i64>,
+    pub format: Option<String>,
+    pub item: Option<i64>,
+    pub menu_problems: Vec<MenuProblem>,
+    /// Messages of the error and all of its sources, outermost first.
+    pub chain: Vec<String>,
+}
+
 pub type NativeExtensionsResult<T> = Result<T, NativeExtensionsError>;
 
 impl Display for NativeExtensionsError {
@@ -54,43 +85,119 @@ impl Display for NativeExtensionsError {
             NativeExtensionsError::EngineContextError(e) => e.fmt(f),
             NativeExtensionsError::PlatformMenuNotFound => write!(f, "platform menu not found"),
             NativeExtensionsError::InvalidMenuElement => write!(f, "invalid menu element"),
+            NativeExtensionsError::InvalidMenu(problems) => {
+                write!(f, "invalid menu: ")?;
+                for (i, problem) in problems.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "{problem}")?;
+                }
+                Ok(())
+            }
             NativeExtensionsError::InvalidMenuConfigurationId => {
                 write!(f, "invalid menu configuration id")
             }
+            NativeExtensionsError::ImageCodecError(m) => write!(f, "image codec error: {m}"),
+            NativeExtensionsError::ItemFormatError {
+                item,
+                format,
+                source,
+            } => write!(f, "failed to read {format} for item {item}: {source}"),
             #[cfg(windows)]
             NativeExtensionsError::WindowsError(_, msg) => write!(f, "{msg}"),
         }
     }
 }
 
-impl std::error::Error for NativeExtensionsError {}
+impl std::error::Error for NativeExtensionsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            NativeExtensionsError::IOError(e) => Some(e),
+            NativeExtensionsError::ItemFormatError { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
 
 impl NativeExtensionsError {
+    /// Wraps the error with item and format that were being read.
+    pub fn for_item_format(self, item: i64, format: &str) -> Self {
+        NativeExtensionsError::ItemFormatError {
+            item,
+            format: format.into(),
+            source: Box::new(self),
+        }
+    }
+
+    pub fn detail(&self) -> ErrorDetail {
+        let mut detail = match self {
+            NativeExtensionsError::ItemFormatError {
+                item,
+                format,
+                source,
+            } => ErrorDetail {
+                wrapper: Some(self.kind().into()),
+                item: Some(*item),
+                format: Some(format.clone()),
+                ..source.detail()
+            },
+            NativeExtensionsError::IOError(e) => ErrorDetail {
+                os_error_code: e.raw_os_error().map(|code| code as i64),
+                io_error_kind: Some(format!("{:?}", e.kind())),
+                ..Default::default()
+            },
+            NativeExtensionsError::InvalidMenu(problems) => ErrorDetail {
+                menu_problems: problems.clone(),
+                ..Default::default()
+            },
+            #[cfg(windows)]
+            NativeExtensionsError::WindowsError(hresult, _) => ErrorDetail {
+                hresult: Some(hresult.0 as i64),
+                ..Default::default()
+            },
+            _ => ErrorDetail::default(),
+        };
+        if detail.wrapper.is_none() {
+            detail.kind = self.kind().into();
+        }
+        detail.chain =
+            std::iter::successors(Some(self as &(dyn std::error::Error + 'static)), |e| {
+                e.source()
+            })
+            .map(|e| e.to_string())
+            .collect();
+        detail
+    }
+
     fn get_detail(&self) -> Value {
+        self.detail().into()
+    }
+
+    fn kind(&self) -> &'static str {
         match self {
-            NativeExtensionsError::UnknownError => "unknownError".into(),
-            NativeExtensionsError::MethodCallError(_) => "methodCallError".into(),
-            NativeExtensionsError::OtherError(_) => "otherError".into(),
-            NativeExtensionsError::DataSourceNotFound => "dataSourceNotFound".into(),
-            NativeExtensionsError::ReaderNotFound => "readerNotFound".into(),
-            NativeExtensionsError::PlatformContextNotFound => "platformContextNotFound".into(),
-            NativeExtensionsError::UnsupportedOperation => "unsupportedOperation".into(),
-            NativeExtensionsError::VirtualFileSessionNotFound => {
-                "virtualFileSessionNotFound".into()
-            }
-            NativeExtensionsError::VirtualFileReceiveError(_) => "virtualFileReceiveError".into(),
-            NativeExtensionsError::IOError(_) => "ioError".into(),
-            NativeExtensionsError::InvalidData => "invalidData".into(),
-            NativeExtensionsError::DragSessionNotFound => "dragSessionNotFound".into(),
-            NativeExtensionsError::MouseEventNotFound => "mouseEventNotFound".into(),
-            NativeExtensionsError::EngineContextError(_) => "engineContextError".into(),
-            NativeExtensionsError::PlatformMenuNotFound => "platformMenuNotFound".into(),
-            NativeExtensionsError::InvalidMenuElement => "invalidMenuElement".into(),
-            NativeExtensionsError::InvalidMenuConfigurationId => {
-                "invalidMenuConfigurationId".into()
-            }
+            NativeExtensionsError::UnknownError => "unknownError",
+            NativeExtensionsError::MethodCallError(_) => "methodCallError",
+            NativeExtensionsError::OtherError(_) => "otherError",
+            NativeExtensionsError::DataSourceNotFound => "dataSourceNotFound",
+            NativeExtensionsError::ReaderNotFound => "readerNotFound",
+            NativeExtensionsError::PlatformContextNotFound => "platformContextNotFound",
+            NativeExtensionsError::UnsupportedOperation => "unsupportedOperation",
+            NativeExtensionsError::VirtualFileSessionNotFound => "virtualFileSessionNotFound",
+            NativeExtensionsError::VirtualFileReceiveError(_) => "virtualFileReceiveError",
+            NativeExtensionsError::IOError(_) => "ioError",
+            NativeExtensionsError::InvalidData => "invalidData",
+            NativeExtensionsError::DragSessionNotFound => "dragSessionNotFound",
+            NativeExtensionsError::MouseEventNotFound => "mouseEventNotFound",
+            NativeExtensionsError::EngineContextError(_) => "engineContextError",
+            NativeExtensionsError::PlatformMenuNotFound => "platformMenuNotFound",
+            NativeExtensionsError::InvalidMenuElement => "invalidMenuElement",
+            NativeExtensionsError::InvalidMenu(_) => "invalidMenu",
+            NativeExtensionsError::InvalidMenuConfigurationId => "invalidMenuConfigurationId",
+            NativeExtensionsError::ImageCodecError(_) => "imageCodecError",
+            NativeExtensionsError::ItemFormatError { .. } => "itemFormatError",
             #[cfg(windows)]
-            NativeExtensionsError::WindowsError(_, _) => "windowsError".into(),
+            NativeExtensionsError::WindowsError(_, _) => "windowsError",
         }
     }
 
@@ -130,3 +237,35 @@ impl From<irondash_engine_context::Error> for NativeExtensionsError {
         NativeExtensionsError::EngineContextError(e)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::NativeExtensionsError;
+
+    #[test]
+    fn test_error_detail() {
+        let error: NativeExtensionsError = io::Error::from_raw_os_error(2).into();
+        let detail = error.detail();
+        assert_eq!(detail.kind, "ioError");
+        assert_eq!(detail.os_error_code, Some(2));
+        assert_eq!(detail.io_error_kind.as_deref(), Some("NotFound"));
+        assert_eq!(detail.chain.len(), 2);
+
+        let error = error.for_item_format(3, "public.png");
+        let detail = error.detail();
+        assert_eq!(detail.kind, "ioError");
+        assert_eq!(detail.wrapper.as_deref(), Some("itemFormatError"));
+        assert_eq!(detail.item, Some(3));
+        assert_eq!(detail.format.as_deref(), Some("public.png"));
+        assert_eq!(detail.os_error_code, Some(2));
+        assert_eq!(detail.chain.len(), 3);
+        assert!(detail.chain[0].starts_with("failed to read public.png for item 3"));
+
+        let detail = NativeExtensionsError::UnsupportedOperation.detail();
+        assert_eq!(detail.kind, "unsupportedOperation");
+        assert_eq!(detail.wrapper, None);
+        assert_eq!(detail.chain, vec!["unsupported operation".to_string()]);
+    }
+}
@@ -13,6 +13,7 @@ use irondash_message_channel::{
 use crate::{
     context::Context,
     error::{NativeExtensionsError, NativeExtensionsResult},
+    keyboard_map::platform_code_for_logical_key,
     log::OkLog,
     platform_impl::platform::PlatformHotKeyManager,
     util::NextId,
@@ -28,6 +29,40 @@ pub struct HotKeyCreateRequest {
     pub platform_code: i64,
 }
 
+/// Hot key creation arguments. Platform code can be omitted if logical key
+/// name is provided, in which case it is derived from US layout.
+#[derive(TryFromValue, Debug)]
+#[irondash(rename_all = "camelCase")]
+struct HotKeyCreateArgs {
+    alt: bool,
+    shift: bool,
+    meta: bool,
+    control: bool,
+    platform_code: Option<i64>,
+    logical_key: Option<String>,
+}
+
+impl HotKeyCreateArgs {
+    fn into_request(self) -> NativeExtensionsResult<HotKeyCreateRequest> {
+        let platform_code = match (self.platform_code, &self.logical_key) {
+            (Some(platform_code), _) => platform_code,
+            (None, Some(logical_key)) => {
+                platform_code_for_logical_key(logical_key).ok_or_else(|| {
+                    NativeExtensionsError::OtherError(format!("unknown key {logical_key:?}"))
+                })?
+            }
+            (None, None) => return Err(NativeExtensionsError::InvalidData),
+        };
+        Ok(HotKeyCreateRequest {
+            alt: self.alt,
+            shift: self.shift,
+            meta: self.meta,
+            control: self.control,
+            platform_code,
+        })
+    }
+}
+
 #[derive(TryFromValue, Debug)]
 struct HotKeyDestroyRequest {
     pub handle: HotKeyHandle,
@@ -72,8 +107,9 @@ impl HotKeyManager {
     fn create_hot_key(
         &self,
         isolate_id: IsolateId,
-        request: HotKeyCreateRequest,
+        args: HotKeyCreateArgs,
     ) -> NativeExtensionsResult<Option<HotKeyHandle>> {
+        let request = args.into_request()?;
         let handle = HotKeyHandle(self.next_id.next_id());
         let res = self.platform_manager.create_hot_key(handle, request);
         if let Err(NativeExtensionsError::UnsupportedOperation) = res {
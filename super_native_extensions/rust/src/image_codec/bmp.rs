@@ -0,0 +1,528 @@
+//! BMP and DIB encoder and decoder.
+//!
+//! DIB (device independent bitmap) is BMP without the file header. This is
+//! how bitmaps are stored in Windows clipboard (`CF_DIB`, `CF_DIBV5`).
+
+use crate::{
+    api_model::ImageData,
+    error::{NativeExtensionsError, NativeExtensionsResult},
+};
+
+use super::{
+    alloc_rgba, check_dimensions, check_image, codec_error, image_from_rgba, png::decode_png,
+};
+
+const BI_RGB: u32 = 0;
+const BI_RLE8: u32 = 1;
+const BI_RLE4: u32 = 2;
+const BI_BITFIELDS: u32 = 3;
+const BI_JPEG: u32 = 4;
+const BI_PNG: u32 = 5;
+const BI_ALPHABITFIELDS: u32 = 6;
+
+const BITMAPCOREHEADER_SIZE: u32 = 12;
+const BITMAPINFOHEADER_SIZE: u32 = 40;
+const BITMAPV5HEADER_SIZE: u32 = 124;
+
+fn truncated() -> NativeExtensionsError {
+    codec_error("bitmap data is truncated")
+}
+
+/// Returns `len` bytes starting at `offset` without risking overflow.
+fn slice(data: &[u8], offset: usize, len: usize) -> Option<&[u8]> {
+    data.get(offset..)?.get(..len)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> NativeExtensionsResult<u16> {
+    match slice(data, offset, 2) {
+        Some(&[b0, b1]) => Ok(u16::from_le_bytes([b0, b1])),
+        _ => Err(truncated()),
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> NativeExtensionsResult<u32> {
+    match slice(data, offset, 4) {
+        Some(&[b0, b1, b2, b3]) => Ok(u32::from_le_bytes([b0, b1, b2, b3])),
+        _ => Err(truncated()),
+    }
+}
+
+/// Decodes BMP file (starting with `BM` file header).
+pub fn decode_bmp(data: &[u8]) -> NativeExtensionsResult<ImageData> {
+    if !data.starts_with(b"BM") {
+        return Err(codec_error("not a BMP image"));
+    }
+    let pixel_offset = (read_u32(data, 10)? as usize)
+        .checked_sub(14)
+        .ok_or_else(|| codec_error("invalid BMP pixel data offset"))?;
+    decode(data.get(14..).ok_or_else(truncated)?, Some(pixel_offset))
+}
+
+/// Decodes packed DIB (bitmap header followed by color table and pixels).
+/// Supports `BITMAPCOREHEADER`, `BITMAPINFOHEADER` and `BITMAPV4HEADER` /
+/// `BITMAPV5HEADER`.
+pub fn decode_dib(data: &[u8]) -> NativeExtensionsResult<ImageData> {
+    decode(data, None)
+}
+
+/// Encodes image as 32-bit BMP file with `BITMAPV5HEADER` (to preserve alpha).
+pub fn encode_bmp(image: &ImageData) -> NativeExtensionsResult<Vec<u8>> {
+    let (width, height, stride) = check_image(image)?;
+    let header_size = 14 + BITMAPV5HEADER_SIZE as usize;
+    let image_size = width * height * 4;
+    let mut out = Vec::with_capacity(header_size + image_size);
+    out.extend_from_slice(b"BM");
+    out.extend_from_slice(&((header_size + image_size) as u32).to_le_bytes());
+    out.extend_from_slice(&[0; 4]);
+    out.extend_from_slice(&(header_size as u32).to_le_bytes());
+
+    let mut header = vec![0u8; BITMAPV5HEADER_SIZE as usize];
+    let mut put = |offset: usize, value: u32| {
+        header[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    };
+    put(0, BITMAPV5HEADER_SIZE);
+    put(4, width as u32);
+    put(8, height as u32); // bottom-up
+    put(12, 1 | (32 << 16)); // planes, bit count
+    put(16, BI_BITFIELDS);
+    put(20, image_size as u32);
+    put(24, 2835); // 72 DPI
+    put(28, 2835);
+    put(40, 0x00FF0000);
+    put(44, 0x0000FF00);
+    put(48, 0x000000FF);
+    put(52, 0xFF000000);
+    put(56, 0x73524742); // LCS_sRGB
+    put(108, 4); // LCS_GM_IMAGES
+    out.extend_from_slice(&header);
+
+    for y in (0..height).rev() {
+        for pixel in image.data[y * stride..y * stride + width * 4].chunks(4) {
+            out.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+        }
+    }
+    Ok(out)
+}
+
+struct Bitmap<'a> {
+    width: usize,
+    height: usize,
+    top_down: bool,
+    bit_count: u16,
+    /// BGR triplets / quads
+    palette: &'a [u8],
+    palette_entry_size: usize,
+    /// Red, green, blue and alpha mask
+    masks: [u32; 4],
+}
+
+impl<'a> Bitmap<'a> {
+    fn palette_color(&self, index: usize) -> [u8; 4] {
+        match slice(self.palette, index * self.palette_entry_size, 3) {
+            Some(&[b, g, r]) => [r, g, b, 255],
+            _ => [0, 0, 0, 255],
+        }
+    }
+
+    fn row_in_output(&self, row: usize) -> usize {
+        if self.top_down {
+            row
+        } else {
+            self.height - 1 - row
+        }
+    }
+
+    fn decode_uncompressed(&self, pixels: &[u8]) -> NativeExtensionsResult<Vec<u8>> {
+        let bit_count = self.bit_count as usize;
+        if !matches!(bit_count, 1 | 4 | 8 | 16 | 24 | 32) {
+            return Err(codec_error("unsupported bitmap bit count"));
+        }
+        // Dimensions are validated, so this can not overflow.
+        let row_len = (self.width * bit_count).div_ceil(8);
+        let stride = row_len.div_ceil(4) * 4;
+        // Last row doesn't need to be padded.
+        let needed = stride
+            .checked_mul(self.height - 1)
+            .and_then(|len| len.checked_add(row_len));
+        if needed.is_none_or(|needed| pixels.len() < needed) {
+            return Err(codec_error("bitmap pixel data is truncated"));
+        }
+        let mut out = alloc_rgba(self.width, self.height)?;
+        for row in 0..self.height {
+            let src = slice(pixels, row * stride, row_len).ok_or_else(truncated)?;
+            let dst_row = self.row_in_output(row) * self.width * 4;
+            for x in 0..self.width {
+                let pixel = match bit_count {
+                    1 | 4 | 8 => {
+                        let bit = x * bit_count;
+                        let shift = 8 - bit_count - bit % 8;
+                        let byte = src.get(bit / 8).copied().unwrap_or(0);
+                        let index = (byte >> shift) & ((1u16 << bit_count) - 1) as u8;
+                        self.palette_color(index as usize)
+                    }
+                    24 => match slice(src, x * 3, 3) {
+                        Some(&[b, g, r]) => [r, g, b, 255],
+                        _ => [0, 0, 0, 255],
+                    },
+                    _ => {
+                        let value = match slice(src, x * bit_count / 8, bit_count / 8) {
+                            Some(&[b0, b1]) => u16::from_le_bytes([b0, b1]) as u32,
+                            Some(&[b0, b1, b2, b3]) => u32::from_le_bytes([b0, b1, b2, b3]),
+                            _ => 0,
+                        };
+                        let [r, g, b, a] = self.masks;
+                        [
+                            channel(value, r),
+                            channel(value, g),
+                            channel(value, b),
+                            if a == 0 { 255 } else { channel(value, a) },
+                        ]
+                    }
+                };
+                if let Some(dst) = out.get_mut(dst_row + x * 4..dst_row + x * 4 + 4) {
+                    dst.copy_from_slice(&pixel);
+                }
+            }
+        }
+        // Many applications leave the alpha channel zeroed. Fully transparent
+        // bitmap is almost certainly not what was intended.
+        if self.masks[3] != 0 && out.chunks(4).all(|p| p[3] == 0) {
+            out.chunks_mut(4).for_each(|p| p[3] = 255);
+        }
+        Ok(out)
+    }
+
+    fn decode_rle(&self, pixels: &[u8], rle4: bool) -> NativeExtensionsResult<Vec<u8>> {
+        // Pixels skipped by delta or end of line escapes remain transparent.
+        let mut out = alloc_rgba(self.width, self.height)?;
+        let mut set_pixel = |x: usize, y: usize, index: u8| {
+            if x < self.width && y < self.height {
+                let offset = (self.row_in_output(y) * self.width + x) * 4;
+                if let Some(pixel) = out.get_mut(offset..offset + 4) {
+                    pixel.copy_from_slice(&self.palette_color(index as usize));
+                }
+            }
+        };
+        let (mut x, mut y, mut pos) = (0usize, 0usize, 0usize);
+        loop {
+            let (count, value) = match slice(pixels, pos, 2) {
+                Some(&[count, value]) => (count as usize, value),
+                _ => return Err(truncated()),
+            };
+            pos += 2;
+            if count > 0 {
+                for i in 0..count {
+                    let index = match (rle4, i % 2) {
+                        (false, _) => value,
+                        (true, 0) => value >> 4,
+                        (true, _) => value & 0x0F,
+                    };
+                    set_pixel(x, y, index);
+                    x += 1;
+                }
+                continue;
+            }
+            match value {
+                0 => {
+                    x = 0;
+                    y += 1;
+                }
+                1 => break,
+                2 => {
+                    let Some(&[dx, dy]) = slice(pixels, pos, 2) else {
+                        return Err(truncated());
+                    };
+                    x += dx as usize;
+                    y += dy as usize;
+                    pos += 2;
+                }
+                len => {
+                    let len = len as usize;
+                    let byte_len = if rle4 { len.div_ceil(2) } else { len };
+                    let data = slice(pixels, pos, byte_len).ok_or_else(truncated)?;
+                    for i in 0..len {
+                        let byte = data.get(if rle4 { i / 2 } else { i });
+                        let index = match (rle4, byte) {
+                            (_, None) => 0,
+                            (false, Some(byte)) => *byte,
+                            (true, Some(byte)) if i % 2 == 0 => byte >> 4,
+                            (true, Some(byte)) => byte & 0x0F,
+                        };
+                        set_pixel(x, y, index);
+                        x += 1;
+                    }
+                    // Absolute runs are padded to 16-bit boundary.
+                    pos += (byte_len + 1) & !1;
+                }
+            }
+            if y >= self.height {
+                break;
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Extracts color channel described by `mask` and scales it to 8 bits.
+fn channel(value: u32, mask: u32) -> u8 {
+    if mask == 0 {
+        return 0;
+    }
+    let shift = mask.trailing_zeros();
+    let max = (mask >> shift) as u64;
+    let value = ((value & mask) >> shift) as u64;
+    ((value * 255 + max / 2) / max) as u8
+}
+
+fn decode(dib: &[u8], pixel_offset: Option<usize>) -> NativeExtensionsResult<ImageData> {
+    let header_size = read_u32(dib, 0)?;
+    let (width, height, bit_count, compression, colors_used, palette_entry_size);
+    let mut masks = [0u32; 4];
+    if header_size == BITMAPCOREHEADER_SIZE {
+        width = read_u16(dib, 4)? as i32;
+        height = read_u16(dib, 6)? as i32;
+        bit_count = read_u16(dib, 10)?;
+        compression = BI_RGB;
+        colors_used = 0;
+        palette_entry_size = 3;
+    } else if header_size >= BITMAPINFOHEADER_SIZE {
+        width = read_u32(dib, 4)? as i32;
+        height = read_u32(dib, 8)? as i32;
+        bit_count = read_u16(dib, 14)?;
+        compression = read_u32(dib, 16)?;
+        colors_used = read_u32(dib, 32)?;
+        palette_entry_size = 4;
+        // BITMAPV2INFOHEADER and later include color masks.
+        if header_size >= 52 {
+            for (i, mask) in masks.iter_mut().take(3).enumerate() {
+                *mask = read_u32(dib, 40 + i * 4)?;
+            }
+        }
+        if header_size >= 56 {
+            masks[3] = read_u32(dib, 52)?;
+        }
+    } else {
+        return Err(codec_error("unsupported bitmap header"));
+    }
+
+    let mut pos = header_size as usize;
+    // With BITMAPINFOHEADER the masks follow the header.
+    if header_size == BITMAPINFOHEADER_SIZE {
+        let mask_count = match compression {
+            BI_BITFIELDS => 3,
+            BI_ALPHABITFIELDS => 4,
+            _ => 0,
+        };
+        for (i, mask) in masks.iter_mut().take(mask_count).enumerate() {
+            *mask = read_u32(dib, pos + i * 4)?;
+        }
+        pos += mask_count * 4;
+    }
+    if compression == BI_RGB {
+        masks = match bit_count {
+            16 => [0x7C00, 0x03E0, 0x001F, 0],
+            // Alpha is formally reserved for BI_RGB but commonly used.
+            32 => [0x00FF0000, 0x0000FF00, 0x000000FF, 0xFF000000],
+            _ => masks,
+        };
+    }
+
+    let palette_len = if bit_count <= 8 {
+        match colors_used {
+            0 => 1 << bit_count,
+            count => count.min(1 << bit_count),
+        }
+    } else {
+        colors_used
+    } as usize;
+    let palette = palette_len
+        .checked_mul(palette_entry_size)
+        .and_then(|len| slice(dib, pos, len))
+        .ok_or_else(|| codec_error("bitmap color table is truncated"))?;
+    pos += palette.len();
+
+    let pixels = dib
+        .get(pixel_offset.unwrap_or(pos)..)
+        .ok_or_else(|| codec_error("invalid bitmap pixel data offset"))?;
+
+    if compression == BI_PNG {
+        return decode_png(pixels);
+    }
+    if compression == BI_JPEG {
+        return Err(codec_error("JPEG compressed bitmaps are not supported"));
+    }
+    if width <= 0 || height == 0 {
+        return Err(codec_error("invalid bitmap dimensions"));
+    }
+    check_dimensions(width as u32, height.unsigned_abs())?;
+    let bitmap = Bitmap {
+        width: width as usize,
+        height: height.unsigned_abs() as usize,
+        top_down: height < 0,
+        bit_count,
+        palette,
+        palette_entry_size,
+        masks,
+    };
+    let out = match compression {
+        BI_RGB | BI_BITFIELDS | BI_ALPHABITFIELDS => bitmap.decode_uncompressed(pixels)?,
+        BI_RLE8 if bit_count == 8 => bitmap.decode_rle(pixels, false)?,
+        BI_RLE4 if bit_count == 4 => bitmap.decode_rle(pixels, true)?,
+        _ => return Err(codec_error("unsupported bitmap compression")),
+    };
+    Ok(image_from_rgba(bitmap.width, bitmap.height, out))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api_model::ImageData;
+
+    use super::{decode_bmp, decode_dib, encode_bmp};
+
+    fn info_header(
+        size: u32,
+        width: i32,
+        height: i32,
+        bit_count: u16,
+        compression: u32,
+    ) -> Vec<u8> {
+        let mut res = Vec::new();
+        res.extend_from_slice(&size.to_le_bytes());
+        res.extend_from_slice(&width.to_le_bytes());
+        res.extend_from_slice(&height.to_le_bytes());
+        res.extend_from_slice(&1u16.to_le_bytes());
+        res.extend_from_slice(&bit_count.to_le_bytes());
+        res.extend_from_slice(&compression.to_le_bytes());
+        res.resize(size as usize, 0);
+        res
+    }
+
+    #[test]
+    fn test_bmp_24bit_bottom_up() {
+        let mut dib = info_header(40, 2, 2, 24, 0);
+        // Bottom row first, rows padded to 4 bytes
+        dib.extend_from_slice(&[255, 0, 0, 0, 255, 0, 0, 0]);
+        dib.extend_from_slice(&[0, 0, 255, 255, 255, 255, 0, 0]);
+        let mut bmp = b"BM".to_vec();
+        bmp.extend_from_slice(&((14 + dib.len()) as u32).to_le_bytes());
+        bmp.extend_from_slice(&[0, 0, 0, 0]);
+        bmp.extend_from_slice(&(14u32 + 40).to_le_bytes());
+        bmp.extend_from_slice(&dib);
+        let image = decode_bmp(&bmp).unwrap();
+        assert_eq!((image.width, image.height), (2, 2));
+        assert_eq!(
+            image.data,
+            [
+                255, 0, 0, 255, 255, 255, 255, 255, // top row
+                0, 0, 255, 255, 0, 255, 0, 255, // bottom row
+            ]
+        );
+    }
+
+    #[test]
+    fn test_encode() {
+        let image = ImageData {
+            width: 3,
+            height: 2,
+            bytes_per_row: 12,
+            data: (0..24).map(|v| v * 10).collect(),
+            device_pixel_ratio: None,
+        };
+        let decoded = decode_bmp(&encode_bmp(&image).unwrap()).unwrap();
+        assert_eq!((decoded.width, decoded.height), (3, 2));
+        assert_eq!(decoded.data, image.data);
+    }
+
+    #[test]
+    fn test_dib_v5_alpha() {
+        // Top-down 2x1 BITMAPV5HEADER with BI_BITFIELDS and alpha mask
+        let mut dib = info_header(124, 2, -1, 32, 3);
+        for (i, mask) in [0x00FF0000u32, 0x0000FF00, 0x000000FF, 0xFF000000]
+            .iter()
+            .enumerate()
+        {
+            dib[40 + i * 4..44 + i * 4].copy_from_slice(&mask.to_le_bytes());
+        }
+        dib.extend_from_slice(&[10, 20, 30, 128, 0, 0, 255, 0]);
+        let image = decode_dib(&dib).unwrap();
+        assert_eq!(image.data, [30, 20, 10, 128, 255, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_dib_palette_and_rle() {
+        // 16-bit BI_RGB defaults to 5-5-5
+        let mut dib = info_header(40, 1, 1, 16, 0);
+        dib.extend_from_slice(&0x7C00u16.to_le_bytes());
+        dib.extend_from_slice(&[0, 0]);
+        assert_eq!(decode_dib(&dib).unwrap().data, [255, 0, 0, 255]);
+
+        // 32-bit BI_RGB with zeroed alpha is treated as opaque
+        let mut dib = info_header(40, 1, 1, 32, 0);
+        dib.extend_from_slice(&[1, 2, 3, 0]);
+        assert_eq!(decode_dib(&dib).unwrap().data, [3, 2, 1, 255]);
+
+        // 4x2 RLE8 with two color palette
+        let mut dib = info_header(40, 4, 2, 8, 1);
+        dib[32..36].copy_from_slice(&2u32.to_le_bytes());
+        dib.extend_from_slice(&[0, 0, 0, 0, 255, 255, 255, 0]);
+        dib.extend_from_slice(&[
+            4, 1, 0, 0, // bottom row: 4 white pixels, end of line
+            0, 3, 0, 1, 0, 0, // top row: absolute run of 3 pixels (padded)
+            0, 1, // end of bitmap
+        ]);
+        let image = decode_dib(&dib).unwrap();
+        let gray: Vec<u8> = image.data.chunks(4).map(|p| p[0]).collect();
+        assert_eq!(gray, [0, 255, 0, 0, 255, 255, 255, 255]);
+        // Pixel not covered by RLE data is transparent
+        assert_eq!(image.data[15], 0);
+    }
+
+    #[test]
+    fn test_truncated() {
+        let image = ImageData {
+            width: 3,
+            height: 2,
+            bytes_per_row: 12,
+            data: vec![7; 24],
+            device_pixel_ratio: None,
+        };
+        let bmp = encode_bmp(&image).unwrap();
+        for len in 0..bmp.len() {
+            assert!(decode_bmp(&bmp[..len]).is_err());
+        }
+        let mut dib = info_header(40, 4, 2, 8, 1);
+        dib[32..36].copy_from_slice(&2u32.to_le_bytes());
+        dib.extend_from_slice(&[0, 0, 0, 0, 255, 255, 255, 0]);
+        dib.extend_from_slice(&[4, 1, 0, 0, 0, 3, 0, 1, 0, 0, 0, 1]);
+        for len in 0..dib.len() {
+            assert!(decode_dib(&dib[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_malformed() {
+        // Huge color table, pixel data offset and dimensions.
+        let mut dib = info_header(40, 1, 1, 32, 0);
+        dib[32..36].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(decode_dib(&dib).is_err());
+        let mut bmp = b"BM".to_vec();
+        bmp.extend_from_slice(&[0; 8]);
+        bmp.extend_from_slice(&u32::MAX.to_le_bytes());
+        bmp.extend_from_slice(&info_header(40, 1, 1, 32, 0));
+        assert!(decode_bmp(&bmp).is_err());
+        let mut dib = info_header(40, 1, 1, 32, 0);
+        dib[0..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(decode_dib(&dib).is_err());
+        let dib = info_header(40, i32::MAX, i32::MIN, 32, 0);
+        assert!(decode_dib(&dib).is_err());
+        let dib = info_header(40, 0x4000, 0x4000, 32, 0);
+        assert!(decode_dib(&dib).is_err());
+        // Unsupported bit count, RLE runs and deltas past the image.
+        assert!(decode_dib(&info_header(40, 1, 1, 7, 0)).is_err());
+        let mut dib = info_header(40, 2, 2, 8, 1);
+        dib[32..36].copy_from_slice(&1u32.to_le_bytes());
+        dib.extend_from_slice(&[0, 0, 0, 0]);
+        dib.extend_from_slice(&[255, 0, 0, 3, 9, 9, 9, 0, 0, 2, 255, 255]);
+        assert_eq!(decode_dib(&dib).unwrap().data.len(), 16);
+    }
+}
@@ -0,0 +1,983 @@
+//! Baseline JPEG (ITU T.81) encoder and decoder.
+//!
+//! Encoder produces 4:4:4 JFIF image using standard tables. Decoder supports
+//! sequential Huffman coded grayscale and YCbCr images with any sampling
+//! factors. Progressive and arithmetic coded images are not supported.
+
+use std::f32::consts::PI;
+
+use crate::{api_model::ImageData, error::NativeExtensionsResult};
+
+use super::{
+    alloc_rgba, alloc_zeroed, check_dimensions, check_image, codec_error, image_from_rgba,
+};
+
+/// Natural (row major) index of coefficient at given zigzag position.
+const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20,
+    13, 6, 7, 14, 21, 28, 35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59,
+    52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+// Tables from Annex K of the specification.
+
+const LUMINANCE_QUANTIZATION: [u8; 64] = [
+    16, 11, 10, 16, 24, 40, 51, 61, 12, 12, 14, 19, 26, 58, 60, 55, 14, 13, 16, 24, 40, 57, 69, 56,
+    14, 17, 22, 29, 51, 87, 80, 62, 18, 22, 37, 56, 68, 109, 103, 77, 24, 35, 55, 64, 81, 104, 113,
+    92, 49, 64, 78, 87, 103, 121, 120, 101, 72, 92, 95, 98, 112, 100, 103, 99,
+];
+
+const CHROMINANCE_QUANTIZATION: [u8; 64] = [
+    17, 18, 24, 47, 99, 99, 99, 99, 18, 21, 26, 66, 99, 99, 99, 99, 24, 26, 56, 99, 99, 99, 99, 99,
+    47, 66, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99,
+    99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99,
+];
+
+const DC_LUMINANCE_BITS: [u8; 16] = [0, 1, 5, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0];
+const DC_CHROMINANCE_BITS: [u8; 16] = [0, 3, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0];
+const DC_VALUES: [u8; 12] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+
+const AC_LUMINANCE_BITS: [u8; 16] = [0, 2, 1, 3, 3, 2, 4, 3, 5, 5, 4, 4, 0, 0, 1, 0x7D];
+const AC_LUMINANCE_VALUES: [u8; 162] = [
+    0x01, 0x02, 0x03, 0x00, 0x04, 0x11, 0x05, 0x12, 0x21, 0x31, 0x41, 0x06, 0x13, 0x51, 0x61, 0x07,
+    0x22, 0x71, 0x14, 0x32, 0x81, 0x91, 0xA1, 0x08, 0x23, 0x42, 0xB1, 0xC1, 0x15, 0x52, 0xD1, 0xF0,
+    0x24, 0x33, 0x62, 0x72, 0x82, 0x09, 0x0A, 0x16, 0x17, 0x18, 0x19, 0x1A, 0x25, 0x26, 0x27, 0x28,
+    0x29, 0x2A, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3A, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49,
+    0x4A, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5A, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69,
+    0x6A, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7A, 0x83, 0x84, 0x85, 0x86, 0x87, 0x88, 0x89,
+    0x8A, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9A, 0xA2, 0xA3, 0xA4, 0xA5, 0xA6, 0xA7,
+    0xA8, 0xA9, 0xAA, 0xB2, 0xB3, 0xB4, 0xB5, 0xB6, 0xB7, 0xB8, 0xB9, 0xBA, 0xC2, 0xC3, 0xC4, 0xC5,
+    0xC6, 0xC7, 0xC8, 0xC9, 0xCA, 0xD2, 0xD3, 0xD4, 0xD5, 0xD6, 0xD7, 0xD8, 0xD9, 0xDA, 0xE1, 0xE2,
+    0xE3, 0xE4, 0xE5, 0xE6, 0xE7, 0xE8, 0xE9, 0xEA, 0xF1, 0xF2, 0xF3, 0xF4, 0xF5, 0xF6, 0xF7, 0xF8,
+    0xF9, 0xFA,
+];
+
+const AC_CHROMINANCE_BITS: [u8; 16] = [0, 2, 1, 2, 4, 4, 3, 4, 7, 5, 4, 4, 0, 1, 2, 0x77];
+const AC_CHROMINANCE_VALUES: [u8; 162] = [
+    0x00, 0x01, 0x02, 0x03, 0x11, 0x04, 0x05, 0x21, 0x31, 0x06, 0x12, 0x41, 0x51, 0x07, 0x61, 0x71,
+    0x13, 0x22, 0x32, 0x81, 0x08, 0x14, 0x42, 0x91, 0xA1, 0xB1, 0xC1, 0x09, 0x23, 0x33, 0x52, 0xF0,
+    0x15, 0x62, 0x72, 0xD1, 0x0A, 0x16, 0x24, 0x34, 0xE1, 0x25, 0xF1, 0x17, 0x18, 0x19, 0x1A, 0x26,
+    0x27, 0x28, 0x29, 0x2A, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3A, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48,
+    0x49, 0x4A, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5A, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68,
+    0x69, 0x6A, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7A, 0x82, 0x83, 0x84, 0x85, 0x86, 0x87,
+    0x88, 0x89, 0x8A, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9A, 0xA2, 0xA3, 0xA4, 0xA5,
+    0xA6, 0xA7, 0xA8, 0xA9, 0xAA, 0xB2, 0xB3, 0xB4, 0xB5, 0xB6, 0xB7, 0xB8, 0xB9, 0xBA, 0xC2, 0xC3,
+    0xC4, 0xC5, 0xC6, 0xC7, 0xC8, 0xC9, 0xCA, 0xD2, 0xD3, 0xD4, 0xD5, 0xD6, 0xD7, 0xD8, 0xD9, 0xDA,
+    0xE2, 0xE3, 0xE4, 0xE5, 0xE6, 0xE7, 0xE8, 0xE9, 0xEA, 0xF2, 0xF3, 0xF4, 0xF5, 0xF6, 0xF7, 0xF8,
+    0xF9, 0xFA,
+];
+
+/// `COSINES[u][x] = C(u) / 2 * cos((2x + 1)uπ / 16)`, shared by forward and
+/// inverse DCT.
+fn cosines() -> [[f32; 8]; 8] {
+    let mut res = [[0f32; 8]; 8];
+    for (u, row) in res.iter_mut().enumerate() {
+        let c = if u == 0 { 1.0 / 2f32.sqrt() } else { 1.0 };
+        for (x, value) in row.iter_mut().enumerate() {
+            *value = c / 2.0 * ((2 * x + 1) as f32 * u as f32 * PI / 16.0).cos();
+        }
+    }
+    res
+}
+
+/// Canonical Huffman codes as (code, length) indexed by symbol.
+fn huffman_codes(bits: &[u8; 16], values: &[u8]) -> [(u16, u8); 256] {
+    let mut res = [(0u16, 0u8); 256];
+    let mut code = 0u16;
+    let mut k = 0;
+    for (i, count) in bits.iter().enumerate() {
+        for _ in 0..*count {
+            res[values[k] as usize] = (code, i as u8 + 1);
+            code += 1;
+            k += 1;
+        }
+        code <<= 1;
+    }
+    res
+}
+
+/// Number of bits needed to represent magnitude of value.
+fn magnitude_category(value: i32) -> u8 {
+    (32 - value.unsigned_abs().leading_zeros()) as u8
+}
+
+//
+// Encoder
+//
+
+struct BitWriter {
+    out: Vec<u8>,
+    bit_buf: u32,
+    bit_count: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, count: u8) {
+        for i in (0..count).rev() {
+            self.bit_buf = (self.bit_buf << 1) | ((value >> i) & 1);
+            self.bit_count += 1;
+            if self.bit_count == 8 {
+                let byte = self.bit_buf as u8;
+                self.out.push(byte);
+                if byte == 0xFF {
+                    // Byte stuffing
+                    self.out.push(0);
+                }
+                self.bit_buf = 0;
+                self.bit_count = 0;
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        // Pad with one bits.
+        while self.bit_count != 0 {
+            self.write(1, 1);
+        }
+    }
+}
+
+struct ComponentEncoder {
+    /// Quantization table in natural order
+    quantization: [u16; 64],
+    dc_codes: [(u16, u8); 256],
+    ac_codes: [(u16, u8); 256],
+    prediction: i32,
+}
+
+impl ComponentEncoder {
+    fn encode_block(&mut self, block: &[f32; 64], cosines: &[[f32; 8]; 8], writer: &mut BitWriter) {
+        // Separable forward DCT: rows then columns.
+        let mut tmp = [0f32; 64];
+        for y in 0..8 {
+            for u in 0..8 {
+                tmp[y * 8 + u] = (0..8).map(|x| cosines[u][x] * block[y * 8 + x]).sum();
+            }
+        }
+        let mut coefficients = [0i32; 64];
+        for v in 0..8 {
+            for u in 0..8 {
+                let value: f32 = (0..8).map(|y| cosines[v][y] * tmp[y * 8 + u]).sum();
+                let q = self.quantization[v * 8 + u] as f32;
+                coefficients[v * 8 + u] = (value / q).round() as i32;
+            }
+        }
+
+        let diff = coefficients[0] - self.prediction;
+        self.prediction = coefficients[0];
+        let category = magnitude_category(diff);
+        let (code, len) = self.dc_codes[category as usize];
+        writer.write(code as u32, len);
+        write_value(writer, diff, category);
+
+        let mut run = 0;
+        for k in 1..64 {
+            let value = coefficients[ZIGZAG[k]];
+            if value == 0 {
+                run += 1;
+                continue;
+            }
+            while run > 15 {
+                let (code, len) = self.ac_codes[0xF0];
+                writer.write(code as u32, len);
+                run -= 16;
+            }
+            let category = magnitude_category(value);
+            let (code, len) = self.ac_codes[(run << 4) | category as usize];
+            writer.write(code as u32, len);
+            write_value(writer, value, category);
+            run = 0;
+        }
+        if run > 0 {
+            let (code, len) = self.ac_codes[0x00];
+            writer.write(code as u32, len);
+        }
+    }
+}
+
+/// Writes additional bits of coefficient (negative values as one's complement).
+fn write_value(writer: &mut BitWriter, value: i32, category: u8) {
+    if category > 0 {
+        let bits = if value < 0 { value - 1 } else { value };
+        writer.write(bits as u32 & ((1 << category) - 1), category);
+    }
+}
+
+fn scaled_quantization(table: &[u8; 64], quality: u8) -> [u16; 64] {
+    let quality = quality.clamp(1, 100) as u32;
+    let scale = if quality < 50 {
+        5000 / quality
+    } else {
+        200 - quality * 2
+    };
+    table.map(|v| ((v as u32 * scale + 50) / 100).clamp(1, 255) as u16)
+}
+
+fn write_segment(out: &mut Vec<u8>, marker: u8, data: &[u8]) {
+    out.extend_from_slice(&[0xFF, marker]);
+    out.extend_from_slice(&(data.len() as u16 + 2).to_be_bytes());
+    out.extend_from_slice(data);
+}
+
+/// Encodes image as JPEG with given quality (1 - 100). JPEG has no alpha
+/// channel, transparent pixels are composed over white background.
+pub fn encode_jpeg(image: &ImageData, quality: u8) -> NativeExtensionsResult<Vec<u8>> {
+    let (width, height, stride) = check_image(image)?;
+    if width > u16::MAX as usize || height > u16::MAX as usize {
+        return Err(codec_error("image is too large for JPEG"));
+    }
+    let luminance = scaled_quantization(&LUMINANCE_QUANTIZATION, quality);
+    let chrominance = scaled_quantization(&CHROMINANCE_QUANTIZATION, quality);
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&[0xFF, 0xD8]);
+    write_segment(
+        &mut out,
+        0xE0,
+        &[b'J', b'F', b'I', b'F', 0, 1, 1, 0, 0, 1, 0, 1, 0, 0],
+    );
+    for (id, table) in [(0u8, &luminance), (1, &chrominance)] {
+        let mut data = vec![id];
+        data.extend(ZIGZAG.iter().map(|i| table[*i] as u8));
+        write_segment(&mut out, 0xDB, &data);
+    }
+    let mut frame = vec![8];
+    frame.extend_from_slice(&(height as u16).to_be_bytes());
+    frame.extend_from_slice(&(width as u16).to_be_bytes());
+    frame.extend_from_slice(&[3, 1, 0x11, 0, 2, 0x11, 1, 3, 0x11, 1]);
+    write_segment(&mut out, 0xC0, &frame);
+    for (class_id, bits, values) in [
+        (0x00, &DC_LUMINANCE_BITS, &DC_VALUES[..]),
+        (0x10, &AC_LUMINANCE_BITS, &AC_LUMINANCE_VALUES[..]),
+        (0x01, &DC_CHROMINANCE_BITS, &DC_VALUES[..]),
+        (0x11, &AC_CHROMINANCE_BITS, &AC_CHROMINANCE_VALUES[..]),
+    ] {
+        let mut data = vec![class_id];
+        data.extend_from_slice(bits);
+        data.extend_from_slice(values);
+        write_segment(&mut out, 0xC4, &data);
+    }
+    write_segment(&mut out, 0xDA, &[3, 1, 0x00, 2, 0x11, 3, 0x11, 0, 63, 0]);
+
+    let luminance_codes = (
+        huffman_codes(&DC_LUMINANCE_BITS, &DC_VALUES),
+        huffman_codes(&AC_LUMINANCE_BITS, &AC_LUMINANCE_VALUES),
+    );
+    let chrominance_codes = (
+        huffman_codes(&DC_CHROMINANCE_BITS, &DC_VALUES),
+        huffman_codes(&AC_CHROMINANCE_BITS, &AC_CHROMINANCE_VALUES),
+    );
+    let mut components = [
+        (luminance, luminance_codes),
+        (chrominance, chrominance_codes),
+        (chrominance, chrominance_codes),
+    ]
+    .map(|(quantization, (dc_codes, ac_codes))| ComponentEncoder {
+        quantization,
+        dc_codes,
+        ac_codes,
+        prediction: 0,
+    });
+
+    let cosines = cosines();
+    let mut writer = BitWriter {
+        out,
+        bit_buf: 0,
+        bit_count: 0,
+    };
+    let mut blocks = [[0f32; 64]; 3];
+    for block_y in (0..height).step_by(8) {
+        for block_x in (0..width).step_by(8) {
+            let [y_block, cb_block, cr_block] = &mut blocks;
+            for i in 0..64 {
+                // Replicate edge pixels for partial blocks.
+                let x = (block_x + i % 8).min(width - 1);
+                let y = (block_y + i / 8).min(height - 1);
+                let offset = y * stride + x * 4;
+                let pixel = &image.data[offset..offset + 4];
+                let alpha = pixel[3] as f32 / 255.0;
+                let [r, g, b] = [pixel[0], pixel[1], pixel[2]]
+                    .map(|c| c as f32 * alpha + 255.0 * (1.0 - alpha));
+                y_block[i] = 0.299 * r + 0.587 * g + 0.114 * b - 128.0;
+                cb_block[i] = -0.168736 * r - 0.331264 * g + 0.5 * b;
+                cr_block[i] = 0.5 * r - 0.418688 * g - 0.081312 * b;
+            }
+            for (component, block) in components.iter_mut().zip(&blocks) {
+                component.encode_block(block, &cosines, &mut writer);
+            }
+        }
+    }
+    writer.flush();
+    let mut out = writer.out;
+    out.extend_from_slice(&[0xFF, 0xD9]);
+    Ok(out)
+}
+
+//
+// Decoder
+//
+
+struct HuffmanTable {
+    max_code: [i32; 17],
+    min_code: [i32; 17],
+    value_offset: [i32; 17],
+    values: Vec<u8>,
+}
+
+impl HuffmanTable {
+    fn new(bits: &[u8], values: &[u8]) -> Self {
+        let mut res = HuffmanTable {
+            max_code: [-1; 17],
+            min_code: [0; 17],
+            value_offset: [0; 17],
+            values: values.to_vec(),
+        };
+        let (mut code, mut k) = (0i32, 0i32);
+        for len in 1..=16 {
+            let count = bits[len - 1] as i32;
+            res.value_offset[len] = k;
+            res.min_code[len] = code;
+            code += count;
+            k += count;
+            if count > 0 {
+                res.max_code[len] = code - 1;
+            }
+            code <<= 1;
+        }
+        res
+    }
+
+    fn decode(&self, reader: &mut EntropyReader) -> NativeExtensionsResult<u8> {
+        let mut code = reader.bit() as i32;
+        for len in 1..=16 {
+            if code <= self.max_code[len] {
+                let index = self.value_offset[len] + code - self.min_code[len];
+                return self
+                    .values
+                    .get(index as usize)
+                    .copied()
+                    .ok_or_else(|| codec_error("invalid JPEG Huffman code"));
+            }
+            code = (code << 1) | reader.bit() as i32;
+        }
+        Err(codec_error("invalid JPEG Huffman code"))
+    }
+}
+
+/// Reads entropy coded segment. Stuffed zero bytes are skipped; when marker
+/// or end of data is encountered reader keeps returning zero bits and marks
+/// itself as exhausted.
+struct EntropyReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit_buf: u32,
+    bit_count: u32,
+    /// Set when reader ran past the end of entropy coded data. Valid scans
+    /// never need more bits than encoded, so this means data is truncated.
+    exhausted: bool,
+}
+
+impl<'a> EntropyReader<'a> {
+    fn next_byte(&mut self) -> u8 {
+        match self.data.get(self.pos) {
+            Some(0xFF) if self.data.get(self.pos + 1) == Some(&0) => {
+                self.pos += 2;
+                0xFF
+            }
+            // Marker or end of data
+            Some(0xFF) | None => {
+                self.exhausted = true;
+                0
+            }
+            Some(byte) => {
+                self.pos += 1;
+                *byte
+            }
+        }
+    }
+
+    fn bit(&mut self) -> u32 {
+        if self.bit_count == 0 {
+            self.bit_buf = self.next_byte() as u32;
+            self.bit_count = 8;
+        }
+        self.bit_count -= 1;
+        (self.bit_buf >> self.bit_count) & 1
+    }
+
+    fn bits(&mut self, count: u8) -> u32 {
+        (0..count).fold(0, |acc, _| (acc << 1) | self.bit())
+    }
+
+    /// Reads additional bits of coefficient with given magnitude category.
+    fn value(&mut self, category: u8) -> NativeExtensionsResult<i32> {
+        if category == 0 {
+            return Ok(0);
+        }
+        if category > 16 {
+            return Err(codec_error("invalid JPEG magnitude category"));
+        }
+        let value = self.bits(category) as i32;
+        if value < 1 << (category - 1) {
+            Ok(value - (1 << category) + 1)
+        } else {
+            Ok(value)
+        }
+    }
+
+    /// Skips restart marker and discards remaining bits.
+    fn restart(&mut self) -> NativeExtensionsResult<()> {
+        self.bit_count = 0;
+        match self.data.get(self.pos..).and_then(|rest| rest.get(..2)) {
+            Some([0xFF, 0xD0..=0xD7]) => {
+                self.pos += 2;
+                Ok(())
+            }
+            _ => Err(codec_error("missing JPEG restart marker")),
+        }
+    }
+}
+
+struct Component {
+    id: u8,
+    h: usize,
+    v: usize,
+    quantization: usize,
+    dc_table: usize,
+    ac_table: usize,
+    prediction: i32,
+    /// Decoded samples, padded to whole MCUs.
+    plane: Vec<u8>,
+    plane_width: usize,
+}
+
+struct Decoder {
+    width: usize,
+    height: usize,
+    components: Vec<Component>,
+    /// Quantization tables in zigzag order
+    quantization: [[u16; 64]; 4],
+    dc_tables: [Option<HuffmanTable>; 4],
+    ac_tables: [Option<HuffmanTable>; 4],
+    restart_interval: usize,
+    /// Transform flag from Adobe APP14 segment.
+    adobe_transform: Option<u8>,
+    cosines: [[f32; 8]; 8],
+}
+
+impl Decoder {
+    fn max_sampling(&self) -> (usize, usize) {
+        let h = self.components.iter().map(|c| c.h).max().unwrap_or(1);
+        let v = self.components.iter().map(|c| c.v).max().unwrap_or(1);
+        (h, v)
+    }
+
+    fn mcu_count(&self) -> (usize, usize) {
+        let (h, v) = self.max_sampling();
+        (self.width.div_ceil(8 * h), self.height.div_ceil(8 * v))
+    }
+
+    fn parse_frame(&mut self, data: &[u8]) -> NativeExtensionsResult<()> {
+        if !self.components.is_empty() {
+            return Err(codec_error("multiple JPEG frames are not supported"));
+        }
+        let Some(&[precision, h0, h1, w0, w1, count]) = data.get(..6) else {
+            return Err(codec_error("invalid JPEG frame header"));
+        };
+        if precision != 8 {
+            return Err(codec_error("unsupported JPEG precision"));
+        }
+        self.height = u16::from_be_bytes([h0, h1]) as usize;
+        self.width = u16::from_be_bytes([w0, w1]) as usize;
+        check_dimensions(self.width as u32, self.height as u32)?;
+        let count = count as usize;
+        if count != 1 && count != 3 {
+            return Err(codec_error("unsupported JPEG component count"));
+        }
+        for i in 0..count {
+            let Some(&[id, sampling, quantization]) = data.get(6 + i * 3..9 + i * 3) else {
+                return Err(codec_error("invalid JPEG frame header"));
+            };
+            let (h, v) = ((sampling >> 4) as usize, (sampling & 0x0F) as usize);
+            if !(1..=4).contains(&h) || !(1..=4).contains(&v) || quantization > 3 {
+                return Err(codec_error("invalid JPEG frame header"));
+            }
+            self.components.push(Component {
+                id,
+                h,
+                v,
+                quantization: quantization as usize,
+                dc_table: 0,
+                ac_table: 0,
+                prediction: 0,
+                plane: Vec::new(),
+                plane_width: 0,
+            });
+        }
+        let (mcus_x, mcus_y) = self.mcu_count();
+        for c in &mut self.components {
+            c.plane_width = mcus_x * c.h * 8;
+            let len = c
+                .plane_width
+                .checked_mul(mcus_y * c.v * 8)
+                .ok_or_else(|| codec_error("image is too large"))?;
+            c.plane = alloc_zeroed(len)?;
+        }
+        Ok(())
+    }
+
+    fn parse_quantization(&mut self, mut data: &[u8]) -> NativeExtensionsResult<()> {
+        while let Some((info, rest)) = data.split_first() {
+            let invalid = || codec_error("invalid JPEG quantization table");
+            let (precision, id) = (info >> 4, (info & 0x0F) as usize);
+            let len = if precision == 0 { 64 } else { 128 };
+            let table = rest.get(..len).ok_or_else(invalid)?;
+            let target = self.quantization.get_mut(id).ok_or_else(invalid)?;
+            match precision {
+                0 => target
+                    .iter_mut()
+                    .zip(table)
+                    .for_each(|(q, v)| *q = *v as u16),
+                _ => target
+                    .iter_mut()
+                    .zip(table.chunks_exact(2))
+                    .for_each(|(q, v)| *q = u16::from_be_bytes([v[0], v[1]])),
+            }
+            data = &rest[len..];
+        }
+        Ok(())
+    }
+
+    fn parse_huffman(&mut self, mut data: &[u8]) -> NativeExtensionsResult<()> {
+        while let Some((info, rest)) = data.split_first() {
+            let invalid = || codec_error("invalid JPEG Huffman table");
+            let (class, id) = (info >> 4, (info & 0x0F) as usize);
+            let bits = rest.get(..16).ok_or_else(invalid)?;
+            let count: usize = bits.iter().map(|b| *b as usize).sum();
+            let values = rest.get(16..16 + count).ok_or_else(invalid)?;
+            let tables = match class {
+                0 => &mut self.dc_tables,
+                1 => &mut self.ac_tables,
+                _ => return Err(invalid()),
+            };
+            *tables.get_mut(id).ok_or_else(invalid)? = Some(HuffmanTable::new(bits, values));
+            data = &rest[16 + count..];
+        }
+        Ok(())
+    }
+
+    fn decode_block(
+        &self,
+        component: &Component,
+        prediction: &mut i32,
+        reader: &mut EntropyReader,
+        block_x: usize,
+        block_y: usize,
+        plane: &mut [u8],
+    ) -> NativeExtensionsResult<()> {
+        let missing = || codec_error("missing JPEG Huffman table");
+        let dc_table = self.dc_tables[component.dc_table]
+            .as_ref()
+            .ok_or_else(missing)?;
+        let ac_table = self.ac_tables[component.ac_table]
+            .as_ref()
+            .ok_or_else(missing)?;
+        let quantization = &self.quantization[component.quantization];
+
+        let mut coefficients = [0f32; 64];
+        let category = dc_table.decode(reader)?;
+        *prediction = prediction.wrapping_add(reader.value(category)?);
+        coefficients[0] = prediction.wrapping_mul(quantization[0] as i32) as f32;
+        let mut k = 1;
+        while k < 64 {
+            let symbol = ac_table.decode(reader)?;
+            let (run, category) = ((symbol >> 4) as usize, symbol & 0x0F);
+            if category == 0 {
+                if run != 15 {
+                    break; // End of block
+                }
+                k += 16;
+                continue;
+            }
+            k += run;
+            if k > 63 {
+                return Err(codec_error("invalid JPEG coefficient index"));
+            }
+            coefficients[ZIGZAG[k]] =
+                reader.value(category)?.wrapping_mul(quantization[k] as i32) as f32;
+            k += 1;
+        }
+
+        // Separable inverse DCT: columns then rows.
+        let cosines = &self.cosines;
+        let mut tmp = [0f32; 64];
+        for y in 0..8 {
+            for u in 0..8 {
+                tmp[y * 8 + u] = (0..8)
+                    .map(|v| cosines[v][y] * coefficients[v * 8 + u])
+                    .sum();
+            }
+        }
+        for y in 0..8 {
+            let row = (block_y * 8 + y) * component.plane_width + block_x * 8;
+            // Blocks always lie within the plane, which is padded to whole MCUs.
+            let Some(row) = plane.get_mut(row..row + 8) else {
+                return Err(codec_error("invalid JPEG block position"));
+            };
+            for (x, sample) in row.iter_mut().enumerate() {
+                let value: f32 = (0..8).map(|u| cosines[u][x] * tmp[y * 8 + u]).sum();
+                *sample = (value + 128.0).round().clamp(0.0, 255.0) as u8;
+            }
+        }
+        if reader.exhausted {
+            return Err(codec_error("JPEG data is truncated"));
+        }
+        Ok(())
+    }
+
+    /// Decodes scan starting at `data[pos]`. Returns position after the
+    /// entropy coded data.
+    fn decode_scan(
+        &mut self,
+        header: &[u8],
+        data: &[u8],
+        pos: usize,
+    ) -> NativeExtensionsResult<usize> {
+        let invalid = || codec_error("invalid JPEG scan header");
+        let count = *header.first().ok_or_else(invalid)? as usize;
+        let mut scan_components = Vec::with_capacity(count);
+        for i in 0..count {
+            let c = header.get(1 + i * 2..3 + i * 2).ok_or_else(invalid)?;
+            let index = self
+                .components
+                .iter()
+                .position(|component| component.id == c[0])
+                .filter(|index| !scan_components.contains(index))
+                .ok_or_else(invalid)?;
+            let component = &mut self.components[index];
+            component.dc_table = (c[1] >> 4) as usize & 3;
+            component.ac_table = (c[1] & 0x0F) as usize & 3;
+            component.prediction = 0;
+            scan_components.push(index);
+        }
+        if scan_components.is_empty() {
+            return Err(invalid());
+        }
+
+        let mut reader = EntropyReader {
+            data,
+            pos,
+            bit_buf: 0,
+            bit_count: 0,
+            exhausted: false,
+        };
+        // Planes are temporarily taken out to allow decoding into them while
+        // borrowing decoder.
+        let mut planes: Vec<Vec<u8>> = scan_components
+            .iter()
+            .map(|i| std::mem::take(&mut self.components[*i].plane))
+            .collect();
+        let mut predictions = vec![0i32; scan_components.len()];
+        let (mcus_x, mcus_y) = self.mcu_count();
+        let (max_h, max_v) = self.max_sampling();
+        let result = (|| {
+            if let [index] = scan_components[..] {
+                // Non-interleaved scan, MCU is single block.
+                let c = &self.components[index];
+                let blocks_x = (self.width * c.h).div_ceil(max_h).div_ceil(8);
+                let blocks_y = (self.height * c.v).div_ceil(max_v).div_ceil(8);
+                for i in 0..blocks_x * blocks_y {
+                    if self.restart_interval > 0 && i > 0 && i % self.restart_interval == 0 {
+                        reader.restart()?;
+                        predictions[0] = 0;
+                    }
+                    let (x, y) = (i % blocks_x, i / blocks_x);
+                    self.decode_block(c, &mut predictions[0], &mut reader, x, y, &mut planes[0])?;
+                }
+            } else {
+                for i in 0..mcus_x * mcus_y {
+                    if self.restart_interval > 0 && i > 0 && i % self.restart_interval == 0 {
+                        reader.restart()?;
+                        predictions.iter_mut().for_each(|p| *p = 0);
+                    }
+                    let (mcu_x, mcu_y) = (i % mcus_x, i / mcus_x);
+                    for (n, index) in scan_components.iter().enumerate() {
+                        let c = &self.components[*index];
+                        for v in 0..c.v {
+                            for h in 0..c.h {
+                                self.decode_block(
+                                    c,
+                                    &mut predictions[n],
+                                    &mut reader,
+                                    mcu_x * c.h + h,
+                                    mcu_y * c.v + v,
+                                    &mut planes[n],
+                                )?;
+                            }
+                        }
+                    }
+                }
+            }
+            Ok(())
+        })();
+        for (index, plane) in scan_components.iter().zip(planes) {
+            self.components[*index].plane = plane;
+        }
+        result.map(|_| reader.pos)
+    }
+
+    fn output(&self) -> NativeExtensionsResult<ImageData> {
+        let (max_h, max_v) = self.max_sampling();
+        let sample = |c: &Component, x: usize, y: usize| {
+            let index = (y * c.v / max_v) * c.plane_width + x * c.h / max_h;
+            c.plane.get(index).copied().unwrap_or(0) as f32
+        };
+        let mut out = alloc_rgba(self.width, self.height)?;
+        for (y, row) in out.chunks_exact_mut(self.width * 4).enumerate() {
+            for (x, dst) in row.chunks_exact_mut(4).enumerate() {
+                let pixel = match &self.components[..] {
+                    [gray] => {
+                        let v = sample(gray, x, y) as u8;
+                        [v, v, v, 255]
+                    }
+                    [c1, c2, c3] => {
+                        let (a, b, c) = (sample(c1, x, y), sample(c2, x, y), sample(c3, x, y));
+                        if self.adobe_transform == Some(0) {
+                            [a as u8, b as u8, c as u8, 255]
+                        } else {
+                            let (cb, cr) = (b - 128.0, c - 128.0);
+                            let [r, g, b] = [
+                                a + 1.402 * cr,
+                                a - 0.344136 * cb - 0.714136 * cr,
+                                a + 1.772 * cb,
+                            ]
+                            .map(|v| v.round().clamp(0.0, 255.0) as u8);
+                            [r, g, b, 255]
+                        }
+                    }
+                    _ => return Err(codec_error("unsupported JPEG component count")),
+                };
+                dst.copy_from_slice(&pixel);
+            }
+        }
+        Ok(image_from_rgba(self.width, self.height, out))
+    }
+}
+
+pub fn decode_jpeg(data: &[u8]) -> NativeExtensionsResult<ImageData> {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return Err(codec_error("not a JPEG image"));
+    }
+    let mut decoder = Decoder {
+        width: 0,
+        height: 0,
+        components: Vec::new(),
+        quantization: [[1; 64]; 4],
+        dc_tables: Default::default(),
+        ac_tables: Default::default(),
+        restart_interval: 0,
+        adobe_transform: None,
+        cosines: cosines(),
+    };
+    let truncated = || codec_error("JPEG data is truncated");
+    let mut pos = 2;
+    let mut decoded_scan = false;
+    loop {
+        // Skip to next marker (there might be fill bytes or garbage).
+        while data
+            .get(pos..)
+            .is_some_and(|rest| !matches!(rest, [0xFF, next, ..] if *next != 0xFF))
+        {
+            pos += 1;
+        }
+        let marker = *data.get(pos + 1).ok_or_else(truncated)?;
+        pos += 2;
+        match marker {
+            0xD9 => break,
+            0x00 | 0x01 | 0xD0..=0xD8 => continue,
+            _ => {}
+        }
+        let len = match data.get(pos..).and_then(|rest| rest.get(..2)) {
+            Some(&[l0, l1]) if u16::from_be_bytes([l0, l1]) >= 2 => {
+                u16::from_be_bytes([l0, l1]) as usize
+            }
+            _ => return Err(truncated()),
+        };
+        let segment = data.get(pos + 2..pos + len).ok_or_else(truncated)?;
+        pos += len;
+        match marker {
+            0xC0 | 0xC1 => {
+                if !decoder.components.is_empty() {
+                    return Err(codec_error("multiple JPEG frames"));
+                }
+                decoder.parse_frame(segment)?
+            }
+            0xC2 | 0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF => {
+                return Err(codec_error("unsupported JPEG coding process"))
+            }
+            0xC4 => decoder.parse_huffman(segment)?,
+            0xDB => decoder.parse_quantization(segment)?,
+            0xDD => {
+                let Some(&[i0, i1]) = segment.get(..2) else {
+                    return Err(truncated());
+                };
+                decoder.restart_interval = u16::from_be_bytes([i0, i1]) as usize;
+            }
+            0xEE if segment.starts_with(b"Adobe") => {
+                decoder.adobe_transform = segment.get(11).copied();
+            }
+            0xDA => {
+                if decoder.components.is_empty() {
+                    return Err(codec_error("JPEG scan before frame header"));
+                }
+                pos = decoder.decode_scan(segment, data, pos)?;
+                decoded_scan = true;
+            }
+            _ => {}
+        }
+    }
+    if !decoded_scan {
+        return Err(codec_error("JPEG image has no scan"));
+    }
+    decoder.output()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api_model::ImageData;
+
+    use super::{decode_jpeg, encode_jpeg, EntropyReader};
+
+    #[test]
+    fn test_round_trip() {
+        // Smooth gradient with partial blocks
+        let (width, height) = (21, 13);
+        let mut data = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                data.extend_from_slice(&[(x * 12) as u8, (y * 19) as u8, 128, 255]);
+            }
+        }
+        let image = ImageData {
+            width,
+            height,
+            bytes_per_row: width * 4,
+            data,
+            device_pixel_ratio: None,
+        };
+        let encoded = encode_jpeg(&image, 95).unwrap();
+        let decoded = decode_jpeg(&encoded).unwrap();
+        assert_eq!((decoded.width, decoded.height), (width, height));
+        let max_error = image
+            .data
+            .iter()
+            .zip(&decoded.data)
+            .map(|(a, b)| (*a as i32 - *b as i32).abs())
+            .max()
+            .unwrap();
+        assert!(max_error <= 8, "max error {max_error}");
+    }
+
+    #[test]
+    fn test_transparent_over_white() {
+        let image = ImageData {
+            width: 1,
+            height: 1,
+            bytes_per_row: 4,
+            data: vec![0, 0, 0, 0],
+            device_pixel_ratio: None,
+        };
+        let decoded = decode_jpeg(&encode_jpeg(&image, 90).unwrap()).unwrap();
+        assert!(decoded.data[0..3].iter().all(|c| *c >= 250));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(decode_jpeg(&[0xFF, 0xD8, 0xFF, 0xD9]).is_err());
+        assert!(decode_jpeg(b"\x89PNG").is_err());
+        let image = ImageData {
+            width: 8,
+            height: 8,
+            bytes_per_row: 32,
+            data: vec![100; 256],
+            device_pixel_ratio: None,
+        };
+        let encoded = encode_jpeg(&image, 50).unwrap();
+        assert!(decode_jpeg(&encoded[..encoded.len() / 2]).is_err());
+    }
+
+    #[test]
+    fn test_magnitude_category() {
+        let mut reader = EntropyReader {
+            data: &[0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00],
+            pos: 0,
+            bit_buf: 0,
+            bit_count: 0,
+            exhausted: false,
+        };
+        assert_eq!(reader.value(16).unwrap(), 0xFFFF);
+        assert!(reader.value(17).is_err());
+    }
+
+    fn test_image() -> Vec<u8> {
+        let image = ImageData {
+            width: 19,
+            height: 11,
+            bytes_per_row: 19 * 4,
+            data: (0..19 * 11 * 4).map(|v| (v * 7) as u8).collect(),
+            device_pixel_ratio: None,
+        };
+        encode_jpeg(&image, 75).unwrap()
+    }
+
+    #[test]
+    fn test_truncated() {
+        let data = test_image();
+        for len in 0..data.len() {
+            assert!(decode_jpeg(&data[..len]).is_err(), "length {len}");
+        }
+    }
+
+    #[test]
+    fn test_malformed() {
+        let segment = |marker: u8, data: &[u8]| {
+            let mut res = vec![0xFF, marker];
+            res.extend_from_slice(&(data.len() as u16 + 2).to_be_bytes());
+            res.extend_from_slice(data);
+            res
+        };
+        let jpeg = |segments: &[Vec<u8>]| {
+            let mut res = vec![0xFF, 0xD8];
+            segments.iter().for_each(|s| res.extend_from_slice(s));
+            res.extend_from_slice(&[0xFF, 0xD9]);
+            res
+        };
+        let frame = segment(0xC0, &[8, 0, 8, 0, 8, 1, 1, 0x11, 0]);
+        // Segment length shorter than its header, tables with invalid ids.
+        assert!(decode_jpeg(&[0xFF, 0xD8, 0xFF, 0xDB, 0, 1]).is_err());
+        assert!(decode_jpeg(&jpeg(&[segment(0xDB, &[0x05; 65])])).is_err());
+        assert!(decode_jpeg(&jpeg(&[segment(0xC4, &[0x25; 17])])).is_err());
+        assert!(decode_jpeg(&jpeg(&[segment(0xDB, &[0x00; 10])])).is_err());
+        // Scan referencing unknown component, scan with missing tables.
+        let scan = segment(0xDA, &[1, 9, 0, 0, 63, 0]);
+        assert!(decode_jpeg(&jpeg(&[frame.clone(), scan])).is_err());
+        let scan = segment(0xDA, &[1, 1, 0, 0, 63, 0]);
+        assert!(decode_jpeg(&jpeg(&[frame.clone(), scan.clone()])).is_err());
+        // Huge image with no entropy coded data must fail quickly.
+        let huge = segment(
+            0xC0,
+            &[8, 0x10, 0, 0x10, 0, 3, 1, 0x11, 0, 2, 0x11, 1, 3, 0x11, 1],
+        );
+        let mut data = test_image();
+        let sof = data.windows(2).position(|w| w == [0xFF, 0xC0]).unwrap();
+        data.splice(sof..sof + 19, huge.iter().copied());
+        assert!(decode_jpeg(&data).is_err());
+        // Flipped bits must never panic.
+        let data = test_image();
+        for i in 0..data.len() * 8 {
+            let mut corrupted = data.clone();
+            corrupted[i / 8] ^= 1 << (i % 8);
+            let _ = decode_jpeg(&corrupted);
+        }
+    }
+}
@@ -0,0 +1,194 @@
+//! Portable image codecs shared by all platforms.
+//!
+//! Converts between [ImageData] (straight RGBA) and PNG, JPEG, BMP / DIB and
+//! TIFF without relying on platform APIs, so that readers can provide the same
+//! formats everywhere.
+
+use crate::{
+    api_model::ImageData,
+    error::{NativeExtensionsError, NativeExtensionsResult},
+};
+
+mod bmp;
+mod jpeg;
+mod png;
+mod synthesis;
+mod tiff;
+mod zlib;
+
+pub use bmp::{decode_bmp, decode_dib, encode_bmp};
+pub use jpeg::{decode_jpeg, encode_jpeg};
+pub use png::{decode_png, encode_png};
+pub use synthesis::{
+    convert_image, image_synthesis_source, is_image_format, synthesized_image_formats,
+};
+pub use tiff::{decode_tiff, encode_tiff};
+#[cfg(fuzzing)]
+pub use zlib::zlib_decompress;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Bmp,
+    Tiff,
+}
+
+impl ImageFormat {
+    /// Detects image format from file signature. Headerless DIB can not be
+    /// detected and must be decoded with [decode_dib] explicitly.
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(&png::SIGNATURE) {
+            Some(ImageFormat::Png)
+        } else if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(ImageFormat::Jpeg)
+        } else if data.starts_with(b"BM") {
+            Some(ImageFormat::Bmp)
+        } else if data.starts_with(b"II*\0") || data.starts_with(b"MM\0*") {
+            Some(ImageFormat::Tiff)
+        } else {
+            None
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "image/png",
+            ImageFormat::Jpeg => "image/jpeg",
+            ImageFormat::Bmp => "image/bmp",
+            ImageFormat::Tiff => "image/tiff",
+        }
+    }
+}
+
+/// Decodes image in any supported format.
+pub fn decode_image(data: &[u8]) -> NativeExtensionsResult<ImageData> {
+    match ImageFormat::detect(data) {
+        Some(ImageFormat::Png) => decode_png(data),
+        Some(ImageFormat::Jpeg) => decode_jpeg(data),
+        Some(ImageFormat::Bmp) => decode_bmp(data),
+        Some(ImageFormat::Tiff) => decode_tiff(data),
+        None => Err(codec_error("unknown image format")),
+    }
+}
+
+fn codec_error(message: &str) -> NativeExtensionsError {
+    NativeExtensionsError::ImageCodecError(message.into())
+}
+
+/// Upper bound for decoded images, protects against allocating huge buffers
+/// for malformed headers.
+const MAX_PIXELS: u64 = 1 << 28;
+
+fn check_dimensions(width: u32, height: u32) -> NativeExtensionsResult<()> {
+    if width == 0 || height == 0 {
+        return Err(codec_error("image must not be empty"));
+    }
+    if width as u64 * height as u64 > MAX_PIXELS || width > i32::MAX as u32 / 4 {
+        return Err(codec_error("image is too large"));
+    }
+    Ok(())
+}
+
+/// Allocates zeroed buffer for decoded data. Allocation failure is reported
+/// as error instead of aborting the process.
+fn alloc_zeroed(len: usize) -> NativeExtensionsResult<Vec<u8>> {
+    let mut res = Vec::new();
+    res.try_reserve_exact(len)
+        .map_err(|_| codec_error("not enough memory to decode image"))?;
+    res.resize(len, 0);
+    Ok(res)
+}
+
+/// Allocates zeroed RGBA buffer for decoded image.
+fn alloc_rgba(width: usize, height: usize) -> NativeExtensionsResult<Vec<u8>> {
+    let len = width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(4))
+        .ok_or_else(|| codec_error("image is too large"))?;
+    alloc_zeroed(len)
+}
+
+/// Validates image to be encoded. Returns width, height and bytes per row.
+fn check_image(image: &ImageData) -> NativeExtensionsResult<(usize, usize, usize)> {
+    if image.width <= 0 || image.height <= 0 {
+        return Err(codec_error("image must not be empty"));
+    }
+    let (width, height) = (image.width as usize, image.height as usize);
+    let stride = image.bytes_per_row as usize;
+    if stride < width * 4 || image.data.len() < stride * (height - 1) + width * 4 {
+        return Err(codec_error("image data is too short"));
+    }
+    Ok((width, height, stride))
+}
+
+fn image_from_rgba(width: usize, height: usize, data: Vec<u8>) -> ImageData {
+    ImageData {
+        width: width as i32,
+        height: height as i32,
+        bytes_per_row: width as i32 * 4,
+        data,
+        device_pixel_ratio: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api_model::ImageData;
+
+    use super::{decode_dib, decode_image, encode_png, zlib::zlib_decompress, ImageFormat};
+
+    #[test]
+    fn test_detect() {
+        let image = ImageData {
+            width: 1,
+            height: 1,
+            bytes_per_row: 4,
+            data: vec![1, 2, 3, 4],
+            device_pixel_ratio: None,
+        };
+        let png = encode_png(&image).unwrap();
+        assert_eq!(ImageFormat::detect(&png), Some(ImageFormat::Png));
+        assert_eq!(decode_image(&png).unwrap().data, image.data);
+        assert_eq!(
+            ImageFormat::detect(b"MM\0*\0\0\0\x08"),
+            Some(ImageFormat::Tiff)
+        );
+        assert_eq!(ImageFormat::detect(b"\x28\0\0\0"), None);
+        assert!(decode_image(b"\x28\0\0\0").is_err());
+    }
+
+    /// Seed corpus of fuzz targets must stay decodable, otherwise fuzzer
+    /// starts from inputs rejected early.
+    #[test]
+    fn test_fuzz_seeds() {
+        let corpus = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz/corpus");
+        let mut count = 0;
+        for (target, decode) in [
+            (
+                "decode_image",
+                &(|d: &[u8]| decode_image(d).map(|_| ())) as &dyn Fn(&[u8]) -> _,
+            ),
+            ("decode_dib", &|d: &[u8]| decode_dib(d).map(|_| ())),
+            ("zlib_decompress", &|d: &[u8]| {
+                zlib_decompress(d, 1 << 24).map(|_| ())
+            }),
+        ] {
+            for entry in std::fs::read_dir(corpus.join(target)).unwrap() {
+                let path = entry.unwrap().path();
+                if !path
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .starts_with("seed_")
+                {
+                    continue;
+                }
+                let data = std::fs::read(&path).unwrap();
+                assert!(decode(&data).is_ok(), "{path:?}");
+                count += 1;
+            }
+        }
+        assert!(count > 0);
+    }
+}
@@ -0,0 +1,600 @@
+//! PNG (ISO/IEC 15948) encoder and decoder.
+//!
+//! Encoder always produces 8-bit RGBA non-interlaced image. Decoder supports
+//! all color types, bit depths, interlacing and transparency chunk.
+
+use crate::{api_model::ImageData, error::NativeExtensionsResult};
+
+use super::{
+    alloc_rgba, check_dimensions, check_image, codec_error, image_from_rgba,
+    zlib::{zlib_compress, zlib_decompress},
+};
+
+pub const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+const CRC_TABLE: [u32; 256] = make_crc_table();
+
+const fn make_crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xEDB88320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+fn crc32(parts: &[&[u8]]) -> u32 {
+    let mut crc = 0xFFFFFFFFu32;
+    for part in parts {
+        for byte in *part {
+            crc = CRC_TABLE[((crc ^ *byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+        }
+    }
+    crc ^ 0xFFFFFFFF
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+//
+// Encoder
+//
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    out.extend_from_slice(&crc32(&[kind, data]).to_be_bytes());
+}
+
+/// Applies filter to a row. `prev` is the previous unfiltered row.
+fn filter_row(filter: u8, row: &[u8], prev: &[u8], bpp: usize, out: &mut Vec<u8>) {
+    out.push(filter);
+    for i in 0..row.len() {
+        let a = if i >= bpp { row[i - bpp] } else { 0 };
+        let b = prev[i];
+        let c = if i >= bpp { prev[i - bpp] } else { 0 };
+        let predicted = match filter {
+            0 => 0,
+            1 => a,
+            2 => b,
+            3 => ((a as u16 + b as u16) / 2) as u8,
+            _ => paeth(a, b, c),
+        };
+        out.push(row[i].wrapping_sub(predicted));
+    }
+}
+
+/// Encodes straight RGBA image data as PNG.
+pub fn encode_png(image: &ImageData) -> NativeExtensionsResult<Vec<u8>> {
+    let (width, height, stride) = check_image(image)?;
+    let row_len = width * 4;
+
+    // Pick filter per row using the minimum sum of absolute differences
+    // heuristic recommended by PNG specification.
+    let zero_row = vec![0u8; row_len];
+    let mut filtered = Vec::with_capacity((row_len + 1) * height);
+    let mut candidate = Vec::with_capacity(row_len + 1);
+    let mut best = Vec::with_capacity(row_len + 1);
+    for y in 0..height {
+        let row = &image.data[y * stride..y * stride + row_len];
+        let prev = if y > 0 {
+            &image.data[(y - 1) * stride..(y - 1) * stride + row_len]
+        } else {
+            &zero_row
+        };
+        let mut best_score = u64::MAX;
+        for filter in 0..5 {
+            candidate.clear();
+            filter_row(filter, row, prev, 4, &mut candidate);
+            let score: u64 = candidate[1..]
+                .iter()
+                .map(|v| (*v as i8).unsigned_abs() as u64)
+                .sum();
+            if score < best_score {
+                best_score = score;
+                std::mem::swap(&mut best, &mut candidate);
+            }
+        }
+        filtered.extend_from_slice(&best);
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    header.extend_from_slice(&[8, 6, 0, 0, 0]); // 8-bit RGBA, no interlace
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&SIGNATURE);
+    write_chunk(&mut out, b"IHDR", &header);
+    write_chunk(&mut out, b"IDAT", &zlib_compress(&filtered));
+    write_chunk(&mut out, b"IEND", &[]);
+    Ok(out)
+}
+
+//
+// Decoder
+//
+
+struct Header {
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+    color_type: u8,
+    interlaced: bool,
+}
+
+impl Header {
+    fn parse(data: &[u8]) -> NativeExtensionsResult<Self> {
+        if data.len() != 13 {
+            return Err(codec_error("invalid PNG header"));
+        }
+        let header = Header {
+            width: u32::from_be_bytes(data[0..4].try_into().unwrap()),
+            height: u32::from_be_bytes(data[4..8].try_into().unwrap()),
+            bit_depth: data[8],
+            color_type: data[9],
+            interlaced: data[12] == 1,
+        };
+        let valid_depth = match header.color_type {
+            0 => matches!(header.bit_depth, 1 | 2 | 4 | 8 | 16),
+            3 => matches!(header.bit_depth, 1 | 2 | 4 | 8),
+            2 | 4 | 6 => matches!(header.bit_depth, 8 | 16),
+            _ => false,
+        };
+        if !valid_depth || data[10] != 0 || data[11] != 0 || data[12] > 1 {
+            return Err(codec_error("unsupported PNG format"));
+        }
+        check_dimensions(header.width, header.height)?;
+        Ok(header)
+    }
+
+    fn channels(&self) -> usize {
+        match self.color_type {
+            2 => 3,
+            4 => 2,
+            6 => 4,
+            _ => 1,
+        }
+    }
+
+    fn bits_per_pixel(&self) -> usize {
+        self.channels() * self.bit_depth as usize
+    }
+
+    fn row_len(&self, width: usize) -> Option<usize> {
+        width
+            .checked_mul(self.bits_per_pixel())
+            .map(|bits| bits.div_ceil(8))
+    }
+
+    /// Size of filtered (sub)image including filter type bytes.
+    fn pass_len(&self, width: usize, height: usize) -> Option<usize> {
+        self.row_len(width)?.checked_add(1)?.checked_mul(height)
+    }
+}
+
+struct Decoder<'a> {
+    header: Header,
+    palette: &'a [u8],
+    transparency: Option<&'a [u8]>,
+}
+
+impl<'a> Decoder<'a> {
+    /// Returns sample at given index. Row length is derived from image width,
+    /// so the index is always in range; out of range samples read as zero.
+    fn sample(&self, row: &[u8], index: usize) -> u16 {
+        let byte = |i: usize| row.get(i).copied().unwrap_or(0);
+        match self.header.bit_depth {
+            8 => byte(index) as u16,
+            16 => u16::from_be_bytes([byte(index * 2), byte(index * 2 + 1)]),
+            depth => {
+                let depth = depth as usize;
+                let bit = index * depth;
+                let shift = 8 - depth - bit % 8;
+                ((byte(bit / 8) >> shift) & ((1 << depth) - 1) as u8) as u16
+            }
+        }
+    }
+
+    fn to_u8(&self, sample: u16) -> u8 {
+        match self.header.bit_depth {
+            16 => (sample >> 8) as u8,
+            8 => sample as u8,
+            depth => (sample as u32 * 255 / ((1 << depth) - 1)) as u8,
+        }
+    }
+
+    /// Whether samples match the color key from tRNS chunk.
+    fn is_transparent_key(&self, samples: &[u16]) -> bool {
+        match self.transparency {
+            Some(key) if key.len() >= samples.len() * 2 => samples
+                .iter()
+                .zip(key.chunks_exact(2))
+                .all(|(s, k)| u16::from_be_bytes([k[0], k[1]]) == *s),
+            _ => false,
+        }
+    }
+
+    fn pixel(&self, row: &[u8], x: usize) -> [u8; 4] {
+        let channels = self.header.channels();
+        let s = |i: usize| self.sample(row, x * channels + i);
+        match self.header.color_type {
+            0 => {
+                let gray = s(0);
+                let v = self.to_u8(gray);
+                let alpha = if self.is_transparent_key(&[gray]) {
+                    0
+                } else {
+                    255
+                };
+                [v, v, v, alpha]
+            }
+            2 => {
+                let rgb = [s(0), s(1), s(2)];
+                let alpha = if self.is_transparent_key(&rgb) {
+                    0
+                } else {
+                    255
+                };
+                [
+                    self.to_u8(rgb[0]),
+                    self.to_u8(rgb[1]),
+                    self.to_u8(rgb[2]),
+                    alpha,
+                ]
+            }
+            3 => {
+                let index = s(0) as usize;
+                let color = match self.palette.get(index * 3..index * 3 + 3) {
+                    Some(&[r, g, b]) => [r, g, b],
+                    _ => [0; 3],
+                };
+                let alpha = self
+                    .transparency
+                    .and_then(|t| t.get(index).copied())
+                    .unwrap_or(255);
+                [color[0], color[1], color[2], alpha]
+            }
+            4 => {
+                let v = self.to_u8(s(0));
+                [v, v, v, self.to_u8(s(1))]
+            }
+            _ => [
+                self.to_u8(s(0)),
+                self.to_u8(s(1)),
+                self.to_u8(s(2)),
+                self.to_u8(s(3)),
+            ],
+        }
+    }
+
+    /// Unfilters (sub)image of given size starting at `raw[*offset]` and
+    /// stores pixels to `out` at positions given by `position`.
+    fn decode_pass(
+        &self,
+        raw: &mut [u8],
+        offset: &mut usize,
+        width: usize,
+        height: usize,
+        out: &mut [u8],
+        position: impl Fn(usize, usize) -> usize,
+    ) -> NativeExtensionsResult<()> {
+        let truncated = || codec_error("PNG image data is truncated");
+        let row_len = self.header.row_len(width).ok_or_else(truncated)?;
+        let len = self.header.pass_len(width, height).ok_or_else(truncated)?;
+        let pass = offset
+            .checked_add(len)
+            .and_then(|end| raw.get_mut(*offset..end))
+            .ok_or_else(truncated)?;
+        let bpp = (self.header.bits_per_pixel() / 8).max(1);
+        let mut prev: &[u8] = &[];
+        for (y, line) in pass.chunks_exact_mut(row_len + 1).enumerate() {
+            let Some((filter, row)) = line.split_first_mut() else {
+                return Err(truncated());
+            };
+            unfilter_row(*filter, row, prev, bpp)?;
+            for x in 0..width {
+                let pos = position(x, y) * 4;
+                if let Some(pixel) = out.get_mut(pos..pos + 4) {
+                    pixel.copy_from_slice(&self.pixel(row, x));
+                }
+            }
+            prev = row;
+        }
+        *offset += len;
+        Ok(())
+    }
+}
+
+/// Reverses filter applied to a row. `prev` is the previous unfiltered row,
+/// or empty slice for the first row.
+fn unfilter_row(filter: u8, row: &mut [u8], prev: &[u8], bpp: usize) -> NativeExtensionsResult<()> {
+    for i in 0..row.len() {
+        let a = if i >= bpp { row[i - bpp] } else { 0 };
+        let b = prev.get(i).copied().unwrap_or(0);
+        let c = match i.checked_sub(bpp) {
+            Some(i) => prev.get(i).copied().unwrap_or(0),
+            None => 0,
+        };
+        let predicted = match filter {
+            0 => 0,
+            1 => a,
+            2 => b,
+            3 => ((a as u16 + b as u16) / 2) as u8,
+            4 => paeth(a, b, c),
+            _ => return Err(codec_error("invalid PNG filter type")),
+        };
+        row[i] = row[i].wrapping_add(predicted);
+    }
+    Ok(())
+}
+
+/// Adam7 passes as (x offset, y offset, x step, y step).
+const ADAM7: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+pub fn decode_png(data: &[u8]) -> NativeExtensionsResult<ImageData> {
+    if !data.starts_with(&SIGNATURE) {
+        return Err(codec_error("not a PNG image"));
+    }
+    let mut pos = SIGNATURE.len();
+    let mut header = None;
+    let mut palette: &[u8] = &[];
+    let mut transparency = None;
+    let mut compressed = Vec::new();
+    let truncated = || codec_error("PNG data is truncated");
+    loop {
+        let rest = data.get(pos..).unwrap_or_default();
+        let Some(&[l0, l1, l2, l3, k0, k1, k2, k3]) = rest.get(..8) else {
+            return Err(truncated());
+        };
+        let len = u32::from_be_bytes([l0, l1, l2, l3]) as usize;
+        let kind = &[k0, k1, k2, k3];
+        let chunk = rest
+            .get(8..)
+            .and_then(|rest| rest.get(..len))
+            .ok_or_else(truncated)?;
+        let Some(&[c0, c1, c2, c3]) = rest.get(8 + chunk.len()..).and_then(|rest| rest.get(..4))
+        else {
+            return Err(truncated());
+        };
+        // Checksums would stop fuzzer from reaching anything past the chunk
+        // parser.
+        if !cfg!(fuzzing) && u32::from_be_bytes([c0, c1, c2, c3]) != crc32(&[kind, chunk]) {
+            return Err(codec_error("PNG chunk checksum mismatch"));
+        }
+        pos += 12 + chunk.len();
+        match kind {
+            b"IHDR" => header = Some(Header::parse(chunk)?),
+            b"PLTE" => palette = chunk,
+            b"tRNS" => transparency = Some(chunk),
+            b"IDAT" => compressed.extend_from_slice(chunk),
+            b"IEND" => break,
+            // Ancillary chunks have lowercase first letter.
+            _ if k0 & 0x20 != 0 => {}
+            _ => return Err(codec_error("unsupported critical PNG chunk")),
+        }
+    }
+    let header = header.ok_or_else(|| codec_error("missing PNG header"))?;
+    if header.color_type == 3 && palette.is_empty() {
+        return Err(codec_error("missing PNG palette"));
+    }
+    let (width, height) = (header.width as usize, header.height as usize);
+    let passes: Vec<_> = if header.interlaced {
+        ADAM7
+            .iter()
+            .map(|(x0, y0, dx, dy)| {
+                let pass_width = (width + dx - 1 - x0) / dx;
+                let pass_height = (height + dy - 1 - y0) / dy;
+                (*x0, *y0, *dx, *dy, pass_width, pass_height)
+            })
+            .filter(|(.., w, h)| *w > 0 && *h > 0)
+            .collect()
+    } else {
+        // Non-interlaced image is a single pass covering all pixels.
+        vec![(0, 0, 1, 1, width, height)]
+    };
+    // Decompressed data is never larger than all passes together; this also
+    // bounds memory used by malicious streams.
+    let raw_len = passes
+        .iter()
+        .try_fold(0usize, |len, (.., w, h)| {
+            len.checked_add(header.pass_len(*w, *h)?)
+        })
+        .ok_or_else(|| codec_error("image is too large"))?;
+    let mut raw = zlib_decompress(&compressed, raw_len)?;
+    if raw.len() < raw_len {
+        return Err(codec_error("PNG image data is truncated"));
+    }
+    let decoder = Decoder {
+        header,
+        palette,
+        transparency,
+    };
+    let mut out = alloc_rgba(width, height)?;
+    let mut offset = 0;
+    for (x0, y0, dx, dy, pass_width, pass_height) in passes {
+        decoder.decode_pass(
+            &mut raw,
+            &mut offset,
+            pass_width,
+            pass_height,
+            &mut out,
+            |x, y| (y0 + y * dy) * width + x0 + x * dx,
+        )?;
+    }
+    Ok(image_from_rgba(width, height, out))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{api_model::ImageData, image_codec::zlib::zlib_compress};
+
+    use super::{crc32, decode_png, encode_png, write_chunk, SIGNATURE};
+
+    fn test_image(width: i32, height: i32) -> ImageData {
+        let mut data = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                data.extend_from_slice(&[(x * 16) as u8, (y * 16) as u8, (x ^ y) as u8, 200]);
+            }
+            // Padding that must be ignored
+            data.extend_from_slice(&[1, 2, 3, 4]);
+        }
+        ImageData {
+            width,
+            height,
+            bytes_per_row: width * 4 + 4,
+            data,
+            device_pixel_ratio: None,
+        }
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(&[b"IEND"]), 0xAE426082);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let image = test_image(13, 7);
+        let decoded = decode_png(&encode_png(&image).unwrap()).unwrap();
+        assert_eq!((decoded.width, decoded.height), (13, 7));
+        assert_eq!(decoded.bytes_per_row, 13 * 4);
+        for y in 0..7 {
+            let row = &image.data[y * 56..y * 56 + 52];
+            assert_eq!(&decoded.data[y * 52..y * 52 + 52], row);
+        }
+    }
+
+    fn png(header: &[u8], extra: &[(&[u8; 4], &[u8])], raw: &[u8]) -> Vec<u8> {
+        let mut out = SIGNATURE.to_vec();
+        write_chunk(&mut out, b"IHDR", header);
+        for (kind, data) in extra {
+            write_chunk(&mut out, kind, data);
+        }
+        write_chunk(&mut out, b"IDAT", &zlib_compress(raw));
+        write_chunk(&mut out, b"IEND", &[]);
+        out
+    }
+
+    #[test]
+    fn test_palette_and_gray() {
+        // 3x1, 2-bit palette, second entry transparent
+        let header = [0, 0, 0, 3, 0, 0, 0, 1, 2, 3, 0, 0, 0];
+        let palette = [255, 0, 0, 0, 255, 0, 0, 0, 255];
+        let data = png(
+            &header,
+            &[(b"PLTE", &palette), (b"tRNS", &[255, 0])],
+            &[0, 0b00011000],
+        );
+        let image = decode_png(&data).unwrap();
+        assert_eq!(image.data, [255, 0, 0, 255, 0, 255, 0, 0, 0, 0, 255, 255]);
+
+        // 2x1, 16-bit gray with Sub filter
+        let header = [0, 0, 0, 2, 0, 0, 0, 1, 16, 0, 0, 0, 0];
+        let image = decode_png(&png(&header, &[], &[1, 0x10, 0x00, 0x10, 0x00])).unwrap();
+        assert_eq!(image.data, [0x10, 0x10, 0x10, 255, 0x20, 0x20, 0x20, 255]);
+    }
+
+    #[test]
+    fn test_interlaced() {
+        // 3x3 8-bit gray, Adam7 passes 2 and 3 are empty
+        let header = [0, 0, 0, 3, 0, 0, 0, 3, 8, 0, 0, 0, 1];
+        let raw = [
+            0, 0, // pass 1: (0,0)
+            0, 2, // pass 4: (2,0)
+            0, 6, 8, // pass 5: (0,2), (2,2)
+            0, 1, 0, 7, // pass 6: (1,0), (1,2)
+            0, 3, 4, 5, // pass 7: row 1
+        ];
+        let image = decode_png(&png(&header, &[], &raw)).unwrap();
+        let gray: Vec<u8> = image.data.chunks(4).map(|p| p[0]).collect();
+        assert_eq!(gray, [0, 1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let mut data = encode_png(&test_image(2, 2)).unwrap();
+        assert!(decode_png(&data[..data.len() - 20]).is_err());
+        data[20] ^= 1;
+        assert!(decode_png(&data).is_err());
+        assert!(decode_png(b"GIF89a").is_err());
+    }
+
+    #[test]
+    fn test_truncated() {
+        let data = encode_png(&test_image(5, 3)).unwrap();
+        for len in 0..data.len() {
+            assert!(decode_png(&data[..len]).is_err());
+        }
+        // Missing rows in otherwise valid stream
+        let header = [0, 0, 0, 3, 0, 0, 0, 3, 8, 0, 0, 0, 0];
+        assert!(decode_png(&png(&header, &[], &[0, 1, 2, 3, 0, 4, 5, 6])).is_err());
+        let header = [0, 0, 0, 3, 0, 0, 0, 3, 8, 0, 0, 0, 1];
+        assert!(decode_png(&png(&header, &[], &[0, 0, 0, 2])).is_err());
+    }
+
+    #[test]
+    fn test_malformed() {
+        // Huge dimensions with tiny data must not allocate output.
+        let header = [0, 0, 0x40, 0, 0, 0, 0x40, 0, 8, 6, 0, 0, 0];
+        assert!(decode_png(&png(&header, &[], &[0; 16])).is_err());
+        // Excess data is bounded by expected image size.
+        let header = [0, 0, 0, 1, 0, 0, 0, 1, 8, 0, 0, 0, 0];
+        assert!(decode_png(&png(&header, &[], &vec![0; 100000])).is_err());
+        // Invalid filter type, palette index out of range, short tRNS.
+        assert!(decode_png(&png(&header, &[], &[5, 0])).is_err());
+        let header = [0, 0, 0, 2, 0, 0, 0, 1, 8, 3, 0, 0, 0];
+        let image = decode_png(&png(
+            &header,
+            &[(b"PLTE", &[1, 2, 3]), (b"tRNS", &[])],
+            &[0, 0, 7],
+        ))
+        .unwrap();
+        assert_eq!(image.data, [1, 2, 3, 255, 0, 0, 0, 255]);
+        // Chunk length pointing far past the end of data.
+        let mut data = SIGNATURE.to_vec();
+        data.extend_from_slice(&[0xFF, 0xFF, 0xFF, 0xF0, b'I', b'H', b'D', b'R']);
+        assert!(decode_png(&data).is_err());
+        // Flipped bits must never panic.
+        let data = encode_png(&test_image(4, 4)).unwrap();
+        for i in 0..data.len() * 8 {
+            let mut corrupted = data.clone();
+            corrupted[i / 8] ^= 1 << (i % 8);
+            let _ = decode_png(&corrupted);
+        }
+    }
+}
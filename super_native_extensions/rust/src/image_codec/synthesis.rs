@@ -0,0 +1,206 @@
+//! Synthesizing image formats that an item does not provide natively.
+//!
+//! When item exposes at least one image format, every other image format
+//! known for current platform can be produced by decoding the existing data
+//! and encoding it again.
+
+use crate::error::NativeExtensionsResult;
+
+use super::{
+    codec_error, decode_bmp, decode_dib, decode_jpeg, decode_png, decode_tiff, encode_bmp,
+    encode_jpeg, encode_png, encode_tiff,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Codec {
+    Png,
+    Tiff,
+    Bmp,
+    /// Headerless DIB as used by Windows clipboard. Can only be decoded.
+    Dib,
+    Jpeg,
+}
+
+/// Platform image formats. Order determines which format is preferred as
+/// source for conversion; lossless formats come first.
+#[cfg(target_os = "windows")]
+const IMAGE_FORMATS: &[(&str, Codec)] = &[
+    ("PNG", Codec::Png),
+    ("NativeShell_CF_6", Codec::Tiff),
+    ("NativeShell_CF_17", Codec::Dib),
+    ("NativeShell_CF_8", Codec::Dib),
+    ("JFIF", Codec::Jpeg),
+];
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+const IMAGE_FORMATS: &[(&str, Codec)] = &[
+    ("public.png", Codec::Png),
+    ("public.tiff", Codec::Tiff),
+    ("com.microsoft.bmp", Codec::Bmp),
+    ("public.jpeg", Codec::Jpeg),
+];
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "ios")))]
+const IMAGE_FORMATS: &[(&str, Codec)] = &[
+    ("image/png", Codec::Png),
+    ("image/tiff", Codec::Tiff),
+    ("image/bmp", Codec::Bmp),
+    ("image/jpeg", Codec::Jpeg),
+];
+
+/// JPEG quality used when synthesizing JPEG data.
+const JPEG_QUALITY: u8 = 90;
+
+fn codec_for_format(format: &str) -> Option<Codec> {
+    IMAGE_FORMATS
+        .iter()
+        .find(|(name, _)| *name == format)
+        .map(|(_, codec)| *codec)
+}
+
+/// Returns whether the format is an image format that can be used as
+/// conversion source.
+pub fn is_image_format(format: &str) -> bool {
+    codec_for_format(format).is_some()
+}
+
+/// Returns image formats that can be synthesized from `sources` and are not
+/// already present in `formats`.
+pub fn synthesized_image_formats(formats: &[String], sources: &[String]) -> Vec<String> {
+    if !sources.iter().any(|f| is_image_format(f)) {
+        return Vec::new();
+    }
+    IMAGE_FORMATS
+        .iter()
+        .filter(|(name, codec)| *codec != Codec::Dib && !formats.iter().any(|f| f == name))
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+/// Returns the preferred source format from which `format` can be
+/// synthesized, or `None` if `format` is not a synthesizable image format.
+pub fn image_synthesis_source<'a>(format: &str, sources: &'a [String]) -> Option<&'a str> {
+    match codec_for_format(format) {
+        Some(Codec::Dib) | None => return None,
+        Some(_) => {}
+    }
+    IMAGE_FORMATS
+        .iter()
+        .filter(|(name, _)| *name != format)
+        .find_map(|(name, _)| sources.iter().find(|f| f == name))
+        .map(|f| f.as_str())
+}
+
+/// Converts image data from `source` format to `target` format.
+pub fn convert_image(data: &[u8], source: &str, target: &str) -> NativeExtensionsResult<Vec<u8>> {
+    let image = match codec_for_format(source) {
+        Some(Codec::Png) => decode_png(data),
+        Some(Codec::Tiff) => decode_tiff(data),
+        Some(Codec::Bmp) => decode_bmp(data),
+        Some(Codec::Dib) => decode_dib(data),
+        Some(Codec::Jpeg) => decode_jpeg(data),
+        None => Err(codec_error("unsupported source format")),
+    }?;
+    match codec_for_format(target) {
+        Some(Codec::Png) => encode_png(&image),
+        Some(Codec::Tiff) => encode_tiff(&image),
+        Some(Codec::Bmp) => encode_bmp(&image),
+        Some(Codec::Jpeg) => encode_jpeg(&image, JPEG_QUALITY),
+        Some(Codec::Dib) | None => Err(codec_error("unsupported target format")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{api_model::ImageData, image_codec::decode_image};
+
+    use super::{
+        convert_image, image_synthesis_source, synthesized_image_formats, Codec, IMAGE_FORMATS,
+    };
+
+    fn format(codec: Codec) -> String {
+        IMAGE_FORMATS
+            .iter()
+            .find(|(_, c)| *c == codec)
+            .unwrap()
+            .0
+            .to_owned()
+    }
+
+    #[test]
+    fn test_synthesized_formats() {
+        let text = vec!["text/plain".to_owned()];
+        assert!(synthesized_image_formats(&text, &text).is_empty());
+
+        let formats = vec![text[0].clone(), format(Codec::Jpeg), format(Codec::Tiff)];
+        let synthesized = synthesized_image_formats(&formats, &formats);
+        assert!(synthesized.contains(&format(Codec::Png)));
+        assert!(!synthesized.contains(&format(Codec::Jpeg)));
+        assert!(!synthesized.contains(&format(Codec::Tiff)));
+
+        // Lossless source is preferred.
+        assert_eq!(
+            image_synthesis_source(&format(Codec::Png), &formats),
+            Some(format(Codec::Tiff).as_str())
+        );
+        assert_eq!(image_synthesis_source("text/plain", &formats), None);
+    }
+
+    #[test]
+    fn test_convert() {
+        let image = ImageData {
+            width: 2,
+            height: 1,
+            bytes_per_row: 8,
+            data: vec![255, 0, 0, 255, 0, 0, 255, 128],
+            device_pixel_ratio: None,
+        };
+        let tiff = convert_image(
+            &super::encode_png(&image).unwrap(),
+            &format(Codec::Png),
+            &format(Codec::Tiff),
+        )
+        .unwrap();
+        assert_eq!(decode_image(&tiff).unwrap().data, image.data);
+        assert!(convert_image(&[1, 2, 3], &format(Codec::Png), &format(Codec::Jpeg)).is_err());
+    }
+
+    /// Decoding corrupted images must fail gracefully instead of panicking.
+    #[test]
+    fn test_corrupted_input() {
+        let image = ImageData {
+            width: 9,
+            height: 7,
+            bytes_per_row: 36,
+            data: (0..252).map(|v| (v * 13) as u8).collect(),
+            device_pixel_ratio: None,
+        };
+        let encoded = [
+            super::encode_png(&image).unwrap(),
+            super::encode_tiff(&image).unwrap(),
+            super::encode_bmp(&image).unwrap(),
+            super::encode_jpeg(&image, 80).unwrap(),
+        ];
+        // xorshift, deterministic so that failures are reproducible
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        let mut random = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for data in &encoded {
+            for len in 0..data.len() {
+                decode_image(&data[..len]).ok();
+            }
+            for _ in 0..500 {
+                let mut data = data.clone();
+                for _ in 0..1 + random() % 4 {
+                    let index = (random() % data.len() as u64) as usize;
+                    data[index] ^= (random() % 255 + 1) as u8;
+                }
+                decode_image(&data).ok();
+            }
+        }
+    }
+}
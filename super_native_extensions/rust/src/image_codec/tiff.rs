@@ -0,0 +1,794 @@
+//! Baseline TIFF encoder and decoder.
+//!
+//! Encoder writes uncompressed RGBA image. Decoder reads the first image of
+//! the file. Supports strips with no, PackBits or
+//! LZW compression (with optional horizontal predictor), bilevel, grayscale,
+//! palette and RGB images with optional alpha channel.
+
+use crate::{api_model::ImageData, error::NativeExtensionsResult};
+
+use super::{alloc_rgba, check_dimensions, check_image, codec_error, image_from_rgba};
+
+const TAG_IMAGE_WIDTH: u16 = 256;
+const TAG_IMAGE_LENGTH: u16 = 257;
+const TAG_BITS_PER_SAMPLE: u16 = 258;
+const TAG_COMPRESSION: u16 = 259;
+const TAG_PHOTOMETRIC: u16 = 262;
+const TAG_STRIP_OFFSETS: u16 = 273;
+const TAG_SAMPLES_PER_PIXEL: u16 = 277;
+const TAG_ROWS_PER_STRIP: u16 = 278;
+const TAG_STRIP_BYTE_COUNTS: u16 = 279;
+const TAG_PLANAR_CONFIGURATION: u16 = 284;
+const TAG_PREDICTOR: u16 = 317;
+const TAG_COLOR_MAP: u16 = 320;
+const TAG_TILE_WIDTH: u16 = 322;
+const TAG_EXTRA_SAMPLES: u16 = 338;
+
+const COMPRESSION_NONE: u32 = 1;
+const COMPRESSION_LZW: u32 = 5;
+const COMPRESSION_PACKBITS: u32 = 32773;
+
+const PHOTOMETRIC_WHITE_IS_ZERO: u32 = 0;
+const PHOTOMETRIC_BLACK_IS_ZERO: u32 = 1;
+const PHOTOMETRIC_RGB: u32 = 2;
+const PHOTOMETRIC_PALETTE: u32 = 3;
+
+const EXTRA_SAMPLE_ASSOCIATED_ALPHA: u32 = 1;
+const EXTRA_SAMPLE_UNASSOCIATED_ALPHA: u32 = 2;
+
+/// Encodes image as uncompressed little endian TIFF with unassociated alpha.
+pub fn encode_tiff(image: &ImageData) -> NativeExtensionsResult<Vec<u8>> {
+    let (width, height, stride) = check_image(image)?;
+    const ENTRY_COUNT: usize = 11;
+    let ifd_len = 2 + ENTRY_COUNT * 12 + 4;
+    let bits_offset = 8 + ifd_len;
+    let data_offset = bits_offset + 8;
+    let data_len = width * height * 4;
+
+    let mut out = Vec::with_capacity(data_offset + data_len);
+    out.extend_from_slice(b"II*\0");
+    out.extend_from_slice(&8u32.to_le_bytes());
+    out.extend_from_slice(&(ENTRY_COUNT as u16).to_le_bytes());
+    let mut entry = |tag: u16, kind: u16, count: u32, value: u32| {
+        out.extend_from_slice(&tag.to_le_bytes());
+        out.extend_from_slice(&kind.to_le_bytes());
+        out.extend_from_slice(&count.to_le_bytes());
+        out.extend_from_slice(&value.to_le_bytes());
+    };
+    const SHORT: u16 = 3;
+    const LONG: u16 = 4;
+    entry(TAG_IMAGE_WIDTH, LONG, 1, width as u32);
+    entry(TAG_IMAGE_LENGTH, LONG, 1, height as u32);
+    entry(TAG_BITS_PER_SAMPLE, SHORT, 4, bits_offset as u32);
+    entry(TAG_COMPRESSION, SHORT, 1, COMPRESSION_NONE);
+    entry(TAG_PHOTOMETRIC, SHORT, 1, PHOTOMETRIC_RGB);
+    entry(TAG_STRIP_OFFSETS, LONG, 1, data_offset as u32);
+    entry(TAG_SAMPLES_PER_PIXEL, SHORT, 1, 4);
+    entry(TAG_ROWS_PER_STRIP, LONG, 1, height as u32);
+    entry(TAG_STRIP_BYTE_COUNTS, LONG, 1, data_len as u32);
+    entry(TAG_PLANAR_CONFIGURATION, SHORT, 1, 1);
+    entry(TAG_EXTRA_SAMPLES, SHORT, 1, EXTRA_SAMPLE_UNASSOCIATED_ALPHA);
+    out.extend_from_slice(&[0; 4]); // no next IFD
+    for _ in 0..4 {
+        out.extend_from_slice(&8u16.to_le_bytes());
+    }
+    for y in 0..height {
+        out.extend_from_slice(&image.data[y * stride..y * stride + width * 4]);
+    }
+    Ok(out)
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes<const N: usize>(&self, offset: usize) -> NativeExtensionsResult<[u8; N]> {
+        offset
+            .checked_add(N)
+            .and_then(|end| self.data.get(offset..end))
+            .map(|b| b.try_into().unwrap())
+            .ok_or_else(|| codec_error("TIFF data is truncated"))
+    }
+
+    fn u16(&self, offset: usize) -> NativeExtensionsResult<u16> {
+        let bytes = self.bytes(offset)?;
+        Ok(match self.big_endian {
+            true => u16::from_be_bytes(bytes),
+            false => u16::from_le_bytes(bytes),
+        })
+    }
+
+    fn u32(&self, offset: usize) -> NativeExtensionsResult<u32> {
+        let bytes = self.bytes(offset)?;
+        Ok(match self.big_endian {
+            true => u32::from_be_bytes(bytes),
+            false => u32::from_le_bytes(bytes),
+        })
+    }
+
+    /// Reads values of IFD entry. Only integer types are supported, entries
+    /// of other types are returned as empty.
+    fn entry(&self, offset: usize) -> NativeExtensionsResult<(u16, Vec<u32>)> {
+        let tag = self.u16(offset)?;
+        let value_size = match self.u16(offset.saturating_add(2))? {
+            1 | 6 | 7 => 1, // BYTE, SBYTE, UNDEFINED
+            3 | 8 => 2,     // SHORT, SSHORT
+            4 | 9 => 4,     // LONG, SLONG
+            _ => return Ok((tag, Vec::new())),
+        };
+        let count = self.u32(offset.saturating_add(4))? as usize;
+        let total = count
+            .checked_mul(value_size)
+            .ok_or_else(|| codec_error("invalid TIFF entry"))?;
+        let values_offset = if total <= 4 {
+            offset.saturating_add(8)
+        } else {
+            self.u32(offset.saturating_add(8))? as usize
+        };
+        if self.data.len() < values_offset.saturating_add(total) {
+            return Err(codec_error("TIFF data is truncated"));
+        }
+        let values = (0..count)
+            .map(|i| {
+                let offset = values_offset + i * value_size;
+                match value_size {
+                    1 => self.bytes(offset).map(|[v]| v as u32),
+                    2 => self.u16(offset).map(|v| v as u32),
+                    _ => self.u32(offset),
+                }
+            })
+            .collect::<NativeExtensionsResult<_>>()?;
+        Ok((tag, values))
+    }
+}
+
+struct Ifd {
+    entries: Vec<(u16, Vec<u32>)>,
+}
+
+impl Ifd {
+    fn get(&self, tag: u16) -> Option<&[u32]> {
+        self.entries
+            .iter()
+            .find(|(t, _)| *t == tag)
+            .map(|(_, v)| v.as_slice())
+    }
+
+    fn value(&self, tag: u16, default: Option<u32>) -> NativeExtensionsResult<u32> {
+        self.get(tag)
+            .and_then(|v| v.first().copied())
+            .or(default)
+            .ok_or_else(|| codec_error(&format!("missing TIFF tag {tag}")))
+    }
+}
+
+fn decode_packbits(data: &[u8], expected_len: usize) -> NativeExtensionsResult<Vec<u8>> {
+    // Each pair of input bytes expands to at most 128 bytes; don't trust
+    // expected length of a tiny strip.
+    let mut out = Vec::with_capacity(expected_len.min(data.len().saturating_mul(64)));
+    let mut pos = 0;
+    while let Some(n) = data.get(pos).filter(|_| out.len() < expected_len) {
+        let n = *n as i8;
+        pos += 1;
+        match n {
+            0..=127 => {
+                let len = n as usize + 1;
+                let literal = data
+                    .get(pos..)
+                    .and_then(|rest| rest.get(..len))
+                    .ok_or_else(|| codec_error("PackBits data is truncated"))?;
+                out.extend_from_slice(literal);
+                pos += len;
+            }
+            -127..=-1 => {
+                let value = *data
+                    .get(pos)
+                    .ok_or_else(|| codec_error("PackBits data is truncated"))?;
+                out.resize(out.len() + (1 - n as isize) as usize, value);
+                pos += 1;
+            }
+            _ => {} // -128 is no-op
+        }
+    }
+    Ok(out)
+}
+
+fn decode_lzw(data: &[u8], expected_len: usize) -> NativeExtensionsResult<Vec<u8>> {
+    const CLEAR: usize = 256;
+    const EOI: usize = 257;
+    const FIRST: usize = 258;
+    const MAX_CODES: usize = 4096;
+
+    let mut prefix = vec![0u16; MAX_CODES];
+    let mut suffix = vec![0u8; MAX_CODES];
+    let mut lengths = vec![0usize; MAX_CODES];
+    for i in 0..256 {
+        suffix[i] = i as u8;
+        lengths[i] = 1;
+    }
+    // Codes are at most 12 bits wide so they always index into the tables.
+    let emit = |code: usize, out: &mut Vec<u8>, prefix: &[u16], suffix: &[u8], len: usize| {
+        let start = out.len();
+        out.resize(start + len, 0);
+        let mut code = code;
+        for byte in out[start..].iter_mut().rev() {
+            *byte = suffix.get(code).copied().unwrap_or(0);
+            code = prefix.get(code).copied().unwrap_or(0) as usize;
+        }
+    };
+
+    // Output is limited by expected length, but LZW can expand tiny input
+    // a lot, so let the buffer grow as needed.
+    let mut out = Vec::new();
+    let (mut bit_buf, mut bit_count, mut pos) = (0u32, 0u32, 0usize);
+    let mut width = 9;
+    let mut next_code = FIRST;
+    let mut prev: Option<usize> = None;
+    loop {
+        while bit_count < width {
+            let Some(byte) = data.get(pos) else {
+                break;
+            };
+            bit_buf = (bit_buf << 8) | *byte as u32;
+            bit_count += 8;
+            pos += 1;
+        }
+        if bit_count < width {
+            // Some encoders don't write end of information code.
+            break;
+        }
+        let code = ((bit_buf >> (bit_count - width)) & ((1 << width) - 1)) as usize;
+        bit_count -= width;
+        if code == EOI {
+            break;
+        }
+        if code == CLEAR {
+            next_code = FIRST;
+            width = 9;
+            prev = None;
+            continue;
+        }
+        match prev {
+            None => {
+                if code >= CLEAR {
+                    return Err(codec_error("invalid LZW code"));
+                }
+                out.push(code as u8);
+            }
+            Some(prev) => {
+                let start = out.len();
+                let length = |code: usize| lengths.get(code).copied().unwrap_or(0);
+                if code < next_code {
+                    emit(code, &mut out, &prefix, &suffix, length(code));
+                } else if code == next_code {
+                    emit(prev, &mut out, &prefix, &suffix, length(prev));
+                } else {
+                    return Err(codec_error("invalid LZW code"));
+                }
+                let first = *out
+                    .get(start)
+                    .ok_or_else(|| codec_error("invalid LZW code"))?;
+                if code == next_code {
+                    out.push(first);
+                }
+                if next_code < MAX_CODES {
+                    prefix[next_code] = prev as u16;
+                    suffix[next_code] = first;
+                    lengths[next_code] = length(prev) + 1;
+                    next_code += 1;
+                }
+            }
+        }
+        prev = Some(code);
+        // TIFF LZW switches code width one code early.
+        width = match next_code + 1 {
+            0..=511 => 9,
+            512..=1023 => 10,
+            1024..=2047 => 11,
+            _ => 12,
+        };
+        if out.len() >= expected_len {
+            break;
+        }
+    }
+    Ok(out)
+}
+
+struct Layout {
+    width: usize,
+    height: usize,
+    bits_per_sample: usize,
+    samples_per_pixel: usize,
+    photometric: u32,
+    /// `Some(true)` for premultiplied alpha.
+    alpha: Option<bool>,
+    big_endian: bool,
+}
+
+impl Layout {
+    fn row_len(&self) -> NativeExtensionsResult<usize> {
+        self.width
+            .checked_mul(self.samples_per_pixel * self.bits_per_sample)
+            .map(|bits| bits.div_ceil(8))
+            .ok_or_else(|| codec_error("TIFF image is too large"))
+    }
+
+    /// Returns sample at given index. Rows are at least `row_len` long, so
+    /// the index is always in range; out of range samples read as zero.
+    fn sample(&self, row: &[u8], index: usize) -> u16 {
+        let byte = |i: usize| row.get(i).copied().unwrap_or(0);
+        match self.bits_per_sample {
+            8 => byte(index) as u16,
+            16 => {
+                let bytes = [byte(index * 2), byte(index * 2 + 1)];
+                match self.big_endian {
+                    true => u16::from_be_bytes(bytes),
+                    false => u16::from_le_bytes(bytes),
+                }
+            }
+            bits => {
+                let bit = index * bits;
+                let shift = 8 - bits - bit % 8;
+                ((byte(bit / 8) >> shift) & ((1u16 << bits) - 1) as u8) as u16
+            }
+        }
+    }
+
+    fn to_u8(&self, sample: u16) -> u8 {
+        match self.bits_per_sample {
+            16 => (sample >> 8) as u8,
+            8 => sample as u8,
+            bits => (sample as u32 * 255 / ((1 << bits) - 1)) as u8,
+        }
+    }
+
+    /// Reverses horizontal differencing predictor in place.
+    fn undo_predictor(&self, row: &mut [u8]) {
+        let spp = self.samples_per_pixel;
+        match self.bits_per_sample {
+            8 => {
+                for i in spp..row.len() {
+                    row[i] = row[i].wrapping_add(row[i - spp]);
+                }
+            }
+            16 => {
+                for i in spp..row.len() / 2 {
+                    let value = self.sample(row, i).wrapping_add(self.sample(row, i - spp));
+                    let bytes = match self.big_endian {
+                        true => value.to_be_bytes(),
+                        false => value.to_le_bytes(),
+                    };
+                    row[i * 2..i * 2 + 2].copy_from_slice(&bytes);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn decode_tiff(data: &[u8]) -> NativeExtensionsResult<ImageData> {
+    let big_endian = match data.get(0..4) {
+        Some(b"II*\0") => false,
+        Some(b"MM\0*") => true,
+        _ => return Err(codec_error("not a TIFF image")),
+    };
+    let reader = Reader { data, big_endian };
+    let ifd_offset = reader.u32(4)? as usize;
+    let entry_count = reader.u16(ifd_offset)? as usize;
+    let entries = (0..entry_count)
+        .map(|i| reader.entry(ifd_offset.saturating_add(2 + i * 12)))
+        .collect::<NativeExtensionsResult<_>>()?;
+    let ifd = Ifd { entries };
+
+    if ifd.get(TAG_TILE_WIDTH).is_some() {
+        return Err(codec_error("tiled TIFF images are not supported"));
+    }
+    if ifd.value(TAG_PLANAR_CONFIGURATION, Some(1))? != 1 {
+        return Err(codec_error("planar TIFF images are not supported"));
+    }
+    let width = ifd.value(TAG_IMAGE_WIDTH, None)?;
+    let height = ifd.value(TAG_IMAGE_LENGTH, None)?;
+    check_dimensions(width, height)?;
+    let samples_per_pixel = ifd.value(TAG_SAMPLES_PER_PIXEL, Some(1))? as usize;
+    let bits_per_sample = ifd.get(TAG_BITS_PER_SAMPLE).unwrap_or(&[1]);
+    if bits_per_sample.len() != samples_per_pixel {
+        return Err(codec_error("invalid TIFF bits per sample"));
+    }
+    let photometric = ifd.value(TAG_PHOTOMETRIC, None)?;
+    let color_samples = match photometric {
+        PHOTOMETRIC_WHITE_IS_ZERO | PHOTOMETRIC_BLACK_IS_ZERO | PHOTOMETRIC_PALETTE => 1,
+        PHOTOMETRIC_RGB => 3,
+        _ => return Err(codec_error("unsupported TIFF photometric interpretation")),
+    };
+    let bits = bits_per_sample[0] as usize;
+    // Sub-byte samples are only supported for single sample pixels.
+    let valid_bits = match photometric {
+        PHOTOMETRIC_PALETTE => matches!(bits, 1 | 2 | 4 | 8),
+        _ if samples_per_pixel > 1 => matches!(bits, 8 | 16),
+        _ => matches!(bits, 1 | 2 | 4 | 8 | 16),
+    };
+    if !valid_bits
+        || samples_per_pixel < color_samples
+        || samples_per_pixel > 4
+        || bits_per_sample.iter().any(|b| *b as usize != bits)
+    {
+        return Err(codec_error("unsupported TIFF sample format"));
+    }
+    let alpha = if samples_per_pixel > color_samples {
+        match ifd.get(TAG_EXTRA_SAMPLES).and_then(|v| v.first()) {
+            Some(&EXTRA_SAMPLE_ASSOCIATED_ALPHA) => Some(true),
+            Some(&EXTRA_SAMPLE_UNASSOCIATED_ALPHA) => Some(false),
+            _ => None,
+        }
+    } else {
+        None
+    };
+    let layout = Layout {
+        width: width as usize,
+        height: height as usize,
+        bits_per_sample: bits,
+        samples_per_pixel,
+        photometric,
+        alpha,
+        big_endian,
+    };
+    let color_map = if photometric == PHOTOMETRIC_PALETTE {
+        let map = ifd
+            .get(TAG_COLOR_MAP)
+            .ok_or_else(|| codec_error("missing TIFF color map"))?;
+        if map.len() != 3 << bits {
+            return Err(codec_error("invalid TIFF color map"));
+        }
+        map
+    } else {
+        &[]
+    };
+
+    // Decompress strips into continuous buffer.
+    let compression = ifd.value(TAG_COMPRESSION, Some(COMPRESSION_NONE))?;
+    let predictor = ifd.value(TAG_PREDICTOR, Some(1))?;
+    let rows_per_strip = (ifd.value(TAG_ROWS_PER_STRIP, Some(height))? as usize).max(1);
+    let offsets = ifd
+        .get(TAG_STRIP_OFFSETS)
+        .ok_or_else(|| codec_error("missing TIFF strip offsets"))?;
+    let byte_counts = ifd
+        .get(TAG_STRIP_BYTE_COUNTS)
+        .ok_or_else(|| codec_error("missing TIFF strip byte counts"))?;
+    let row_len = layout.row_len()?;
+    let image_len = row_len
+        .checked_mul(layout.height)
+        .ok_or_else(|| codec_error("TIFF image is too large"))?;
+    // Not preallocated; strips are validated against available data first.
+    let mut raw = Vec::new();
+    for (offset, count) in offsets.iter().zip(byte_counts) {
+        let rows = rows_per_strip.min(layout.height - raw.len() / row_len);
+        if rows == 0 {
+            break;
+        }
+        let expected_len = rows * row_len;
+        let strip = (*offset as usize)
+            .checked_add(*count as usize)
+            .and_then(|end| data.get(*offset as usize..end))
+            .ok_or_else(|| codec_error("TIFF strip is out of bounds"))?;
+        let mut strip = match compression {
+            COMPRESSION_NONE => strip.to_vec(),
+            COMPRESSION_PACKBITS => decode_packbits(strip, expected_len)?,
+            COMPRESSION_LZW => decode_lzw(strip, expected_len)?,
+            _ => return Err(codec_error("unsupported TIFF compression")),
+        };
+        if strip.len() < expected_len {
+            return Err(codec_error("TIFF strip is truncated"));
+        }
+        strip.truncate(expected_len);
+        if predictor == 2 {
+            strip
+                .chunks_mut(row_len)
+                .for_each(|row| layout.undo_predictor(row));
+        }
+        raw.extend_from_slice(&strip);
+    }
+    if raw.len() < image_len {
+        return Err(codec_error("TIFF image data is truncated"));
+    }
+
+    let mut out = alloc_rgba(layout.width, layout.height)?;
+    for (row, dst) in raw
+        .chunks(row_len)
+        .zip(out.chunks_exact_mut(layout.width * 4))
+    {
+        for (x, dst) in dst.chunks_exact_mut(4).enumerate() {
+            let s = |i: usize| layout.sample(row, x * samples_per_pixel + i);
+            let mut pixel = match photometric {
+                PHOTOMETRIC_RGB => [
+                    layout.to_u8(s(0)),
+                    layout.to_u8(s(1)),
+                    layout.to_u8(s(2)),
+                    255,
+                ],
+                PHOTOMETRIC_PALETTE => {
+                    let index = s(0) as usize;
+                    let size = 1 << bits;
+                    let color = |i: usize| (color_map.get(i).copied().unwrap_or(0) >> 8) as u8;
+                    [
+                        color(index),
+                        color(size + index),
+                        color(2 * size + index),
+                        255,
+                    ]
+                }
+                _ => {
+                    let mut v = layout.to_u8(s(0));
+                    if layout.photometric == PHOTOMETRIC_WHITE_IS_ZERO {
+                        v = 255 - v;
+                    }
+                    [v, v, v, 255]
+                }
+            };
+            if let Some(premultiplied) = layout.alpha {
+                let alpha = layout.to_u8(s(color_samples));
+                pixel[3] = alpha;
+                if premultiplied && alpha > 0 {
+                    for c in &mut pixel[0..3] {
+                        *c = ((*c as u32 * 255 + alpha as u32 / 2) / alpha as u32).min(255) as u8;
+                    }
+                }
+            }
+            dst.copy_from_slice(&pixel);
+        }
+    }
+    Ok(image_from_rgba(layout.width, layout.height, out))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api_model::ImageData;
+
+    use crate::error::NativeExtensionsError;
+
+    use super::{decode_tiff, encode_tiff};
+
+    /// Builds single strip TIFF from (tag, type, values) entries.
+    fn tiff(big_endian: bool, entries: &[(u16, u16, &[u32])], strip: &[u8]) -> Vec<u8> {
+        let u16_bytes = |v: u16| match big_endian {
+            true => v.to_be_bytes(),
+            false => v.to_le_bytes(),
+        };
+        let u32_bytes = |v: u32| match big_endian {
+            true => v.to_be_bytes(),
+            false => v.to_le_bytes(),
+        };
+        let mut out = if big_endian { b"MM\0*" } else { b"II*\0" }.to_vec();
+        out.extend_from_slice(&u32_bytes(8 + strip.len() as u32));
+        out.extend_from_slice(strip);
+        let ifd_len = 2 + 12 * (entries.len() + 2) + 4;
+        let mut extra = Vec::new();
+        out.extend_from_slice(&u16_bytes(entries.len() as u16 + 2));
+        let strip_entries: [(u16, u16, &[u32]); 2] =
+            [(273, 4, &[8]), (279, 4, &[strip.len() as u32])];
+        for (tag, kind, values) in entries.iter().chain(strip_entries.iter()) {
+            out.extend_from_slice(&u16_bytes(*tag));
+            out.extend_from_slice(&u16_bytes(*kind));
+            out.extend_from_slice(&u32_bytes(values.len() as u32));
+            let mut data = Vec::new();
+            for value in *values {
+                match kind {
+                    3 => data.extend_from_slice(&u16_bytes(*value as u16)),
+                    _ => data.extend_from_slice(&u32_bytes(*value)),
+                }
+            }
+            if data.len() <= 4 {
+                data.resize(4, 0);
+                out.extend_from_slice(&data);
+            } else {
+                let offset = 8 + strip.len() + ifd_len + extra.len();
+                out.extend_from_slice(&u32_bytes(offset as u32));
+                extra.extend_from_slice(&data);
+            }
+        }
+        out.extend_from_slice(&[0; 4]);
+        out.extend_from_slice(&extra);
+        out
+    }
+
+    #[test]
+    fn test_uncompressed_rgba() {
+        for big_endian in [false, true] {
+            let data = tiff(
+                big_endian,
+                &[
+                    (256, 3, &[2]),
+                    (257, 3, &[1]),
+                    (258, 3, &[8, 8, 8, 8]),
+                    (262, 3, &[2]),
+                    (277, 3, &[4]),
+                    (338, 3, &[1]),
+                ],
+                // Second pixel has premultiplied alpha
+                &[255, 0, 0, 255, 0, 64, 0, 128],
+            );
+            let image = decode_tiff(&data).unwrap();
+            assert_eq!((image.width, image.height), (2, 1));
+            assert_eq!(image.data, [255, 0, 0, 255, 0, 128, 0, 128]);
+        }
+    }
+
+    #[test]
+    fn test_encode() {
+        let image = ImageData {
+            width: 2,
+            height: 3,
+            bytes_per_row: 12,
+            data: (0..36).map(|v| v * 7).collect(),
+            device_pixel_ratio: None,
+        };
+        let decoded = decode_tiff(&encode_tiff(&image).unwrap()).unwrap();
+        assert_eq!((decoded.width, decoded.height), (2, 3));
+        assert_eq!(
+            decoded.data,
+            image
+                .data
+                .chunks(12)
+                .flat_map(|row| &row[..8])
+                .copied()
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_packbits_gray() {
+        let data = tiff(
+            false,
+            &[
+                (256, 4, &[5]),
+                (257, 4, &[1]),
+                (258, 3, &[8]),
+                (259, 3, &[32773]),
+                (262, 3, &[0]),
+            ],
+            // Four copies of 0x10 followed by literal 0xFF
+            &[0xFD, 0x10, 0x00, 0xFF],
+        );
+        let image = decode_tiff(&data).unwrap();
+        let gray: Vec<u8> = image.data.chunks(4).map(|p| p[0]).collect();
+        assert_eq!(gray, [0xEF, 0xEF, 0xEF, 0xEF, 0x00]);
+    }
+
+    #[test]
+    fn test_lzw_palette() {
+        // Example from section 13 of TIFF 6.0 specification, codes
+        // 256, 7, 258, 8, 8, 258, 6, 6, 257 packed as 9-bit values.
+        let codes = [256u32, 7, 258, 8, 8, 258, 6, 6, 257];
+        let mut strip = Vec::new();
+        let (mut bits, mut count) = (0u64, 0);
+        for code in codes {
+            bits = (bits << 9) | code as u64;
+            count += 9;
+            while count >= 8 {
+                strip.push((bits >> (count - 8)) as u8);
+                count -= 8;
+            }
+        }
+        strip.push((bits << (8 - count)) as u8);
+
+        let mut color_map = vec![0u32; 3 * 256];
+        color_map[6] = 0xFFFF; // red
+        color_map[256 + 7] = 0xFFFF; // green
+        color_map[512 + 8] = 0xFFFF; // blue
+        let data = tiff(
+            true,
+            &[
+                (256, 3, &[9]),
+                (257, 3, &[1]),
+                (258, 3, &[8]),
+                (259, 3, &[5]),
+                (262, 3, &[3]),
+                (320, 3, &color_map),
+            ],
+            &strip,
+        );
+        let image = decode_tiff(&data).unwrap();
+        let colors: Vec<[u8; 3]> = image.data.chunks(4).map(|p| [p[0], p[1], p[2]]).collect();
+        let (r, g, b) = ([255, 0, 0], [0, 255, 0], [0, 0, 255]);
+        assert_eq!(colors, [g, g, g, b, b, g, g, r, r]);
+    }
+
+    #[test]
+    fn test_truncated() {
+        let image = ImageData {
+            width: 3,
+            height: 2,
+            bytes_per_row: 12,
+            data: vec![100; 24],
+            device_pixel_ratio: None,
+        };
+        let data = encode_tiff(&image).unwrap();
+        for len in 0..data.len() {
+            assert!(
+                matches!(
+                    decode_tiff(&data[..len]),
+                    Err(NativeExtensionsError::ImageCodecError(_))
+                ),
+                "length {len}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_malformed() {
+        let rgb: [(u16, u16, &[u32]); 3] = [(256, 3, &[1]), (257, 3, &[1]), (262, 3, &[2])];
+        let decode = |entries: &[(u16, u16, &[u32])]| {
+            let entries: Vec<_> = rgb.iter().chain(entries).copied().collect();
+            decode_tiff(&tiff(false, &entries, &[0; 8]))
+        };
+        let is_codec_error = |res| matches!(res, Err(NativeExtensionsError::ImageCodecError(_)));
+        assert!(decode(&[(258, 3, &[8, 8, 8]), (277, 3, &[3])]).is_ok());
+        // Empty and wrong length bits per sample.
+        assert!(is_codec_error(decode(&[(258, 3, &[]), (277, 3, &[3])])));
+        assert!(is_codec_error(decode(&[(258, 3, &[8]), (277, 3, &[3])])));
+        // Too many samples and sub-byte samples in RGB image.
+        assert!(is_codec_error(decode(&[(258, 3, &[8; 5]), (277, 3, &[5])])));
+        assert!(is_codec_error(decode(&[
+            (258, 3, &[4, 4, 4]),
+            (277, 3, &[3])
+        ])));
+        // Strip offset + count overflows.
+        assert!(is_codec_error(decode(&[
+            (258, 3, &[8, 8, 8]),
+            (277, 3, &[3]),
+            (273, 4, &[0xFFFF_FFF0]),
+            (279, 4, &[0xFFFF_FFF0]),
+        ])));
+        // Huge image with tiny compressed strips.
+        let huge: [(u16, u16, &[u32]); 4] = [
+            (256, 4, &[0x4000]),
+            (257, 4, &[0x4000]),
+            (258, 3, &[8]),
+            (262, 3, &[1]),
+        ];
+        for compression in [1, 5, 32773] {
+            let compression = [compression];
+            let entries: Vec<_> = huge
+                .iter()
+                .copied()
+                .chain([(259, 3, &compression[..])])
+                .collect();
+            assert!(is_codec_error(decode_tiff(&tiff(
+                false,
+                &entries,
+                &[0x80, 0xFF, 0x00]
+            ))));
+        }
+        // Invalid LZW codes: code past the table and data without clear code.
+        let gray: Vec<(u16, u16, &[u32])> = vec![
+            (256, 3, &[4]),
+            (257, 3, &[1]),
+            (258, 3, &[8]),
+            (259, 3, &[5]),
+            (262, 3, &[1]),
+        ];
+        assert!(is_codec_error(decode_tiff(&tiff(
+            false,
+            &gray,
+            &[0x80, 0x7F, 0xFF, 0xFF]
+        ))));
+        assert!(is_codec_error(decode_tiff(&tiff(false, &gray, &[0xFF; 4]))));
+        // IFD past the end of data.
+        assert!(is_codec_error(decode_tiff(b"II*\0\xFF\xFF\xFF\xFF")));
+        // Flipped bits must never panic.
+        let image = ImageData {
+            width: 2,
+            height: 2,
+            bytes_per_row: 8,
+            data: vec![50; 16],
+            device_pixel_ratio: None,
+        };
+        let data = encode_tiff(&image).unwrap();
+        for i in 0..data.len() * 8 {
+            let mut corrupted = data.clone();
+            corrupted[i / 8] ^= 1 << (i % 8);
+            let _ = decode_tiff(&corrupted);
+        }
+    }
+}
@@ -0,0 +1,620 @@
+//! Minimal zlib (RFC 1950) / deflate (RFC 1951) implementation used by PNG.
+//!
+//! Decoder supports all block types. Encoder uses LZ77 with fixed Huffman
+//! codes, which is good enough for screenshots and keeps the code small.
+
+use crate::error::{NativeExtensionsError, NativeExtensionsResult};
+
+use super::codec_error;
+
+pub fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 is the largest n for which b can not overflow before reduction.
+    for chunk in data.chunks(5552) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
+
+/// Decompresses zlib stream. Fails if decompressed data would exceed
+/// `max_len` bytes.
+pub fn zlib_decompress(data: &[u8], max_len: usize) -> NativeExtensionsResult<Vec<u8>> {
+    if data.len() < 6 {
+        return Err(codec_error("zlib stream too short"));
+    }
+    let (cmf, flg) = (data[0], data[1]);
+    if cmf & 0x0F != 8 || !u16::from_be_bytes([cmf, flg]).is_multiple_of(31) {
+        return Err(codec_error("invalid zlib header"));
+    }
+    if flg & 0x20 != 0 {
+        return Err(codec_error("zlib preset dictionary is not supported"));
+    }
+    let (res, consumed) = inflate(&data[2..], max_len)?;
+    let trailer = data
+        .get(2 + consumed..)
+        .and_then(|rest| rest.get(..4))
+        .ok_or_else(|| codec_error("missing zlib checksum"))?;
+    if !cfg!(fuzzing) && u32::from_be_bytes(trailer.try_into().unwrap()) != adler32(&res) {
+        return Err(codec_error("zlib checksum mismatch"));
+    }
+    Ok(res)
+}
+
+pub fn zlib_compress(data: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter::default();
+    writer.out.extend_from_slice(&[0x78, 0x9C]);
+    deflate(data, &mut writer);
+    let mut res = writer.finish();
+    res.extend_from_slice(&adler32(data).to_be_bytes());
+    res
+}
+
+//
+// Decoder
+//
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// Order in which code length code lengths are stored in dynamic block header.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit_buf: u64,
+    bit_count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            bit_buf: 0,
+            bit_count: 0,
+        }
+    }
+
+    fn refill(&mut self) {
+        while self.bit_count <= 56 {
+            let Some(byte) = self.data.get(self.pos) else {
+                break;
+            };
+            self.bit_buf |= (*byte as u64) << self.bit_count;
+            self.pos += 1;
+            self.bit_count += 8;
+        }
+    }
+
+    /// Returns next `count` bits without consuming them. Missing bits past the
+    /// end of input are zero.
+    fn peek(&mut self, count: u32) -> u32 {
+        if self.bit_count < count {
+            self.refill();
+        }
+        (self.bit_buf & ((1u64 << count) - 1)) as u32
+    }
+
+    fn consume(&mut self, count: u32) -> NativeExtensionsResult<()> {
+        if self.bit_count < count {
+            return Err(codec_error("unexpected end of deflate stream"));
+        }
+        self.bit_buf >>= count;
+        self.bit_count -= count;
+        Ok(())
+    }
+
+    fn bits(&mut self, count: u32) -> NativeExtensionsResult<u32> {
+        let res = self.peek(count);
+        self.consume(count)?;
+        Ok(res)
+    }
+
+    fn align_to_byte(&mut self) {
+        let rem = self.bit_count % 8;
+        self.bit_buf >>= rem;
+        self.bit_count -= rem;
+    }
+
+    /// Number of input bytes consumed so far (after aligning to byte).
+    fn bytes_consumed(&self) -> usize {
+        self.pos - (self.bit_count / 8) as usize
+    }
+
+    fn read_bytes(&mut self, len: usize) -> NativeExtensionsResult<&'a [u8]> {
+        // Only called when aligned; return buffered bytes to the input first.
+        let start = self.bytes_consumed();
+        self.bit_buf = 0;
+        self.bit_count = 0;
+        let res = self
+            .data
+            .get(start..)
+            .and_then(|rest| rest.get(..len))
+            .ok_or_else(|| codec_error("unexpected end of deflate stream"))?;
+        self.pos = start + res.len();
+        Ok(res)
+    }
+}
+
+fn reverse_bits(mut code: u32, len: u32) -> u32 {
+    let mut res = 0;
+    for _ in 0..len {
+        res = (res << 1) | (code & 1);
+        code >>= 1;
+    }
+    res
+}
+
+/// Canonical Huffman decoding table indexed by next `max_len` input bits.
+/// Each entry is `symbol << 4 | code length`.
+struct Huffman {
+    table: Vec<u16>,
+    max_len: u32,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> NativeExtensionsResult<Self> {
+        let max_len = lengths.iter().copied().max().unwrap_or(0) as u32;
+        if max_len == 0 {
+            // Valid for distance codes of blocks that only contain literals.
+            return Ok(Self {
+                table: vec![0; 1],
+                max_len: 0,
+            });
+        }
+        let mut count = [0u32; 16];
+        for len in lengths {
+            count[*len as usize] += 1;
+        }
+        count[0] = 0;
+        let mut next_code = [0u32; 16];
+        let mut code = 0;
+        for len in 1..16 {
+            code = (code + count[len - 1]) << 1;
+            next_code[len] = code;
+        }
+        let mut table = vec![0u16; 1 << max_len];
+        for (symbol, len) in lengths.iter().enumerate() {
+            let len = *len as u32;
+            if len == 0 {
+                continue;
+            }
+            let code = next_code[len as usize];
+            next_code[len as usize] += 1;
+            if code >= 1 << len {
+                return Err(codec_error("invalid Huffman code lengths"));
+            }
+            let entry = ((symbol as u16) << 4) | len as u16;
+            let first = reverse_bits(code, len) as usize;
+            for slot in table.iter_mut().skip(first).step_by(1 << len) {
+                *slot = entry;
+            }
+        }
+        Ok(Self { table, max_len })
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> NativeExtensionsResult<u16> {
+        // Table has 1 << max_len entries, so any peeked value is in range.
+        let entry = self
+            .table
+            .get(reader.peek(self.max_len) as usize)
+            .copied()
+            .unwrap_or(0);
+        let len = (entry & 0xF) as u32;
+        if len == 0 {
+            return Err(codec_error("invalid Huffman code"));
+        }
+        reader.consume(len)?;
+        Ok(entry >> 4)
+    }
+}
+
+fn fixed_lengths() -> ([u8; 288], [u8; 30]) {
+    let mut lit = [8u8; 288];
+    lit[144..256].fill(9);
+    lit[256..280].fill(7);
+    (lit, [5u8; 30])
+}
+
+fn read_dynamic_tables(reader: &mut BitReader) -> NativeExtensionsResult<(Huffman, Huffman)> {
+    let hlit = reader.bits(5)? as usize + 257;
+    let hdist = reader.bits(5)? as usize + 1;
+    let hclen = reader.bits(4)? as usize + 4;
+    let mut code_lengths = [0u8; 19];
+    for index in CODE_LENGTH_ORDER.iter().take(hclen) {
+        code_lengths[*index] = reader.bits(3)? as u8;
+    }
+    let code_length_huffman = Huffman::new(&code_lengths)?;
+    let mut lengths = Vec::with_capacity(hlit + hdist);
+    while lengths.len() < hlit + hdist {
+        let symbol = code_length_huffman.decode(reader)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                let prev = *lengths
+                    .last()
+                    .ok_or_else(|| codec_error("invalid code length repeat"))?;
+                (prev, 3 + reader.bits(2)?)
+            }
+            17 => (0, 3 + reader.bits(3)?),
+            18 => (0, 11 + reader.bits(7)?),
+            _ => return Err(codec_error("invalid code length symbol")),
+        };
+        for _ in 0..repeat {
+            lengths.push(value);
+        }
+    }
+    if lengths.len() != hlit + hdist {
+        return Err(codec_error("code lengths overflow"));
+    }
+    Ok((
+        Huffman::new(&lengths[..hlit])?,
+        Huffman::new(&lengths[hlit..])?,
+    ))
+}
+
+/// Decompresses raw deflate stream. Returns decompressed data and number of
+/// input bytes consumed. Fails if output would exceed `max_len` bytes, which
+/// bounds memory used by highly compressed malicious streams.
+pub fn inflate(data: &[u8], max_len: usize) -> NativeExtensionsResult<(Vec<u8>, usize)> {
+    let mut reader = BitReader::new(data);
+    let mut out = Vec::<u8>::with_capacity(data.len().saturating_mul(4).min(max_len));
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                reader.align_to_byte();
+                let header = reader.read_bytes(4)?;
+                let len = u16::from_le_bytes([header[0], header[1]]);
+                let nlen = u16::from_le_bytes([header[2], header[3]]);
+                if len != !nlen {
+                    return Err(codec_error("invalid stored block length"));
+                }
+                let bytes = reader.read_bytes(len as usize)?;
+                if out.len() + bytes.len() > max_len {
+                    return Err(too_long());
+                }
+                out.extend_from_slice(bytes);
+            }
+            1 => {
+                let (lit, dist) = fixed_lengths();
+                inflate_block(
+                    &mut reader,
+                    &mut out,
+                    max_len,
+                    &Huffman::new(&lit)?,
+                    &Huffman::new(&dist)?,
+                )?;
+            }
+            2 => {
+                let (lit, dist) = read_dynamic_tables(&mut reader)?;
+                inflate_block(&mut reader, &mut out, max_len, &lit, &dist)?;
+            }
+            _ => return Err(codec_error("invalid deflate block type")),
+        }
+        if last {
+            break;
+        }
+    }
+    reader.align_to_byte();
+    Ok((out, reader.bytes_consumed()))
+}
+
+fn too_long() -> NativeExtensionsError {
+    codec_error("decompressed data is too long")
+}
+
+fn inflate_block(
+    reader: &mut BitReader,
+    out: &mut Vec<u8>,
+    max_len: usize,
+    lit: &Huffman,
+    dist: &Huffman,
+) -> NativeExtensionsResult<()> {
+    loop {
+        let symbol = lit.decode(reader)? as usize;
+        match symbol {
+            0..=255 => {
+                if out.len() >= max_len {
+                    return Err(too_long());
+                }
+                out.push(symbol as u8)
+            }
+            256 => return Ok(()),
+            257..=285 => {
+                let index = symbol - 257;
+                let length = LENGTH_BASE.get(index).copied().unwrap_or(0) as usize
+                    + reader.bits(LENGTH_EXTRA.get(index).copied().unwrap_or(0) as u32)? as usize;
+                let dist_symbol = dist.decode(reader)? as usize;
+                let (Some(base), Some(extra)) =
+                    (DIST_BASE.get(dist_symbol), DIST_EXTRA.get(dist_symbol))
+                else {
+                    return Err(codec_error("invalid distance symbol"));
+                };
+                let distance = *base as usize + reader.bits(*extra as u32)? as usize;
+                if distance > out.len() {
+                    return Err(codec_error("distance too far back"));
+                }
+                if out.len() + length > max_len {
+                    return Err(too_long());
+                }
+                // Copy byte by byte, source and destination may overlap.
+                for _ in 0..length {
+                    let byte = out[out.len() - distance];
+                    out.push(byte);
+                }
+            }
+            _ => return Err(codec_error("invalid literal/length symbol")),
+        }
+    }
+}
+
+//
+// Encoder
+//
+
+#[derive(Default)]
+struct BitWriter {
+    out: Vec<u8>,
+    bit_buf: u64,
+    bit_count: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, count: u32) {
+        self.bit_buf |= (value as u64) << self.bit_count;
+        self.bit_count += count;
+        while self.bit_count >= 8 {
+            self.out.push(self.bit_buf as u8);
+            self.bit_buf >>= 8;
+            self.bit_count -= 8;
+        }
+    }
+
+    /// Huffman codes are packed starting with the most significant bit.
+    fn write_code(&mut self, code: u32, len: u32) {
+        self.write(reverse_bits(code, len), len);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bit_count > 0 {
+            self.out.push(self.bit_buf as u8);
+        }
+        self.out
+    }
+}
+
+fn write_fixed_literal(writer: &mut BitWriter, symbol: u32) {
+    match symbol {
+        0..=143 => writer.write_code(0x30 + symbol, 8),
+        144..=255 => writer.write_code(0x190 + symbol - 144, 9),
+        256..=279 => writer.write_code(symbol - 256, 7),
+        _ => writer.write_code(0xC0 + symbol - 280, 8),
+    }
+}
+
+fn write_match(writer: &mut BitWriter, length: usize, distance: usize) {
+    let index = LENGTH_BASE
+        .iter()
+        .rposition(|b| *b as usize <= length)
+        .unwrap();
+    write_fixed_literal(writer, 257 + index as u32);
+    writer.write(
+        (length - LENGTH_BASE[index] as usize) as u32,
+        LENGTH_EXTRA[index] as u32,
+    );
+    let index = DIST_BASE
+        .iter()
+        .rposition(|b| *b as usize <= distance)
+        .unwrap();
+    writer.write_code(index as u32, 5);
+    writer.write(
+        (distance - DIST_BASE[index] as usize) as u32,
+        DIST_EXTRA[index] as u32,
+    );
+}
+
+const WINDOW_SIZE: usize = 32768;
+const HASH_BITS: u32 = 15;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const MAX_CHAIN: usize = 32;
+
+fn hash(data: &[u8]) -> usize {
+    let value = (data[0] as u32) << 16 | (data[1] as u32) << 8 | data[2] as u32;
+    (value.wrapping_mul(2654435761) >> (32 - HASH_BITS)) as usize
+}
+
+/// Writes data as single final block using fixed Huffman codes.
+fn deflate(data: &[u8], writer: &mut BitWriter) {
+    writer.write(1, 1); // BFINAL
+    writer.write(1, 2); // BTYPE = fixed Huffman
+
+    const NONE: usize = usize::MAX;
+    let mut head = vec![NONE; 1 << HASH_BITS];
+    let mut prev = vec![NONE; WINDOW_SIZE];
+    let insert = |pos: usize, head: &mut [usize], prev: &mut [usize]| {
+        if pos + MIN_MATCH <= data.len() {
+            let h = hash(&data[pos..]);
+            prev[pos % WINDOW_SIZE] = head[h];
+            head[h] = pos;
+        }
+    };
+
+    let mut pos = 0;
+    while pos < data.len() {
+        let mut best_len = 0;
+        let mut best_dist = 0;
+        if pos + MIN_MATCH <= data.len() {
+            let max_len = MAX_MATCH.min(data.len() - pos);
+            let mut candidate = head[hash(&data[pos..])];
+            let mut chain = 0;
+            while candidate != NONE && pos - candidate <= WINDOW_SIZE && chain < MAX_CHAIN {
+                let len = data[candidate..]
+                    .iter()
+                    .zip(&data[pos..pos + max_len])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if len > best_len {
+                    best_len = len;
+                    best_dist = pos - candidate;
+                    if len == max_len {
+                        break;
+                    }
+                }
+                let next = prev[candidate % WINDOW_SIZE];
+                // Entries in prev can be overwritten by newer positions.
+                if next == NONE || next >= candidate {
+                    break;
+                }
+                candidate = next;
+                chain += 1;
+            }
+        }
+        if best_len >= MIN_MATCH {
+            write_match(writer, best_len, best_dist);
+            for i in pos..pos + best_len {
+                insert(i, &mut head, &mut prev);
+            }
+            pos += best_len;
+        } else {
+            write_fixed_literal(writer, data[pos] as u32);
+            insert(pos, &mut head, &mut prev);
+            pos += 1;
+        }
+    }
+    write_fixed_literal(writer, 256);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{adler32, zlib_compress, zlib_decompress};
+
+    #[test]
+    fn test_adler32() {
+        assert_eq!(adler32(b"Wikipedia"), 0x11E60398);
+    }
+
+    #[test]
+    fn test_decompress_reference() {
+        // Produced by Python zlib.compress(), covering all three block types.
+        let stored = [
+            0x78, 0x01, 0x01, 0x05, 0x00, 0xFA, 0xFF, 0x68, 0x65, 0x6C, 0x6C, 0x6F, 0x06, 0x2C,
+            0x02, 0x15,
+        ];
+        assert_eq!(zlib_decompress(&stored, usize::MAX).unwrap(), b"hello");
+        let fixed = [
+            0x78, 0xDA, 0x4B, 0x4C, 0xC4, 0x04, 0x49, 0x70, 0x90, 0x0C, 0x02, 0x29, 0x29, 0x0A,
+            0x89, 0x49, 0x45, 0x89, 0xC9, 0x89, 0x29, 0x20, 0x0A, 0x95, 0x4D, 0x91, 0xEE, 0x2A,
+            0x52, 0x01, 0x00, 0x84, 0xE9, 0x45, 0xA9,
+        ];
+        let line = "aaaaaaaaaaaaaaaaaaaabbbbbbbbbbcccccdd abracadabra abracadabra ";
+        let expected = format!("{line}{line}{}", "z".repeat(49));
+        assert_eq!(
+            zlib_decompress(&fixed, usize::MAX).unwrap(),
+            expected.as_bytes()
+        );
+        let dynamic = [
+            0x78, 0xDA, 0x15, 0xC7, 0xB1, 0x09, 0x00, 0x30, 0x0C, 0x03, 0xC1, 0x59, 0x5D, 0x3C,
+            0x28, 0x8D, 0x0C, 0xB6, 0xF6, 0x27, 0x49, 0x73, 0x70, 0x8C, 0x28, 0x0F, 0x38, 0x7A,
+            0x48, 0x2E, 0x60, 0x3F, 0x43, 0x88, 0xAB, 0x6B, 0x0E, 0xFF, 0x26, 0x9D, 0xDE, 0x0B,
+            0x0B, 0x97, 0x14, 0xA2,
+        ];
+        assert_eq!(
+            zlib_decompress(&dynamic, usize::MAX).unwrap(),
+            b"erheanreentheenhhnaeeesaeeeretetnaoarieaeeenetotos"
+        );
+    }
+
+    #[test]
+    fn test_compress() {
+        let text: Vec<u8> = (0..2000u32)
+            .flat_map(|i| format!("line {} {}\n", i % 17, i % 5).into_bytes())
+            .collect();
+        let compressed = zlib_compress(&text);
+        assert!(compressed.len() < text.len() / 4);
+        assert_eq!(zlib_decompress(&compressed, usize::MAX).unwrap(), text);
+    }
+
+    #[test]
+    fn test_round_trip_binary() {
+        let mut state = 1u32;
+        let data: Vec<u8> = (0..100000)
+            .map(|i| {
+                state = state.wrapping_mul(1103515245).wrapping_add(12345);
+                if i % 3 == 0 {
+                    (state >> 24) as u8
+                } else {
+                    (i / 7) as u8
+                }
+            })
+            .collect();
+        assert_eq!(
+            zlib_decompress(&zlib_compress(&data), usize::MAX).unwrap(),
+            data
+        );
+        assert_eq!(
+            zlib_decompress(&zlib_compress(&[]), usize::MAX).unwrap(),
+            b""
+        );
+        let mut corrupted = zlib_compress(&data);
+        let len = corrupted.len();
+        corrupted[len - 1] ^= 1;
+        assert!(zlib_decompress(&corrupted, usize::MAX).is_err());
+    }
+
+    #[test]
+    fn test_output_limit() {
+        let data = vec![0u8; 100000];
+        let compressed = zlib_compress(&data);
+        assert_eq!(zlib_decompress(&compressed, data.len()).unwrap(), data);
+        assert!(zlib_decompress(&compressed, data.len() - 1).is_err());
+        // Stored block
+        let stored = [
+            0x78, 0x01, 0x01, 0x05, 0x00, 0xFA, 0xFF, 0x68, 0x65, 0x6C, 0x6C, 0x6F, 0x06, 0x2C,
+            0x02, 0x15,
+        ];
+        assert!(zlib_decompress(&stored, 4).is_err());
+    }
+
+    #[test]
+    fn test_malformed() {
+        let text: Vec<u8> = (0..500u32)
+            .flat_map(|i| format!("{} {}\n", i % 13, i).into_bytes())
+            .collect();
+        let compressed = zlib_compress(&text);
+        for len in 0..compressed.len() {
+            assert!(zlib_decompress(&compressed[..len], usize::MAX).is_err());
+        }
+        // Flip every bit; decoder must fail gracefully or produce some output.
+        for i in 0..compressed.len() * 8 {
+            let mut corrupted = compressed.clone();
+            corrupted[i / 8] ^= 1 << (i % 8);
+            let _ = zlib_decompress(&corrupted, text.len() * 2);
+        }
+        // Invalid block type, distance too far back.
+        assert!(zlib_decompress(&[0x78, 0x01, 0x07, 0, 0, 0, 0], usize::MAX).is_err());
+        assert!(zlib_decompress(&[0x78, 0x01, 0x03, 0x02, 0, 0, 0, 0], usize::MAX).is_err());
+    }
+}
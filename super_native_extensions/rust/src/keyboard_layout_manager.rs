@@ -1,15 +1,19 @@
 use std::{
     cell::RefCell,
     collections::HashSet,
+    convert::TryInto,
     rc::{Rc, Weak},
 };
 
 use irondash_message_channel::{
-    IntoValue, IsolateId, Late, MethodCall, MethodCallReply, MethodHandler, MethodInvoker,
-    RegisteredMethodHandler, Value,
+    IntoPlatformResult, IntoValue, IsolateId, Late, MethodCall, MethodCallReply, MethodHandler,
+    MethodInvoker, PlatformResult, RegisteredMethodHandler, TryFromValue, Value,
 };
 
-use crate::{context::Context, log::OkLog, platform_impl::platform::PlatformKeyboardLayout};
+use crate::{
+    context::Context, error::NativeExtensionsResult, log::OkLog,
+    platform_impl::platform::PlatformKeyboardLayout,
+};
 
 #[derive(IntoValue, Clone)]
 #[irondash(rename_all = "camelCase")]
@@ -21,12 +25,68 @@ pub struct Key {
     pub logical_alt: Option<i64>,
     pub logical_alt_shift: Option<i64>,
     pub logical_meta: Option<i64>,
+    /// Whether the key is a dead key. For dead keys the logical value is the
+    /// character that the dead key represents (i.e. ´ for dead acute).
+    pub dead: bool,
+    pub dead_shift: bool,
+    pub dead_alt: bool,
+    pub dead_alt_shift: bool,
+}
+
+/// Character produced by typing `base` after dead key.
+#[derive(IntoValue, Clone, Debug, PartialEq)]
+#[irondash(rename_all = "camelCase")]
+pub struct ComposeSequence {
+    /// Logical value of the dead key.
+    pub dead_key: i64,
+    /// Logical value of the key typed after the dead key.
+    pub base: i64,
+    pub result: String,
 }
 
 #[derive(IntoValue, Clone)]
 #[irondash(rename_all = "camelCase")]
 pub struct KeyboardLayout {
     pub keys: Vec<Key>,
+    /// Compose sequences for dead keys in this layout.
+    pub compose: Vec<ComposeSequence>,
+}
+
+/// Describes a keyboard layout installed in the system.
+#[derive(IntoValue, Clone, Debug, PartialEq)]
+#[irondash(rename_all = "camelCase")]
+pub struct KeyboardLayoutInfo {
+    /// Platform specific layout identifier (XKB group index on Linux, HKL on
+    /// Windows, index of enabled keyboard input source on macOS).
+    pub id: i64,
+    /// Human readable layout name.
+    pub name: String,
+    /// Language (or layout code where language is not known) of the layout.
+    pub language: Option<String>,
+    /// Whether this layout is currently active.
+    pub selected: bool,
+}
+
+/// XKB keymap used to build keyboard layout independently of the layouts
+/// installed in the system.
+#[derive(TryFromValue, Debug, Clone)]
+#[irondash(tag = "type", rename_all = "camelCase")]
+pub enum KeymapDescription {
+    /// RMLVO names, i.e. `layout: "de"`, `variant: "nodeadkeys"`.
+    #[irondash(rename_all = "camelCase")]
+    Names {
+        rules: Option<String>,
+        model: Option<String>,
+        layout: String,
+        variant: Option<String>,
+        options: Option<String>,
+    },
+    /// Compiled keymap in XKB text format.
+    #[irondash(rename_all = "camelCase")]
+    Text { keymap: String },
+    /// Path to a file containing compiled keymap in XKB text format.
+    #[irondash(rename_all = "camelCase")]
+    File { path: String },
 }
 
 pub struct KeyboardLayoutManager {
@@ -58,20 +118,55 @@ impl KeyboardLayoutManager {
         }
         .register("KeyboardLayoutManager")
     }
-}
 
-impl MethodHandler for KeyboardLayoutManager {
-    fn on_method_call(&self, call: MethodCall, reply: MethodCallReply) {
-        #[allow(clippy::single_match)]
+    fn get_keyboard_layouts(&self) -> NativeExtensionsResult<Vec<KeyboardLayoutInfo>> {
+        self.platform_layout.get_layouts()
+    }
+
+    /// Returns key table for given layout without activating it.
+    fn get_keyboard_layout_for_id(
+        &self,
+        id: i64,
+    ) -> NativeExtensionsResult<Option<KeyboardLayout>> {
+        self.platform_layout.get_layout(id)
+    }
+
+    fn get_keyboard_layout_for_keymap(
+        &self,
+        description: KeymapDescription,
+    ) -> NativeExtensionsResult<KeyboardLayout> {
+        self.platform_layout.get_layout_for_keymap(description)
+    }
+
+    fn select_keyboard_layout(&self, id: i64) -> NativeExtensionsResult<()> {
+        self.platform_layout.select_layout(id)
+    }
+
+    fn on_method_call(&self, call: MethodCall) -> PlatformResult {
         match call.method.as_str() {
             "getKeyboardLayout" => {
                 self.isolates.borrow_mut().insert(call.isolate);
-                let layout = self.platform_layout.get_current_layout();
-                reply.send_ok(layout);
+                Ok(self.platform_layout.get_current_layout().into())
             }
-            _ => {}
+            "getKeyboardLayouts" => self.get_keyboard_layouts().into_platform_result(),
+            "getKeyboardLayoutForId" => self
+                .get_keyboard_layout_for_id(call.args.try_into()?)
+                .into_platform_result(),
+            "getKeyboardLayoutForKeymap" => self
+                .get_keyboard_layout_for_keymap(call.args.try_into()?)
+                .into_platform_result(),
+            "selectKeyboardLayout" => self
+                .select_keyboard_layout(call.args.try_into()?)
+                .into_platform_result(),
+            _ => Ok(Value::Null),
         }
     }
+}
+
+impl MethodHandler for KeyboardLayoutManager {
+    fn on_method_call(&self, call: MethodCall, reply: MethodCallReply) {
+        reply.send(self.on_method_call(call))
+    }
 
     fn assign_weak_self(&self, weak_self: Weak<Self>) {
         let delegate: Weak<dyn KeyboardLayoutDelegate> = weak_self;
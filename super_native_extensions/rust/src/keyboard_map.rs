@@ -0,0 +1,49 @@
+use crate::accelerator::LogicalKey;
+
+include!(concat!(env!("OUT_DIR"), "/generated_keyboard_map.rs"));
+
+/// Returns name of the key that produces given logical value on US layout.
+pub fn key_name_for_logical(logical: i64) -> Option<&'static str> {
+    KEY_NAMES_BY_LOGICAL
+        .binary_search_by_key(&logical, |(value, _)| *value)
+        .ok()
+        .map(|index| KEY_NAMES_BY_LOGICAL[index].1)
+}
+
+/// Returns platform key code for logical key name (i.e. "A", "KeyA" or "Esc").
+/// The key is resolved according to US layout.
+pub fn platform_code_for_logical_key(name: &str) -> Option<i64> {
+    let value = LogicalKey::from_name(name)?.value()?;
+    let key_name = key_name_for_logical(value)?;
+    get_key_map()
+        .into_iter()
+        .find(|entry| entry.name == key_name)
+        .map(|entry| entry.platform)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{get_key_map, key_name_for_logical, platform_code_for_logical_key};
+
+    #[test]
+    fn test_reverse_lookup() {
+        assert_eq!(key_name_for_logical('a' as i64), Some("KeyA"));
+        assert_eq!(key_name_for_logical('1' as i64), Some("Digit1"));
+        assert_eq!(key_name_for_logical(-1), None);
+
+        let platform_code = |name: &str| {
+            get_key_map()
+                .into_iter()
+                .find(|entry| entry.name == name)
+                .map(|entry| entry.platform)
+        };
+        assert!(platform_code("KeyA").is_some());
+        assert_eq!(platform_code_for_logical_key("A"), platform_code("KeyA"));
+        assert_eq!(platform_code_for_logical_key("KeyA"), platform_code("KeyA"));
+        assert_eq!(
+            platform_code_for_logical_key("Esc"),
+            platform_code("Escape")
+        );
+        assert_eq!(platform_code_for_logical_key("Not A Key"), None);
+    }
+}
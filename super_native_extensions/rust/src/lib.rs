@@ -29,6 +29,7 @@ use menu_manager::GetMenuManager;
 use irondash_message_channel::{irondash_init_message_channel_context, FunctionResult};
 use reader_manager::GetDataReaderManager;
 
+mod accelerator;
 mod api_model;
 mod blur;
 mod clipboard_events_manager;
@@ -37,14 +38,22 @@ mod clipboard_writer;
 mod context;
 mod data_provider_manager;
 mod drag_manager;
+mod drag_trace;
+mod drop_auto_scroll;
+mod drop_dwell;
 mod drop_manager;
+mod drop_policy;
 mod error;
 mod hot_key_manager;
+mod image_codec;
 mod keyboard_layout_manager;
+mod keyboard_map;
 mod log;
 mod menu_manager;
 mod reader_manager;
 mod shadow;
+#[cfg(test)]
+mod test_util;
 mod util;
 mod value_coerce;
 mod value_promise;
@@ -52,7 +61,7 @@ mod value_promise;
 #[allow(dead_code)]
 mod segmented_queue;
 
-// #[cfg(not(test))]
+#[cfg(not(feature = "mock"))]
 #[path = "."]
 mod platform_impl {
     #[cfg(any(target_os = "ios", target_os = "macos"))]
@@ -72,17 +81,26 @@ mod platform_impl {
     pub mod platform;
 }
 
-// #[cfg(test)]
-// #[path = "."]
-// mod platform_impl {
-//     #[path = "mock/mod.rs"]
-//     pub mod platform;
-// }
+#[cfg(feature = "mock")]
+#[path = "."]
+mod platform_impl {
+    #[path = "mock/mod.rs"]
+    pub mod platform;
+}
 
 mod platform {
     pub(crate) use super::platform_impl::platform::*;
 }
 
+/// Decoders exposed to fuzz targets in `fuzz/`.
+#[cfg(fuzzing)]
+pub mod fuzzing {
+    pub use crate::image_codec::{
+        decode_bmp, decode_dib, decode_image, decode_jpeg, decode_png, decode_tiff,
+        zlib_decompress,
+    };
+}
+
 struct DataTransferPlugin {
     _context: Context,
 }
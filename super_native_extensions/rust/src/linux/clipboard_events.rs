@@ -1,17 +1,100 @@
-use crate::clipboard_events_manager::{
-    ClipboardEventManagerDelegate, PlatformClipboardEventManagerId,
+use std::{
+    cell::{Cell, RefCell},
+    rc::{Rc, Weak},
 };
-use std::rc::Weak;
 
-pub struct PlatformClipboardEventManager {}
+use gdk::prelude::ObjectExt;
+use gtk::Clipboard;
+use irondash_message_channel::Late;
+
+use crate::{
+    api_model::ClipboardSelection,
+    clipboard_events_manager::{ClipboardEventManagerDelegate, PlatformClipboardEventManagerId},
+    error::NativeExtensionsResult,
+    log::OkLog,
+};
+
+use super::common::clipboard_for_selection;
+
+pub struct PlatformClipboardEventManager {
+    id: PlatformClipboardEventManagerId,
+    delegate: Weak<dyn ClipboardEventManagerDelegate>,
+}
 
 impl PlatformClipboardEventManager {
     pub fn new(
-        _id: PlatformClipboardEventManagerId,
-        _delegate: Weak<dyn ClipboardEventManagerDelegate>,
+        id: PlatformClipboardEventManagerId,
+        delegate: Weak<dyn ClipboardEventManagerDelegate>,
     ) -> Self {
-        Self {}
+        Self { id, delegate }
     }
 
-    pub fn assign_weak_self(&self, _weak: Weak<PlatformClipboardEventManager>) {}
+    pub fn assign_weak_self(&self, weak: Weak<PlatformClipboardEventManager>) {
+        ClipboardMonitor::with(|monitor| monitor.add_listener(weak));
+    }
+
+    fn on_clipboard_changed(&self, change_count: i64) {
+        if let Some(delegate) = self.delegate.upgrade() {
+            delegate.on_clipboard_changed(self.id, change_count);
+        }
+    }
+}
+
+/// Listens to `owner-change` signal of the default clipboard. There is only
+/// one monitor per thread so that all isolates observe the same change count.
+struct ClipboardMonitor {
+    clipboard: Late<Clipboard>,
+    change_count: Cell<i64>,
+    listeners: RefCell<Vec<Weak<PlatformClipboardEventManager>>>,
+}
+
+thread_local! {
+    static MONITOR: Rc<ClipboardMonitor> = Rc::new(ClipboardMonitor {
+        clipboard: Late::new(),
+        change_count: Cell::new(0),
+        listeners: RefCell::new(Vec::new()),
+    });
+}
+
+impl ClipboardMonitor {
+    fn with<F: FnOnce(&Rc<ClipboardMonitor>)>(f: F) {
+        MONITOR.with(f)
+    }
+
+    fn add_listener(self: &Rc<Self>, listener: Weak<PlatformClipboardEventManager>) {
+        if !self.clipboard.is_set() {
+            self.connect().ok_log();
+        }
+        let mut listeners = self.listeners.borrow_mut();
+        listeners.retain(|l| l.strong_count() > 0);
+        listeners.push(listener);
+    }
+
+    fn connect(self: &Rc<Self>) -> NativeExtensionsResult<()> {
+        let clipboard = clipboard_for_selection(ClipboardSelection::Clipboard)?;
+        // gtk-rs does not generate binding for this signal (GdkEventOwnerChange).
+        let weak_self = Rc::downgrade(self);
+        clipboard.connect_local("owner-change", false, move |_| {
+            if let Some(this) = weak_self.upgrade() {
+                this.on_owner_change();
+            }
+            None
+        });
+        self.clipboard.set(clipboard);
+        Ok(())
+    }
+
+    fn on_owner_change(&self) {
+        let change_count = self.change_count.get() + 1;
+        self.change_count.set(change_count);
+        let listeners: Vec<_> = self
+            .listeners
+            .borrow()
+            .iter()
+            .filter_map(|l| l.upgrade())
+            .collect();
+        for listener in listeners {
+            listener.on_clipboard_changed(change_count);
+        }
+    }
 }
@@ -1,17 +1,30 @@
-use std::ffi::{CStr, CString};
+use std::{
+    ffi::{CStr, CString},
+    future::Future,
+    os::raw::c_int,
+    pin::pin,
+    task::{Context, Poll, Waker},
+    time::{Duration, Instant},
+};
 
 use gdk::{
     cairo::{Format, ImageSurface},
     glib::translate::{FromGlibPtrNone, ToGlibPtr, ToGlibPtrMut},
-    Atom, Event, EventType,
+    prelude::ObjectExt,
+    Atom, Display, Event, EventType,
 };
-use gdk_sys::{gdk_atom_intern, gdk_atom_name, GdkAtom};
+use gdk_sys::{gdk_atom_intern, gdk_atom_name, GdkAtom, GdkDisplay};
 use glib_sys::GFALSE;
-use gtk::{TargetEntry, TargetList};
+use gtk::{Clipboard, TargetEntry, TargetList};
 use gtk_sys::{gtk_target_table_new_from_list, gtk_targets_include_text};
+use irondash_run_loop::RunLoop;
+use x11::xlib;
 
-use crate::api_model::ImageData;
-use crate::error::{NativeExtensionsError::OtherError, NativeExtensionsResult};
+use crate::api_model::{ClipboardSelection, ImageData};
+use crate::error::{
+    NativeExtensionsError::{OtherError, UnsupportedOperation},
+    NativeExtensionsResult,
+};
 
 // Use gtk function to set/retrieve text (there are multiple possible format,
 // we don't want to mess with that)
@@ -21,6 +34,49 @@ pub const TYPE_TEXT: &str = "text/plain";
 // URI list, when reading URI list is split into multiple items.
 pub const TYPE_URI: &str = "text/uri-list";
 
+/// GTK gives up on selection transfers that make no progress for this long
+/// (`IDLE_ABORT_TIME`), waiting for data any longer is pointless.
+pub const SELECTION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Pumps the platform run loop until `poll` returns a value or the timeout
+/// elapses. Only meant for GTK callbacks that must provide data before
+/// returning. Returns `None` on timeout.
+pub fn poll_until<T, F>(timeout: Duration, mut poll: F) -> Option<T>
+where
+    F: FnMut() -> Option<T>,
+{
+    let deadline = Instant::now() + timeout;
+    // Ensures that the blocking run loop iteration returns at the deadline.
+    let _wake_up = RunLoop::current().schedule(timeout, || {});
+    loop {
+        if let Some(result) = poll() {
+            return Some(result);
+        }
+        if Instant::now() >= deadline {
+            return None;
+        }
+        RunLoop::current().platform_run_loop.poll_once();
+    }
+}
+
+/// Runs the future while pumping the platform run loop until it completes.
+/// If it doesn't complete within the timeout the future is dropped, which
+/// cancels the pending work, and `None` is returned.
+pub fn wait_for<F: Future>(future: F, timeout: Duration) -> Option<F::Output> {
+    let mut future = pin!(future);
+    // Every completion is delivered through a run loop callback, which ends
+    // the run loop iteration, so the future doesn't need to be woken.
+    poll_until(timeout, || {
+        match future
+            .as_mut()
+            .poll(&mut Context::from_waker(Waker::noop()))
+        {
+            Poll::Ready(result) => Some(result),
+            Poll::Pending => None,
+        }
+    })
+}
+
 pub trait AtomExt {
     fn from_string(s: &str) -> GdkAtom;
     fn to_string(&self) -> String;
@@ -101,6 +157,21 @@ pub fn surface_from_image_data(image: ImageData, opacity: f64) -> ImageSurface {
     res
 }
 
+pub(super) fn clipboard_for_selection(
+    selection: ClipboardSelection,
+) -> NativeExtensionsResult<Clipboard> {
+    unsafe { gtk::set_initialized() };
+    let display = Display::default().ok_or_else(|| OtherError("Display not found".into()))?;
+    match selection {
+        ClipboardSelection::Clipboard => {
+            Clipboard::default(&display).ok_or_else(|| OtherError("Clipboard not found".into()))
+        }
+        ClipboardSelection::Primary => {
+            Ok(Clipboard::for_display(&display, &gdk::SELECTION_PRIMARY))
+        }
+    }
+}
+
 pub(super) fn synthesize_button_up(event: &Event) -> NativeExtensionsResult<Event> {
     if event.event_type() != EventType::ButtonPress
         && event.event_type() != EventType::DoubleButtonPress
@@ -117,3 +188,49 @@ pub(super) fn synthesize_button_up(event: &Event) -> NativeExtensionsResult<Even
     e.type_ = gdk_sys::GDK_BUTTON_RELEASE;
     Ok(event)
 }
+
+extern "C" {
+    fn gdk_x11_display_get_xdisplay(display: *mut GdkDisplay) -> *mut xlib::Display;
+    fn gdk_x11_display_error_trap_push(display: *mut GdkDisplay);
+    fn gdk_x11_display_error_trap_pop(display: *mut GdkDisplay) -> c_int;
+}
+
+/// Connection to the X server used by GDK. Only available when running
+/// under X11 (or XWayland) backend.
+pub(super) struct X11Display {
+    display: Display,
+    pub xdisplay: *mut xlib::Display,
+    pub root: xlib::Window,
+}
+
+impl X11Display {
+    /// Returns the default display if it is an X11 display. Fails with
+    /// [UnsupportedOperation] when there is no display or it isn't X11
+    /// (i.e. Wayland), so that callers can report missing support.
+    pub fn get() -> NativeExtensionsResult<Self> {
+        let display = Display::default().ok_or(UnsupportedOperation)?;
+        if display.type_().name() != "GdkX11Display" {
+            return Err(UnsupportedOperation);
+        }
+        let gdk_display: *mut GdkDisplay = display.to_glib_none().0;
+        let xdisplay = unsafe { gdk_x11_display_get_xdisplay(gdk_display) };
+        if xdisplay.is_null() {
+            return Err(UnsupportedOperation);
+        }
+        let root = unsafe { xlib::XDefaultRootWindow(xdisplay) };
+        Ok(Self {
+            display,
+            xdisplay,
+            root,
+        })
+    }
+
+    /// Runs the closure with X errors trapped. Returns the X error code of
+    /// the first failed request (or 0 if all requests succeeded).
+    pub fn with_error_trap<F: FnOnce()>(&self, f: F) -> c_int {
+        let display: *mut GdkDisplay = self.display.to_glib_none().0;
+        unsafe { gdk_x11_display_error_trap_push(display) };
+        f();
+        unsafe { gdk_x11_display_error_trap_pop(display) }
+    }
+}
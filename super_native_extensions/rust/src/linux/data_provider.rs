@@ -1,37 +1,74 @@
 use std::{
     cell::RefCell,
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     rc::{Rc, Weak},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicI32, Ordering},
+        Arc, Mutex,
+    },
 };
 
-use gdk::{Atom, Display};
+use gdk::Atom;
 
-use gtk::{Clipboard, SelectionData, TargetList};
+use gtk::{SelectionData, TargetList};
 use irondash_message_channel::{IsolateId, Late};
-use irondash_run_loop::RunLoop;
+use log::warn;
+use url::Url;
 
 use crate::{
-    api_model::{DataProvider, DataProviderValueId, DataRepresentation},
+    api_model::{
+        ClipboardSelection, DataProvider, DataProviderValueId, DataRepresentation,
+        VirtualFileStorage,
+    },
     data_provider_manager::{DataProviderHandle, PlatformDataProviderDelegate},
     error::{NativeExtensionsError, NativeExtensionsResult},
     log::OkLog,
+    segmented_queue::SegmentedQueueWriter,
     value_coerce::{CoerceToData, StringFormat},
+    value_promise::ValuePromiseResult,
 };
 
-use super::common::{target_includes_text, TargetListExt, TYPE_TEXT, TYPE_URI};
+use super::{
+    common::{
+        clipboard_for_selection, poll_until, target_includes_text, wait_for, TargetListExt,
+        SELECTION_TIMEOUT, TYPE_TEXT, TYPE_URI,
+    },
+    items::{encode_items, item_target, parse_item_target, ItemFormats, TYPE_ITEMS},
+    virtual_file::{fetch_virtual_file, TemporaryFolder, VirtualFileContent},
+};
+
+static STREAM_ENTRIES: Mutex<BTreeMap<i32, SegmentedQueueWriter>> = Mutex::new(BTreeMap::new());
 
-pub fn platform_stream_write(_handle: i32, _data: &[u8]) -> i32 {
-    0
+pub(super) fn add_stream_entry(writer: SegmentedQueueWriter) -> i32 {
+    static NEXT_STREAM_ENTRY_HANDLE: AtomicI32 = AtomicI32::new(1);
+    let handle = NEXT_STREAM_ENTRY_HANDLE.fetch_add(1, Ordering::Relaxed);
+    STREAM_ENTRIES.lock().unwrap().insert(handle, writer);
+    handle
 }
 
-pub fn platform_stream_close(_handle: i32, _delete: bool) {}
+pub fn platform_stream_write(handle: i32, data: &[u8]) -> i32 {
+    let entries = STREAM_ENTRIES.lock().unwrap();
+    match entries.get(&handle) {
+        Some(entry) => {
+            entry.write(data);
+            1
+        }
+        None => 0,
+    }
+}
+
+pub fn platform_stream_close(handle: i32, _delete: bool) {
+    let entry = STREAM_ENTRIES.lock().unwrap().remove(&handle);
+    if let Some(entry) = entry {
+        entry.close();
+    }
+}
 
 pub struct PlatformDataProvider {
     weak_self: Late<Weak<Self>>,
-    delegate: Weak<dyn PlatformDataProviderDelegate>,
-    isolate_id: IsolateId,
-    data: DataProvider,
+    pub(super) delegate: Weak<dyn PlatformDataProviderDelegate>,
+    pub(super) isolate_id: IsolateId,
+    pub(super) data: DataProvider,
 }
 
 impl PlatformDataProvider {
@@ -54,9 +91,10 @@ impl PlatformDataProvider {
 
     pub async fn write_to_clipboard(
         providers: Vec<(Rc<PlatformDataProvider>, Arc<DataProviderHandle>)>,
+        selection: ClipboardSelection,
     ) -> NativeExtensionsResult<()> {
         let data_object = DataObject::new(providers);
-        data_object.write_to_clipboard()
+        data_object.write_to_clipboard(selection)
     }
 }
 
@@ -68,6 +106,7 @@ struct ProviderEntry {
 pub struct DataObject {
     providers: Vec<ProviderEntry>,
     cache: RefCell<HashMap<DataProviderValueId, Option<Vec<u8>>>>,
+    temporary_folders: RefCell<Vec<TemporaryFolder>>,
 }
 
 impl DataObject {
@@ -81,6 +120,7 @@ impl DataObject {
                 })
                 .collect(),
             cache: RefCell::new(HashMap::new()),
+            temporary_folders: RefCell::new(Vec::new()),
         })
     }
 
@@ -97,7 +137,7 @@ impl DataObject {
         Ok(())
     }
 
-    fn get_data_for_item(&self, item: &PlatformDataProvider, ty: &str) -> Option<Vec<u8>> {
+    fn get_data_for_item(&self, item: &Rc<PlatformDataProvider>, ty: &str) -> Option<Vec<u8>> {
         for data in &item.data.representations {
             match data {
                 DataRepresentation::Simple { format, data } => {
@@ -112,30 +152,112 @@ impl DataObject {
                         }
                         if let Some(delegate) = item.delegate.upgrade() {
                             let promise = delegate.get_lazy_data(item.isolate_id, *id, None);
-                            loop {
-                                if let Some(result) = promise.try_take() {
-                                    match result {
-                                        crate::value_promise::ValuePromiseResult::Ok { value } => {
-                                            let data = value.coerce_to_data(StringFormat::Utf8);
-                                            self.cache.borrow_mut().insert(*id, data.clone());
-                                            return data;
-                                        }
-                                        crate::value_promise::ValuePromiseResult::Cancelled => {
-                                            return None;
-                                        }
-                                    }
+                            // Gives up on providers that don't answer in time.
+                            // Result is not cached so that next request asks
+                            // again.
+                            let result = poll_until(SELECTION_TIMEOUT, || promise.try_take());
+                            return match result {
+                                Some(ValuePromiseResult::Ok { value }) => {
+                                    let data = value.coerce_to_data(StringFormat::Utf8);
+                                    self.cache.borrow_mut().insert(*id, data.clone());
+                                    data
+                                }
+                                Some(ValuePromiseResult::Cancelled) => None,
+                                None => {
+                                    warn!("Lazy data for {format} timed out");
+                                    None
                                 }
-                                RunLoop::current().platform_run_loop.poll_once();
-                            }
+                            };
                         }
                     }
                 }
-                _ => {}
+                DataRepresentation::VirtualFile {
+                    id,
+                    format,
+                    storage_suggestion,
+                } => {
+                    let storage = storage_suggestion.unwrap_or(VirtualFileStorage::TemporaryFile);
+                    // Virtual files stored in temporary files are exposed as
+                    // file URIs, in-memory files directly under their format.
+                    let matches = match storage {
+                        VirtualFileStorage::TemporaryFile => ty == TYPE_URI,
+                        VirtualFileStorage::Memory => format == ty,
+                    };
+                    if matches {
+                        return self.get_virtual_file(item, *id, storage);
+                    }
+                }
             }
         }
         None
     }
 
+    fn get_virtual_file(
+        &self,
+        item: &Rc<PlatformDataProvider>,
+        id: DataProviderValueId,
+        storage: VirtualFileStorage,
+    ) -> Option<Vec<u8>> {
+        if let Some(cached) = self.cache.borrow().get(&id).cloned() {
+            return cached;
+        }
+        // GTK expects selection data to be set before the callback returns.
+        let item = item.clone();
+        let content = wait_for(
+            async move { fetch_virtual_file(&item, id, storage).await },
+            SELECTION_TIMEOUT,
+        );
+        let Some(content) = content else {
+            warn!("Virtual file request timed out");
+            return None;
+        };
+        let data = match content.ok_log()? {
+            VirtualFileContent::File { path, folder } => {
+                // Keep the file around for as long as this data object can be
+                // pasted or dropped, and a while after (see [DataObject::drop]).
+                self.temporary_folders.borrow_mut().push(folder);
+                Url::from_file_path(path).ok()?.as_str().as_bytes().to_vec()
+            }
+            VirtualFileContent::Memory(data) => data,
+        };
+        self.cache.borrow_mut().insert(id, Some(data.clone()));
+        Some(data)
+    }
+
+    /// Formats that can be served for given item.
+    fn formats_for_item(item: &PlatformDataProvider) -> Vec<String> {
+        let mut res = Vec::<String>::new();
+        for repr in &item.data.representations {
+            let format = match repr {
+                DataRepresentation::Simple { format, data: _ } => format.as_str(),
+                DataRepresentation::Lazy { format, id: _ } => format.as_str(),
+                DataRepresentation::VirtualFile {
+                    format,
+                    storage_suggestion: Some(VirtualFileStorage::Memory),
+                    ..
+                } => format.as_str(),
+                DataRepresentation::VirtualFile { .. } => TYPE_URI,
+            };
+            if !res.iter().any(|f| f == format) {
+                res.push(format.into());
+            }
+        }
+        res
+    }
+
+    /// Only lists formats. Data is requested separately through item targets
+    /// so that lazy data and virtual files are only resolved when read.
+    fn get_items_data(&self) -> Vec<u8> {
+        let items: Vec<_> = self
+            .providers
+            .iter()
+            .map(|item| ItemFormats {
+                formats: Self::formats_for_item(&item.provider),
+            })
+            .collect();
+        encode_items(&items)
+    }
+
     pub fn get_data(&self, selection_data: &SelectionData) -> NativeExtensionsResult<()> {
         let target = selection_data.target();
         let is_text = target_includes_text(&target);
@@ -156,6 +278,16 @@ impl DataObject {
                 }
             }
             Self::set_data_(selection_data, &data)?;
+        } else if target == TYPE_ITEMS {
+            Self::set_data_(selection_data, &self.get_items_data())?;
+        } else if let Some((index, format)) = parse_item_target(&target) {
+            let data = self
+                .providers
+                .get(index)
+                .and_then(|item| self.get_data_for_item(&item.provider, format));
+            if let Some(data) = data {
+                selection_data.set(&selection_data.target(), 8, &data);
+            }
         } else if let Some(item) = self.providers.first() {
             if let Some(data) = self.get_data_for_item(&item.provider, &target) {
                 Self::set_data_(selection_data, &data)?;
@@ -164,14 +296,13 @@ impl DataObject {
         Ok(())
     }
 
-    pub fn write_to_clipboard(self: &Rc<Self>) -> NativeExtensionsResult<()> {
-        unsafe { gtk::set_initialized() };
+    pub fn write_to_clipboard(
+        self: &Rc<Self>,
+        selection: ClipboardSelection,
+    ) -> NativeExtensionsResult<()> {
+        let clipboard = clipboard_for_selection(selection)?;
         let list = self.create_target_list();
         let targets = list.get_target_entries();
-        let display = Display::default()
-            .ok_or_else(|| NativeExtensionsError::OtherError("Display not found".into()))?;
-        let clipboard = Clipboard::default(&display)
-            .ok_or_else(|| NativeExtensionsError::OtherError("Clipboard not found".into()))?;
         let self_clone = self.clone();
         clipboard.set_with_data(&targets, move |_, selection_data, _| {
             self_clone.get_data(selection_data).ok_log();
@@ -185,22 +316,44 @@ impl DataObject {
             if ty == TYPE_TEXT {
                 list.add_text_targets(0);
             } else {
-                list.add(&Atom::intern(ty), 0, 0);
+                let atom = Atom::intern(ty);
+                if list.find(&atom).is_none() {
+                    list.add(&atom, 0, 0);
+                }
             }
         }
         if let Some(item) = self.providers.first() {
-            for repr in &item.provider.data.representations {
-                match repr {
-                    DataRepresentation::Simple { format, data: _ } => {
-                        add(&list, format);
-                    }
-                    DataRepresentation::Lazy { format, id: _ } => {
-                        add(&list, format);
-                    }
-                    _ => {}
+            for format in Self::formats_for_item(&item.provider) {
+                add(&list, &format);
+            }
+        }
+        // URIs from all items are merged into single URI list.
+        let has_uri = self
+            .providers
+            .iter()
+            .any(|item| Self::formats_for_item(&item.provider).contains(&TYPE_URI.into()));
+        if has_uri {
+            add(&list, TYPE_URI);
+        }
+        if self.providers.len() > 1 {
+            add(&list, TYPE_ITEMS);
+            for (index, item) in self.providers.iter().enumerate() {
+                for format in Self::formats_for_item(&item.provider) {
+                    add(&list, &item_target(index, &format));
                 }
             }
         }
         list
     }
 }
+
+impl Drop for DataObject {
+    /// Receivers may read virtual files after the drag session ended or the
+    /// clipboard content was replaced (i.e. file managers copy dropped files
+    /// asynchronously), so the files are not removed right away.
+    fn drop(&mut self) {
+        for folder in self.temporary_folders.take() {
+            folder.release();
+        }
+    }
+}
@@ -5,7 +5,7 @@ use std::{
 
 use gdk::{
     glib::{translate::from_glib_none, WeakRef},
-    Atom, DragAction, DragContext,
+    Atom, DragAction, DragContext, ModifierType,
 };
 
 use gtk::{
@@ -19,6 +19,7 @@ use irondash_message_channel::{Late, Value};
 use irondash_run_loop::RunLoop;
 
 use crate::{
+    accelerator::Modifiers,
     api_model::{DropOperation, Point},
     drop_manager::{
         BaseDropEvent, DropEvent, DropItem, DropSessionId, PlatformDropContextDelegate,
@@ -31,7 +32,7 @@ use crate::{
 };
 
 use super::{
-    common::{TargetListExt, TYPE_TEXT, TYPE_URI},
+    common::{TargetListExt, TYPE_TEXT},
     drag_common::DropOperationExt,
     PlatformDataReader, WidgetReader,
 };
@@ -119,6 +120,29 @@ impl PlatformDropContext {
             .ok_or_else(|| NativeExtensionsError::OtherError("View was already released".into()))
     }
 
+    /// Keyboard modifiers currently pressed, as reported by the pointer device.
+    pub fn current_modifiers(&self) -> Modifiers {
+        let state = self
+            .view()
+            .ok()
+            .and_then(|view| {
+                let window = view.window()?;
+                let pointer = view.display().default_seat()?.pointer()?;
+                Some(window.device_position(&pointer).3)
+            })
+            .unwrap_or_else(ModifierType::empty);
+        Modifiers {
+            alt: state.contains(ModifierType::MOD1_MASK),
+            // Device state is not translated to virtual modifiers, so on X11
+            // the Super key is usually only reported as MOD4.
+            meta: state.intersects(
+                ModifierType::META_MASK | ModifierType::SUPER_MASK | ModifierType::MOD4_MASK,
+            ),
+            shift: state.contains(ModifierType::SHIFT_MASK),
+            control: state.contains(ModifierType::CONTROL_MASK),
+        }
+    }
+
     fn delegate(&self) -> NativeExtensionsResult<Rc<dyn PlatformDropContextDelegate>> {
         self.delegate
             .upgrade()
@@ -173,12 +197,7 @@ impl PlatformDropContext {
             items: (0..number_of_items)
                 .map(|i| DropItem {
                     item_id: (i as i64).into(),
-                    formats: reader_info
-                        .targets
-                        .iter()
-                        .filter(|f| i == 0 || *f == TYPE_URI)
-                        .cloned()
-                        .collect(),
+                    formats: reader_info.item_formats.get(i).cloned().unwrap_or_default(),
                     local_data: local_data.get(i).cloned().unwrap_or(Value::Null),
                 })
                 .collect(),
@@ -1,28 +1,254 @@
-use std::rc::Weak;
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    os::raw::{c_int, c_uint},
+    ptr::null_mut,
+    rc::Weak,
+};
+
+use gdk_sys::{
+    gdk_window_add_filter, gdk_window_remove_filter, GdkEvent, GdkFilterReturn, GdkXEvent,
+    GDK_FILTER_CONTINUE, GDK_FILTER_REMOVE,
+};
+use glib_sys::gpointer;
+use irondash_message_channel::Late;
+use x11::{keysym::XK_Num_Lock, xlib};
 
 use crate::{
     error::{NativeExtensionsError, NativeExtensionsResult},
     hot_key_manager::{HotKeyCreateRequest, HotKeyHandle, HotKeyManagerDelegate},
 };
 
-pub struct PlatformHotKeyManager {}
+use super::common::X11Display;
+
+struct HotKey {
+    key_code: c_int,
+    modifiers: c_uint,
+    pressed: Cell<bool>,
+}
+
+pub struct PlatformHotKeyManager {
+    delegate: Weak<dyn HotKeyManagerDelegate>,
+    hot_keys: RefCell<HashMap<HotKeyHandle, HotKey>>,
+    weak_self: Late<Weak<Self>>,
+    /// Weak reference given to the GDK event filter. Released together with
+    /// the filter when the manager is dropped.
+    filter_data: Cell<*mut Weak<PlatformHotKeyManager>>,
+}
+
+/// Modifiers that are relevant for hot key matching.
+const MODIFIER_MASK: c_uint = xlib::ShiftMask | xlib::ControlMask | xlib::Mod1Mask | xlib::Mod4Mask;
 
 impl PlatformHotKeyManager {
-    pub fn new(_delegate: Weak<dyn HotKeyManagerDelegate>) -> Self {
-        Self {}
+    pub fn new(delegate: Weak<dyn HotKeyManagerDelegate>) -> Self {
+        Self {
+            delegate,
+            hot_keys: RefCell::new(HashMap::new()),
+            weak_self: Late::new(),
+            filter_data: Cell::new(null_mut()),
+        }
+    }
+
+    pub fn assign_weak_self(&self, weak: Weak<PlatformHotKeyManager>) {
+        self.weak_self.set(weak);
     }
 
-    pub fn assign_weak_self(&self, _weak: Weak<PlatformHotKeyManager>) {}
+    fn install_filter(&self) {
+        if self.filter_data.get().is_null() {
+            let data = Box::into_raw(Box::new(self.weak_self.clone()));
+            unsafe { gdk_window_add_filter(null_mut(), Some(event_filter), data as gpointer) };
+            self.filter_data.set(data);
+        }
+    }
+
+    /// Returns modifier mask for NumLock key, which depends on the modifier
+    /// mapping of current keyboard.
+    fn num_lock_mask(display: &X11Display) -> c_uint {
+        unsafe {
+            let key_code = xlib::XKeysymToKeycode(display.xdisplay, XK_Num_Lock as _);
+            if key_code == 0 {
+                return 0;
+            }
+            let map = xlib::XGetModifierMapping(display.xdisplay);
+            if map.is_null() {
+                return 0;
+            }
+            let per_modifier = (*map).max_keypermod as usize;
+            let key_codes = std::slice::from_raw_parts((*map).modifiermap, 8 * per_modifier);
+            let mut res = 0;
+            for (i, codes) in key_codes.chunks(per_modifier.max(1)).enumerate() {
+                if codes.contains(&key_code) {
+                    res = 1 << i;
+                    break;
+                }
+            }
+            xlib::XFreeModifiermap(map);
+            res
+        }
+    }
+
+    /// Passive grabs match modifier state exactly, so each hot key needs to be
+    /// grabbed for every combination of lock modifiers.
+    fn lock_modifier_variants(display: &X11Display) -> Vec<c_uint> {
+        let num_lock = Self::num_lock_mask(display);
+        let mut res = vec![0, xlib::LockMask];
+        if num_lock != 0 {
+            res.push(num_lock);
+            res.push(num_lock | xlib::LockMask);
+        }
+        res
+    }
+
+    fn ungrab(display: &X11Display, hot_key: &HotKey) {
+        for lock_modifiers in Self::lock_modifier_variants(display) {
+            unsafe {
+                xlib::XUngrabKey(
+                    display.xdisplay,
+                    hot_key.key_code,
+                    hot_key.modifiers | lock_modifiers,
+                    display.root,
+                );
+            }
+        }
+    }
 
     pub fn create_hot_key(
         &self,
-        _handle: HotKeyHandle,
-        _request: HotKeyCreateRequest,
+        handle: HotKeyHandle,
+        request: HotKeyCreateRequest,
     ) -> NativeExtensionsResult<()> {
-        Err(NativeExtensionsError::UnsupportedOperation)
+        let display = X11Display::get()?;
+        let mut modifiers = 0;
+        if request.alt {
+            modifiers |= xlib::Mod1Mask;
+        }
+        if request.control {
+            modifiers |= xlib::ControlMask;
+        }
+        if request.shift {
+            modifiers |= xlib::ShiftMask;
+        }
+        if request.meta {
+            modifiers |= xlib::Mod4Mask;
+        }
+        let hot_key = HotKey {
+            // On Linux platform code is the X11 hardware keycode.
+            key_code: request.platform_code as c_int,
+            modifiers,
+            pressed: Cell::new(false),
+        };
+        let error = display.with_error_trap(|| {
+            for lock_modifiers in Self::lock_modifier_variants(&display) {
+                unsafe {
+                    xlib::XGrabKey(
+                        display.xdisplay,
+                        hot_key.key_code,
+                        hot_key.modifiers | lock_modifiers,
+                        display.root,
+                        xlib::False,
+                        xlib::GrabModeAsync,
+                        xlib::GrabModeAsync,
+                    );
+                }
+            }
+            unsafe { xlib::XSync(display.xdisplay, xlib::False) };
+        });
+        if error != 0 {
+            display.with_error_trap(|| Self::ungrab(&display, &hot_key));
+            return Err(NativeExtensionsError::OtherError(format!(
+                "Failed to grab hot key (X error {error}), \
+                 it is likely already registered by another application"
+            )));
+        }
+        // Report only the final release when key is held down.
+        unsafe {
+            xlib::XkbSetDetectableAutoRepeat(display.xdisplay, xlib::True, null_mut());
+        }
+        self.install_filter();
+        self.hot_keys.borrow_mut().insert(handle, hot_key);
+        Ok(())
+    }
+
+    pub fn destroy_hot_key(&self, handle: HotKeyHandle) -> NativeExtensionsResult<()> {
+        let hot_key = self.hot_keys.borrow_mut().remove(&handle);
+        if let Some(hot_key) = hot_key {
+            let display = X11Display::get()?;
+            display.with_error_trap(|| Self::ungrab(&display, &hot_key));
+        }
+        Ok(())
     }
 
-    pub fn destroy_hot_key(&self, _handle: HotKeyHandle) -> NativeExtensionsResult<()> {
-        Err(NativeExtensionsError::UnsupportedOperation)
+    fn on_key_event(&self, event: &xlib::XKeyEvent) -> bool {
+        let display = match X11Display::get() {
+            Ok(display) => display,
+            Err(_) => return false,
+        };
+        if event.window != display.root {
+            return false;
+        }
+        let pressed = event.type_ == xlib::KeyPress;
+        let hot_keys = self.hot_keys.borrow();
+        let hot_key = hot_keys.iter().find(|(_, hot_key)| {
+            hot_key.key_code == event.keycode as c_int
+                && if pressed {
+                    hot_key.modifiers == event.state & MODIFIER_MASK
+                } else {
+                    // Modifiers may have been released before the key itself.
+                    hot_key.pressed.get()
+                }
+        });
+        let (handle, hot_key) = match hot_key {
+            Some(hot_key) => hot_key,
+            None => return false,
+        };
+        if hot_key.pressed.replace(pressed) == pressed {
+            // Repeated press (autorepeat could not be made detectable).
+            return true;
+        }
+        let handle = *handle;
+        drop(hot_keys);
+        if let Some(delegate) = self.delegate.upgrade() {
+            if pressed {
+                delegate.on_hot_key_pressed(handle);
+            } else {
+                delegate.on_hot_key_released(handle);
+            }
+        }
+        true
+    }
+}
+
+unsafe extern "C" fn event_filter(
+    xevent: *mut GdkXEvent,
+    _event: *mut GdkEvent,
+    data: gpointer,
+) -> GdkFilterReturn {
+    let event = &*(xevent as *const xlib::XEvent);
+    let event_type = event.get_type();
+    if event_type == xlib::KeyPress || event_type == xlib::KeyRelease {
+        let manager = &*(data as *const Weak<PlatformHotKeyManager>);
+        if let Some(manager) = manager.upgrade() {
+            if manager.on_key_event(&event.key) {
+                return GDK_FILTER_REMOVE;
+            }
+        }
+    }
+    GDK_FILTER_CONTINUE
+}
+
+impl Drop for PlatformHotKeyManager {
+    fn drop(&mut self) {
+        let filter_data = self.filter_data.replace(null_mut());
+        if !filter_data.is_null() {
+            unsafe {
+                gdk_window_remove_filter(null_mut(), Some(event_filter), filter_data as gpointer);
+                drop(Box::from_raw(filter_data));
+            }
+        }
+        if let Ok(display) = X11Display::get() {
+            for hot_key in self.hot_keys.borrow().values() {
+                display.with_error_trap(|| Self::ungrab(&display, hot_key));
+            }
+        }
     }
 }
@@ -0,0 +1,141 @@
+//! Encoding of multiple items in single selection target.
+//!
+//! GTK selection can only carry one value per target, so apart from URI list
+//! only the first item would survive clipboard or drag. When writing more than
+//! one item, data object also offers [TYPE_ITEMS] target with the formats of
+//! every item and one [item_target] per item and format through which the
+//! data is requested on demand. The layout of [TYPE_ITEMS] is (integers are
+//! little endian u32):
+//!
+//! ```text
+//! data:   "SNEI" | version (2) | item count | item * item count
+//! item:   format count | format * format count
+//! format: name length | name (UTF-8)
+//! ```
+
+pub const TYPE_ITEMS: &str = "application/x-super-native-extensions-items";
+
+const ITEM_TARGET_PREFIX: &str = "application/x-super-native-extensions-item-";
+
+const MAGIC: &[u8; 4] = b"SNEI";
+const VERSION: u32 = 2;
+
+/// Target for data of given format of given item.
+pub fn item_target(item: usize, format: &str) -> String {
+    format!("{ITEM_TARGET_PREFIX}{item}/{format}")
+}
+
+/// Returns item index and format for target created with [item_target].
+pub fn parse_item_target(target: &str) -> Option<(usize, &str)> {
+    let (item, format) = target.strip_prefix(ITEM_TARGET_PREFIX)?.split_once('/')?;
+    Some((item.parse().ok()?, format))
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ItemFormats {
+    pub formats: Vec<String>,
+}
+
+pub fn encode_items(items: &[ItemFormats]) -> Vec<u8> {
+    fn write_u32(res: &mut Vec<u8>, value: usize) {
+        res.extend_from_slice(&(value as u32).to_le_bytes());
+    }
+    let mut res = Vec::new();
+    res.extend_from_slice(MAGIC);
+    write_u32(&mut res, VERSION as usize);
+    write_u32(&mut res, items.len());
+    for item in items {
+        write_u32(&mut res, item.formats.len());
+        for format in &item.formats {
+            write_u32(&mut res, format.len());
+            res.extend_from_slice(format.as_bytes());
+        }
+    }
+    res
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.data.len() < len {
+            return None;
+        }
+        let (res, rest) = self.data.split_at(len);
+        self.data = rest;
+        Some(res)
+    }
+
+    fn read_u32(&mut self) -> Option<usize> {
+        let bytes = self.take(4)?;
+        Some(u32::from_le_bytes(bytes.try_into().ok()?) as usize)
+    }
+
+    fn read_bytes(&mut self) -> Option<&'a [u8]> {
+        let len = self.read_u32()?;
+        self.take(len)
+    }
+}
+
+/// Decodes items encoded with [encode_items]. Returns `None` for malformed
+/// data or unknown version.
+pub fn decode_items(data: &[u8]) -> Option<Vec<ItemFormats>> {
+    let mut decoder = Decoder { data };
+    if decoder.take(MAGIC.len())? != MAGIC || decoder.read_u32()? != VERSION as usize {
+        return None;
+    }
+    let item_count = decoder.read_u32()?;
+    let mut items = Vec::new();
+    for _ in 0..item_count {
+        let format_count = decoder.read_u32()?;
+        let mut formats = Vec::new();
+        for _ in 0..format_count {
+            formats.push(String::from_utf8(decoder.read_bytes()?.to_vec()).ok()?);
+        }
+        items.push(ItemFormats { formats });
+    }
+    if !decoder.data.is_empty() {
+        return None;
+    }
+    Some(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_items, encode_items, item_target, parse_item_target, ItemFormats};
+
+    fn item(formats: &[&str]) -> ItemFormats {
+        ItemFormats {
+            formats: formats.iter().map(|f| f.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let items = vec![
+            item(&["text/plain", "text/html"]),
+            item(&[]),
+            item(&["image/png"]),
+        ];
+        let encoded = encode_items(&items);
+        assert_eq!(decode_items(&encoded).unwrap(), items);
+    }
+
+    #[test]
+    fn test_malformed() {
+        let encoded = encode_items(&[item(&["text/plain"])]);
+        assert!(decode_items(&encoded[..encoded.len() - 1]).is_none());
+        assert!(decode_items(&[encoded.as_slice(), &[0]].concat()).is_none());
+        assert!(decode_items(b"SNEX").is_none());
+        assert!(decode_items(&[]).is_none());
+    }
+
+    #[test]
+    fn test_item_target() {
+        let target = item_target(3, "text/html");
+        assert_eq!(parse_item_target(&target), Some((3, "text/html")));
+        assert_eq!(parse_item_target("text/html"), None);
+    }
+}
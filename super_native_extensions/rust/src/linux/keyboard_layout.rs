@@ -1,7 +1,9 @@
 use std::{
     cell::{Cell, RefCell},
-    os::raw::c_ulong,
-    rc::Weak,
+    collections::{HashMap, HashSet},
+    ffi::CStr,
+    os::raw::{c_int, c_uint, c_ulong},
+    rc::{Rc, Weak},
 };
 
 use gdk::{
@@ -10,34 +12,260 @@ use gdk::{
 };
 use gtk::Widget;
 use irondash_message_channel::Late;
+use x11::xlib;
 
 use crate::{
-    keyboard_layout_manager::{Key, KeyboardLayout, KeyboardLayoutDelegate},
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    keyboard_layout_manager::{
+        ComposeSequence, Key, KeyboardLayout, KeyboardLayoutDelegate, KeyboardLayoutInfo,
+        KeymapDescription,
+    },
+    keyboard_map::{get_key_map, KeyMapEntry},
     log::OkLog,
 };
 
-use super::signal::Signal;
+use super::{
+    common::X11Display,
+    signal::Signal,
+    xkb::{dead_key_char, keysym_char, XkbComposeTable, XkbKeymap},
+};
 
 pub struct PlatformKeyboardLayout {
     current_layout: RefCell<Option<KeyboardLayout>>,
     current_group: Cell<u8>,
     delegate: Weak<dyn KeyboardLayoutDelegate>,
     key_press_hook: Late<c_ulong>,
+    /// Compose tables by locale. Loading a table parses the system Compose
+    /// files, so tables (and failures to load them) are cached.
+    compose_tables: RefCell<HashMap<String, Option<Rc<XkbComposeTable>>>>,
 }
 
-include!(concat!(env!("OUT_DIR"), "/generated_keyboard_map.rs"));
+fn create_key(key: gdk::ffi::GdkKeymapKey) -> KeymapKey {
+    unsafe { from_glib_none(&key as *const _) }
+}
 
-fn lookup_key(keymap: &Keymap, key: &KeymapKey) -> Option<i64> {
+fn lookup_keysym(keymap: &Keymap, keycode: u32, group: u8, level: u32) -> Option<u32> {
     // Weird behavior, on SVK keyboard enter returns 'a' and left control returns 'A'.
-    if key.keycode() == 36 || key.keycode() == 37 {
+    if keycode == 36 || keycode == 37 {
         return None;
     }
-    let res = keymap.lookup_key(key)?.to_unicode()? as i64;
-    if res < 0x20 {
-        // ignore control characters
-        return None;
+    let key = create_key(gdk::ffi::GdkKeymapKey {
+        keycode,
+        group: group as _,
+        level: level as _,
+    });
+    keymap.lookup_key(&key).map(|key| *key)
+}
+
+/// Returns logical value for keysym and whether the keysym is a dead key.
+fn keysym_logical(keysym: u32) -> Option<(i64, bool)> {
+    match dead_key_char(keysym) {
+        Some(dead) => Some((dead, true)),
+        None => keysym_char(keysym).map(|c| (c, false)),
     }
-    Some(res)
+}
+
+/// Resolves every dead key in the layout followed by every other key
+/// through the compose table.
+fn compose_sequences(
+    table: &XkbComposeTable,
+    keysyms: &[(u32, i64, bool)],
+) -> Vec<ComposeSequence> {
+    let mut seen = HashSet::new();
+    let mut res = Vec::new();
+    for (dead_keysym, dead_key, _) in keysyms.iter().filter(|k| k.2) {
+        for (keysym, base, _) in keysyms.iter().filter(|k| !k.2) {
+            if !seen.insert((*dead_keysym, *keysym)) {
+                continue;
+            }
+            if let Some(result) = table.compose(&[*dead_keysym, *keysym]) {
+                res.push(ComposeSequence {
+                    dead_key: *dead_key,
+                    base: *base,
+                    result,
+                });
+            }
+        }
+    }
+    res
+}
+
+/// Builds keyboard layout from function returning keysym for given keycode
+/// and shift level. Shared by GDK and XKB keymaps.
+fn build_keyboard_layout<F>(keysym: F, compose: Option<&XkbComposeTable>) -> KeyboardLayout
+where
+    F: Fn(u32, u32) -> Option<u32>,
+{
+    let mut keysyms = Vec::new();
+    let mut lookup = |keycode: u32, level: u32| {
+        let keysym = keysym(keycode, level)?;
+        let (logical, dead) = keysym_logical(keysym)?;
+        keysyms.push((keysym, logical, dead));
+        Some((logical, dead))
+    };
+    let keys = get_key_map()
+        .iter()
+        .map(|entry| {
+            let keycode = entry.platform as u32;
+            let key = lookup(keycode, 0);
+            let key_shift = key.and_then(|_| lookup(keycode, 1));
+            Key {
+                platform: entry.platform,
+                physical: entry.physical,
+                logical: key.map(|k| k.0).or(entry.logical),
+                logical_shift: key_shift.map(|k| k.0),
+                logical_alt: None,
+                logical_alt_shift: None,
+                logical_meta: None,
+                dead: key.map(|k| k.1).unwrap_or(false),
+                dead_shift: key_shift.map(|k| k.1).unwrap_or(false),
+                dead_alt: false,
+                dead_alt_shift: false,
+            }
+        })
+        .collect();
+    let compose = compose
+        .map(|table| compose_sequences(table, &keysyms))
+        .unwrap_or_default();
+    KeyboardLayout { keys, compose }
+}
+
+const XKB_USE_CORE_KBD: c_uint = 0x0100;
+const XKB_SYMBOLS_NAME_MASK: c_uint = 1 << 2;
+const XKB_GROUP_NAMES_MASK: c_uint = 1 << 12;
+
+/// Symbol files that are included by XKB options rather than layouts.
+const XKB_OPTION_SYMBOLS: &[&str] = &[
+    "pc",
+    "inet",
+    "group",
+    "level3",
+    "level5",
+    "ctrl",
+    "compose",
+    "terminate",
+    "altwin",
+    "capslock",
+    "keypad",
+    "kpdl",
+    "eurosign",
+    "lv3",
+    "nbsp",
+    "shift",
+    "srvr_ctrl",
+    "mod_led",
+    "caps",
+    "shift_caps",
+];
+
+/// Extracts layout names from XKB symbols name (i.e. "pc+us+ru:2+inet(evdev)"
+/// becomes ["us", "ru"]). Variants are dropped.
+fn layouts_from_symbols(symbols: &str) -> Vec<String> {
+    let mut res = Vec::<String>::new();
+    for part in symbols.split('+') {
+        let (name, index) = match part.split_once(':') {
+            Some((name, index)) => (name, index.parse::<usize>().ok()),
+            None => (part, None),
+        };
+        let name = name.split('(').next().unwrap_or(name);
+        if name.is_empty() || XKB_OPTION_SYMBOLS.contains(&name) {
+            continue;
+        }
+        // First layout has no explicit index.
+        let index = match index {
+            Some(index) => index,
+            None if res.is_empty() => 1,
+            None => continue,
+        };
+        if index == 0 || index > res.len() + 1 {
+            continue;
+        }
+        if index == res.len() + 1 {
+            res.push(name.into());
+        } else {
+            res[index - 1] = name.into();
+        }
+    }
+    res
+}
+
+/// Group names and layout names from XKB. Only available on X11.
+struct XkbNames {
+    groups: Vec<String>,
+    layouts: Vec<String>,
+}
+
+impl XkbNames {
+    fn get() -> NativeExtensionsResult<Self> {
+        let display = X11Display::get()?;
+        let atom_name = |atom: xlib::Atom| -> Option<String> {
+            if atom == 0 {
+                return None;
+            }
+            unsafe {
+                let name = xlib::XGetAtomName(display.xdisplay, atom);
+                if name.is_null() {
+                    return None;
+                }
+                let res = CStr::from_ptr(name).to_string_lossy().into_owned();
+                xlib::XFree(name as *mut _);
+                Some(res)
+            }
+        };
+        unsafe {
+            let desc = xlib::XkbAllocKeyboard();
+            if desc.is_null() {
+                return Err(NativeExtensionsError::OtherError(
+                    "failed to allocate XKB keyboard".into(),
+                ));
+            }
+            let status = xlib::XkbGetNames(
+                display.xdisplay,
+                XKB_SYMBOLS_NAME_MASK | XKB_GROUP_NAMES_MASK,
+                desc,
+            );
+            let res = if status == xlib::Success as c_int && !(*desc).names.is_null() {
+                let names = &*(*desc).names;
+                Ok(Self {
+                    groups: names.groups.iter().map_while(|a| atom_name(*a)).collect(),
+                    layouts: atom_name(names.symbols)
+                        .map(|s| layouts_from_symbols(&s))
+                        .unwrap_or_default(),
+                })
+            } else {
+                Err(NativeExtensionsError::OtherError(
+                    "failed to get XKB names".into(),
+                ))
+            };
+            xlib::XkbFreeKeyboard(desc, 0, xlib::True);
+            res
+        }
+    }
+}
+
+/// Builds keyboard layout for given layout (group) of XKB keymap. Matches
+/// the layout that would be produced from GDK keymap for the same group.
+fn keyboard_layout_from_xkb(
+    keymap: &XkbKeymap,
+    layout: u32,
+    compose: Option<&XkbComposeTable>,
+) -> KeyboardLayout {
+    build_keyboard_layout(
+        |keycode, level| keymap.keysym(keycode, layout, level),
+        compose,
+    )
+}
+
+/// Builds keyboard layout for given group of GDK keymap.
+fn keyboard_layout_from_gdk(
+    keymap: &Keymap,
+    group: u8,
+    compose: Option<&XkbComposeTable>,
+) -> KeyboardLayout {
+    build_keyboard_layout(
+        |keycode, level| lookup_keysym(keymap, keycode, group, level),
+        compose,
+    )
 }
 
 impl PlatformKeyboardLayout {
@@ -48,9 +276,22 @@ impl PlatformKeyboardLayout {
             current_layout: RefCell::new(None),
             delegate,
             key_press_hook: Late::new(),
+            compose_tables: RefCell::new(HashMap::new()),
         }
     }
 
+    fn compose_table(&self, locale: &str) -> Option<Rc<XkbComposeTable>> {
+        self.compose_tables
+            .borrow_mut()
+            .entry(locale.into())
+            .or_insert_with(|| XkbComposeTable::from_locale(locale).ok_log().map(Rc::new))
+            .clone()
+    }
+
+    fn system_compose_table(&self) -> Option<Rc<XkbComposeTable>> {
+        self.compose_table(&XkbComposeTable::system_locale())
+    }
+
     pub fn get_current_layout(&self) -> Option<KeyboardLayout> {
         Some(
             self.current_layout
@@ -60,16 +301,120 @@ impl PlatformKeyboardLayout {
         )
     }
 
+    fn keymap() -> NativeExtensionsResult<Keymap> {
+        Display::default()
+            .and_then(|display| Keymap::for_display(&display))
+            .ok_or_else(|| NativeExtensionsError::OtherError("keymap not available".into()))
+    }
+
+    /// Number of groups (layouts) in the keymap. GDK does not expose this
+    /// directly, so it is derived from groups that the 'A' key is mapped in.
+    fn group_count(keymap: &Keymap) -> u8 {
+        keymap
+            .entries_for_keycode(38)
+            .iter()
+            .map(|(key, _)| key.group() as u8 + 1)
+            .max()
+            .unwrap_or(1)
+    }
+
+    pub fn get_layouts(&self) -> NativeExtensionsResult<Vec<KeyboardLayoutInfo>> {
+        let keymap = Self::keymap()?;
+        let names = XkbNames::get().ok();
+        let layouts = (0..Self::group_count(&keymap))
+            .map(|group| {
+                let group_index = group as usize;
+                KeyboardLayoutInfo {
+                    id: group as i64,
+                    name: names
+                        .as_ref()
+                        .and_then(|names| names.groups.get(group_index).cloned())
+                        .unwrap_or_else(|| format!("Group {}", group + 1)),
+                    language: names
+                        .as_ref()
+                        .and_then(|names| names.layouts.get(group_index).cloned()),
+                    selected: group == self.current_group.get(),
+                }
+            })
+            .collect();
+        Ok(layouts)
+    }
+
+    pub fn get_layout(&self, id: i64) -> NativeExtensionsResult<Option<KeyboardLayout>> {
+        let keymap = Self::keymap()?;
+        if id < 0 || id >= Self::group_count(&keymap) as i64 {
+            return Ok(None);
+        }
+        let compose = self.system_compose_table();
+        Ok(Some(keyboard_layout_from_gdk(
+            &keymap,
+            id as u8,
+            compose.as_deref(),
+        )))
+    }
+
+    pub fn get_layout_for_keymap(
+        &self,
+        description: KeymapDescription,
+    ) -> NativeExtensionsResult<KeyboardLayout> {
+        let keymap = match description {
+            KeymapDescription::Names {
+                rules,
+                model,
+                layout,
+                variant,
+                options,
+            } => XkbKeymap::from_names(
+                rules.as_deref(),
+                model.as_deref(),
+                Some(&layout),
+                variant.as_deref(),
+                options.as_deref(),
+            )?,
+            KeymapDescription::Text { keymap } => XkbKeymap::from_string(&keymap)?,
+            KeymapDescription::File { path } => {
+                XkbKeymap::from_string(&std::fs::read_to_string(path)?)?
+            }
+        };
+        check_single_layout(&keymap)?;
+        // Use compose table for "C" locale so that the result does not
+        // depend on the environment.
+        let compose = self.compose_table("C");
+        Ok(keyboard_layout_from_xkb(&keymap, 0, compose.as_deref()))
+    }
+
+    pub fn select_layout(&self, id: i64) -> NativeExtensionsResult<()> {
+        let keymap = Self::keymap()?;
+        if id < 0 || id >= Self::group_count(&keymap) as i64 {
+            return Err(NativeExtensionsError::OtherError(format!(
+                "invalid keyboard layout id {id}"
+            )));
+        }
+        let display = X11Display::get()?;
+        let mut success = false;
+        let error = display.with_error_trap(|| unsafe {
+            success = xlib::XkbLockGroup(display.xdisplay, XKB_USE_CORE_KBD, id as c_uint) != 0;
+            xlib::XFlush(display.xdisplay);
+        });
+        if !success || error != 0 {
+            return Err(NativeExtensionsError::OtherError(
+                "failed to lock XKB group".into(),
+            ));
+        }
+        if self.current_group.get() != id as u8 {
+            self.current_group.set(id as u8);
+            self.on_layout_changed();
+        }
+        Ok(())
+    }
+
     fn create_keyboard_layout(&self) -> KeyboardLayout {
         let key_map = get_key_map();
         if let Some(display) = Display::default() {
             if let Some(keymap) = Keymap::for_display(&display) {
                 let group = self.get_group(&keymap);
-                let keys: Vec<Key> = key_map
-                    .iter()
-                    .map(|a| self.key_from_entry(a, &keymap, group))
-                    .collect();
-                return KeyboardLayout { keys };
+                let compose = self.system_compose_table();
+                return keyboard_layout_from_gdk(&keymap, group, compose.as_deref());
             }
         }
 
@@ -99,14 +444,7 @@ impl PlatformKeyboardLayout {
     }
 
     fn is_ascii(&self, keymap: &Keymap, group: u8, code: u32) -> bool {
-        let key = lookup_key(
-            keymap,
-            &Self::create_key(gdk::ffi::GdkKeymapKey {
-                keycode: code,
-                group: group as _,
-                level: 0,
-            }),
-        );
+        let key = lookup_keysym(keymap, code, group, 0).and_then(keysym_char);
         if let Some(key) = key {
             if key < 256 {
                 let char = key as u8 as char;
@@ -148,47 +486,10 @@ impl PlatformKeyboardLayout {
         true
     }
 
-    fn create_key(key: gdk::ffi::GdkKeymapKey) -> gdk::KeymapKey {
-        unsafe { from_glib_none(&key as *const _) }
-    }
-
-    fn key_from_entry(&self, entry: &KeyMapEntry, keymap: &Keymap, group: u8) -> Key {
-        let key = lookup_key(
-            keymap,
-            &Self::create_key(gdk::ffi::GdkKeymapKey {
-                keycode: entry.platform as u32,
-                group: group as _,
-                level: 0,
-            }),
-        );
-
-        let key_shift = if let Some(_key) = key {
-            lookup_key(
-                keymap,
-                &Self::create_key(gdk::ffi::GdkKeymapKey {
-                    keycode: entry.platform as u32,
-                    group: group as _,
-                    level: 1,
-                }),
-            )
-        } else {
-            None
-        };
-
-        Key {
-            platform: entry.platform,
-            physical: entry.physical,
-            logical: key.or(entry.logical),
-            logical_shift: key_shift,
-            logical_alt: None,
-            logical_alt_shift: None,
-            logical_meta: None,
-        }
-    }
-
     fn fallback_map(keys: &[KeyMapEntry]) -> KeyboardLayout {
         KeyboardLayout {
             keys: keys.iter().map(Self::fallback_key_from_entry).collect(),
+            compose: Vec::new(),
         }
     }
 
@@ -201,6 +502,10 @@ impl PlatformKeyboardLayout {
             logical_alt: None,
             logical_alt_shift: None,
             logical_meta: None,
+            dead: false,
+            dead_shift: false,
+            dead_alt: false,
+            dead_alt_shift: false,
         }
     }
 
@@ -286,3 +591,125 @@ impl Drop for PlatformKeyboardLayout {
         }
     }
 }
+
+/// [KeyboardLayout] describes a single layout, keymaps with multiple groups
+/// (i.e. `layout: "us,de"`) are rejected rather than silently using the
+/// first one.
+fn check_single_layout(keymap: &XkbKeymap) -> NativeExtensionsResult<()> {
+    match keymap.num_layouts() {
+        1 => Ok(()),
+        count => Err(NativeExtensionsError::OtherError(format!(
+            "keymap must contain exactly one layout, found {count}"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        check_single_layout, keyboard_layout_from_xkb, layouts_from_symbols, XkbComposeTable,
+        XkbKeymap,
+    };
+
+    #[test]
+    fn test_layouts_from_symbols() {
+        assert_eq!(
+            layouts_from_symbols("pc+us+ru:2+inet(evdev)+group(alt_shift_toggle)"),
+            vec!["us", "ru"]
+        );
+        assert_eq!(
+            layouts_from_symbols("pc+de(nodeadkeys)+cz(qwerty):2+us:3+inet(evdev)"),
+            vec!["de", "cz", "us"]
+        );
+        assert!(layouts_from_symbols("pc+inet(evdev)").is_empty());
+    }
+
+    fn logical(keymap: &XkbKeymap, platform: i64) -> (Option<i64>, Option<i64>) {
+        let layout = keyboard_layout_from_xkb(keymap, 0, None);
+        let key = layout.keys.iter().find(|k| k.platform == platform).unwrap();
+        (key.logical, key.logical_shift)
+    }
+
+    /// Compiles keymap from layout name. Returns `None`, skipping the test,
+    /// when libxkbcommon or XKB data (xkeyboard-config) is not installed.
+    fn keymap(layout: &str) -> Option<XkbKeymap> {
+        match XkbKeymap::from_names(None, None, Some(layout), None, None) {
+            Ok(keymap) => Some(keymap),
+            Err(err) => {
+                eprintln!("skipping test, XKB data not available: {err}");
+                None
+            }
+        }
+    }
+
+    #[test]
+    fn test_keyboard_layout_from_xkb() {
+        let Some(us) = keymap("us") else {
+            return;
+        };
+        assert_eq!(us.num_layouts(), 1);
+        // Y key
+        assert_eq!(logical(&us, 29), (Some('y' as i64), Some('Y' as i64)));
+        // Digit 2
+        assert_eq!(logical(&us, 11), (Some('2' as i64), Some('@' as i64)));
+
+        let de = keymap("de").unwrap();
+        assert_eq!(de.layout_name(0).as_deref(), Some("German"));
+        assert_eq!(logical(&de, 29), (Some('z' as i64), Some('Z' as i64)));
+        assert_eq!(logical(&de, 11), (Some('2' as i64), Some('"' as i64)));
+
+        let minimal = XkbKeymap::from_string(MINIMAL_KEYMAP).unwrap();
+        assert_eq!(logical(&minimal, 38), (Some('q' as i64), Some('Q' as i64)));
+        assert!(XkbKeymap::from_string("xkb_keymap { garbage").is_err());
+    }
+
+    #[test]
+    fn test_multiple_layouts() {
+        let Some(us_de) = keymap("us,de") else {
+            return;
+        };
+        assert_eq!(us_de.num_layouts(), 2);
+        assert!(check_single_layout(&us_de).is_err());
+        assert!(check_single_layout(&keymap("us").unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_dead_keys() {
+        let Some(de) = keymap("de") else {
+            return;
+        };
+        let Ok(compose) = XkbComposeTable::from_locale("C") else {
+            eprintln!("skipping test, compose data not available");
+            return;
+        };
+        let layout = keyboard_layout_from_xkb(&de, 0, Some(&compose));
+
+        // Key right of ß is dead acute / dead grave.
+        let key = layout.keys.iter().find(|k| k.platform == 21).unwrap();
+        assert_eq!(key.logical, Some('\u{b4}' as i64));
+        assert_eq!(key.logical_shift, Some('`' as i64));
+        assert!(key.dead && key.dead_shift);
+
+        let key = layout.keys.iter().find(|k| k.platform == 29).unwrap();
+        assert!(!key.dead && !key.dead_shift);
+
+        let composed = |dead_key: char, base: char| {
+            layout
+                .compose
+                .iter()
+                .find(|c| c.dead_key == dead_key as i64 && c.base == base as i64)
+                .map(|c| c.result.clone())
+        };
+        assert_eq!(composed('\u{b4}', 'e').as_deref(), Some("é"));
+        assert_eq!(composed('`', 'A').as_deref(), Some("À"));
+        assert_eq!(composed('^', 'o').as_deref(), Some("ô"));
+    }
+
+    const MINIMAL_KEYMAP: &str = r#"xkb_keymap {
+    xkb_keycodes { minimum = 8; maximum = 255; <AC01> = 38; };
+    xkb_types { include "complete" };
+    xkb_compat { include "complete" };
+    xkb_symbols { key <AC01> { [ q, Q ] }; };
+};
+"#;
+}
@@ -1,46 +1,208 @@
 use std::{
     cell::{Cell, RefCell},
+    collections::HashMap,
+    ffi::CString,
     fmt::Write,
     rc::{Rc, Weak},
 };
 
 use gdk::{
-    glib::{translate::from_glib_none, WeakRef},
-    prelude::Cast,
+    cairo::{ImageSurface, Operator},
+    glib::{
+        gobject_ffi::GObject,
+        translate::{from_glib_full, from_glib_none, ToGlibPtr},
+        Object, Value, WeakRef,
+    },
+    prelude::{Cast, ObjectExt, ObjectType},
     Event, Gravity, ModifierType, Rectangle,
 };
+use glib_sys::{GFALSE, GTRUE};
 use gtk::{
     traits::{
-        AccelLabelExt, BinExt, BoxExt, CheckMenuItemExt, ContainerExt, GtkMenuExt, GtkMenuItemExt,
-        LabelExt, MenuShellExt, SpinnerExt, WidgetExt,
+        AccelLabelExt, BinExt, BoxExt, CheckMenuItemExt, ContainerExt, GridExt, GtkMenuExt,
+        GtkMenuItemExt, GtkWindowExt, LabelExt, MenuShellExt, OrientableExt, SpinnerExt,
+        StyleContextExt, WidgetExt,
     },
-    Widget,
+    Inhibit, Widget,
+};
+use gtk_sys::{
+    gtk_status_icon_new, gtk_status_icon_set_from_icon_name, gtk_status_icon_set_from_pixbuf,
+    gtk_status_icon_set_tooltip_text, gtk_status_icon_set_visible, GtkStatusIcon, GtkWidget,
 };
-use gtk_sys::GtkWidget;
 use irondash_engine_context::EngineContext;
 use irondash_message_channel::IsolateId;
 use irondash_run_loop::{spawn, util::FutureCompleter};
 
 use crate::{
+    accelerator::{Accelerator, DisplayStyle, Modifiers},
     api_model::{
-        Activator, ImageData, Menu, MenuActionState, MenuElement, MenuImage,
-        ShowContextMenuRequest, ShowContextMenuResponse,
+        ImageData, Menu, MenuAction, MenuActionState, MenuConfiguration, MenuElement, MenuImage,
+        Rect, ShowContextMenuRequest, ShowContextMenuResponse, TargettedImage,
     },
     error::{NativeExtensionsError, NativeExtensionsResult},
     log::OkLog,
     menu_manager::{PlatformMenuContextDelegate, PlatformMenuContextId, PlatformMenuDelegate},
+    shadow::WithShadow,
 };
 
 use super::common::{surface_from_image_data, synthesize_button_up};
 
 pub struct PlatformMenuContext {
+    id: PlatformMenuContextId,
     _delegate: Weak<dyn PlatformMenuContextDelegate>,
     view: WeakRef<Widget>,
+    menu_bar: RefCell<Option<gtk::MenuBar>>,
+    tray: RefCell<Option<StatusIcon>>,
+    preview: RefCell<Option<Rc<MenuPreview>>>,
+}
+
+/// Tray icon backed by GtkStatusIcon, which is not wrapped by gtk-rs.
+/// Clicking the icon shows the menu.
+///
+/// GtkStatusIcon uses the XEmbed system tray, which GNOME and most Wayland
+/// compositors no longer provide; there the icon is not shown at all.
+/// StatusNotifierItem (AppIndicator) would need libayatana-appindicator,
+/// which is not a dependency of the plugin.
+struct StatusIcon {
+    icon: Object,
+    menu: Rc<RefCell<Option<Rc<PlatformMenu>>>>,
+}
+
+impl StatusIcon {
+    fn new() -> Self {
+        let icon: Object = unsafe { from_glib_full(gtk_status_icon_new() as *mut GObject) };
+        let menu = Rc::new(RefCell::new(None::<Rc<PlatformMenu>>));
+        let show_menu = {
+            let menu = menu.clone();
+            move |_: &[Value]| {
+                if let Some(menu) = menu.borrow().as_ref() {
+                    menu.menu.popup_at_pointer(None);
+                }
+                None
+            }
+        };
+        icon.connect_local("activate", false, show_menu.clone());
+        icon.connect_local("popup-menu", false, show_menu);
+        Self { icon, menu }
+    }
+
+    fn status_icon(&self) -> *mut GtkStatusIcon {
+        self.icon.as_ptr() as *mut _
+    }
+
+    fn update(&self, menu: Rc<PlatformMenu>, icon: Option<ImageData>, tooltip: Option<String>) {
+        self.menu.replace(Some(menu));
+        let pixbuf = icon.and_then(|icon| {
+            let (width, height) = (icon.width, icon.height);
+            let surface = surface_from_image_data(icon, 1.0);
+            gdk::pixbuf_get_from_surface(&surface, 0, 0, width, height)
+        });
+        let tooltip = tooltip.and_then(|t| CString::new(t).ok());
+        unsafe {
+            match pixbuf {
+                Some(pixbuf) => {
+                    gtk_status_icon_set_from_pixbuf(self.status_icon(), pixbuf.to_glib_none().0)
+                }
+                None => {
+                    let name = CString::new("application-x-executable").unwrap();
+                    gtk_status_icon_set_from_icon_name(self.status_icon(), name.as_ptr());
+                }
+            }
+            gtk_status_icon_set_tooltip_text(
+                self.status_icon(),
+                tooltip
+                    .as_ref()
+                    .map(|t| t.as_ptr())
+                    .unwrap_or(std::ptr::null()),
+            );
+            gtk_status_icon_set_visible(self.status_icon(), GTRUE);
+        }
+    }
+}
+
+impl Drop for StatusIcon {
+    fn drop(&mut self) {
+        unsafe { gtk_status_icon_set_visible(self.status_icon(), GFALSE) };
+    }
+}
+
+const PREVIEW_SHADOW_RADIUS: i32 = 10;
+const PREVIEW_MENU_SPACING: i32 = 8;
+
+/// Popup window showing menu preview image next to the context menu.
+struct MenuPreview {
+    configuration_id: i64,
+    window: gtk::Window,
+    surface: Rc<RefCell<Option<ImageSurface>>>,
+    /// Menu location in root coordinates. Preview is placed to the left.
+    anchor: (i32, i32),
+}
+
+impl MenuPreview {
+    fn new(configuration_id: i64, anchor: (i32, i32)) -> Self {
+        let window = gtk::Window::new(gtk::WindowType::Popup);
+        window.set_app_paintable(true);
+        if let Some(visual) = WidgetExt::screen(&window).and_then(|s| s.rgba_visual()) {
+            window.set_visual(Some(&visual));
+        }
+        let surface = Rc::new(RefCell::new(None::<ImageSurface>));
+        let surface_clone = surface.clone();
+        window.connect_draw(move |_, cr| {
+            cr.set_operator(Operator::Source);
+            cr.set_source_rgba(0.0, 0.0, 0.0, 0.0);
+            cr.paint().ok_log();
+            if let Some(surface) = surface_clone.borrow().as_ref() {
+                cr.set_source_surface(surface, 0.0, 0.0).ok_log();
+                cr.paint().ok_log();
+            }
+            Inhibit(true)
+        });
+        Self {
+            configuration_id,
+            window,
+            surface,
+            anchor,
+        }
+    }
+
+    fn set_image(&self, image: ImageData) {
+        let image = TargettedImage {
+            rect: Rect::xywh(0.0, 0.0, image.point_width(), image.point_height()),
+            image_data: image,
+        }
+        .with_shadow(PREVIEW_SHADOW_RADIUS);
+        let width = (image.rect.width as i32).max(1);
+        let height = (image.rect.height as i32).max(1);
+        self.surface
+            .replace(Some(surface_from_image_data(image.image_data, 1.0)));
+        self.window.resize(width, height);
+        self.window.move_(
+            (self.anchor.0 - width - PREVIEW_MENU_SPACING).max(0),
+            self.anchor.1,
+        );
+        self.window.queue_draw();
+    }
+
+    /// Returns whether point in root coordinates is inside the preview.
+    fn contains(&self, x: f64, y: f64) -> bool {
+        match self.window.window() {
+            Some(window) => {
+                let (_, origin_x, origin_y) = window.origin();
+                let (x, y) = (x as i32 - origin_x, y as i32 - origin_y);
+                x >= 0 && y >= 0 && x < window.width() && y < window.height()
+            }
+            None => false,
+        }
+    }
 }
 
 pub struct PlatformMenu {
+    isolate: IsolateId,
+    delegate: Weak<dyn PlatformMenuDelegate>,
+    source: RefCell<Menu>,
     menu: gtk::Menu,
     item_selected: Rc<Cell<bool>>,
+    action_items: ActionItems,
 }
 
 impl std::fmt::Debug for PlatformMenu {
@@ -49,15 +211,28 @@ impl std::fmt::Debug for PlatformMenu {
     }
 }
 
+/// Menu item created for an action. Same action can be translated more than
+/// once (context menu and menu bar).
+struct ActionItem {
+    item: WeakRef<gtk::MenuItem>,
+    // Set while item is being updated; GTK activates check menu items when
+    // changing their state.
+    updating: Rc<Cell<bool>>,
+}
+
+type ActionItems = Rc<RefCell<HashMap<i64, Vec<ActionItem>>>>;
+
 struct MenuContext {
     item_selected: Rc<Cell<bool>>,
+    action_items: ActionItems,
     on_menu_open_callbacks: Vec<Box<dyn FnOnce(&gtk::Menu)>>,
 }
 
 impl MenuContext {
-    fn new(item_selected: Rc<Cell<bool>>) -> Self {
+    fn new(item_selected: Rc<Cell<bool>>, action_items: ActionItems) -> Self {
         Self {
             item_selected,
+            action_items,
             on_menu_open_callbacks: Vec::new(),
         }
     }
@@ -68,53 +243,18 @@ impl MenuContext {
 }
 
 impl PlatformMenu {
-    fn activator_label_code(activator: &Activator) -> u32 {
-        let label = activator.trigger.to_lowercase();
-        let value = match label.as_str() {
-            // these must match labels from LogicalKeyboardKey
-            "f1" => gdk_sys::GDK_KEY_F1,
-            "f2" => gdk_sys::GDK_KEY_F2,
-            "f3" => gdk_sys::GDK_KEY_F3,
-            "f4" => gdk_sys::GDK_KEY_F4,
-            "f5" => gdk_sys::GDK_KEY_F5,
-            "f6" => gdk_sys::GDK_KEY_F6,
-            "f7" => gdk_sys::GDK_KEY_F7,
-            "f8" => gdk_sys::GDK_KEY_F8,
-            "f9" => gdk_sys::GDK_KEY_F9,
-            "f10" => gdk_sys::GDK_KEY_F10,
-            "f11" => gdk_sys::GDK_KEY_F11,
-            "f12" => gdk_sys::GDK_KEY_F12,
-            "home" => gdk_sys::GDK_KEY_Home,
-            "end" => gdk_sys::GDK_KEY_End,
-            "insert" => gdk_sys::GDK_KEY_Insert,
-            "delete" => gdk_sys::GDK_KEY_Delete,
-            "backspace" => gdk_sys::GDK_KEY_BackSpace,
-            "page up" => gdk_sys::GDK_KEY_Page_Up,
-            "page down" => gdk_sys::GDK_KEY_Page_Down,
-            "space" => gdk_sys::GDK_KEY_space,
-            "tab" => gdk_sys::GDK_KEY_Tab,
-            "enter" => gdk_sys::GDK_KEY_Return,
-            "arrow up" => gdk_sys::GDK_KEY_Up,
-            "arrow down" => gdk_sys::GDK_KEY_Down,
-            "arrow left" => gdk_sys::GDK_KEY_Left,
-            "arrow right" => gdk_sys::GDK_KEY_Right,
-            _ => label.chars().next().unwrap_or(0 as char) as i32,
-        };
-        value as u32
-    }
-
-    fn activator_modifier_type(activator: &Activator) -> ModifierType {
+    fn modifier_type(modifiers: &Modifiers) -> ModifierType {
         let mut res = ModifierType::empty();
-        if activator.alt {
+        if modifiers.alt {
             res |= ModifierType::MOD1_MASK;
         }
-        if activator.meta {
+        if modifiers.meta {
             res |= ModifierType::META_MASK;
         }
-        if activator.control {
+        if modifiers.control {
             res |= ModifierType::CONTROL_MASK;
         }
-        if activator.shift {
+        if modifiers.shift {
             res |= ModifierType::SHIFT_MASK;
         }
         res
@@ -152,11 +292,12 @@ impl PlatformMenu {
     fn translate_menu(
         menu: &Menu,
         item_selected: Rc<Cell<bool>>,
+        action_items: ActionItems,
         isolate: IsolateId,
         delegate: &Weak<dyn PlatformMenuDelegate>,
     ) -> gtk::Menu {
         let res = gtk::Menu::new();
-        let mut context = MenuContext::new(item_selected);
+        let mut context = MenuContext::new(item_selected, action_items);
         for element in &menu.children {
             let menu_item = Self::translate_menu_element(element, &mut context, isolate, delegate);
             res.add(&menu_item);
@@ -182,72 +323,20 @@ impl PlatformMenu {
     ) -> gtk::MenuItem {
         match element {
             MenuElement::Action(action) => {
-                let item = match &action.state {
-                    MenuActionState::None => gtk::MenuItem::new(),
-                    state => {
-                        let res = gtk::CheckMenuItem::new();
-                        res.set_active(
-                            state == &MenuActionState::CheckOn
-                                || state == &MenuActionState::RadioOn,
-                        );
-                        res.set_inconsistent(state == &MenuActionState::CheckMixed);
-                        res.set_draw_as_radio(
-                            state == &MenuActionState::RadioOn
-                                || state == &MenuActionState::RadioOff,
-                        );
-                        res.upcast()
-                    }
-                };
-
-                let label = if let Some(MenuImage::Image { data }) = &action.image {
-                    let surface = surface_from_image_data(data.clone(), 1.0);
-                    let image = gtk::Image::from_surface(Some(&surface));
-                    let item_box = gtk::Box::new(gtk::Orientation::Horizontal, 6);
-                    item_box.add(&image);
-                    let label = gtk::AccelLabel::new(&Self::convert_mnemonics(
-                        action.title.as_deref().unwrap_or_default(),
-                    ));
-                    label.set_use_underline(true);
-                    label.set_xalign(0.0);
-                    item_box.pack_end(&label, true, true, 0);
-                    item.add(&item_box);
-                    label
-                } else {
-                    item.set_label(&Self::convert_mnemonics(
-                        action.title.as_deref().unwrap_or_default(),
-                    ));
-                    item.child()
-                        .and_then(|c| c.downcast::<gtk::AccelLabel>().ok())
-                        .unwrap()
-                };
-
-                if let Some(activator) = &action.activator {
-                    label.set_accel(
-                        Self::activator_label_code(activator),
-                        Self::activator_modifier_type(activator),
-                    );
-                }
-
-                item.set_sensitive(!action.attributes.disabled);
-
-                let item_selected = context.item_selected.clone();
-                let delegate = delegate.clone();
-                let unique_id = action.unique_id;
-                item.connect_activate(move |_| {
-                    if let Some(delegate) = delegate.upgrade() {
-                        item_selected.set(true);
-                        delegate.on_action(isolate, unique_id);
-                    }
-                });
-                item
+                Self::translate_action(action, context, isolate, delegate)
             }
             MenuElement::Menu(menu) => {
                 let item = gtk::MenuItem::new();
                 item.set_label(&Self::convert_mnemonics(
                     menu.title.as_deref().unwrap_or_default(),
                 ));
-                let submenu =
-                    Self::translate_menu(menu, context.item_selected.clone(), isolate, delegate);
+                let submenu = Self::translate_menu(
+                    menu,
+                    context.item_selected.clone(),
+                    context.action_items.clone(),
+                    isolate,
+                    delegate,
+                );
                 item.set_submenu(Some(&submenu));
                 item
             }
@@ -264,6 +353,7 @@ impl PlatformMenu {
                 let delegate = delegate.clone();
                 let unique_id = deferred.unique_id;
                 let item_selected = context.item_selected.clone();
+                let action_items = context.action_items.clone();
                 context.on_menu_open(move |menu| {
                     if let Some(delegate) = delegate.upgrade() {
                         let menu = menu.clone();
@@ -275,6 +365,7 @@ impl PlatformMenu {
                                 menu,
                                 item_clone,
                                 item_selected,
+                                action_items,
                             )
                             .await;
                         });
@@ -287,6 +378,132 @@ impl PlatformMenu {
         }
     }
 
+    fn translate_action(
+        action: &MenuAction,
+        context: &mut MenuContext,
+        isolate: IsolateId,
+        delegate: &Weak<dyn PlatformMenuDelegate>,
+    ) -> gtk::MenuItem {
+        let item = match &action.state {
+            MenuActionState::None => gtk::MenuItem::new(),
+            _ => gtk::CheckMenuItem::new().upcast(),
+        };
+        let updating = Rc::new(Cell::new(false));
+        Self::update_action_item(&item, action, &updating);
+
+        let item_selected = context.item_selected.clone();
+        let delegate = delegate.clone();
+        let unique_id = action.unique_id;
+        let updating_clone = updating.clone();
+        item.connect_activate(move |_| {
+            if updating_clone.get() {
+                return;
+            }
+            if let Some(delegate) = delegate.upgrade() {
+                item_selected.set(true);
+                delegate.on_action(isolate, unique_id);
+            }
+        });
+
+        let weak = WeakRef::new();
+        weak.set(Some(&item));
+        context
+            .action_items
+            .borrow_mut()
+            .entry(unique_id)
+            .or_default()
+            .push(ActionItem {
+                item: weak,
+                updating,
+            });
+        item
+    }
+
+    /// Sets title, image, accelerator, sensitivity and check state of menu
+    /// item. Existing item content is replaced.
+    fn update_action_item(item: &gtk::MenuItem, action: &MenuAction, updating: &Cell<bool>) {
+        if let Some(child) = item.child() {
+            item.remove(&child);
+        }
+        let image = match &action.image {
+            Some(MenuImage::Image { data }) => Some(data),
+            _ => None,
+        };
+        let accelerator = action
+            .activator
+            .as_ref()
+            .and_then(Accelerator::from_activator);
+        // Accelerator label can only show single keystroke, chords are
+        // displayed as plain text instead.
+        let chord = accelerator
+            .as_ref()
+            .filter(|a| a.is_chord())
+            .map(|a| a.display(DisplayStyle::current()));
+
+        let label = if image.is_some() || chord.is_some() {
+            let item_box = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+            if let Some(data) = image {
+                let surface = surface_from_image_data(data.clone(), 1.0);
+                let image = gtk::Image::from_surface(Some(&surface));
+                item_box.add(&image);
+            }
+            if let Some(chord) = chord {
+                let chord_label = gtk::Label::new(Some(&chord));
+                chord_label.style_context().add_class("dim-label");
+                item_box.pack_end(&chord_label, false, false, 0);
+            }
+            let label = gtk::AccelLabel::new(&Self::convert_mnemonics(
+                action.title.as_deref().unwrap_or_default(),
+            ));
+            label.set_use_underline(true);
+            label.set_xalign(0.0);
+            item_box.pack_end(&label, true, true, 0);
+            item.add(&item_box);
+            label
+        } else {
+            item.set_label(&Self::convert_mnemonics(
+                action.title.as_deref().unwrap_or_default(),
+            ));
+            item.child()
+                .and_then(|c| c.downcast::<gtk::AccelLabel>().ok())
+                .unwrap()
+        };
+
+        if let Some(accelerator) = accelerator.filter(|a| !a.is_chord()) {
+            let stroke = accelerator.first();
+            if let Some(keyval) = stroke.key.gtk_keyval() {
+                label.set_accel(keyval, Self::modifier_type(&stroke.modifiers));
+            }
+        }
+
+        item.set_sensitive(!action.attributes.disabled);
+
+        if let Some(item) = item.downcast_ref::<gtk::CheckMenuItem>() {
+            let state = &action.state;
+            updating.set(true);
+            item.set_active(
+                state == &MenuActionState::CheckOn || state == &MenuActionState::RadioOn,
+            );
+            item.set_inconsistent(state == &MenuActionState::CheckMixed);
+            item.set_draw_as_radio(
+                state == &MenuActionState::RadioOn || state == &MenuActionState::RadioOff,
+            );
+            updating.set(false);
+        }
+    }
+
+    /// Resizes menu window after its content has changed while being shown.
+    fn update_menu_size(menu: &gtk::Menu) {
+        let top_level = menu.toplevel();
+        let win = top_level.as_ref().and_then(|w| w.window());
+        if let (Some(win), Some(top_level)) = (win, top_level) {
+            if win.is_visible() {
+                let natural_size = top_level.preferred_size().1;
+                win.resize(natural_size.width, natural_size.height);
+            }
+        }
+    }
+
     async fn load_deferred_menu_item(
         delegate: Rc<dyn PlatformMenuDelegate>,
         isolate: IsolateId,
@@ -294,6 +511,7 @@ impl PlatformMenu {
         menu: gtk::Menu,
         deferred_item: gtk::MenuItem,
         item_selected: Rc<Cell<bool>>,
+        action_items: ActionItems,
     ) {
         if let Some(result) = delegate.get_deferred_menu(isolate, item_id).await.ok_log() {
             let mut current_index = 0;
@@ -306,7 +524,7 @@ impl PlatformMenu {
             });
 
             if let Some(mut index) = actual_index {
-                let mut context = MenuContext::new(item_selected);
+                let mut context = MenuContext::new(item_selected, action_items);
                 for element in result {
                     let translated = Self::translate_menu_element(
                         &element,
@@ -320,15 +538,7 @@ impl PlatformMenu {
                 }
             }
             menu.remove(&deferred_item);
-
-            let top_level = menu.toplevel();
-            let win = top_level.as_ref().and_then(|w| w.window());
-            if let (Some(win), Some(top_level)) = (win, top_level) {
-                if win.is_visible() {
-                    let natural_size = top_level.preferred_size().1;
-                    win.resize(natural_size.width, natural_size.height);
-                }
-            }
+            Self::update_menu_size(&menu);
         }
     }
 
@@ -338,17 +548,103 @@ impl PlatformMenu {
         menu: Menu,
     ) -> NativeExtensionsResult<Rc<Self>> {
         let item_selected = Rc::new(Cell::new(false));
-        let menu = Self::translate_menu(&menu, item_selected.clone(), isolate, &delegate);
+        let action_items = ActionItems::default();
+        let translated = Self::translate_menu(
+            &menu,
+            item_selected.clone(),
+            action_items.clone(),
+            isolate,
+            &delegate,
+        );
         Ok(Rc::new(Self {
-            menu,
+            isolate,
+            delegate,
+            source: RefCell::new(menu),
+            menu: translated,
             item_selected,
+            action_items,
         }))
     }
+
+    fn find_action_mut(elements: &mut [MenuElement], unique_id: i64) -> Option<&mut MenuAction> {
+        elements.iter_mut().find_map(|element| match element {
+            MenuElement::Action(action) if action.unique_id == unique_id => Some(action),
+            MenuElement::Menu(menu) => Self::find_action_mut(&mut menu.children, unique_id),
+            _ => None,
+        })
+    }
+
+    /// Updates already translated items for given actions, including items
+    /// in menus that are currently shown. Actions are matched by unique id;
+    /// actions that were not translated yet (i.e. inside deferred elements
+    /// not loaded) are ignored.
+    pub fn update_elements(&self, actions: Vec<MenuAction>) -> NativeExtensionsResult<()> {
+        for action in actions {
+            let items: Vec<_> = {
+                let mut action_items = self.action_items.borrow_mut();
+                let Some(items) = action_items.get_mut(&action.unique_id) else {
+                    // Unknown action, not part of this menu.
+                    continue;
+                };
+                items.retain(|item| item.item.upgrade().is_some());
+                items
+                    .iter()
+                    .filter_map(|item| item.item.upgrade().map(|i| (i, item.updating.clone())))
+                    .collect()
+            };
+            for (item, updating) in items {
+                let parent = item.parent();
+                let is_check = item.downcast_ref::<gtk::CheckMenuItem>().is_some();
+                if is_check == (action.state != MenuActionState::None) {
+                    Self::update_action_item(&item, &action, &updating);
+                    item.show_all();
+                } else if let Some(shell) = parent
+                    .as_ref()
+                    .and_then(|p| p.downcast_ref::<gtk::MenuShell>())
+                {
+                    // Check menu item and plain menu item are different
+                    // widget types so the item needs to be replaced.
+                    let index = shell.children().iter().position(|c| c == &item);
+                    let mut context =
+                        MenuContext::new(self.item_selected.clone(), self.action_items.clone());
+                    let new_item =
+                        Self::translate_action(&action, &mut context, self.isolate, &self.delegate);
+                    new_item.show_all();
+                    shell.insert(&new_item, index.map(|i| i as i32).unwrap_or(-1));
+                    shell.remove(&item);
+                }
+                if let Some(menu) = parent.and_then(|p| p.downcast::<gtk::Menu>().ok()) {
+                    Self::update_menu_size(&menu);
+                }
+            }
+            let mut source = self.source.borrow_mut();
+            if let Some(existing) = Self::find_action_mut(&mut source.children, action.unique_id) {
+                *existing = action;
+            }
+        }
+        Ok(())
+    }
+
+    /// Appends top level elements of this menu to the menu bar. GTK widgets
+    /// can only have one parent so the items are translated again.
+    fn populate_menu_bar(&self, menu_bar: &gtk::MenuBar) {
+        let mut context = MenuContext::new(Rc::new(Cell::new(false)), self.action_items.clone());
+        for element in &self.source.borrow().children {
+            // Deferred elements are loaded when their parent menu is shown,
+            // which doesn't happen for the menu bar itself.
+            if matches!(element, MenuElement::Deferred(_)) {
+                continue;
+            }
+            let item =
+                Self::translate_menu_element(element, &mut context, self.isolate, &self.delegate);
+            menu_bar.append(&item);
+        }
+    }
 }
 
 impl PlatformMenuContext {
     pub fn new(
-        _id: PlatformMenuContextId,
+        id: PlatformMenuContextId,
         engine_handle: i64,
         delegate: Weak<dyn PlatformMenuContextDelegate>,
     ) -> NativeExtensionsResult<Self> {
@@ -360,19 +656,141 @@ impl PlatformMenuContext {
         weak.set(Some(&view));
 
         Ok(Self {
+            id,
             _delegate: delegate,
             view: weak,
+            menu_bar: RefCell::new(None),
+            tray: RefCell::new(None),
+            preview: RefCell::new(None),
         })
     }
 
     pub fn assign_weak_self(&self, _weak_self: Weak<Self>) {}
 
+    /// Places menu bar above the Flutter view in the container that holds the
+    /// view. When the view is the only child of a window or other bin (as in
+    /// the default runner), it is moved to a vertical box together with the
+    /// menu bar. The box is kept when menu bar is removed so that the view
+    /// is only reparented once.
+    fn attach_menu_bar(&self, menu_bar: &gtk::MenuBar) -> NativeExtensionsResult<()> {
+        let view = self
+            .view
+            .upgrade()
+            .ok_or(NativeExtensionsError::PlatformContextNotFound)?;
+        let parent = view
+            .parent()
+            .and_then(|p| p.downcast::<gtk::Container>().ok())
+            .ok_or_else(|| {
+                NativeExtensionsError::OtherError("Flutter view has no parent container".into())
+            })?;
+        if let Some(parent) = parent.downcast_ref::<gtk::Box>() {
+            if parent.orientation() == gtk::Orientation::Vertical {
+                parent.pack_start(menu_bar, false, false, 0);
+                parent.reorder_child(menu_bar, 0);
+                return Ok(());
+            }
+        }
+        if let Some(parent) = parent.downcast_ref::<gtk::Grid>() {
+            parent.attach_next_to(menu_bar, Some(&view), gtk::PositionType::Top, 1, 1);
+            return Ok(());
+        }
+        if let Some(parent) = parent.downcast_ref::<gtk::Bin>() {
+            let container = gtk::Box::new(gtk::Orientation::Vertical, 0);
+            // `view` keeps the widget alive while it has no parent.
+            parent.remove(&view);
+            container.pack_start(menu_bar, false, false, 0);
+            container.pack_start(&view, true, true, 0);
+            parent.add(&container);
+            container.show();
+            return Ok(());
+        }
+        Err(NativeExtensionsError::OtherError(
+            "Flutter view must be inside vertical GtkBox, GtkGrid or GtkBin to show menu bar"
+                .into(),
+        ))
+    }
+
+    /// Installs menu as application menu bar. Existing menu bar is updated in
+    /// place so that the view is not reparented again. `None` removes the
+    /// menu bar.
+    pub fn set_menu_bar(&self, menu: Option<Rc<PlatformMenu>>) -> NativeExtensionsResult<()> {
+        let Some(menu) = menu else {
+            if let Some(menu_bar) = self.menu_bar.take() {
+                if let Some(parent) = menu_bar
+                    .parent()
+                    .and_then(|p| p.downcast::<gtk::Container>().ok())
+                {
+                    parent.remove(&menu_bar);
+                }
+            }
+            return Ok(());
+        };
+        let menu_bar = self.menu_bar.borrow().clone();
+        let menu_bar = match menu_bar {
+            Some(menu_bar) => menu_bar,
+            None => {
+                let menu_bar = gtk::MenuBar::new();
+                self.attach_menu_bar(&menu_bar)?;
+                self.menu_bar.replace(Some(menu_bar.clone()));
+                menu_bar
+            }
+        };
+        for child in menu_bar.children() {
+            menu_bar.remove(&child);
+        }
+        menu.populate_menu_bar(&menu_bar);
+        menu_bar.show_all();
+        Ok(())
+    }
+
+    /// Shows menu from status icon in system tray. `None` removes the icon.
+    pub fn set_tray_menu(
+        &self,
+        menu: Option<Rc<PlatformMenu>>,
+        icon: Option<ImageData>,
+        tooltip: Option<String>,
+    ) -> NativeExtensionsResult<()> {
+        let Some(menu) = menu else {
+            self.tray.take();
+            return Ok(());
+        };
+        let mut tray = self.tray.borrow_mut();
+        let tray = tray.get_or_insert_with(StatusIcon::new);
+        tray.update(menu, icon, tooltip);
+        Ok(())
+    }
+
     pub fn update_preview_image(
         &self,
-        _configuration_id: i64,
-        _image_data: ImageData,
+        configuration_id: i64,
+        image_data: ImageData,
     ) -> NativeExtensionsResult<()> {
-        Err(NativeExtensionsError::UnsupportedOperation)
+        let preview = self.preview.borrow().clone();
+        if let Some(preview) = preview.filter(|p| p.configuration_id == configuration_id) {
+            preview.set_image(image_data);
+        }
+        Ok(())
+    }
+
+    /// Shows preview image (or lift image when there is no preview yet) next
+    /// to the menu anchored at given root coordinates.
+    fn show_preview(&self, configuration: &MenuConfiguration, anchor: (i32, i32)) {
+        let preview = Rc::new(MenuPreview::new(configuration.configuration_id, anchor));
+        let image = configuration
+            .preview_image
+            .clone()
+            .unwrap_or_else(|| configuration.lift_image.image_data.clone());
+        preview.set_image(image);
+        preview.window.show();
+        if let Some(previous) = self.preview.replace(Some(preview)) {
+            previous.window.close();
+        }
+    }
+
+    fn hide_preview(&self) {
+        if let Some(preview) = self.preview.take() {
+            preview.window.close();
+        }
     }
 
     fn last_event(&self) -> Option<Event> {
@@ -400,29 +818,74 @@ impl PlatformMenuContext {
         gtk::main_do_event(&mut release);
 
         let view = self.view.upgrade().unwrap();
+        let window = view.window().unwrap();
+        let x = request.location.x as i32 + view.allocated_size().0.x();
+        let y = request.location.y as i32 + view.allocated_size().0.y();
 
         let menu = platform_menu.menu.clone();
         menu.popup_at_rect(
-            &view.window().unwrap(),
-            &Rectangle::new(
-                request.location.x as i32 + view.allocated_size().0.x(),
-                request.location.y as i32 + view.allocated_size().0.y(),
-                0,
-                0,
-            ),
+            &window,
+            &Rectangle::new(x, y, 0, 0),
             Gravity::SouthWest,
             Gravity::NorthWest,
             Some(&event),
         );
+
+        let configuration_id = request.configuration.as_ref().map(|c| c.configuration_id);
+        let mut handlers = Vec::new();
+        if let Some(configuration) = &request.configuration {
+            let (_, origin_x, origin_y) = window.origin();
+            self.show_preview(configuration, (origin_x + x, origin_y + y));
+            let delegate = self._delegate.clone();
+            let id = self.id;
+            let configuration_id = configuration.configuration_id;
+            let preview = Rc::downgrade(self.preview.borrow().as_ref().unwrap());
+            // Menu grabs the pointer so clicks on preview are delivered to
+            // the menu (and dismiss it).
+            handlers.push(menu.connect_button_release_event(move |_, event| {
+                let (x, y) = event.root();
+                if let (Some(preview), Some(delegate)) = (preview.upgrade(), delegate.upgrade()) {
+                    if preview.contains(x, y) {
+                        delegate.on_preview_action(id, configuration_id);
+                    }
+                }
+                Inhibit(false)
+            }));
+            if let Some(delegate) = self._delegate.upgrade() {
+                delegate.on_show_menu(self.id, configuration_id);
+            }
+        }
+
         let (future, completer) = FutureCompleter::new();
         let completer = Rc::new(RefCell::new(Some(completer)));
 
         let item_selected = platform_menu.item_selected.clone();
-        menu.connect_selection_done(move |_menu| {
-            completer.take().unwrap().complete(ShowContextMenuResponse {
-                item_selected: item_selected.get(),
-            });
-        });
-        Ok(future.await)
+        handlers.push(menu.connect_selection_done(move |_menu| {
+            if let Some(completer) = completer.take() {
+                completer.complete(ShowContextMenuResponse {
+                    item_selected: item_selected.get(),
+                });
+            }
+        }));
+        let response = future.await;
+
+        // Menu is reused for subsequent invocations.
+        for handler in handlers {
+            menu.disconnect(handler);
+        }
+        if let Some(configuration_id) = configuration_id {
+            self.hide_preview();
+            if let Some(delegate) = self._delegate.upgrade() {
+                delegate.on_hide_menu(self.id, configuration_id, response.item_selected);
+            }
+        }
+        Ok(response)
+    }
+}
+
+impl Drop for PlatformMenuContext {
+    fn drop(&mut self) {
+        // Preview is a toplevel window, it would outlive the context.
+        self.hide_preview();
     }
 }
@@ -6,10 +6,15 @@ mod drag;
 mod drag_common;
 mod drop;
 mod hot_key;
+mod items;
 mod keyboard_layout;
 mod menu;
 mod reader;
+mod selection_stream;
 mod signal;
+mod virtual_file;
+mod xkb;
+mod xkb_sys;
 
 pub use clipboard_events::*;
 pub use data_provider::*;
@@ -1,27 +1,38 @@
 use std::{
     cell::{Cell, RefCell},
     collections::HashMap,
+    fs,
     os::raw::c_uint,
     path::{Path, PathBuf},
     rc::Rc,
     sync::Arc,
+    thread,
 };
 
-use gdk::{glib::SignalHandlerId, prelude::ObjectExt, Atom, Display, DragContext};
+use gdk::{glib::SignalHandlerId, prelude::ObjectExt, Atom, DragContext};
 use gtk::{traits::WidgetExt, Clipboard, SelectionData, Widget};
 
 use irondash_message_channel::{Late, Value};
-use irondash_run_loop::{spawn, util::FutureCompleter};
+use irondash_run_loop::{
+    spawn,
+    util::{Capsule, FutureCompleter},
+    RunLoop,
+};
 use url::Url;
 
 use crate::{
+    api_model::ClipboardSelection,
     error::{NativeExtensionsError, NativeExtensionsResult},
-    reader_manager::{ReadProgress, VirtualFileReader},
+    reader_manager::{ItemDataStream, ReadProgress, VirtualFileReader},
+    util::get_target_path,
 };
 
 use super::{
     clipboard_async::ClipboardAsync,
-    common::{target_includes_text, TYPE_TEXT, TYPE_URI},
+    common::{clipboard_for_selection, target_includes_text, TYPE_TEXT, TYPE_URI},
+    items::{decode_items, item_target, TYPE_ITEMS},
+    selection_stream::SelectionStream,
+    virtual_file::{is_virtual_file, LocalFileReader},
 };
 
 pub struct PlatformDataReader {
@@ -33,6 +44,14 @@ pub struct PlatformDataReader {
 struct Inner {
     targets: Vec<String>,
     uris: Vec<String>,
+    /// Items listed in [TYPE_ITEMS] target (when written by
+    /// super_native_extensions with multiple items).
+    items: Option<Vec<ItemInfo>>,
+}
+
+struct ItemInfo {
+    formats: Vec<String>,
+    uri: Option<String>,
 }
 
 enum Reader {
@@ -72,7 +91,7 @@ impl Reader {
 
 pub struct ReaderInfo {
     pub number_of_items: usize,
-    pub targets: Vec<String>,
+    pub item_formats: Vec<Vec<String>>,
 }
 
 impl PlatformDataReader {
@@ -91,23 +110,59 @@ impl PlatformDataReader {
                     targets.push(TYPE_TEXT.into());
                 }
             }
-            let uris = if targets.iter().any(|t| t == TYPE_URI) {
+            let items = if targets.iter().any(|t| t == TYPE_ITEMS) {
+                self.read_item_list().await
+            } else {
+                None
+            };
+            // With item list present URIs are requested per item.
+            let uris = if items.is_none() && targets.iter().any(|t| t == TYPE_URI) {
                 self.reader.get_uri_list().await
             } else {
                 Vec::new()
             };
             // double check - we might have been preempted
             if !self.inner.is_set() {
-                self.inner.set(Inner { targets, uris })
+                self.inner.set(Inner {
+                    targets,
+                    uris,
+                    items,
+                })
             }
         }
     }
 
+    /// Reads [TYPE_ITEMS] target and URIs of listed items. Data of other
+    /// formats is only requested when read.
+    async fn read_item_list(&self) -> Option<Vec<ItemInfo>> {
+        let data = self.reader.get_data(TYPE_ITEMS).await?;
+        let mut items = Vec::new();
+        for (index, item) in decode_items(&data)?.into_iter().enumerate() {
+            let uri = if item.formats.iter().any(|f| f == TYPE_URI) {
+                let data = self.reader.get_data(&item_target(index, TYPE_URI)).await;
+                data.and_then(|data| {
+                    let uris = String::from_utf8_lossy(&data);
+                    uris.lines().next().map(|uri| uri.trim().to_owned())
+                })
+            } else {
+                None
+            };
+            items.push(ItemInfo {
+                formats: item.formats,
+                uri,
+            });
+        }
+        Some(items)
+    }
+
     pub fn reader_info(self: &Rc<Self>) -> Option<ReaderInfo> {
         if self.inner.is_set() {
+            let number_of_items = self.number_of_items();
             Some(ReaderInfo {
-                number_of_items: 1.max(self.inner.uris.len()),
-                targets: self.inner.targets.clone(),
+                number_of_items,
+                item_formats: (0..number_of_items as i64)
+                    .map(|item| self.formats_for_item(item))
+                    .collect(),
             })
         } else {
             let this = self.clone();
@@ -120,28 +175,67 @@ impl PlatformDataReader {
 
     pub async fn get_items(&self) -> NativeExtensionsResult<Vec<i64>> {
         self.init().await;
-        // uris from urilist are represented as separate items
-        let num_items = 1.max(self.inner.uris.len());
-        Ok((0..num_items as i64).collect())
+        Ok((0..self.number_of_items() as i64).collect())
+    }
+
+    fn number_of_items(&self) -> usize {
+        match &self.inner.items {
+            Some(items) => items.len(),
+            // uris from urilist are represented as separate items
+            None => 1.max(self.inner.uris.len()),
+        }
+    }
+
+    fn uri_for_item(&self, item: i64) -> Option<String> {
+        match &self.inner.items {
+            Some(items) => items.get(item as usize)?.uri.clone(),
+            None => self.inner.uris.get(item as usize).cloned(),
+        }
     }
 
     pub async fn get_formats_for_item(&self, item: i64) -> NativeExtensionsResult<Vec<String>> {
         self.init().await;
-        if item == 0 {
-            Ok(self.inner.targets.clone())
+        Ok(self.formats_for_item(item))
+    }
+
+    fn formats_for_item(&self, item: i64) -> Vec<String> {
+        let mut formats = if let Some(items) = &self.inner.items {
+            items
+                .get(item as usize)
+                .map(|item| item.formats.clone())
+                .unwrap_or_default()
+        } else if item == 0 {
+            self.inner.targets.clone()
         } else if (item as usize) < self.inner.uris.len() {
-            Ok(vec![TYPE_URI.into()])
+            vec![TYPE_URI.into()]
         } else {
-            Ok(Vec::new())
+            Vec::new()
+        };
+        if let Some(format) = self.virtual_file_format(item) {
+            // make virtual file highest priority
+            formats.insert(0, format);
         }
+        formats
+    }
+
+    /// Returns path for item if it is a virtual file materialized by
+    /// another super_native_extensions application.
+    fn virtual_file_path(&self, item: i64) -> Option<PathBuf> {
+        let uri = self.uri_for_item(item)?;
+        let path = Url::parse(&uri).ok()?.to_file_path().ok()?;
+        is_virtual_file(&path).then_some(path)
+    }
+
+    fn virtual_file_format(&self, item: i64) -> Option<String> {
+        let path = self.virtual_file_path(item)?;
+        Some(mime_from_name(&path.file_name()?.to_string_lossy()))
     }
 
     pub async fn get_suggested_name_for_item(
         &self,
         item: i64,
     ) -> NativeExtensionsResult<Option<String>> {
-        let item = item as usize;
-        let uri = self.inner.uris.get(item).and_then(|u| Url::parse(u).ok());
+        let uri = self.uri_for_item(item).and_then(|u| Url::parse(&u).ok());
         if let Some(uri) = uri {
             if let Some(mut segments) = uri.path_segments() {
                 let last: Option<&str> = segments.next_back().filter(|s| !s.is_empty());
@@ -155,8 +249,7 @@ impl PlatformDataReader {
         &self,
         item: i64,
     ) -> NativeExtensionsResult<Option<String>> {
-        let item = item as usize;
-        let uri = self.inner.uris.get(item).and_then(|u| Url::parse(u).ok());
+        let uri = self.uri_for_item(item).and_then(|u| Url::parse(&u).ok());
         if let Some(uri) = uri {
             let name: Option<&str> = uri.path_segments().and_then(|mut s| s.next_back());
             match name {
@@ -177,6 +270,28 @@ impl PlatformDataReader {
         data_type: String,
         _progress: Option<Arc<ReadProgress>>,
     ) -> NativeExtensionsResult<Value> {
+        self.init().await;
+        if let Some(items) = &self.inner.items {
+            let has_format = items
+                .get(item as usize)
+                .is_some_and(|i| i.formats.contains(&data_type));
+            if !has_format {
+                return Ok(Value::Null);
+            }
+            if data_type == TYPE_URI {
+                return Ok(self.uri_for_item(item).into());
+            }
+            let data = self
+                .reader
+                .get_data(&item_target(item as usize, &data_type))
+                .await;
+            let is_string = target_includes_text(&Atom::intern(&data_type));
+            return Ok(match data {
+                Some(data) if is_string => String::from_utf8_lossy(&data).into_owned().into(),
+                Some(data) => data.into(),
+                None => Value::Null,
+            });
+        }
         let item = item as usize;
         if data_type == TYPE_URI && item < self.inner.uris.len() {
             Ok(self.inner.uris[item].clone().into())
@@ -193,14 +308,13 @@ impl PlatformDataReader {
         }
     }
 
-    pub fn new_clipboard_reader() -> NativeExtensionsResult<Rc<Self>> {
-        unsafe { gtk::set_initialized() };
-        let display = Display::default()
-            .ok_or_else(|| NativeExtensionsError::OtherError("Display not found".into()))?;
-        let clipboard = Clipboard::default(&display)
-            .ok_or_else(|| NativeExtensionsError::OtherError("Clipboard not found".into()))?;
+    pub fn new_clipboard_reader(selection: ClipboardSelection) -> NativeExtensionsResult<Rc<Self>> {
+        let clipboard = clipboard_for_selection(selection)?;
         let res = Rc::new(PlatformDataReader {
-            reader: Reader::Clipboard(ClipboardReader { clipboard }),
+            reader: Reader::Clipboard(ClipboardReader {
+                clipboard,
+                selection,
+            }),
             initializing: Cell::new(false),
             inner: Late::new(),
         });
@@ -227,42 +341,110 @@ impl PlatformDataReader {
 
     pub async fn can_copy_virtual_file_for_item(
         &self,
-        _item: i64,
-        _format: &str,
+        item: i64,
+        format: &str,
     ) -> NativeExtensionsResult<bool> {
-        Ok(false)
+        self.init().await;
+        Ok(self.virtual_file_format(item).as_deref() == Some(format))
     }
 
     pub async fn can_read_virtual_file_for_item(
         &self,
-        _item: i64,
-        _format: &str,
+        item: i64,
+        format: &str,
     ) -> NativeExtensionsResult<bool> {
-        Ok(false)
+        self.can_copy_virtual_file_for_item(item, format).await
+    }
+
+    fn virtual_file_path_for_format(
+        &self,
+        item: i64,
+        format: &str,
+    ) -> NativeExtensionsResult<PathBuf> {
+        match self.virtual_file_path(item) {
+            Some(path) if self.virtual_file_format(item).as_deref() == Some(format) => Ok(path),
+            _ => Err(NativeExtensionsError::VirtualFileReceiveError(
+                "item is not a virtual file".into(),
+            )),
+        }
+    }
+
+    pub async fn create_data_stream_for_item(
+        &self,
+        item: i64,
+        format: &str,
+        _progress: Arc<ReadProgress>,
+    ) -> NativeExtensionsResult<Option<Rc<dyn ItemDataStream>>> {
+        // Drop data is only available through GTK, which hands it out after
+        // the whole transfer is complete.
+        let Reader::Clipboard(clipboard) = &self.reader else {
+            return Ok(None);
+        };
+        self.init().await;
+        let target = if self.inner.items.is_some() {
+            item_target(item as usize, format)
+        } else if item == 0 {
+            format.to_owned()
+        } else {
+            return Ok(None);
+        };
+        // URIs are split per item and text/plain may be synthesized, neither
+        // can be read from the selection as is.
+        if format == TYPE_URI || !self.inner.targets.contains(&target) {
+            return Ok(None);
+        }
+        match SelectionStream::new(clipboard.selection, &target).await {
+            Ok(stream) => Ok(Some(Rc::new(stream))),
+            // Not running on X11.
+            Err(NativeExtensionsError::UnsupportedOperation) => Ok(None),
+            Err(err) => Err(err),
+        }
     }
 
     pub async fn create_virtual_file_reader_for_item(
         &self,
-        _item: i64,
-        _format: &str,
+        item: i64,
+        format: &str,
         _progress: Arc<ReadProgress>,
     ) -> NativeExtensionsResult<Option<Rc<dyn VirtualFileReader>>> {
-        Ok(None)
+        self.init().await;
+        let path = self.virtual_file_path_for_format(item, format)?;
+        Ok(Some(Rc::new(LocalFileReader::new(&path)?)))
     }
 
     pub async fn copy_virtual_file_for_item(
         &self,
-        _item: i64,
-        _format: &str,
-        _target_folder: PathBuf,
+        item: i64,
+        format: &str,
+        target_folder: PathBuf,
         _progress: Arc<ReadProgress>,
     ) -> NativeExtensionsResult<PathBuf> {
-        Err(NativeExtensionsError::UnsupportedOperation)
+        self.init().await;
+        let path = self.virtual_file_path_for_format(item, format)?;
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "file".into());
+        let target_path = get_target_path(&target_folder, &file_name);
+        let (future, completer) = FutureCompleter::new();
+        let mut completer = Capsule::new(completer);
+        let sender = RunLoop::current().new_sender();
+        thread::spawn(move || {
+            let res = fs::copy(&path, &target_path)
+                .map(|_| target_path)
+                .map_err(NativeExtensionsError::from);
+            sender.send(move || {
+                let completer = completer.take().unwrap();
+                completer.complete(res);
+            });
+        });
+        future.await
     }
 }
 
 struct ClipboardReader {
     clipboard: Clipboard,
+    selection: ClipboardSelection,
 }
 
 impl ClipboardReader {
@@ -0,0 +1,314 @@
+use std::{
+    cell::RefCell,
+    ffi::{CStr, CString},
+    os::raw::{c_int, c_long, c_short, c_uchar, c_ulong},
+    ptr, slice,
+    sync::mpsc,
+    thread,
+    time::Instant,
+};
+
+use async_trait::async_trait;
+use irondash_run_loop::{
+    util::{Capsule, FutureCompleter},
+    RunLoop,
+};
+use x11::xlib;
+
+use crate::{
+    api_model::ClipboardSelection,
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    reader_manager::ItemDataStream,
+};
+
+use super::common::{X11Display, SELECTION_TIMEOUT};
+
+type ChunkCompleter = Capsule<FutureCompleter<NativeExtensionsResult<Vec<u8>>>>;
+
+/// Reads selection data directly from the X server. GTK only hands out
+/// `SelectionData` after the whole INCR transfer has been reassembled in
+/// memory; this stream instead returns every INCR chunk as it arrives and
+/// only asks the owner for the next one when it is read.
+///
+/// The transfer runs on a background thread with its own X connection so
+/// that waiting for the selection owner doesn't block the platform thread
+/// (the owner may well be this process).
+pub struct SelectionStream {
+    requests: RefCell<Option<mpsc::Sender<ChunkCompleter>>>,
+    length: Option<i64>,
+}
+
+impl SelectionStream {
+    /// Starts converting the selection to given target. Fails with
+    /// [NativeExtensionsError::UnsupportedOperation] when not running on X11.
+    pub async fn new(selection: ClipboardSelection, target: &str) -> NativeExtensionsResult<Self> {
+        let display = X11Display::get()?;
+        let display_name =
+            unsafe { CStr::from_ptr(xlib::XDisplayString(display.xdisplay)) }.to_owned();
+        let selection = CString::new(match selection {
+            ClipboardSelection::Clipboard => "CLIPBOARD",
+            ClipboardSelection::Primary => "PRIMARY",
+        })
+        .unwrap();
+        let target = CString::new(target).map_err(|_| NativeExtensionsError::InvalidData)?;
+
+        let (future, completer) = FutureCompleter::new();
+        let mut completer = Capsule::new(completer);
+        let (requests, receiver) = mpsc::channel::<ChunkCompleter>();
+        let sender = RunLoop::current().new_sender();
+        thread::spawn(move || {
+            let mut transfer = match Transfer::start(&display_name, &selection, &target) {
+                Ok(transfer) => transfer,
+                Err(err) => {
+                    sender.send(move || completer.take().unwrap().complete(Err(err)));
+                    return;
+                }
+            };
+            let length = transfer.length;
+            sender.send(move || completer.take().unwrap().complete(Ok(length)));
+            // Runs until the stream is closed or dropped.
+            while let Ok(mut completer) = receiver.recv() {
+                let res = transfer.read_next();
+                sender.send(move || completer.take().unwrap().complete(res));
+            }
+        });
+        let length = future.await?;
+        Ok(Self {
+            requests: RefCell::new(Some(requests)),
+            length,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl ItemDataStream for SelectionStream {
+    async fn read_next(&self) -> NativeExtensionsResult<Vec<u8>> {
+        let (future, completer) = FutureCompleter::new();
+        let sent = self
+            .requests
+            .borrow()
+            .as_ref()
+            .is_some_and(|r| r.send(Capsule::new(completer)).is_ok());
+        if !sent {
+            return Err(NativeExtensionsError::OtherError("Stream is closed".into()));
+        }
+        future.await
+    }
+
+    fn length(&self) -> Option<i64> {
+        self.length
+    }
+
+    fn close(&self) -> NativeExtensionsResult<()> {
+        // Disconnecting the channel ends the transfer thread.
+        self.requests.borrow_mut().take();
+        Ok(())
+    }
+}
+
+enum State {
+    /// Whole value was delivered in single property.
+    Single(Vec<u8>),
+    /// INCR transfer in progress. The property holds the last chunk (or the
+    /// INCR marker) until next read; deleting it asks the owner for the next
+    /// chunk, so data is only transferred as fast as it is consumed.
+    Incremental,
+    Done,
+    Failed,
+}
+
+struct Transfer {
+    display: *mut xlib::Display,
+    window: xlib::Window,
+    property: xlib::Atom,
+    length: Option<i64>,
+    state: State,
+}
+
+impl Transfer {
+    fn start(display_name: &CStr, selection: &CStr, target: &CStr) -> NativeExtensionsResult<Self> {
+        let display = unsafe { xlib::XOpenDisplay(display_name.as_ptr()) };
+        if display.is_null() {
+            return Err(NativeExtensionsError::OtherError(
+                "Failed to open X display".into(),
+            ));
+        }
+        let intern = |name: &CStr| unsafe { xlib::XInternAtom(display, name.as_ptr(), 0) };
+        let window = unsafe {
+            let root = xlib::XDefaultRootWindow(display);
+            let window = xlib::XCreateSimpleWindow(display, root, 0, 0, 1, 1, 0, 0, 0);
+            xlib::XSelectInput(display, window, xlib::PropertyChangeMask);
+            window
+        };
+        let mut transfer = Transfer {
+            display,
+            window,
+            property: intern(c"SUPER_NATIVE_EXTENSIONS_SELECTION"),
+            length: None,
+            state: State::Failed,
+        };
+        unsafe {
+            xlib::XConvertSelection(
+                display,
+                intern(selection),
+                intern(target),
+                transfer.property,
+                window,
+                xlib::CurrentTime,
+            );
+            xlib::XFlush(display);
+        }
+        let event = transfer.wait_for_event(|e| {
+            e.get_type() == xlib::SelectionNotify && unsafe { e.selection.requestor } == window
+        })?;
+        if unsafe { event.selection.property } == 0 {
+            return Err(NativeExtensionsError::OtherError(
+                "Selection owner refused conversion".into(),
+            ));
+        }
+        let (ty, data) = transfer.read_property()?;
+        if ty == intern(c"INCR") {
+            // The value is a lower bound of the data size.
+            transfer.length = data
+                .get(..4)
+                .map(|l| u32::from_ne_bytes(l.try_into().unwrap()) as i64);
+            transfer.state = State::Incremental;
+        } else {
+            transfer.delete_property();
+            transfer.length = Some(data.len() as i64);
+            transfer.state = State::Single(data);
+        }
+        Ok(transfer)
+    }
+
+    fn read_next(&mut self) -> NativeExtensionsResult<Vec<u8>> {
+        match std::mem::replace(&mut self.state, State::Failed) {
+            State::Single(data) => {
+                self.state = State::Done;
+                Ok(data)
+            }
+            State::Incremental => {
+                self.delete_property();
+                let property = self.property;
+                self.wait_for_event(|e| {
+                    e.get_type() == xlib::PropertyNotify
+                        && unsafe { e.property.atom } == property
+                        && unsafe { e.property.state } == xlib::PropertyNewValue
+                })?;
+                let (_, data) = self.read_property()?;
+                if data.is_empty() {
+                    // Zero-length chunk ends the transfer.
+                    self.delete_property();
+                    self.state = State::Done;
+                } else {
+                    self.state = State::Incremental;
+                }
+                Ok(data)
+            }
+            State::Done => {
+                self.state = State::Done;
+                Ok(Vec::new())
+            }
+            State::Failed => Err(NativeExtensionsError::OtherError(
+                "Selection transfer failed".into(),
+            )),
+        }
+    }
+
+    fn delete_property(&self) {
+        unsafe {
+            xlib::XDeleteProperty(self.display, self.window, self.property);
+            xlib::XFlush(self.display);
+        }
+    }
+
+    /// Returns the property type and its value. Items of 16 and 32 bit
+    /// formats are packed to 2 and 4 bytes (Xlib stores them as short and
+    /// long).
+    fn read_property(&self) -> NativeExtensionsResult<(xlib::Atom, Vec<u8>)> {
+        let mut ty: xlib::Atom = 0;
+        let mut format: c_int = 0;
+        let mut num_items: c_ulong = 0;
+        let mut bytes_after: c_ulong = 0;
+        let mut data: *mut c_uchar = ptr::null_mut();
+        let status = unsafe {
+            xlib::XGetWindowProperty(
+                self.display,
+                self.window,
+                self.property,
+                0,
+                c_long::MAX / 4,
+                0,
+                xlib::AnyPropertyType as xlib::Atom,
+                &mut ty,
+                &mut format,
+                &mut num_items,
+                &mut bytes_after,
+                &mut data,
+            )
+        };
+        if status != xlib::Success as c_int {
+            return Err(NativeExtensionsError::OtherError(
+                "Failed to read selection property".into(),
+            ));
+        }
+        if data.is_null() {
+            return Ok((ty, Vec::new()));
+        }
+        let num_items = num_items as usize;
+        let res = unsafe {
+            match format {
+                16 => slice::from_raw_parts(data as *const c_short, num_items)
+                    .iter()
+                    .flat_map(|v| (*v as u16).to_ne_bytes())
+                    .collect(),
+                32 => slice::from_raw_parts(data as *const c_long, num_items)
+                    .iter()
+                    .flat_map(|v| (*v as u32).to_ne_bytes())
+                    .collect(),
+                _ => slice::from_raw_parts(data, num_items).to_vec(),
+            }
+        };
+        unsafe { xlib::XFree(data as *mut _) };
+        Ok((ty, res))
+    }
+
+    /// Waits for event matching the predicate. Fails when the selection
+    /// owner doesn't respond within [SELECTION_TIMEOUT].
+    fn wait_for_event<F>(&self, predicate: F) -> NativeExtensionsResult<xlib::XEvent>
+    where
+        F: Fn(&xlib::XEvent) -> bool,
+    {
+        let deadline = Instant::now() + SELECTION_TIMEOUT;
+        loop {
+            while unsafe { xlib::XPending(self.display) } > 0 {
+                let mut event = xlib::XEvent { pad: [0; 24] };
+                unsafe { xlib::XNextEvent(self.display, &mut event) };
+                if predicate(&event) {
+                    return Ok(event);
+                }
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(NativeExtensionsError::OtherError(
+                    "Timed out waiting for selection data".into(),
+                ));
+            }
+            let mut fd = libc::pollfd {
+                fd: unsafe { xlib::XConnectionNumber(self.display) },
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            unsafe { libc::poll(&mut fd, 1, remaining.as_millis().max(1) as c_int) };
+        }
+    }
+}
+
+impl Drop for Transfer {
+    fn drop(&mut self) {
+        unsafe {
+            xlib::XDestroyWindow(self.display, self.window);
+            xlib::XCloseDisplay(self.display);
+        }
+    }
+}
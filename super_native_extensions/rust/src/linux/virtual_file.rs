@@ -0,0 +1,272 @@
+use std::{
+    cell::RefCell,
+    env,
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::Once,
+    thread,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use irondash_run_loop::{
+    util::{Capsule, FutureCompleter},
+    RunLoop,
+};
+use rand::{distributions::Alphanumeric, Rng};
+
+use crate::{
+    api_model::{DataProviderValueId, VirtualFileStorage},
+    data_provider_manager::VirtualFileResult,
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    reader_manager::VirtualFileReader,
+    segmented_queue::{new_segmented_queue, QueueConfiguration, SegmentedQueueReader},
+    util::{get_target_path, DropNotifier},
+};
+
+use super::{add_stream_entry, platform_stream_close, PlatformDataProvider};
+
+/// Virtual files stored as temporary files are materialized in subfolders of
+/// this directory. Used by reader to tell virtual files apart from regular
+/// file URIs.
+fn virtual_files_root() -> PathBuf {
+    env::temp_dir().join("super_native_extensions_virtual_files")
+}
+
+pub(super) fn is_virtual_file(path: &Path) -> bool {
+    path.starts_with(virtual_files_root()) && path.is_file()
+}
+
+/// Folders are grouped by process id so that folders left behind by
+/// processes that did not exit cleanly can be removed.
+fn process_folder() -> PathBuf {
+    virtual_files_root().join(process::id().to_string())
+}
+
+/// Removes folders of processes that are no longer running. Done once per
+/// process, before the first virtual file is written.
+fn remove_stale_folders() {
+    static ONCE: Once = Once::new();
+    ONCE.call_once(|| {
+        let Ok(entries) = fs::read_dir(virtual_files_root()) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let is_stale = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.parse::<u32>().ok())
+                .is_some_and(|pid| !Path::new(&format!("/proc/{pid}")).exists());
+            if is_stale {
+                fs::remove_dir_all(entry.path()).ok();
+            }
+        }
+    });
+}
+
+/// How long folders of released data objects are kept. There is no way to
+/// tell when the receiver is done with the file; folders still pending when
+/// the process exits are removed on next start by [remove_stale_folders].
+const RELEASED_FOLDER_LIFETIME: Duration = Duration::from_secs(10 * 60);
+
+/// Folder holding single virtual file. Removed together with its content
+/// when dropped.
+pub(super) struct TemporaryFolder {
+    path: PathBuf,
+}
+
+impl TemporaryFolder {
+    /// Creates new unique folder for virtual file. Each file is placed in its
+    /// own folder so that it can keep the suggested name.
+    fn new() -> io::Result<Self> {
+        remove_stale_folders();
+        let name: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(16)
+            .map(char::from)
+            .collect();
+        let path = process_folder().join(name);
+        fs::create_dir_all(&path)?;
+        Ok(Self { path })
+    }
+}
+
+impl TemporaryFolder {
+    /// Removes the folder after [RELEASED_FOLDER_LIFETIME].
+    pub fn release(self) {
+        RunLoop::current()
+            .schedule(RELEASED_FOLDER_LIFETIME, move || drop(self))
+            .detach();
+    }
+}
+
+impl Drop for TemporaryFolder {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.path).ok();
+    }
+}
+
+pub(super) enum VirtualFileContent {
+    File {
+        path: PathBuf,
+        folder: TemporaryFolder,
+    },
+    Memory(Vec<u8>),
+}
+
+enum Sink {
+    File(File),
+    Memory(Vec<u8>),
+}
+
+impl Sink {
+    fn drain(mut self, reader: SegmentedQueueReader) -> io::Result<Sink> {
+        loop {
+            let data = reader.read_some(1024 * 1024);
+            if data.is_empty() {
+                return Ok(self);
+            }
+            match &mut self {
+                Sink::File(file) => file.write_all(&data)?,
+                Sink::Memory(buffer) => buffer.extend_from_slice(&data),
+            }
+        }
+    }
+}
+
+/// Requests the virtual file from Dart. Data written to the stream handle is
+/// moved to target on background thread, which reports back through the run
+/// loop once the stream is closed.
+pub(super) async fn fetch_virtual_file(
+    provider: &PlatformDataProvider,
+    id: DataProviderValueId,
+    storage: VirtualFileStorage,
+) -> NativeExtensionsResult<VirtualFileContent> {
+    let delegate = provider
+        .delegate
+        .upgrade()
+        .ok_or_else(|| NativeExtensionsError::OtherError("Data provider is gone".into()))?;
+    let (configuration, target, sink) = match storage {
+        VirtualFileStorage::TemporaryFile => {
+            let folder = TemporaryFolder::new()?;
+            let name = provider.data.suggested_name.as_deref().unwrap_or("file");
+            let path = get_target_path(&folder.path, name);
+            let file = File::create(&path)?;
+            let configuration = QueueConfiguration {
+                memory_segment_max_size: 1024 * 1024 * 4,
+                file_segment_max_length: 1024 * 1024 * 30,
+                max_memory_usage: Some(1024 * 1024 * 12),
+            };
+            (configuration, Some((path, folder)), Sink::File(file))
+        }
+        VirtualFileStorage::Memory => {
+            let configuration = QueueConfiguration {
+                memory_segment_max_size: 1024 * 1024 * 4,
+                file_segment_max_length: 0,
+                max_memory_usage: None,
+            };
+            (configuration, None, Sink::Memory(Vec::new()))
+        }
+    };
+    let (writer, reader) = new_segmented_queue(configuration);
+    let stream_handle = add_stream_entry(writer);
+    // Closing the stream unblocks the drain thread. Also done when the request
+    // is dropped before completing.
+    let close_stream = DropNotifier::new(move || platform_stream_close(stream_handle, false));
+
+    let (drained, drain_completer) = FutureCompleter::new();
+    let mut drain_completer = Capsule::new(drain_completer);
+    let sender = RunLoop::current().new_sender();
+    thread::spawn(move || {
+        let res = sink.drain(reader);
+        sender.send(move || {
+            let completer = drain_completer.take().unwrap();
+            completer.complete(res);
+        });
+    });
+
+    let (done, done_completer) = FutureCompleter::new();
+    let session = delegate.get_virtual_file(
+        provider.isolate_id,
+        id,
+        stream_handle,
+        Box::new(|_| {}),
+        Box::new(|_| {}),
+        Box::new(move |res| {
+            done_completer.complete(res);
+        }),
+    );
+    let result = done.await;
+    drop(session);
+    // Stream should be closed by now. If it isn't, closing it unblocks the
+    // drain thread.
+    drop(close_stream);
+    let sink = drained.await;
+
+    match result {
+        VirtualFileResult::Done => match sink? {
+            Sink::File(file) => {
+                drop(file);
+                let (path, folder) = target.unwrap();
+                Ok(VirtualFileContent::File { path, folder })
+            }
+            Sink::Memory(data) => Ok(VirtualFileContent::Memory(data)),
+        },
+        VirtualFileResult::Error { message } => Err(NativeExtensionsError::OtherError(message)),
+        VirtualFileResult::Cancelled => Err(NativeExtensionsError::VirtualFileReceiveError(
+            "Virtual file request was cancelled".into(),
+        )),
+    }
+}
+
+/// Reads virtual file materialized by another application as local file.
+pub(super) struct LocalFileReader {
+    file: RefCell<Option<File>>,
+    file_name: Option<String>,
+    file_size: i64,
+}
+
+impl LocalFileReader {
+    pub fn new(path: &Path) -> NativeExtensionsResult<Self> {
+        let file = File::open(path)?;
+        let file_size = file.metadata()?.len() as i64;
+        Ok(Self {
+            file: RefCell::new(Some(file)),
+            file_name: path.file_name().map(|n| n.to_string_lossy().into_owned()),
+            file_size,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl VirtualFileReader for LocalFileReader {
+    async fn read_next(&self) -> NativeExtensionsResult<Vec<u8>> {
+        let mut file = self.file.borrow_mut();
+        match file.as_mut() {
+            Some(file) => {
+                let mut buf = vec![0u8; 1024 * 1024];
+                let len = file.read(&mut buf)?;
+                buf.truncate(len);
+                Ok(buf)
+            }
+            None => Err(NativeExtensionsError::VirtualFileReceiveError(
+                "Reader is closed".into(),
+            )),
+        }
+    }
+
+    fn file_size(&self) -> NativeExtensionsResult<Option<i64>> {
+        Ok(Some(self.file_size))
+    }
+
+    fn file_name(&self) -> Option<String> {
+        self.file_name.clone()
+    }
+
+    fn close(&self) -> NativeExtensionsResult<()> {
+        self.file.borrow_mut().take();
+        Ok(())
+    }
+}
@@ -0,0 +1,259 @@
+use std::{
+    ffi::{CStr, CString},
+    os::raw::c_char,
+    ptr::null,
+    slice,
+};
+
+use crate::error::{NativeExtensionsError, NativeExtensionsResult};
+
+use super::xkb_sys::*;
+
+/// XKB keymap compiled by libxkbcommon. Unlike GDK keymap this does not
+/// require a display connection.
+pub struct XkbKeymap {
+    keymap: *mut xkb_keymap,
+}
+
+fn new_context() -> NativeExtensionsResult<*mut xkb_context> {
+    // Ignore XKB_DEFAULT_* environment variables so that the result only
+    // depends on the description.
+    let context = unsafe { xkb_context_new(XKB_CONTEXT_NO_ENVIRONMENT_NAMES) };
+    if context.is_null() {
+        Err(NativeExtensionsError::OtherError(
+            "failed to create XKB context".into(),
+        ))
+    } else {
+        Ok(context)
+    }
+}
+
+/// Characters represented by dead keysyms (XK_dead_grave .. XK_dead_doublegrave).
+/// Spacing variant is used where one exists, combining mark otherwise.
+const DEAD_KEY_CHARS: &[(u32, char)] = &[
+    (0xfe50, '`'),
+    (0xfe51, '\u{b4}'),
+    (0xfe52, '^'),
+    (0xfe53, '~'),
+    (0xfe54, '\u{af}'),
+    (0xfe55, '\u{2d8}'),
+    (0xfe56, '\u{2d9}'),
+    (0xfe57, '\u{a8}'),
+    (0xfe58, '\u{2da}'),
+    (0xfe59, '\u{2dd}'),
+    (0xfe5a, '\u{2c7}'),
+    (0xfe5b, '\u{b8}'),
+    (0xfe5c, '\u{2db}'),
+    (0xfe5d, '\u{37a}'),
+    (0xfe5e, '\u{309b}'),
+    (0xfe5f, '\u{309c}'),
+    (0xfe60, '\u{323}'),
+    (0xfe61, '\u{309}'),
+    (0xfe62, '\u{31b}'),
+    (0xfe63, '/'),
+    (0xfe64, '\u{313}'),
+    (0xfe65, '\u{314}'),
+    (0xfe66, '\u{30f}'),
+];
+
+/// Returns character represented by dead keysym, or `None` if keysym is not
+/// a dead key.
+pub fn dead_key_char(keysym: u32) -> Option<i64> {
+    DEAD_KEY_CHARS
+        .iter()
+        .find(|(sym, _)| *sym == keysym)
+        .map(|(_, c)| *c as i64)
+}
+
+/// Returns character produced by keysym, ignoring control characters.
+pub fn keysym_char(keysym: u32) -> Option<i64> {
+    let res = unsafe { xkb_keysym_to_utf32(keysym) } as i64;
+    if res < 0x20 {
+        None
+    } else {
+        Some(res)
+    }
+}
+
+fn to_cstring(s: Option<&str>) -> NativeExtensionsResult<Option<CString>> {
+    s.map(|s| CString::new(s).map_err(|_| NativeExtensionsError::InvalidData))
+        .transpose()
+}
+
+impl XkbKeymap {
+    /// Compiles keymap from RMLVO (rules, model, layout, variant, options)
+    /// names. Missing values are replaced with system defaults.
+    pub fn from_names(
+        rules: Option<&str>,
+        model: Option<&str>,
+        layout: Option<&str>,
+        variant: Option<&str>,
+        options: Option<&str>,
+    ) -> NativeExtensionsResult<Self> {
+        let rules = to_cstring(rules)?;
+        let model = to_cstring(model)?;
+        let layout = to_cstring(layout)?;
+        let variant = to_cstring(variant)?;
+        let options = to_cstring(options)?;
+        let as_ptr = |s: &Option<CString>| s.as_ref().map(|s| s.as_ptr()).unwrap_or(null());
+        let names = xkb_rule_names {
+            rules: as_ptr(&rules),
+            model: as_ptr(&model),
+            layout: as_ptr(&layout),
+            variant: as_ptr(&variant),
+            options: as_ptr(&options),
+        };
+        let context = new_context()?;
+        let keymap =
+            unsafe { xkb_keymap_new_from_names(context, &names, XKB_KEYMAP_COMPILE_NO_FLAGS) };
+        unsafe { xkb_context_unref(context) };
+        Self::from_raw(keymap)
+    }
+
+    /// Parses keymap in XKB text format (i.e. output of `xkbcomp -xkb`).
+    pub fn from_string(keymap: &str) -> NativeExtensionsResult<Self> {
+        let keymap = CString::new(keymap).map_err(|_| NativeExtensionsError::InvalidData)?;
+        let context = new_context()?;
+        let keymap = unsafe {
+            xkb_keymap_new_from_string(
+                context,
+                keymap.as_ptr(),
+                XKB_KEYMAP_FORMAT_TEXT_V1,
+                XKB_KEYMAP_COMPILE_NO_FLAGS,
+            )
+        };
+        unsafe { xkb_context_unref(context) };
+        Self::from_raw(keymap)
+    }
+
+    fn from_raw(keymap: *mut xkb_keymap) -> NativeExtensionsResult<Self> {
+        if keymap.is_null() {
+            Err(NativeExtensionsError::OtherError(
+                "failed to compile XKB keymap".into(),
+            ))
+        } else {
+            Ok(Self { keymap })
+        }
+    }
+
+    pub fn num_layouts(&self) -> u32 {
+        unsafe { xkb_keymap_num_layouts(self.keymap) }
+    }
+
+    pub fn layout_name(&self, layout: u32) -> Option<String> {
+        let name = unsafe { xkb_keymap_layout_get_name(self.keymap, layout) };
+        if name.is_null() {
+            None
+        } else {
+            Some(
+                unsafe { CStr::from_ptr(name) }
+                    .to_string_lossy()
+                    .into_owned(),
+            )
+        }
+    }
+
+    /// Returns keysyms produced by the key at given layout and shift level.
+    pub fn key_syms(&self, keycode: u32, layout: u32, level: u32) -> &[u32] {
+        let mut syms: *const xkb_keysym_t = null();
+        let count = unsafe {
+            xkb_keymap_key_get_syms_by_level(self.keymap, keycode, layout, level, &mut syms)
+        };
+        if count <= 0 || syms.is_null() {
+            &[]
+        } else {
+            unsafe { slice::from_raw_parts(syms, count as usize) }
+        }
+    }
+
+    /// Returns keysym produced by the key at given layout and shift level.
+    /// Keys producing multiple keysyms are ignored.
+    pub fn keysym(&self, keycode: u32, layout: u32, level: u32) -> Option<u32> {
+        match self.key_syms(keycode, layout, level) {
+            [sym] => Some(*sym),
+            _ => None,
+        }
+    }
+}
+
+impl Drop for XkbKeymap {
+    fn drop(&mut self) {
+        unsafe { xkb_keymap_unref(self.keymap) };
+    }
+}
+
+/// Compose table for a locale (sourced from system Compose files).
+pub struct XkbComposeTable {
+    state: *mut xkb_compose_state,
+}
+
+impl XkbComposeTable {
+    pub fn from_locale(locale: &str) -> NativeExtensionsResult<Self> {
+        let locale = CString::new(locale).map_err(|_| NativeExtensionsError::InvalidData)?;
+        let context = new_context()?;
+        let table = unsafe {
+            xkb_compose_table_new_from_locale(
+                context,
+                locale.as_ptr(),
+                XKB_COMPOSE_COMPILE_NO_FLAGS,
+            )
+        };
+        unsafe { xkb_context_unref(context) };
+        if table.is_null() {
+            return Err(NativeExtensionsError::OtherError(
+                "failed to load compose table".into(),
+            ));
+        }
+        // State keeps reference to the table.
+        let state = unsafe { xkb_compose_state_new(table, XKB_COMPOSE_STATE_NO_FLAGS) };
+        unsafe { xkb_compose_table_unref(table) };
+        if state.is_null() {
+            return Err(NativeExtensionsError::OtherError(
+                "failed to create compose state".into(),
+            ));
+        }
+        Ok(Self { state })
+    }
+
+    /// Locale used for compose table of the current system layout.
+    pub fn system_locale() -> String {
+        ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_else(|| "C".into())
+    }
+
+    /// Returns string composed by given keysym sequence, if any.
+    pub fn compose(&self, sequence: &[u32]) -> Option<String> {
+        unsafe {
+            xkb_compose_state_reset(self.state);
+            for keysym in sequence {
+                xkb_compose_state_feed(self.state, *keysym);
+            }
+            let res = if xkb_compose_state_get_status(self.state) == XKB_COMPOSE_COMPOSED {
+                let mut buffer = [0 as c_char; 64];
+                let len = xkb_compose_state_get_utf8(self.state, buffer.as_mut_ptr(), buffer.len());
+                if len > 0 {
+                    Some(
+                        CStr::from_ptr(buffer.as_ptr())
+                            .to_string_lossy()
+                            .into_owned(),
+                    )
+                } else {
+                    None
+                }
+            } else {
+                None
+            };
+            xkb_compose_state_reset(self.state);
+            res
+        }
+    }
+}
+
+impl Drop for XkbComposeTable {
+    fn drop(&mut self) {
+        unsafe { xkb_compose_state_unref(self.state) };
+    }
+}
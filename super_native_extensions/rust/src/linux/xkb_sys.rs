@@ -0,0 +1,99 @@
+#![allow(non_camel_case_types)]
+
+use std::os::raw::{c_char, c_int};
+
+#[repr(C)]
+pub struct xkb_context {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+pub struct xkb_keymap {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+pub struct xkb_compose_table {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+pub struct xkb_compose_state {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+pub struct xkb_rule_names {
+    pub rules: *const c_char,
+    pub model: *const c_char,
+    pub layout: *const c_char,
+    pub variant: *const c_char,
+    pub options: *const c_char,
+}
+
+pub type xkb_keycode_t = u32;
+pub type xkb_keysym_t = u32;
+pub type xkb_layout_index_t = u32;
+pub type xkb_level_index_t = u32;
+
+pub const XKB_CONTEXT_NO_ENVIRONMENT_NAMES: c_int = 1 << 1;
+pub const XKB_KEYMAP_FORMAT_TEXT_V1: c_int = 1;
+pub const XKB_KEYMAP_COMPILE_NO_FLAGS: c_int = 0;
+pub const XKB_COMPOSE_COMPILE_NO_FLAGS: c_int = 0;
+pub const XKB_COMPOSE_STATE_NO_FLAGS: c_int = 0;
+pub const XKB_COMPOSE_COMPOSED: c_int = 2;
+
+#[link(name = "xkbcommon")]
+extern "C" {
+    pub fn xkb_context_new(flags: c_int) -> *mut xkb_context;
+    pub fn xkb_context_unref(context: *mut xkb_context);
+
+    pub fn xkb_keymap_new_from_names(
+        context: *mut xkb_context,
+        names: *const xkb_rule_names,
+        flags: c_int,
+    ) -> *mut xkb_keymap;
+    pub fn xkb_keymap_new_from_string(
+        context: *mut xkb_context,
+        string: *const c_char,
+        format: c_int,
+        flags: c_int,
+    ) -> *mut xkb_keymap;
+    pub fn xkb_keymap_unref(keymap: *mut xkb_keymap);
+
+    pub fn xkb_keymap_num_layouts(keymap: *mut xkb_keymap) -> xkb_layout_index_t;
+    pub fn xkb_keymap_layout_get_name(
+        keymap: *mut xkb_keymap,
+        idx: xkb_layout_index_t,
+    ) -> *const c_char;
+    pub fn xkb_keymap_key_get_syms_by_level(
+        keymap: *mut xkb_keymap,
+        key: xkb_keycode_t,
+        layout: xkb_layout_index_t,
+        level: xkb_level_index_t,
+        syms_out: *mut *const xkb_keysym_t,
+    ) -> c_int;
+
+    pub fn xkb_keysym_to_utf32(keysym: xkb_keysym_t) -> u32;
+
+    pub fn xkb_compose_table_new_from_locale(
+        context: *mut xkb_context,
+        locale: *const c_char,
+        flags: c_int,
+    ) -> *mut xkb_compose_table;
+    pub fn xkb_compose_table_unref(table: *mut xkb_compose_table);
+
+    pub fn xkb_compose_state_new(
+        table: *mut xkb_compose_table,
+        flags: c_int,
+    ) -> *mut xkb_compose_state;
+    pub fn xkb_compose_state_unref(state: *mut xkb_compose_state);
+    pub fn xkb_compose_state_reset(state: *mut xkb_compose_state);
+    pub fn xkb_compose_state_feed(state: *mut xkb_compose_state, keysym: xkb_keysym_t) -> c_int;
+    pub fn xkb_compose_state_get_status(state: *mut xkb_compose_state) -> c_int;
+    pub fn xkb_compose_state_get_utf8(
+        state: *mut xkb_compose_state,
+        buffer: *mut c_char,
+        size: usize,
+    ) -> c_int;
+}
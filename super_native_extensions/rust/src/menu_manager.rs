@@ -1,6 +1,6 @@
 use std::{
     cell::{Cell, RefCell},
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     rc::{Rc, Weak},
     sync::Arc,
 };
@@ -14,8 +14,10 @@ use irondash_run_loop::spawn;
 use log::warn;
 
 use crate::{
+    accelerator::{Accelerator, DisplayStyle},
     api_model::{
-        DeferredMenuResponse, ImageData, MenuConfiguration, MenuElement, Point,
+        Activator, DeferredMenuResponse, ImageData, Menu, MenuAction, MenuActionState,
+        MenuConfiguration, MenuElement, MenuProblem, MenuProblemKind, Point,
         ShowContextMenuRequest, ShowContextMenuResponse, WritingToolsReplacementRequest,
     },
     context::Context,
@@ -74,6 +76,15 @@ pub struct MenuManager {
     contexts: RefCell<HashMap<PlatformMenuContextId, Rc<PlatformMenuContext>>>,
     next_id: Cell<i64>,
     menus: RefCell<HashMap<i64, Rc<PlatformMenu>>>,
+    /// Menu handle installed as menu bar for each isolate.
+    menu_bars: RefCell<HashMap<IsolateId, i64>>,
+    /// Tray menu installed for each isolate.
+    tray_menus: RefCell<HashMap<IsolateId, SetTrayMenuRequest>>,
+    /// Isolates for which menus with problems are rejected instead of only
+    /// being logged.
+    strict_validation: RefCell<HashSet<IsolateId>>,
+    /// Isolates for which context menus are shown together with preview.
+    context_menu_preview: RefCell<HashSet<IsolateId>>,
 }
 
 pub trait GetMenuManager {
@@ -99,6 +110,136 @@ struct UpdatePreviewImageRequest {
     image: ImageData,
 }
 
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct UpdateMenuRequest {
+    menu_handle: i64,
+    menu: MenuElement,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct UpdateMenuElementsRequest {
+    menu_handle: i64,
+    elements: Vec<MenuAction>,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct SetMenuBarRequest {
+    menu_handle: Option<i64>,
+}
+
+#[derive(TryFromValue, Clone)]
+#[irondash(rename_all = "camelCase")]
+struct SetTrayMenuRequest {
+    menu_handle: Option<i64>,
+    icon: Option<ImageData>,
+    tooltip: Option<String>,
+}
+
+#[derive(IntoValue)]
+#[irondash(rename_all = "camelCase")]
+struct ValidateMenuResponse {
+    problems: Vec<MenuProblem>,
+}
+
+/// Checks menu tree for elements that platform menus can not represent
+/// properly.
+struct MenuValidator {
+    problems: Vec<MenuProblem>,
+    unique_ids: HashSet<i64>,
+    path: Vec<i64>,
+}
+
+impl MenuValidator {
+    fn validate(menu: &Menu) -> Vec<MenuProblem> {
+        let mut validator = Self {
+            problems: Vec::new(),
+            unique_ids: HashSet::new(),
+            path: Vec::new(),
+        };
+        validator.check_unique_id(menu.unique_id);
+        validator.validate_children(&menu.children);
+        // Radio groups are checked after the group ends.
+        validator.problems.sort_by(|a, b| a.path.cmp(&b.path));
+        validator.problems
+    }
+
+    fn report(&mut self, kind: MenuProblemKind, path: Vec<i64>, unique_id: i64) {
+        self.problems.push(MenuProblem {
+            kind,
+            path,
+            unique_id,
+        });
+    }
+
+    fn check_unique_id(&mut self, unique_id: i64) {
+        if !self.unique_ids.insert(unique_id) {
+            self.report(
+                MenuProblemKind::DuplicateUniqueId,
+                self.path.clone(),
+                unique_id,
+            );
+        }
+    }
+
+    fn validate_children(&mut self, children: &[MenuElement]) {
+        // Radio actions are grouped with adjacent radio actions.
+        let mut radio_group = Vec::<(Vec<i64>, i64)>::new();
+        for (index, element) in children.iter().enumerate() {
+            self.path.push(index as i64);
+            let is_radio = matches!(element, MenuElement::Action(action)
+                if matches!(action.state, MenuActionState::RadioOn | MenuActionState::RadioOff));
+            if !is_radio {
+                self.finish_radio_group(&mut radio_group);
+            }
+            match element {
+                MenuElement::Action(action) => {
+                    self.check_unique_id(action.unique_id);
+                    if is_radio {
+                        radio_group.push((self.path.clone(), action.unique_id));
+                    }
+                    if let Some(activator) = &action.activator {
+                        if Accelerator::from_activator(activator).is_none() {
+                            self.report(
+                                MenuProblemKind::UnsupportedActivatorTrigger,
+                                self.path.clone(),
+                                action.unique_id,
+                            );
+                        }
+                    }
+                }
+                MenuElement::Menu(menu) => {
+                    self.check_unique_id(menu.unique_id);
+                    if menu.children.is_empty() {
+                        self.report(
+                            MenuProblemKind::EmptySubmenu,
+                            self.path.clone(),
+                            menu.unique_id,
+                        );
+                    }
+                    self.validate_children(&menu.children);
+                }
+                MenuElement::Deferred(deferred) => {
+                    self.check_unique_id(deferred.unique_id);
+                }
+                MenuElement::Separator(_) => {}
+            }
+            self.path.pop();
+        }
+        self.finish_radio_group(&mut radio_group);
+    }
+
+    fn finish_radio_group(&mut self, group: &mut Vec<(Vec<i64>, i64)>) {
+        if group.len() == 1 {
+            let (path, unique_id) = group.remove(0);
+            self.report(MenuProblemKind::RadioActionWithoutGroup, path, unique_id);
+        }
+        group.clear();
+    }
+}
+
 impl MenuManager {
     pub fn new() -> RegisteredAsyncMethodHandler<Self> {
         Self {
@@ -107,6 +248,10 @@ impl MenuManager {
             contexts: RefCell::new(HashMap::new()),
             next_id: Cell::new(0),
             menus: RefCell::new(HashMap::new()),
+            menu_bars: RefCell::new(HashMap::new()),
+            tray_menus: RefCell::new(HashMap::new()),
+            strict_validation: RefCell::new(HashSet::new()),
+            context_menu_preview: RefCell::new(HashSet::new()),
         }
         .register("MenuManager")
     }
@@ -135,12 +280,62 @@ impl MenuManager {
         Ok(())
     }
 
+    /// Validates menu before it is passed to platform layer. Problems are
+    /// logged, or returned as error if strict validation is enabled.
+    fn check_menu(&self, menu: &Menu, isolate: IsolateId) -> NativeExtensionsResult<()> {
+        let problems = MenuValidator::validate(menu);
+        if problems.is_empty() {
+            Ok(())
+        } else if self.strict_validation.borrow().contains(&isolate) {
+            Err(NativeExtensionsError::InvalidMenu(problems))
+        } else {
+            for problem in problems {
+                warn!("Menu problem: {problem}");
+            }
+            Ok(())
+        }
+    }
+
+    fn validate_menu(&self, menu: MenuElement) -> NativeExtensionsResult<ValidateMenuResponse> {
+        match menu {
+            MenuElement::Menu(menu) => Ok(ValidateMenuResponse {
+                problems: MenuValidator::validate(&menu),
+            }),
+            _ => Err(NativeExtensionsError::InvalidMenuElement),
+        }
+    }
+
+    /// Returns activator display string for current platform, for menus
+    /// that are not rendered natively.
+    fn format_activator(&self, activator: Activator) -> Option<String> {
+        Accelerator::from_activator(&activator).map(|a| a.display(DisplayStyle::current()))
+    }
+
+    fn set_strict_menu_validation(&self, strict: bool, isolate: IsolateId) {
+        let mut strict_validation = self.strict_validation.borrow_mut();
+        if strict {
+            strict_validation.insert(isolate);
+        } else {
+            strict_validation.remove(&isolate);
+        }
+    }
+
+    fn set_context_menu_preview_enabled(&self, enabled: bool, isolate: IsolateId) {
+        let mut context_menu_preview = self.context_menu_preview.borrow_mut();
+        if enabled {
+            context_menu_preview.insert(isolate);
+        } else {
+            context_menu_preview.remove(&isolate);
+        }
+    }
+
     async fn register_menu(
         &self,
         menu: MenuElement,
         isolate: IsolateId,
     ) -> NativeExtensionsResult<i64> {
         if let MenuElement::Menu(menu) = menu {
+            self.check_menu(&menu, isolate)?;
             let platform_menu = PlatformMenu::new(isolate, self.weak_self.clone(), menu)?;
             let id = self.next_id.next_id();
             self.menus.borrow_mut().insert(id, platform_menu);
@@ -150,8 +345,158 @@ impl MenuManager {
         }
     }
 
+    /// Replaces registered menu keeping its handle. Menu bars and tray menus
+    /// showing the menu are updated in place.
+    async fn update_menu(
+        &self,
+        request: UpdateMenuRequest,
+        isolate: IsolateId,
+    ) -> NativeExtensionsResult<()> {
+        if !self.menus.borrow().contains_key(&request.menu_handle) {
+            return Err(NativeExtensionsError::PlatformMenuNotFound);
+        }
+        let MenuElement::Menu(menu) = request.menu else {
+            return Err(NativeExtensionsError::InvalidMenuElement);
+        };
+        self.check_menu(&menu, isolate)?;
+        let platform_menu = PlatformMenu::new(isolate, self.weak_self.clone(), menu)?;
+        self.menus
+            .borrow_mut()
+            .insert(request.menu_handle, platform_menu.clone());
+        let menu_bar_isolates: Vec<_> = self
+            .menu_bars
+            .borrow()
+            .iter()
+            .filter(|(_, handle)| **handle == request.menu_handle)
+            .map(|(isolate, _)| *isolate)
+            .collect();
+        for isolate in menu_bar_isolates {
+            let context = self.contexts.borrow().get(&isolate).cloned();
+            if let Some(context) = context {
+                context.set_menu_bar(Some(platform_menu.clone()))?;
+            }
+        }
+        let tray_menus: Vec<_> = self
+            .tray_menus
+            .borrow()
+            .iter()
+            .filter(|(_, tray)| tray.menu_handle == Some(request.menu_handle))
+            .map(|(isolate, tray)| (*isolate, tray.clone()))
+            .collect();
+        for (isolate, tray) in tray_menus {
+            let context = self.contexts.borrow().get(&isolate).cloned();
+            if let Some(context) = context {
+                context.set_tray_menu(Some(platform_menu.clone()), tray.icon, tray.tooltip)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Updates actions of registered menu in place, matching them by unique
+    /// id. Unlike `update_menu` this works for menus that are currently open.
+    fn update_menu_elements(
+        &self,
+        request: UpdateMenuElementsRequest,
+    ) -> NativeExtensionsResult<()> {
+        let menu = self
+            .menus
+            .borrow()
+            .get(&request.menu_handle)
+            .cloned()
+            .ok_or(NativeExtensionsError::PlatformMenuNotFound)?;
+        menu.update_elements(request.elements)
+    }
+
+    /// Disposes registered menu. Menu bar and tray menu showing the menu are
+    /// removed.
     async fn dispose_menu(&self, id: i64) -> NativeExtensionsResult<()> {
         self.menus.borrow_mut().remove(&id);
+        let mut menu_bar_isolates = Vec::new();
+        self.menu_bars.borrow_mut().retain(|isolate, handle| {
+            let retain = *handle != id;
+            if !retain {
+                menu_bar_isolates.push(*isolate);
+            }
+            retain
+        });
+        let mut tray_isolates = Vec::new();
+        self.tray_menus.borrow_mut().retain(|isolate, tray| {
+            let retain = tray.menu_handle != Some(id);
+            if !retain {
+                tray_isolates.push(*isolate);
+            }
+            retain
+        });
+        for isolate in menu_bar_isolates {
+            let context = self.contexts.borrow().get(&isolate).cloned();
+            if let Some(context) = context {
+                context.set_menu_bar(None)?;
+            }
+        }
+        for isolate in tray_isolates {
+            let context = self.contexts.borrow().get(&isolate).cloned();
+            if let Some(context) = context {
+                context.set_tray_menu(None, None, None)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn set_menu_bar(
+        &self,
+        request: SetMenuBarRequest,
+        isolate: IsolateId,
+    ) -> NativeExtensionsResult<()> {
+        let context = self
+            .contexts
+            .borrow()
+            .get(&isolate)
+            .cloned()
+            .ok_or(NativeExtensionsError::PlatformContextNotFound)?;
+        let menu = match request.menu_handle {
+            Some(handle) => Some(
+                self.menus
+                    .borrow()
+                    .get(&handle)
+                    .cloned()
+                    .ok_or(NativeExtensionsError::PlatformMenuNotFound)?,
+            ),
+            None => None,
+        };
+        context.set_menu_bar(menu)?;
+        match request.menu_handle {
+            Some(handle) => self.menu_bars.borrow_mut().insert(isolate, handle),
+            None => self.menu_bars.borrow_mut().remove(&isolate),
+        };
+        Ok(())
+    }
+
+    fn set_tray_menu(
+        &self,
+        request: SetTrayMenuRequest,
+        isolate: IsolateId,
+    ) -> NativeExtensionsResult<()> {
+        let context = self
+            .contexts
+            .borrow()
+            .get(&isolate)
+            .cloned()
+            .ok_or(NativeExtensionsError::PlatformContextNotFound)?;
+        let menu = match request.menu_handle {
+            Some(handle) => Some(
+                self.menus
+                    .borrow()
+                    .get(&handle)
+                    .cloned()
+                    .ok_or(NativeExtensionsError::PlatformMenuNotFound)?,
+            ),
+            None => None,
+        };
+        context.set_tray_menu(menu, request.icon.clone(), request.tooltip.clone())?;
+        match request.menu_handle {
+            Some(_) => self.tray_menus.borrow_mut().insert(isolate, request),
+            None => self.tray_menus.borrow_mut().remove(&isolate),
+        };
         Ok(())
     }
 
@@ -186,6 +531,13 @@ impl MenuManager {
             .cloned()
             .ok_or(NativeExtensionsError::PlatformMenuNotFound)?;
         menu_request.menu = Some(menu);
+        if self.context_menu_preview.borrow().contains(&isolate_id) {
+            menu_request.configuration = self
+                .get_menu_configuration_for_location(isolate_id, menu_request.location.clone())
+                .await
+                .ok_log_unexpected()
+                .flatten();
+        }
         context.show_context_menu(menu_request).await
     }
 
@@ -368,10 +720,37 @@ impl AsyncMethodHandler for MenuManager {
                     .await?;
                 Ok(id.into())
             }
+            "validateMenu" => Ok(self.validate_menu(call.args.try_into()?)?.into()),
+            "formatActivator" => Ok(self.format_activator(call.args.try_into()?).into()),
+            "setStrictMenuValidation" => {
+                self.set_strict_menu_validation(call.args.try_into()?, call.isolate);
+                Ok(Value::Null)
+            }
+            "updateMenu" => {
+                self.update_menu(call.args.try_into()?, call.isolate)
+                    .await?;
+                Ok(Value::Null)
+            }
+            "updateMenuElements" => {
+                self.update_menu_elements(call.args.try_into()?)?;
+                Ok(Value::Null)
+            }
+            "setMenuBar" => {
+                self.set_menu_bar(call.args.try_into()?, call.isolate)?;
+                Ok(Value::Null)
+            }
+            "setTrayMenu" => {
+                self.set_tray_menu(call.args.try_into()?, call.isolate)?;
+                Ok(Value::Null)
+            }
             "disposeMenu" => {
                 self.dispose_menu(call.args.try_into()?).await?;
                 Ok(Value::Null)
             }
+            "setContextMenuPreviewEnabled" => {
+                self.set_context_menu_preview_enabled(call.args.try_into()?, call.isolate);
+                Ok(Value::Null)
+            }
             "updatePreviewImage" => {
                 self.update_preview_image(call.args.try_into()?, call.isolate)?;
                 Ok(Value::Null)
@@ -385,4 +764,214 @@ impl AsyncMethodHandler for MenuManager {
             _ => Ok(Value::Null),
         }
     }
+
+    fn on_isolate_destroyed(&self, isolate: IsolateId) {
+        let context = self.contexts.borrow_mut().remove(&isolate);
+        let had_menu_bar = self.menu_bars.borrow_mut().remove(&isolate).is_some();
+        let had_tray_menu = self.tray_menus.borrow_mut().remove(&isolate).is_some();
+        if let Some(context) = context {
+            // Menu bar and tray icon would otherwise keep showing menus of
+            // the destroyed isolate.
+            if had_menu_bar {
+                context.set_menu_bar(None).ok_log();
+            }
+            if had_tray_menu {
+                context.set_tray_menu(None, None, None).ok_log();
+            }
+        }
+        self.strict_validation.borrow_mut().remove(&isolate);
+        self.context_menu_preview.borrow_mut().remove(&isolate);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api_model::{
+        Activator, Menu, MenuAction, MenuActionAttributes, MenuActionState, MenuElement,
+        MenuProblemKind, MenuSeparator,
+    };
+
+    use super::MenuValidator;
+
+    fn action(unique_id: i64, state: MenuActionState, trigger: Option<&str>) -> MenuElement {
+        MenuElement::Action(MenuAction {
+            unique_id,
+            identifier: None,
+            title: None,
+            image: None,
+            subitle: None,
+            attributes: MenuActionAttributes {
+                disabled: false,
+                destructive: false,
+            },
+            state,
+            activator: trigger.map(|trigger| Activator {
+                trigger: trigger.into(),
+                alt: false,
+                meta: false,
+                shift: false,
+                control: true,
+                chord: None,
+            }),
+        })
+    }
+
+    fn menu(unique_id: i64, children: Vec<MenuElement>) -> Menu {
+        Menu {
+            unique_id,
+            identifier: None,
+            title: None,
+            subitle: None,
+            image: None,
+            children,
+        }
+    }
+
+    #[test]
+    fn test_validate_menu() {
+        let valid = menu(
+            0,
+            vec![
+                action(1, MenuActionState::RadioOn, Some("Page Up")),
+                action(2, MenuActionState::RadioOff, Some("c")),
+                MenuElement::Separator(MenuSeparator { title: None }),
+                action(3, MenuActionState::CheckOn, None),
+            ],
+        );
+        assert!(MenuValidator::validate(&valid).is_empty());
+
+        let invalid = menu(
+            0,
+            vec![
+                action(1, MenuActionState::RadioOn, None),
+                MenuElement::Separator(MenuSeparator { title: None }),
+                MenuElement::Menu(menu(
+                    2,
+                    vec![
+                        action(3, MenuActionState::None, Some("Not A Key")),
+                        MenuElement::Menu(menu(4, Vec::new())),
+                    ],
+                )),
+                action(3, MenuActionState::None, None),
+            ],
+        );
+        let problems: Vec<_> = MenuValidator::validate(&invalid)
+            .into_iter()
+            .map(|p| (p.kind, p.path, p.unique_id))
+            .collect();
+        assert_eq!(
+            problems,
+            vec![
+                (MenuProblemKind::RadioActionWithoutGroup, vec![0], 1),
+                (MenuProblemKind::UnsupportedActivatorTrigger, vec![2, 0], 3),
+                (MenuProblemKind::EmptySubmenu, vec![2, 1], 4),
+                (MenuProblemKind::DuplicateUniqueId, vec![3], 3),
+            ]
+        );
+    }
+
+    #[cfg(feature = "mock")]
+    #[test]
+    fn test_dispose_menu() {
+        use irondash_message_channel::IsolateId;
+
+        use crate::{context::Context, test_util::block_on};
+
+        use super::{
+            GetMenuManager, MenuContextInitRequest, SetMenuBarRequest, SetTrayMenuRequest,
+        };
+
+        let context = Context::new();
+        let menu_manager = context.menu_manager();
+        let isolate = IsolateId(1);
+        menu_manager
+            .new_context(isolate, MenuContextInitRequest { engine_handle: 0 })
+            .unwrap();
+        let menu_handle = block_on(menu_manager.register_menu(
+            MenuElement::Menu(menu(0, vec![action(1, MenuActionState::None, None)])),
+            isolate,
+        ))
+        .unwrap();
+        menu_manager
+            .set_menu_bar(
+                SetMenuBarRequest {
+                    menu_handle: Some(menu_handle),
+                },
+                isolate,
+            )
+            .unwrap();
+        menu_manager
+            .set_tray_menu(
+                SetTrayMenuRequest {
+                    menu_handle: Some(menu_handle),
+                    icon: None,
+                    tooltip: None,
+                },
+                isolate,
+            )
+            .unwrap();
+        let platform_context = menu_manager.get_platform_menu_contexts()[0].clone();
+        assert!(platform_context.menu_bar().is_some());
+        assert!(platform_context.tray_menu().is_some());
+
+        block_on(menu_manager.dispose_menu(menu_handle)).unwrap();
+        assert!(platform_context.menu_bar().is_none());
+        assert!(platform_context.tray_menu().is_none());
+        assert!(menu_manager.menu_bars.borrow().is_empty());
+        assert!(menu_manager.tray_menus.borrow().is_empty());
+    }
+
+    #[cfg(feature = "mock")]
+    #[test]
+    fn test_isolate_destroyed() {
+        use irondash_message_channel::{AsyncMethodHandler, IsolateId};
+
+        use crate::{context::Context, test_util::block_on};
+
+        use super::{
+            GetMenuManager, MenuContextInitRequest, SetMenuBarRequest, SetTrayMenuRequest,
+        };
+
+        let context = Context::new();
+        let menu_manager = context.menu_manager();
+        let isolate = IsolateId(1);
+        menu_manager
+            .new_context(isolate, MenuContextInitRequest { engine_handle: 0 })
+            .unwrap();
+        menu_manager.set_strict_menu_validation(true, isolate);
+        menu_manager.set_context_menu_preview_enabled(true, isolate);
+        let menu_handle = block_on(menu_manager.register_menu(
+            MenuElement::Menu(menu(0, vec![action(1, MenuActionState::None, None)])),
+            isolate,
+        ))
+        .unwrap();
+        menu_manager
+            .set_menu_bar(
+                SetMenuBarRequest {
+                    menu_handle: Some(menu_handle),
+                },
+                isolate,
+            )
+            .unwrap();
+        menu_manager
+            .set_tray_menu(
+                SetTrayMenuRequest {
+                    menu_handle: Some(menu_handle),
+                    icon: None,
+                    tooltip: None,
+                },
+                isolate,
+            )
+            .unwrap();
+        let platform_context = menu_manager.get_platform_menu_contexts()[0].clone();
+
+        menu_manager.on_isolate_destroyed(isolate);
+        assert!(platform_context.menu_bar().is_none());
+        assert!(platform_context.tray_menu().is_none());
+        assert!(menu_manager.get_platform_menu_contexts().is_empty());
+        assert!(menu_manager.menu_bars.borrow().is_empty());
+        assert!(menu_manager.tray_menus.borrow().is_empty());
+        assert!(menu_manager.strict_validation.borrow().is_empty());
+        assert!(menu_manager.context_menu_preview.borrow().is_empty());
+    }
 }
@@ -0,0 +1,51 @@
+use std::rc::Weak;
+
+use crate::clipboard_events_manager::{
+    ClipboardEventManagerDelegate, PlatformClipboardEventManagerId,
+};
+
+pub struct PlatformClipboardEventManager {
+    id: PlatformClipboardEventManagerId,
+    delegate: Weak<dyn ClipboardEventManagerDelegate>,
+}
+
+/// Clipboard event that can be simulated on the mock event manager.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClipboardEvent {
+    Cut,
+    Copy,
+    Paste,
+    SelectAll,
+}
+
+impl PlatformClipboardEventManager {
+    pub fn new(
+        id: PlatformClipboardEventManagerId,
+        delegate: Weak<dyn ClipboardEventManagerDelegate>,
+    ) -> Self {
+        Self { id, delegate }
+    }
+
+    pub fn assign_weak_self(&self, _weak: Weak<PlatformClipboardEventManager>) {}
+
+    /// Dispatches the event to delegate. Returns whether the event was handled.
+    pub async fn simulate_event(&self, event: ClipboardEvent) -> bool {
+        let delegate = match self.delegate.upgrade() {
+            Some(delegate) => delegate,
+            None => return false,
+        };
+        match event {
+            ClipboardEvent::Cut => delegate.on_cut(self.id).await,
+            ClipboardEvent::Copy => delegate.on_copy(self.id).await,
+            ClipboardEvent::Paste => delegate.on_paste(self.id).await,
+            ClipboardEvent::SelectAll => delegate.on_select_all(self.id).await,
+        }
+    }
+
+    /// Notifies delegate that clipboard content has changed.
+    pub fn simulate_clipboard_changed(&self, change_count: i64) {
+        if let Some(delegate) = self.delegate.upgrade() {
+            delegate.on_clipboard_changed(self.id, change_count);
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    rc::{Rc, Weak},
+    sync::{
+        atomic::{AtomicI32, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use irondash_message_channel::{IsolateId, Late};
+
+use crate::{
+    api_model::{ClipboardSelection, DataProvider},
+    data_provider_manager::{DataProviderHandle, PlatformDataProviderDelegate},
+    error::NativeExtensionsResult,
+};
+
+/// Buffers for virtual files being written by Dart side, keyed by stream handle.
+static STREAM_ENTRIES: Mutex<BTreeMap<i32, Vec<u8>>> = Mutex::new(BTreeMap::new());
+
+pub(super) fn new_stream_entry() -> i32 {
+    static NEXT_STREAM_ENTRY_HANDLE: AtomicI32 = AtomicI32::new(1);
+    let handle = NEXT_STREAM_ENTRY_HANDLE.fetch_add(1, Ordering::Relaxed);
+    STREAM_ENTRIES.lock().unwrap().insert(handle, Vec::new());
+    handle
+}
+
+pub(super) fn take_stream_entry(handle: i32) -> Option<Vec<u8>> {
+    STREAM_ENTRIES.lock().unwrap().remove(&handle)
+}
+
+pub fn platform_stream_write(handle: i32, data: &[u8]) -> i32 {
+    let mut entries = STREAM_ENTRIES.lock().unwrap();
+    match entries.get_mut(&handle) {
+        Some(buffer) => {
+            buffer.extend_from_slice(data);
+            1
+        }
+        None => 0,
+    }
+}
+
+pub fn platform_stream_close(handle: i32, delete: bool) {
+    if delete {
+        STREAM_ENTRIES.lock().unwrap().remove(&handle);
+    }
+}
+
+type ClipboardContents = Vec<(Rc<PlatformDataProvider>, Arc<DataProviderHandle>)>;
+
+thread_local! {
+    static CLIPBOARD: RefCell<HashMap<ClipboardSelection, ClipboardContents>> =
+        RefCell::new(HashMap::new());
+}
+
+pub struct PlatformDataProvider {
+    weak_self: Late<Weak<Self>>,
+    pub(super) delegate: Weak<dyn PlatformDataProviderDelegate>,
+    pub(super) isolate_id: IsolateId,
+    pub(super) data: DataProvider,
+}
+
+impl PlatformDataProvider {
+    pub fn new(
+        delegate: Weak<dyn PlatformDataProviderDelegate>,
+        isolate_id: IsolateId,
+        data: DataProvider,
+    ) -> Self {
+        Self {
+            weak_self: Late::new(),
+            delegate,
+            isolate_id,
+            data,
+        }
+    }
+
+    pub fn assign_weak_self(&self, weak_self: Weak<Self>) {
+        self.weak_self.set(weak_self);
+    }
+
+    pub async fn write_to_clipboard(
+        providers: Vec<(Rc<PlatformDataProvider>, Arc<DataProviderHandle>)>,
+        selection: ClipboardSelection,
+    ) -> NativeExtensionsResult<()> {
+        // Replacing the content releases handles of previous providers, same
+        // as losing clipboard ownership would on real platform.
+        let previous = CLIPBOARD.with(|c| c.borrow_mut().insert(selection, providers));
+        drop(previous);
+        Ok(())
+    }
+
+    /// Returns data providers currently stored in the mock clipboard.
+    pub fn clipboard_contents(selection: ClipboardSelection) -> Vec<Rc<PlatformDataProvider>> {
+        CLIPBOARD.with(|c| {
+            c.borrow()
+                .get(&selection)
+                .map(|providers| providers.iter().map(|p| p.0.clone()).collect())
+                .unwrap_or_default()
+        })
+    }
+
+    /// Removes all content from the mock clipboard.
+    pub fn clear_clipboard(selection: ClipboardSelection) {
+        let previous = CLIPBOARD.with(|c| c.borrow_mut().remove(&selection));
+        drop(previous);
+    }
+}
@@ -0,0 +1,170 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::{Rc, Weak},
+    sync::Arc,
+};
+
+use irondash_message_channel::{Late, Value};
+
+use crate::{
+    api_model::{DataProviderId, DragConfiguration, DragRequest, DropOperation, Point},
+    data_provider_manager::DataProviderHandle,
+    drag_manager::{
+        DataProviderEntry, DragSessionId, PlatformDragContextDelegate, PlatformDragContextId,
+    },
+    error::{NativeExtensionsError, NativeExtensionsResult},
+};
+
+use super::{PlatformDataProvider, PlatformDataReader};
+
+pub struct PlatformDragContext {
+    id: PlatformDragContextId,
+    delegate: Weak<dyn PlatformDragContextDelegate>,
+    weak_self: Late<Weak<Self>>,
+    sessions: RefCell<HashMap<DragSessionId, Rc<Session>>>,
+}
+
+struct Session {
+    configuration: DragConfiguration,
+    providers: Vec<(Rc<PlatformDataProvider>, Arc<DataProviderHandle>)>,
+    last_position: RefCell<Point>,
+}
+
+impl PlatformDragContext {
+    pub fn new(
+        id: PlatformDragContextId,
+        _engine_handle: i64,
+        delegate: Weak<dyn PlatformDragContextDelegate>,
+    ) -> NativeExtensionsResult<Self> {
+        Ok(Self {
+            id,
+            delegate,
+            weak_self: Late::new(),
+            sessions: RefCell::new(HashMap::new()),
+        })
+    }
+
+    pub fn assign_weak_self(&self, weak_self: Weak<Self>) {
+        self.weak_self.set(weak_self);
+    }
+
+    pub fn needs_combined_drag_image() -> bool {
+        false
+    }
+
+    pub async fn start_drag(
+        &self,
+        request: DragRequest,
+        mut providers: HashMap<DataProviderId, DataProviderEntry>,
+        session_id: DragSessionId,
+    ) -> NativeExtensionsResult<()> {
+        let providers = request
+            .configuration
+            .items
+            .iter()
+            .map(|item| {
+                let entry = providers
+                    .remove(&item.data_provider_id)
+                    .ok_or(NativeExtensionsError::DataSourceNotFound)?;
+                Ok((entry.provider, entry.handle))
+            })
+            .collect::<NativeExtensionsResult<_>>()?;
+        let session = Rc::new(Session {
+            configuration: request.configuration,
+            providers,
+            last_position: RefCell::new(request.position),
+        });
+        self.sessions.borrow_mut().insert(session_id, session);
+        Ok(())
+    }
+
+    fn session(&self, session_id: DragSessionId) -> NativeExtensionsResult<Rc<Session>> {
+        self.sessions
+            .borrow()
+            .get(&session_id)
+            .cloned()
+            .ok_or(NativeExtensionsError::DragSessionNotFound)
+    }
+
+    /// Returns identifiers of all drag sessions in progress.
+    pub fn session_ids(&self) -> Vec<DragSessionId> {
+        self.sessions.borrow().keys().cloned().collect()
+    }
+
+    /// Returns operations allowed by the configuration of given session.
+    pub fn allowed_operations(
+        &self,
+        session_id: DragSessionId,
+    ) -> NativeExtensionsResult<Vec<DropOperation>> {
+        Ok(self
+            .session(session_id)?
+            .configuration
+            .allowed_operations
+            .clone())
+    }
+
+    /// Returns last known position of given session.
+    pub fn last_position(&self, session_id: DragSessionId) -> NativeExtensionsResult<Point> {
+        Ok(self.session(session_id)?.last_position.borrow().clone())
+    }
+
+    /// Creates reader for the items of given session, as a drop target would
+    /// see them.
+    pub fn create_reader(
+        &self,
+        session_id: DragSessionId,
+    ) -> NativeExtensionsResult<Rc<PlatformDataReader>> {
+        let session = self.session(session_id)?;
+        let providers = session.providers.iter().map(|p| p.0.clone()).collect();
+        Ok(PlatformDataReader::new_with_providers(providers))
+    }
+
+    /// Moves the session to new screen location and notifies the delegate.
+    pub fn move_session(
+        &self,
+        session_id: DragSessionId,
+        location: Point,
+    ) -> NativeExtensionsResult<()> {
+        let session = self.session(session_id)?;
+        session.last_position.replace(location.clone());
+        if let Some(delegate) = self.delegate.upgrade() {
+            delegate.drag_session_did_move_to_location(self.id, session_id, location);
+        }
+        Ok(())
+    }
+
+    /// Ends the session with given operation and notifies the delegate.
+    /// Data providers of the session are released afterwards.
+    pub fn end_session(
+        &self,
+        session_id: DragSessionId,
+        operation: DropOperation,
+    ) -> NativeExtensionsResult<()> {
+        let session = self
+            .sessions
+            .borrow_mut()
+            .remove(&session_id)
+            .ok_or(NativeExtensionsError::DragSessionNotFound)?;
+        if let Some(delegate) = self.delegate.upgrade() {
+            delegate.drag_session_did_end_with_operation(self.id, session_id, operation);
+        }
+        drop(session);
+        Ok(())
+    }
+
+    pub fn get_local_data(&self) -> Option<Vec<Value>> {
+        self.sessions
+            .borrow()
+            .values()
+            .next()
+            .map(|s| s.configuration.get_local_data())
+    }
+
+    pub fn get_local_data_for_session_id(
+        &self,
+        session_id: DragSessionId,
+    ) -> NativeExtensionsResult<Vec<Value>> {
+        Ok(self.session(session_id)?.configuration.get_local_data())
+    }
+}
@@ -0,0 +1,238 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::{Rc, Weak},
+};
+
+use irondash_message_channel::{Late, Value};
+use irondash_run_loop::util::FutureCompleter;
+
+use crate::{
+    accelerator::Modifiers,
+    api_model::{DropOperation, Point},
+    drag_manager::DragSessionId,
+    drop_manager::{
+        BaseDropEvent, DropEvent, DropItem, DropSessionId, PlatformDropContextDelegate,
+        PlatformDropContextId,
+    },
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    log::OkLog,
+    reader_manager::RegisteredDataReader,
+    util::NextId,
+};
+
+use super::{PlatformDataReader, PlatformDragContext};
+
+pub struct PlatformDropContext {
+    id: PlatformDropContextId,
+    delegate: Weak<dyn PlatformDropContextDelegate>,
+    weak_self: Late<Weak<Self>>,
+    formats: RefCell<Vec<String>>,
+    next_session_id: Cell<i64>,
+    current_session: RefCell<Option<Rc<Session>>>,
+    modifiers: Cell<Modifiers>,
+}
+
+struct Session {
+    id: DropSessionId,
+    drag_session_id: DragSessionId,
+    allowed_operations: Vec<DropOperation>,
+    local_data: Vec<Value>,
+    platform_reader: Rc<PlatformDataReader>,
+    registered_reader: RegisteredDataReader,
+    last_operation: Cell<DropOperation>,
+}
+
+impl PlatformDropContext {
+    pub fn new(
+        id: PlatformDropContextId,
+        _engine_handle: i64,
+        delegate: Weak<dyn PlatformDropContextDelegate>,
+    ) -> NativeExtensionsResult<Self> {
+        Ok(Self {
+            id,
+            delegate,
+            weak_self: Late::new(),
+            formats: RefCell::new(Vec::new()),
+            next_session_id: Cell::new(0),
+            current_session: RefCell::new(None),
+            modifiers: Cell::new(Modifiers::default()),
+        })
+    }
+
+    pub fn assign_weak_self(&self, weak_self: Weak<Self>) {
+        self.weak_self.set(weak_self);
+    }
+
+    pub fn register_drop_formats(&self, formats: &[String]) -> NativeExtensionsResult<()> {
+        self.formats.replace(formats.to_vec());
+        Ok(())
+    }
+
+    /// Returns formats registered through `register_drop_formats`.
+    pub fn registered_formats(&self) -> Vec<String> {
+        self.formats.borrow().clone()
+    }
+
+    /// Keyboard modifiers pressed during the drag.
+    pub fn current_modifiers(&self) -> Modifiers {
+        self.modifiers.get()
+    }
+
+    /// Sets modifiers reported by `current_modifiers`.
+    pub fn set_modifiers(&self, modifiers: Modifiers) {
+        self.modifiers.set(modifiers);
+    }
+
+    fn delegate(&self) -> NativeExtensionsResult<Rc<dyn PlatformDropContextDelegate>> {
+        self.delegate
+            .upgrade()
+            .ok_or_else(|| NativeExtensionsError::OtherError("missing context delegate".into()))
+    }
+
+    fn new_session(
+        &self,
+        drag_context: &PlatformDragContext,
+        drag_session_id: DragSessionId,
+    ) -> NativeExtensionsResult<Rc<Session>> {
+        let platform_reader = drag_context.create_reader(drag_session_id)?;
+        let registered_reader = self
+            .delegate()?
+            .register_platform_reader(self.id, platform_reader.clone());
+        Ok(Rc::new(Session {
+            id: self.next_session_id.next_id().into(),
+            drag_session_id,
+            allowed_operations: drag_context.allowed_operations(drag_session_id)?,
+            local_data: drag_context.get_local_data_for_session_id(drag_session_id)?,
+            platform_reader,
+            registered_reader,
+            last_operation: Cell::new(DropOperation::None),
+        }))
+    }
+
+    fn create_drop_event(
+        &self,
+        session: &Session,
+        location_in_view: Point,
+        accepted_operation: Option<DropOperation>,
+    ) -> NativeExtensionsResult<DropEvent> {
+        let reader = &session.platform_reader;
+        let number_of_items = session.local_data.len().max(reader.item_count());
+        let items = (0..number_of_items)
+            .map(|i| {
+                Ok(DropItem {
+                    item_id: (i as i64).into(),
+                    formats: if i < reader.item_count() {
+                        reader.item_formats(i as i64)?
+                    } else {
+                        Vec::new()
+                    },
+                    local_data: session.local_data.get(i).cloned().unwrap_or(Value::Null),
+                })
+            })
+            .collect::<NativeExtensionsResult<_>>()?;
+        Ok(DropEvent {
+            session_id: session.id,
+            location_in_view,
+            allowed_operations: session.allowed_operations.clone(),
+            accepted_operation,
+            items,
+            reader: Some(session.registered_reader.clone()),
+        })
+    }
+
+    /// Equivalent of platform drag motion event. Starts a new drop session
+    /// if the drag session is not over this context yet.
+    pub fn drag_over(
+        &self,
+        drag_context: &PlatformDragContext,
+        drag_session_id: DragSessionId,
+        location_in_view: Point,
+    ) -> NativeExtensionsResult<()> {
+        let current = self.current_session.borrow().clone();
+        let session = match current {
+            Some(session) if session.drag_session_id == drag_session_id => session,
+            current => {
+                if current.is_some() {
+                    self.drag_leave()?;
+                }
+                let session = self.new_session(drag_context, drag_session_id)?;
+                self.current_session.replace(Some(session.clone()));
+                session
+            }
+        };
+        let event = self.create_drop_event(&session, location_in_view, None)?;
+        let session_clone = session.clone();
+        self.delegate()?.send_drop_update(
+            self.id,
+            event,
+            Box::new(move |res| {
+                let res = res.ok_log().unwrap_or(DropOperation::None);
+                session_clone.last_operation.set(res);
+            }),
+        );
+        Ok(())
+    }
+
+    /// Equivalent of drag leaving the view. Does nothing if there is no
+    /// drop session in progress.
+    pub fn drag_leave(&self) -> NativeExtensionsResult<()> {
+        if let Some(session) = self.current_session.take() {
+            let delegate = self.delegate()?;
+            delegate.send_drop_leave(
+                self.id,
+                BaseDropEvent {
+                    session_id: session.id,
+                },
+            );
+            delegate.send_drop_ended(
+                self.id,
+                BaseDropEvent {
+                    session_id: session.id,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Equivalent of releasing the drag over the view. Performs the drop if
+    /// the last drop update accepted an operation and returns the resulting
+    /// operation (`DropOperation::None` if the drop was not performed).
+    pub async fn perform_drop(
+        &self,
+        location_in_view: Point,
+    ) -> NativeExtensionsResult<DropOperation> {
+        let session = match self.current_session.borrow().clone() {
+            Some(session) => session,
+            None => return Ok(DropOperation::None),
+        };
+        let operation = session.last_operation.get();
+        if operation == DropOperation::None {
+            self.drag_leave()?;
+            return Ok(DropOperation::None);
+        }
+        self.current_session.replace(None);
+        let event = self.create_drop_event(&session, location_in_view, Some(operation))?;
+        let (future, completer) = FutureCompleter::new();
+        self.delegate()?.send_perform_drop(
+            self.id,
+            event,
+            Box::new(move |r| completer.complete(r.ok_log().is_some())),
+        );
+        let ok = future.await;
+        self.delegate()?.send_drop_ended(
+            self.id,
+            BaseDropEvent {
+                session_id: session.id,
+            },
+        );
+        Ok(if ok { operation } else { DropOperation::None })
+    }
+
+    /// Returns operation accepted by the last drop update of current session.
+    pub fn last_operation(&self) -> Option<DropOperation> {
+        self.current_session
+            .borrow()
+            .as_ref()
+            .map(|s| s.last_operation.get())
+    }
+}
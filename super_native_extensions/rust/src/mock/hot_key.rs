@@ -0,0 +1,57 @@
+use std::{cell::RefCell, collections::HashMap, rc::Weak};
+
+use crate::{
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    hot_key_manager::{HotKeyCreateRequest, HotKeyHandle, HotKeyManagerDelegate},
+};
+
+pub struct PlatformHotKeyManager {
+    delegate: Weak<dyn HotKeyManagerDelegate>,
+    hot_keys: RefCell<HashMap<HotKeyHandle, HotKeyCreateRequest>>,
+}
+
+impl PlatformHotKeyManager {
+    pub fn new(delegate: Weak<dyn HotKeyManagerDelegate>) -> Self {
+        Self {
+            delegate,
+            hot_keys: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn assign_weak_self(&self, _weak: Weak<PlatformHotKeyManager>) {}
+
+    pub fn create_hot_key(
+        &self,
+        handle: HotKeyHandle,
+        request: HotKeyCreateRequest,
+    ) -> NativeExtensionsResult<()> {
+        self.hot_keys.borrow_mut().insert(handle, request);
+        Ok(())
+    }
+
+    pub fn destroy_hot_key(&self, handle: HotKeyHandle) -> NativeExtensionsResult<()> {
+        self.hot_keys.borrow_mut().remove(&handle);
+        Ok(())
+    }
+
+    /// Simulates pressing and releasing of hot key with given handle.
+    pub fn simulate_hot_key(
+        &self,
+        handle: HotKeyHandle,
+        pressed: bool,
+    ) -> NativeExtensionsResult<()> {
+        if !self.hot_keys.borrow().contains_key(&handle) {
+            return Err(NativeExtensionsError::OtherError(format!(
+                "Hot key {handle:?} not found"
+            )));
+        }
+        if let Some(delegate) = self.delegate.upgrade() {
+            if pressed {
+                delegate.on_hot_key_pressed(handle);
+            } else {
+                delegate.on_hot_key_released(handle);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Weak,
+};
+
+use crate::{
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    keyboard_layout_manager::{
+        KeyboardLayout, KeyboardLayoutDelegate, KeyboardLayoutInfo, KeymapDescription,
+    },
+};
+
+pub struct PlatformKeyboardLayout {
+    current_layout: RefCell<KeyboardLayout>,
+    layouts: RefCell<Vec<(KeyboardLayoutInfo, KeyboardLayout)>>,
+    selected_layout: Cell<Option<i64>>,
+    delegate: Weak<dyn KeyboardLayoutDelegate>,
+}
+
+impl PlatformKeyboardLayout {
+    pub fn new(delegate: Weak<dyn KeyboardLayoutDelegate>) -> Self {
+        Self {
+            current_layout: RefCell::new(KeyboardLayout {
+                keys: Vec::new(),
+                compose: Vec::new(),
+            }),
+            layouts: RefCell::new(Vec::new()),
+            selected_layout: Cell::new(None),
+            delegate,
+        }
+    }
+
+    pub fn get_current_layout(&self) -> Option<KeyboardLayout> {
+        Some(self.current_layout.borrow().clone())
+    }
+
+    pub fn get_layouts(&self) -> NativeExtensionsResult<Vec<KeyboardLayoutInfo>> {
+        let selected = self.selected_layout.get();
+        Ok(self
+            .layouts
+            .borrow()
+            .iter()
+            .map(|(info, _)| KeyboardLayoutInfo {
+                selected: Some(info.id) == selected,
+                ..info.clone()
+            })
+            .collect())
+    }
+
+    pub fn get_layout(&self, id: i64) -> NativeExtensionsResult<Option<KeyboardLayout>> {
+        Ok(self
+            .layouts
+            .borrow()
+            .iter()
+            .find(|(info, _)| info.id == id)
+            .map(|(_, layout)| layout.clone()))
+    }
+
+    pub fn get_layout_for_keymap(
+        &self,
+        _description: KeymapDescription,
+    ) -> NativeExtensionsResult<KeyboardLayout> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
+    pub fn select_layout(&self, id: i64) -> NativeExtensionsResult<()> {
+        let layout = self
+            .get_layout(id)?
+            .ok_or_else(|| NativeExtensionsError::OtherError(format!("unknown layout {id}")))?;
+        self.selected_layout.set(Some(id));
+        self.set_current_layout(layout);
+        Ok(())
+    }
+
+    pub fn assign_weak_self(&self, _weak: Weak<PlatformKeyboardLayout>) {}
+
+    /// Replaces current layout and notifies the delegate.
+    pub fn set_current_layout(&self, layout: KeyboardLayout) {
+        self.current_layout.replace(layout);
+        if let Some(delegate) = self.delegate.upgrade() {
+            delegate.keyboard_map_did_change();
+        }
+    }
+
+    /// Replaces the list of installed layouts. Selection is not changed.
+    pub fn set_layouts(&self, layouts: Vec<(KeyboardLayoutInfo, KeyboardLayout)>) {
+        self.layouts.replace(layouts);
+    }
+}
@@ -0,0 +1,184 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::{Rc, Weak},
+};
+
+use irondash_message_channel::IsolateId;
+use irondash_run_loop::util::FutureCompleter;
+
+use crate::{
+    api_model::{
+        ImageData, Menu, MenuAction, MenuElement, ShowContextMenuRequest, ShowContextMenuResponse,
+    },
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    menu_manager::{PlatformMenuContextDelegate, PlatformMenuContextId, PlatformMenuDelegate},
+};
+
+pub struct PlatformMenuContext {
+    _delegate: Weak<dyn PlatformMenuContextDelegate>,
+    preview_images: RefCell<HashMap<i64, ImageData>>,
+    current_menu: RefCell<Option<(Rc<PlatformMenu>, FutureCompleter<ShowContextMenuResponse>)>>,
+    menu_bar: RefCell<Option<Rc<PlatformMenu>>>,
+    tray_menu: RefCell<Option<Rc<PlatformMenu>>>,
+}
+
+pub struct PlatformMenu {
+    isolate: IsolateId,
+    delegate: Weak<dyn PlatformMenuDelegate>,
+    pub(crate) menu: RefCell<Menu>,
+}
+
+impl std::fmt::Debug for PlatformMenu {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PlatformMenu")
+            .field("menu", &self.menu)
+            .finish()
+    }
+}
+
+fn find_action(elements: &[MenuElement], unique_id: i64) -> Option<&MenuAction> {
+    elements.iter().find_map(|element| match element {
+        MenuElement::Action(action) if action.unique_id == unique_id => Some(action),
+        MenuElement::Menu(menu) => find_action(&menu.children, unique_id),
+        _ => None,
+    })
+}
+
+fn find_action_mut(elements: &mut [MenuElement], unique_id: i64) -> Option<&mut MenuAction> {
+    elements.iter_mut().find_map(|element| match element {
+        MenuElement::Action(action) if action.unique_id == unique_id => Some(action),
+        MenuElement::Menu(menu) => find_action_mut(&mut menu.children, unique_id),
+        _ => None,
+    })
+}
+
+impl PlatformMenu {
+    pub fn new(
+        isolate: IsolateId,
+        delegate: Weak<dyn PlatformMenuDelegate>,
+        menu: Menu,
+    ) -> NativeExtensionsResult<Rc<Self>> {
+        Ok(Rc::new(Self {
+            isolate,
+            delegate,
+            menu: RefCell::new(menu),
+        }))
+    }
+
+    /// Replaces actions with matching unique id. Unknown actions are ignored.
+    pub fn update_elements(&self, actions: Vec<MenuAction>) -> NativeExtensionsResult<()> {
+        let mut menu = self.menu.borrow_mut();
+        for action in actions {
+            if let Some(existing) = find_action_mut(&mut menu.children, action.unique_id) {
+                *existing = action;
+            }
+        }
+        Ok(())
+    }
+
+    /// Activates action with given unique id. Returns `false` if the action
+    /// does not exist or is disabled.
+    pub fn activate(&self, unique_id: i64) -> bool {
+        let disabled = find_action(&self.menu.borrow().children, unique_id)
+            .map(|action| action.attributes.disabled);
+        match disabled {
+            Some(false) => {
+                if let Some(delegate) = self.delegate.upgrade() {
+                    delegate.on_action(self.isolate, unique_id);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl PlatformMenuContext {
+    pub fn new(
+        _id: PlatformMenuContextId,
+        _engine_handle: i64,
+        delegate: Weak<dyn PlatformMenuContextDelegate>,
+    ) -> NativeExtensionsResult<Self> {
+        Ok(Self {
+            _delegate: delegate,
+            preview_images: RefCell::new(HashMap::new()),
+            current_menu: RefCell::new(None),
+            menu_bar: RefCell::new(None),
+            tray_menu: RefCell::new(None),
+        })
+    }
+
+    pub fn assign_weak_self(&self, _weak_self: Weak<Self>) {}
+
+    pub fn set_menu_bar(&self, menu: Option<Rc<PlatformMenu>>) -> NativeExtensionsResult<()> {
+        self.menu_bar.replace(menu);
+        Ok(())
+    }
+
+    /// Returns the menu currently installed as menu bar.
+    pub fn menu_bar(&self) -> Option<Rc<PlatformMenu>> {
+        self.menu_bar.borrow().clone()
+    }
+
+    pub fn set_tray_menu(
+        &self,
+        menu: Option<Rc<PlatformMenu>>,
+        _icon: Option<ImageData>,
+        _tooltip: Option<String>,
+    ) -> NativeExtensionsResult<()> {
+        self.tray_menu.replace(menu);
+        Ok(())
+    }
+
+    /// Returns the menu currently shown from tray icon.
+    pub fn tray_menu(&self) -> Option<Rc<PlatformMenu>> {
+        self.tray_menu.borrow().clone()
+    }
+
+    pub fn update_preview_image(
+        &self,
+        configuration_id: i64,
+        image_data: ImageData,
+    ) -> NativeExtensionsResult<()> {
+        self.preview_images
+            .borrow_mut()
+            .insert(configuration_id, image_data);
+        Ok(())
+    }
+
+    pub fn preview_image(&self, configuration_id: i64) -> Option<ImageData> {
+        self.preview_images.borrow().get(&configuration_id).cloned()
+    }
+
+    /// Returns the menu that is currently being shown, if any.
+    pub fn current_menu(&self) -> Option<Rc<PlatformMenu>> {
+        self.current_menu
+            .borrow()
+            .as_ref()
+            .map(|(menu, _)| menu.clone())
+    }
+
+    /// Hides currently shown menu, optionally activating an action first.
+    pub fn hide_context_menu(&self, action: Option<i64>) {
+        let current = self.current_menu.borrow_mut().take();
+        if let Some((menu, completer)) = current {
+            let item_selected = action.map(|a| menu.activate(a)).unwrap_or(false);
+            completer.complete(ShowContextMenuResponse { item_selected });
+        }
+    }
+
+    pub async fn show_context_menu(
+        &self,
+        request: ShowContextMenuRequest,
+    ) -> NativeExtensionsResult<ShowContextMenuResponse> {
+        let menu = request
+            .menu
+            .ok_or(NativeExtensionsError::PlatformMenuNotFound)?;
+        // Only one menu can be shown at a time.
+        self.hide_context_menu(None);
+        let (future, completer) = FutureCompleter::new();
+        self.current_menu.replace(Some((menu, completer)));
+        Ok(future.await)
+    }
+}
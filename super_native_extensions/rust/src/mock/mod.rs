@@ -1,4 +1,27 @@
+//! In-memory platform backend used for running the managers without a
+//! windowing system. Enabled through the `mock` cargo feature.
+//!
+//! The backend mirrors the public surface of the real platform backends.
+//! Clipboard contents are kept in memory, drag and drop sessions are driven
+//! programmatically and readers are backed directly by in-process data
+//! providers.
+
+mod clipboard_events;
+mod data_provider;
+mod drag;
+mod drop;
+mod hot_key;
+mod keyboard_layout;
+mod menu;
 mod reader;
-mod writer;
+pub mod replay;
+pub mod simulation;
+
+pub use clipboard_events::*;
+pub use data_provider::*;
+pub use drag::*;
+pub use drop::*;
+pub use hot_key::*;
+pub use keyboard_layout::*;
+pub use menu::*;
 pub use reader::*;
-pub use writer::*;
@@ -1,55 +1,359 @@
-use std::{cell::RefCell, rc::Weak};
+use std::{cell::RefCell, fs, path::PathBuf, rc::Rc, sync::Arc};
 
-use nativeshell_core::{util::Late, Value};
+use async_trait::async_trait;
+use irondash_message_channel::Value;
+use irondash_run_loop::util::FutureCompleter;
 
-use crate::error::NativeExtensionsResult;
+use crate::{
+    api_model::{ClipboardSelection, DataProviderValueId, DataRepresentation},
+    data_provider_manager::VirtualFileResult,
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    reader_manager::{ItemDataStream, ReadProgress, VirtualFileReader},
+    util::{get_target_path, Movable},
+    value_promise::ValuePromiseResult,
+};
 
-thread_local! {
-    pub static READERS: RefCell<Vec<Weak<PlatformClipboardReader>>> = RefCell::new(Vec::new());
-}
+use super::{
+    data_provider::{new_stream_entry, take_stream_entry},
+    PlatformDataProvider,
+};
 
-pub struct PlatformClipboardReader {
-    weak_self: Late<Weak<Self>>,
+/// Reader backed directly by in-process data providers. Each provider
+/// represents one item.
+pub struct PlatformDataReader {
+    items: Vec<Rc<PlatformDataProvider>>,
 }
 
-impl PlatformClipboardReader {
+impl PlatformDataReader {
+    pub fn new_clipboard_reader(selection: ClipboardSelection) -> NativeExtensionsResult<Rc<Self>> {
+        Ok(Self::new_with_providers(
+            PlatformDataProvider::clipboard_contents(selection),
+        ))
+    }
+
+    pub fn new_with_providers(items: Vec<Rc<PlatformDataProvider>>) -> Rc<Self> {
+        Rc::new(Self { items })
+    }
+
+    fn item(&self, item: i64) -> NativeExtensionsResult<&Rc<PlatformDataProvider>> {
+        self.items
+            .get(item as usize)
+            .ok_or_else(|| NativeExtensionsError::OtherError(format!("Item {item} not found")))
+    }
+
+    fn representation(&self, item: i64, format: &str) -> Option<&DataRepresentation> {
+        self.items
+            .get(item as usize)?
+            .data
+            .representations
+            .iter()
+            .find(|r| r.format() == format)
+    }
+
+    fn virtual_file_id(&self, item: i64, format: &str) -> Option<DataProviderValueId> {
+        match self.representation(item, format) {
+            Some(DataRepresentation::VirtualFile { id, .. }) => Some(*id),
+            _ => None,
+        }
+    }
+
     pub async fn get_items(&self) -> NativeExtensionsResult<Vec<i64>> {
-        Ok(vec![])
+        Ok((0..self.items.len() as i64).collect())
     }
 
-    pub async fn get_types_for_item(&self, _item: i64) -> NativeExtensionsResult<Vec<String>> {
-        Ok(vec![])
+    /// Returns number of items in the reader.
+    pub fn item_count(&self) -> usize {
+        self.items.len()
     }
 
-    pub async fn get_data_for_item(
+    /// Synchronous variant of `get_formats_for_item`.
+    pub fn item_formats(&self, item: i64) -> NativeExtensionsResult<Vec<String>> {
+        let mut res = Vec::<String>::new();
+        for representation in &self.item(item)?.data.representations {
+            let format = representation.format();
+            if !res.iter().any(|f| f == format) {
+                res.push(format.to_owned());
+            }
+        }
+        Ok(res)
+    }
+
+    pub async fn get_formats_for_item(&self, item: i64) -> NativeExtensionsResult<Vec<String>> {
+        self.item_formats(item)
+    }
+
+    pub async fn get_suggested_name_for_item(
+        &self,
+        item: i64,
+    ) -> NativeExtensionsResult<Option<String>> {
+        Ok(self.item(item)?.data.suggested_name.clone())
+    }
+
+    pub async fn get_item_format_for_uri(
         &self,
         _item: i64,
-        _data_type: String,
+    ) -> NativeExtensionsResult<Option<String>> {
+        Ok(None)
+    }
+
+    pub async fn get_data_for_item(
+        &self,
+        item: i64,
+        data_type: String,
+        _progress: Option<Arc<ReadProgress>>,
     ) -> NativeExtensionsResult<Value> {
-        Ok(Value::Null)
+        let provider = self.item(item)?;
+        match self.representation(item, &data_type) {
+            Some(DataRepresentation::Simple { data, .. }) => Ok(data.clone()),
+            Some(DataRepresentation::Lazy { id, .. }) => {
+                let delegate = provider
+                    .delegate
+                    .upgrade()
+                    .ok_or(NativeExtensionsError::DataSourceNotFound)?;
+                match delegate.get_lazy_data_async(provider.isolate_id, *id).await {
+                    ValuePromiseResult::Ok { value } => Ok(value),
+                    ValuePromiseResult::Cancelled => Ok(Value::Null),
+                }
+            }
+            _ => Ok(Value::Null),
+        }
+    }
+
+    pub fn item_format_is_synthesized(
+        &self,
+        _item: i64,
+        _format: &str,
+    ) -> NativeExtensionsResult<bool> {
+        Ok(false)
+    }
+
+    pub async fn can_copy_virtual_file_for_item(
+        &self,
+        item: i64,
+        format: &str,
+    ) -> NativeExtensionsResult<bool> {
+        Ok(self.virtual_file_id(item, format).is_some())
     }
 
-    pub fn new_default() -> NativeExtensionsResult<Self> {
-        Ok(Self {
-            weak_self: Late::new(),
-        })
+    pub async fn can_read_virtual_file_for_item(
+        &self,
+        item: i64,
+        format: &str,
+    ) -> NativeExtensionsResult<bool> {
+        Ok(self.virtual_file_id(item, format).is_some())
     }
 
-    pub fn assign_weak_self(&self, weak_self: Weak<PlatformClipboardReader>) {
-        self.weak_self.set(weak_self.clone());
-        READERS.with(|f| {
-            f.borrow_mut().push(weak_self);
-        })
+    /// Requests virtual file contents from the provider and waits until
+    /// the whole file is received.
+    async fn fetch_virtual_file(
+        &self,
+        item: i64,
+        format: &str,
+        progress: Arc<ReadProgress>,
+    ) -> NativeExtensionsResult<Vec<u8>> {
+        let provider = self.item(item)?;
+        let id = self
+            .virtual_file_id(item, format)
+            .ok_or(NativeExtensionsError::UnsupportedOperation)?;
+        let delegate = provider
+            .delegate
+            .upgrade()
+            .ok_or(NativeExtensionsError::DataSourceNotFound)?;
+        let stream_handle = new_stream_entry();
+        let (future, completer) = FutureCompleter::new();
+        let progress_clone = progress.clone();
+        let session = delegate.get_virtual_file(
+            provider.isolate_id,
+            id,
+            stream_handle,
+            Box::new(|_| {}),
+            Box::new(move |fraction| progress_clone.report_progress(Some(fraction))),
+            Box::new(move |result| completer.complete(result)),
+        );
+        let weak_session = unsafe { Movable::new(Arc::downgrade(&session)) };
+        progress.set_cancellation_handler(Some(Box::new(move || {
+            if let Some(session) = weak_session.upgrade() {
+                session.dispose();
+            }
+        })));
+        let result = future.await;
+        progress.set_cancellation_handler(None);
+        let data = take_stream_entry(stream_handle);
+        match result {
+            VirtualFileResult::Done => data.ok_or_else(|| {
+                NativeExtensionsError::VirtualFileReceiveError("Stream closed".into())
+            }),
+            VirtualFileResult::Error { message } => {
+                Err(NativeExtensionsError::VirtualFileReceiveError(message))
+            }
+            VirtualFileResult::Cancelled => Err(NativeExtensionsError::VirtualFileReceiveError(
+                "Cancelled".into(),
+            )),
+        }
+    }
+
+    pub async fn create_data_stream_for_item(
+        &self,
+        _item: i64,
+        _format: &str,
+        _progress: Arc<ReadProgress>,
+    ) -> NativeExtensionsResult<Option<Rc<dyn ItemDataStream>>> {
+        Ok(None)
+    }
+
+    pub async fn create_virtual_file_reader_for_item(
+        &self,
+        item: i64,
+        format: &str,
+        progress: Arc<ReadProgress>,
+    ) -> NativeExtensionsResult<Option<Rc<dyn VirtualFileReader>>> {
+        let data = self.fetch_virtual_file(item, format, progress).await?;
+        Ok(Some(Rc::new(MemoryReader {
+            size: data.len() as i64,
+            data: RefCell::new(data),
+            file_name: self.item(item)?.data.suggested_name.clone(),
+        })))
+    }
+
+    pub async fn copy_virtual_file_for_item(
+        &self,
+        item: i64,
+        format: &str,
+        target_folder: PathBuf,
+        progress: Arc<ReadProgress>,
+    ) -> NativeExtensionsResult<PathBuf> {
+        let data = self.fetch_virtual_file(item, format, progress).await?;
+        let file_name = self
+            .item(item)?
+            .data
+            .suggested_name
+            .clone()
+            .unwrap_or_else(|| "file".into());
+        let path = get_target_path(&target_folder, &file_name);
+        fs::write(&path, data)?;
+        Ok(path)
+    }
+}
+
+struct MemoryReader {
+    data: RefCell<Vec<u8>>,
+    size: i64,
+    file_name: Option<String>,
+}
+
+#[async_trait(?Send)]
+impl VirtualFileReader for MemoryReader {
+    async fn read_next(&self) -> NativeExtensionsResult<Vec<u8>> {
+        Ok(self.data.replace(Vec::new()))
+    }
+
+    fn file_size(&self) -> NativeExtensionsResult<Option<i64>> {
+        Ok(Some(self.size))
+    }
+
+    fn file_name(&self) -> Option<String> {
+        self.file_name.clone()
+    }
+
+    fn close(&self) -> NativeExtensionsResult<()> {
+        Ok(())
     }
 }
 
-impl Drop for PlatformClipboardReader {
-    fn drop(&mut self) {
-        READERS
-            .try_with(|f| {
-                f.borrow_mut()
-                    .retain(|a| a.as_ptr() != self.weak_self.as_ptr());
-            })
-            .ok();
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::Cell,
+        rc::{Rc, Weak},
+        sync::Arc,
+    };
+
+    use irondash_message_channel::{IsolateId, Value};
+
+    use crate::{
+        api_model::{ClipboardSelection, DataProvider, DataRepresentation},
+        data_provider_manager::{DataProviderHandle, DataProviderManager},
+        test_util::block_on,
+        util::DropNotifier,
+    };
+
+    use super::{PlatformDataProvider, PlatformDataReader};
+
+    fn simple(format: &str, data: &str) -> DataRepresentation {
+        DataRepresentation::Simple {
+            format: format.into(),
+            data: Value::String(data.into()),
+        }
+    }
+
+    fn provider(representations: Vec<DataRepresentation>) -> Rc<PlatformDataProvider> {
+        let provider = Rc::new(PlatformDataProvider::new(
+            Weak::<DataProviderManager>::new(),
+            IsolateId(1),
+            DataProvider {
+                representations,
+                suggested_name: Some("name.txt".into()),
+            },
+        ));
+        provider.assign_weak_self(Rc::downgrade(&provider));
+        provider
+    }
+
+    #[test]
+    fn test_read_provider_data() {
+        let reader = PlatformDataReader::new_with_providers(vec![
+            provider(vec![
+                simple("text/plain", "Hello"),
+                simple("text/html", "<b>"),
+            ]),
+            provider(vec![simple("text/plain", "World")]),
+        ]);
+        assert_eq!(block_on(reader.get_items()).unwrap(), vec![0, 1]);
+        assert_eq!(
+            block_on(reader.get_formats_for_item(0)).unwrap(),
+            vec!["text/plain".to_owned(), "text/html".to_owned()]
+        );
+        assert_eq!(
+            block_on(reader.get_data_for_item(1, "text/plain".into(), None)).unwrap(),
+            Value::String("World".into())
+        );
+        assert_eq!(
+            block_on(reader.get_data_for_item(1, "text/html".into(), None)).unwrap(),
+            Value::Null
+        );
+        assert_eq!(
+            block_on(reader.get_suggested_name_for_item(0)).unwrap(),
+            Some("name.txt".into())
+        );
+        assert!(block_on(reader.get_formats_for_item(2)).is_err());
+    }
+
+    #[test]
+    fn test_clipboard() {
+        let released = Rc::new(Cell::new(false));
+        let released_clone = released.clone();
+        let handle = Arc::new(DataProviderHandle::from(DropNotifier::new(move || {
+            released_clone.set(true);
+        })));
+        block_on(PlatformDataProvider::write_to_clipboard(
+            vec![(provider(vec![simple("text/plain", "Hello")]), handle)],
+            ClipboardSelection::Clipboard,
+        ))
+        .unwrap();
+
+        let reader =
+            PlatformDataReader::new_clipboard_reader(ClipboardSelection::Clipboard).unwrap();
+        assert_eq!(
+            block_on(reader.get_data_for_item(0, "text/plain".into(), None)).unwrap(),
+            Value::String("Hello".into())
+        );
+        // Primary selection is independent from clipboard.
+        let reader = PlatformDataReader::new_clipboard_reader(ClipboardSelection::Primary).unwrap();
+        assert!(block_on(reader.get_items()).unwrap().is_empty());
+
+        assert!(!released.get());
+        PlatformDataProvider::clear_clipboard(ClipboardSelection::Clipboard);
+        assert!(released.get());
+        assert!(PlatformDataProvider::clipboard_contents(ClipboardSelection::Clipboard).is_empty());
     }
 }
@@ -0,0 +1,431 @@
+use std::{
+    collections::HashMap,
+    rc::{Rc, Weak},
+    sync::Arc,
+};
+
+use irondash_message_channel::{IsolateId, Value};
+use irondash_run_loop::{util::FutureCompleter, RunLoop};
+
+use crate::{
+    api_model::{DataProvider, DataRepresentation, DropOperation, Point},
+    data_provider_manager::DataProviderManager,
+    drag_trace::{DragTrace, DragTraceEvent},
+    drop_manager::{
+        BaseDropEvent, DropEvent, DropItem, DropSessionId, ItemPreviewRequest,
+        PlatformDropContextDelegate,
+    },
+    log::OkLog,
+    reader_manager::RegisteredDataReader,
+    value_promise::{Promise, PromiseResult},
+};
+
+use super::{PlatformDataProvider, PlatformDataReader};
+
+/// Recorded event for which the delegate responded differently during replay.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayMismatch {
+    /// Index of the event within the trace.
+    pub index: usize,
+    pub recorded: DragTraceEvent,
+    pub replayed: DragTraceEvent,
+}
+
+/// Feeds drop events from recorded trace to the delegate, same way the drop
+/// context would during the original session. Drag events and timing are
+/// ignored. Readers of replayed sessions contain items with recorded formats
+/// but no data.
+pub struct DragTraceReplay {
+    delegate: Rc<dyn PlatformDropContextDelegate>,
+    readers: HashMap<(i64, DropSessionId), RegisteredDataReader>,
+}
+
+impl DragTraceReplay {
+    pub fn new(delegate: Rc<dyn PlatformDropContextDelegate>) -> Self {
+        Self {
+            delegate,
+            readers: HashMap::new(),
+        }
+    }
+
+    fn reader(
+        &mut self,
+        context_id: i64,
+        session_id: DropSessionId,
+        item_formats: &[Vec<String>],
+    ) -> RegisteredDataReader {
+        let delegate = &self.delegate;
+        self.readers
+            .entry((context_id, session_id))
+            .or_insert_with(|| {
+                let providers = item_formats
+                    .iter()
+                    .map(|formats| {
+                        let provider = Rc::new(PlatformDataProvider::new(
+                            Weak::<DataProviderManager>::new(),
+                            IsolateId(context_id),
+                            DataProvider {
+                                representations: formats
+                                    .iter()
+                                    .map(|format| DataRepresentation::Simple {
+                                        format: format.clone(),
+                                        data: Value::Null,
+                                    })
+                                    .collect(),
+                                suggested_name: None,
+                            },
+                        ));
+                        provider.assign_weak_self(Rc::downgrade(&provider));
+                        provider
+                    })
+                    .collect();
+                let reader = PlatformDataReader::new_with_providers(providers);
+                delegate.register_platform_reader(IsolateId(context_id), reader)
+            })
+            .clone()
+    }
+
+    fn drop_event(
+        &mut self,
+        context_id: i64,
+        session_id: DropSessionId,
+        location_in_view: &Point,
+        allowed_operations: &[DropOperation],
+        item_formats: &[Vec<String>],
+        accepted_operation: Option<DropOperation>,
+    ) -> DropEvent {
+        DropEvent {
+            session_id,
+            location_in_view: location_in_view.clone(),
+            allowed_operations: allowed_operations.to_vec(),
+            accepted_operation,
+            items: item_formats
+                .iter()
+                .enumerate()
+                .map(|(i, formats)| DropItem {
+                    item_id: (i as i64).into(),
+                    formats: formats.clone(),
+                    local_data: Value::Null,
+                })
+                .collect(),
+            reader: Some(self.reader(context_id, session_id, item_formats)),
+        }
+    }
+
+    /// Replays the event and returns the event as it would be recorded now.
+    async fn replay_event(&mut self, event: &DragTraceEvent) -> DragTraceEvent {
+        match event.clone() {
+            DragTraceEvent::DropUpdate {
+                time,
+                context_id,
+                session_id,
+                location_in_view,
+                allowed_operations,
+                item_formats,
+                operation: _,
+            } => {
+                let drop_event = self.drop_event(
+                    context_id,
+                    session_id,
+                    &location_in_view,
+                    &allowed_operations,
+                    &item_formats,
+                    None,
+                );
+                let (future, completer) = FutureCompleter::new();
+                self.delegate.send_drop_update(
+                    IsolateId(context_id),
+                    drop_event,
+                    Box::new(move |r| completer.complete(r.ok_log())),
+                );
+                let operation = future.await.unwrap_or(DropOperation::None);
+                DragTraceEvent::DropUpdate {
+                    time,
+                    context_id,
+                    session_id,
+                    location_in_view,
+                    allowed_operations,
+                    item_formats,
+                    operation,
+                }
+            }
+            DragTraceEvent::PerformDrop {
+                time,
+                context_id,
+                session_id,
+                location_in_view,
+                allowed_operations,
+                item_formats,
+                accepted_operation,
+                success: _,
+            } => {
+                let drop_event = self.drop_event(
+                    context_id,
+                    session_id,
+                    &location_in_view,
+                    &allowed_operations,
+                    &item_formats,
+                    accepted_operation,
+                );
+                let (future, completer) = FutureCompleter::new();
+                self.delegate.send_perform_drop(
+                    IsolateId(context_id),
+                    drop_event,
+                    Box::new(move |r| completer.complete(r.ok_log().is_some())),
+                );
+                let success = future.await;
+                DragTraceEvent::PerformDrop {
+                    time,
+                    context_id,
+                    session_id,
+                    location_in_view,
+                    allowed_operations,
+                    item_formats,
+                    accepted_operation,
+                    success,
+                }
+            }
+            DragTraceEvent::DropLeave {
+                context_id,
+                session_id,
+                ..
+            } => {
+                self.delegate
+                    .send_drop_leave(IsolateId(context_id), BaseDropEvent { session_id });
+                event.clone()
+            }
+            DragTraceEvent::DropEnded {
+                context_id,
+                session_id,
+                ..
+            } => {
+                self.readers.remove(&(context_id, session_id));
+                self.delegate
+                    .send_drop_ended(IsolateId(context_id), BaseDropEvent { session_id });
+                event.clone()
+            }
+            DragTraceEvent::ItemPreview {
+                time,
+                context_id,
+                session_id,
+                item_id,
+                size,
+                fade_out_delay,
+                fade_out_duration,
+                has_preview: _,
+            } => {
+                let promise = self.delegate.get_preview_for_item(
+                    IsolateId(context_id),
+                    ItemPreviewRequest {
+                        session_id,
+                        item_id,
+                        size: size.clone(),
+                        fade_out_delay,
+                        fade_out_duration,
+                    },
+                );
+                let has_preview = match wait_for_promise(promise).await {
+                    PromiseResult::Ok { value } => value.preview.is_some(),
+                    PromiseResult::Cancelled => false,
+                };
+                DragTraceEvent::ItemPreview {
+                    time,
+                    context_id,
+                    session_id,
+                    item_id,
+                    size,
+                    fade_out_delay,
+                    fade_out_duration,
+                    has_preview,
+                }
+            }
+            DragTraceEvent::DragMove { .. } | DragTraceEvent::DragEnd { .. } => event.clone(),
+        }
+    }
+
+    /// Replays all events of the trace. Returns events for which the delegate
+    /// produced different operation, drop result or preview than recorded.
+    pub async fn replay(&mut self, trace: &DragTrace) -> Vec<ReplayMismatch> {
+        let mut res = Vec::new();
+        for (index, recorded) in trace.events.iter().enumerate() {
+            let replayed = self.replay_event(recorded).await;
+            if &replayed != recorded {
+                res.push(ReplayMismatch {
+                    index,
+                    recorded: recorded.clone(),
+                    replayed,
+                });
+            }
+        }
+        res
+    }
+}
+
+/// Waits for the promise without blocking the run loop.
+async fn wait_for_promise<T>(promise: Arc<Promise<T>>) -> T {
+    loop {
+        if let Some(result) = promise.try_take() {
+            return result;
+        }
+        let (future, completer) = FutureCompleter::new();
+        RunLoop::current()
+            .schedule_next(move || completer.complete(()))
+            .detach();
+        future.await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc, sync::Arc};
+
+    use irondash_message_channel::{MethodCallError, Value};
+
+    use crate::{
+        api_model::{DropOperation, Point, Size},
+        drag_trace::{DragTrace, DragTraceEvent},
+        drop_manager::{
+            BaseDropEvent, DropEvent, DropSessionId, ItemPreviewRequest, ItemPreviewResponse,
+            PlatformDropContextDelegate, PlatformDropContextId,
+        },
+        platform::{PlatformDataReader, PlatformDragContext},
+        reader_manager::RegisteredDataReader,
+        test_util::block_on,
+        value_promise::{Promise, PromiseResult},
+    };
+
+    use super::DragTraceReplay;
+
+    /// Accepts drops of plain text with copy operation.
+    #[derive(Default)]
+    struct Delegate {
+        readers: RefCell<Vec<Rc<PlatformDataReader>>>,
+        ended: RefCell<Vec<DropSessionId>>,
+    }
+
+    impl PlatformDropContextDelegate for Delegate {
+        fn get_platform_drag_contexts(&self) -> Vec<Rc<PlatformDragContext>> {
+            Vec::new()
+        }
+
+        fn send_drop_update(
+            &self,
+            _id: PlatformDropContextId,
+            event: DropEvent,
+            res: Box<dyn FnOnce(Result<DropOperation, MethodCallError>)>,
+        ) {
+            let text = event
+                .items
+                .iter()
+                .any(|i| i.formats.contains(&"text/plain".into()));
+            res(Ok(if text {
+                DropOperation::Copy
+            } else {
+                DropOperation::None
+            }));
+        }
+
+        fn send_perform_drop(
+            &self,
+            _id: PlatformDropContextId,
+            _event: DropEvent,
+            res: Box<dyn FnOnce(Result<(), MethodCallError>)>,
+        ) {
+            res(Ok(()));
+        }
+
+        fn send_drop_leave(&self, _id: PlatformDropContextId, _event: BaseDropEvent) {}
+
+        fn send_drop_ended(&self, _id: PlatformDropContextId, event: BaseDropEvent) {
+            self.ended.borrow_mut().push(event.session_id);
+        }
+
+        fn register_platform_reader(
+            &self,
+            _id: PlatformDropContextId,
+            platform_reader: Rc<PlatformDataReader>,
+        ) -> RegisteredDataReader {
+            self.readers.borrow_mut().push(platform_reader);
+            RegisteredDataReader::new_for_tests(0)
+        }
+
+        fn get_preview_for_item(
+            &self,
+            _id: PlatformDropContextId,
+            _request: ItemPreviewRequest,
+        ) -> Arc<Promise<PromiseResult<ItemPreviewResponse>>> {
+            let res = Arc::new(Promise::new());
+            res.set(PromiseResult::Cancelled);
+            res
+        }
+    }
+
+    fn update(time: f64, formats: &[&str], operation: DropOperation) -> DragTraceEvent {
+        DragTraceEvent::DropUpdate {
+            time,
+            context_id: 1,
+            session_id: 0i64.into(),
+            location_in_view: Point { x: time, y: time },
+            allowed_operations: vec![DropOperation::Copy, DropOperation::Move],
+            item_formats: vec![formats.iter().map(|f| f.to_string()).collect()],
+            operation,
+        }
+    }
+
+    #[test]
+    fn test_replay() {
+        let trace = DragTrace {
+            events: vec![
+                update(0.0, &["text/plain"], DropOperation::Copy),
+                update(0.1, &["text/plain"], DropOperation::Move),
+                DragTraceEvent::PerformDrop {
+                    time: 0.2,
+                    context_id: 1,
+                    session_id: 0i64.into(),
+                    location_in_view: Point { x: 1.0, y: 1.0 },
+                    allowed_operations: vec![DropOperation::Copy],
+                    item_formats: vec![vec!["text/plain".into()]],
+                    accepted_operation: Some(DropOperation::Copy),
+                    success: true,
+                },
+                DragTraceEvent::ItemPreview {
+                    time: 0.3,
+                    context_id: 1,
+                    session_id: 0i64.into(),
+                    item_id: 0.into(),
+                    size: Size::default(),
+                    fade_out_delay: 0.0,
+                    fade_out_duration: 0.0,
+                    has_preview: false,
+                },
+                DragTraceEvent::DropEnded {
+                    time: 0.4,
+                    context_id: 1,
+                    session_id: 0i64.into(),
+                },
+            ],
+        };
+        let delegate = Rc::new(Delegate::default());
+        let mut replay = DragTraceReplay::new(delegate.clone());
+        let mismatches = block_on(replay.replay(&trace));
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].index, 1);
+        assert_eq!(
+            mismatches[0].replayed,
+            update(0.1, &["text/plain"], DropOperation::Copy)
+        );
+
+        // One reader per session, with recorded formats and no data.
+        let readers = delegate.readers.borrow();
+        assert_eq!(readers.len(), 1);
+        assert_eq!(
+            block_on(readers[0].get_formats_for_item(0)).unwrap(),
+            vec!["text/plain".to_owned()]
+        );
+        assert_eq!(
+            block_on(readers[0].get_data_for_item(0, "text/plain".into(), None)).unwrap(),
+            Value::Null
+        );
+        assert_eq!(*delegate.ended.borrow(), vec![0i64.into()]);
+    }
+}
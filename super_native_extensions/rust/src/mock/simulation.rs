@@ -0,0 +1,291 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    api_model::{DragRequest, DropOperation, Point},
+    context::Context,
+    drag_manager::{DragSessionId, GetDragManager, PlatformDragContextId},
+    error::NativeExtensionsResult,
+};
+
+use super::{PlatformDragContext, PlatformDropContext};
+
+/// Drives a drag session the way user input would on a real platform.
+///
+/// Moving the drag notifies the drag context delegate and sends drop updates
+/// to the drop context under the pointer, dropping performs the drop and ends
+/// the drag session with resulting operation. Readers given to the drop side
+/// are backed by data providers of the dragged items.
+///
+/// This is a Rust-only testing API of the mock backend and is not exposed on
+/// any method channel.
+pub struct DragSimulation {
+    drag_context: Rc<PlatformDragContext>,
+    session_id: DragSessionId,
+    target: RefCell<Option<(Rc<PlatformDropContext>, Point)>>,
+}
+
+impl DragSimulation {
+    /// Starts drag session in given drag context through the drag manager,
+    /// same as `startDrag` call from Dart would.
+    pub async fn start(
+        drag_context_id: PlatformDragContextId,
+        request: DragRequest,
+    ) -> NativeExtensionsResult<Self> {
+        let drag_manager = Context::get().drag_manager();
+        let session_id = drag_manager.start_drag(drag_context_id, request).await?;
+        let drag_context = drag_manager.get_platform_drag_context(drag_context_id)?;
+        Ok(Self::new(drag_context, session_id))
+    }
+
+    /// Creates simulation for session already started in the drag context.
+    pub fn new(drag_context: Rc<PlatformDragContext>, session_id: DragSessionId) -> Self {
+        Self {
+            drag_context,
+            session_id,
+            target: RefCell::new(None),
+        }
+    }
+
+    pub fn session_id(&self) -> DragSessionId {
+        self.session_id
+    }
+
+    /// Moves the drag to given screen location. `target` is the drop context
+    /// under the pointer (if any) together with the location in its view.
+    pub fn move_to(
+        &self,
+        screen_location: Point,
+        target: Option<(Rc<PlatformDropContext>, Point)>,
+    ) -> NativeExtensionsResult<()> {
+        self.drag_context
+            .move_session(self.session_id, screen_location)?;
+        let previous = self.target.replace(target.clone());
+        if let Some((previous, _)) = previous {
+            let same_target = matches!(&target, Some((target, _)) if Rc::ptr_eq(target, &previous));
+            if !same_target {
+                previous.drag_leave()?;
+            }
+        }
+        if let Some((target, location_in_view)) = target {
+            target.drag_over(&self.drag_context, self.session_id, location_in_view)?;
+        }
+        Ok(())
+    }
+
+    /// Releases the drag at current location. Returns the operation the drag
+    /// session ended with.
+    pub async fn drop(self) -> NativeExtensionsResult<DropOperation> {
+        let operation = match self.target.take() {
+            Some((target, location_in_view)) => target.perform_drop(location_in_view).await?,
+            None => DropOperation::None,
+        };
+        self.drag_context.end_session(self.session_id, operation)?;
+        Ok(operation)
+    }
+
+    /// Cancels the drag, same as user pressing escape would.
+    pub fn cancel(self) -> NativeExtensionsResult<()> {
+        if let Some((target, _)) = self.target.take() {
+            target.drag_leave()?;
+        }
+        self.drag_context
+            .end_session(self.session_id, DropOperation::UserCancelled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::RefCell,
+        rc::{Rc, Weak},
+        sync::Arc,
+    };
+
+    use irondash_message_channel::{IsolateId, MethodCallError, Value};
+
+    use crate::{
+        api_model::{DropOperation, Point},
+        context::Context,
+        drag_manager::GetDragManager,
+        drop_manager::{
+            BaseDropEvent, DropEvent, ItemPreviewRequest, ItemPreviewResponse,
+            PlatformDropContextDelegate, PlatformDropContextId,
+        },
+        platform::{PlatformDataReader, PlatformDragContext, PlatformDropContext},
+        reader_manager::RegisteredDataReader,
+        test_util::{drag_request, register_text_provider, run_until},
+        value_promise::{Promise, PromiseResult},
+    };
+
+    use super::DragSimulation;
+
+    /// Stands in for Dart side of drop contexts. Accepts plain text with copy
+    /// operation in context 2 and rejects everything elsewhere.
+    #[derive(Default)]
+    struct Delegate {
+        events: RefCell<Vec<String>>,
+        readers: RefCell<Vec<Rc<PlatformDataReader>>>,
+    }
+
+    impl Delegate {
+        fn take_events(&self) -> Vec<String> {
+            self.events.take()
+        }
+    }
+
+    impl PlatformDropContextDelegate for Delegate {
+        fn get_platform_drag_contexts(&self) -> Vec<Rc<PlatformDragContext>> {
+            Context::get().drag_manager().get_platform_drag_contexts()
+        }
+
+        fn send_drop_update(
+            &self,
+            id: PlatformDropContextId,
+            event: DropEvent,
+            res: Box<dyn FnOnce(Result<DropOperation, MethodCallError>)>,
+        ) {
+            let text = event
+                .items
+                .iter()
+                .any(|i| i.formats.contains(&"text/plain".into()));
+            let operation = if id == IsolateId(2) && text {
+                DropOperation::Copy
+            } else {
+                DropOperation::None
+            };
+            self.events.borrow_mut().push(format!(
+                "update {} {} {:?}",
+                id.0, event.location_in_view.x, event.allowed_operations
+            ));
+            res(Ok(operation));
+        }
+
+        fn send_perform_drop(
+            &self,
+            id: PlatformDropContextId,
+            event: DropEvent,
+            res: Box<dyn FnOnce(Result<(), MethodCallError>)>,
+        ) {
+            self.events
+                .borrow_mut()
+                .push(format!("perform {} {:?}", id.0, event.accepted_operation));
+            res(Ok(()));
+        }
+
+        fn send_drop_leave(&self, id: PlatformDropContextId, _event: BaseDropEvent) {
+            self.events.borrow_mut().push(format!("leave {}", id.0));
+        }
+
+        fn send_drop_ended(&self, id: PlatformDropContextId, _event: BaseDropEvent) {
+            self.events.borrow_mut().push(format!("ended {}", id.0));
+        }
+
+        fn register_platform_reader(
+            &self,
+            _id: PlatformDropContextId,
+            platform_reader: Rc<PlatformDataReader>,
+        ) -> RegisteredDataReader {
+            self.readers.borrow_mut().push(platform_reader);
+            RegisteredDataReader::new_for_tests(0)
+        }
+
+        fn get_preview_for_item(
+            &self,
+            _id: PlatformDropContextId,
+            _request: ItemPreviewRequest,
+        ) -> Arc<Promise<PromiseResult<ItemPreviewResponse>>> {
+            let res = Arc::new(Promise::new());
+            res.set(PromiseResult::Cancelled);
+            res
+        }
+    }
+
+    fn point(x: f64, y: f64) -> Point {
+        Point { x, y }
+    }
+
+    fn drop_context(id: i64, delegate: &Rc<Delegate>) -> Rc<PlatformDropContext> {
+        let delegate: Weak<dyn PlatformDropContextDelegate> = Rc::downgrade(delegate) as _;
+        let context = Rc::new(PlatformDropContext::new(IsolateId(id), 0, delegate).unwrap());
+        context.assign_weak_self(Rc::downgrade(&context));
+        context
+    }
+
+    #[test]
+    fn test_simulate_drag() {
+        let context = Context::new();
+        let drag_manager = context.drag_manager();
+        drag_manager.new_context_for_tests(IsolateId(1));
+        let delegate = Rc::new(Delegate::default());
+        let drop_1 = drop_context(2, &delegate);
+        let drop_2 = drop_context(3, &delegate);
+
+        let provider = register_text_provider(IsolateId(1), "Hello");
+        let request = drag_request(&[provider], &[DropOperation::Copy, DropOperation::Move]);
+        let simulation = run_until(DragSimulation::start(IsolateId(1), request)).unwrap();
+        let drag_context = drag_manager
+            .get_platform_drag_context(IsolateId(1))
+            .unwrap();
+        assert_eq!(drag_context.session_ids(), vec![simulation.session_id()]);
+
+        simulation.move_to(point(5.0, 5.0), None).unwrap();
+        assert!(delegate.take_events().is_empty());
+        simulation
+            .move_to(point(10.0, 10.0), Some((drop_1.clone(), point(1.0, 1.0))))
+            .unwrap();
+        assert_eq!(drop_1.last_operation(), Some(DropOperation::Copy));
+        simulation
+            .move_to(point(20.0, 20.0), Some((drop_2.clone(), point(2.0, 2.0))))
+            .unwrap();
+        assert_eq!(drop_2.last_operation(), Some(DropOperation::None));
+        assert!(drop_1.last_operation().is_none());
+        simulation
+            .move_to(point(30.0, 30.0), Some((drop_1.clone(), point(3.0, 3.0))))
+            .unwrap();
+        assert_eq!(drop_1.last_operation(), Some(DropOperation::Copy));
+        assert!(drop_2.last_operation().is_none());
+
+        let operation = run_until(simulation.drop()).unwrap();
+        assert_eq!(operation, DropOperation::Copy);
+        assert_eq!(
+            delegate.take_events(),
+            [
+                "update 2 1 [Copy, Move]",
+                "leave 2",
+                "ended 2",
+                "update 3 2 [Copy, Move]",
+                "leave 3",
+                "ended 3",
+                "update 2 3 [Copy, Move]",
+                "perform 2 Some(Copy)",
+                "ended 2",
+            ]
+        );
+        assert!(drag_context.session_ids().is_empty());
+
+        // Reader of the accepted session serves data of the dragged item.
+        let reader = delegate.readers.borrow().last().unwrap().clone();
+        assert_eq!(reader.item_count(), 1);
+        let data = run_until(async move {
+            reader
+                .get_data_for_item(0, "text/plain".into(), None)
+                .await
+                .unwrap()
+        });
+        assert_eq!(data, Value::String("Hello".into()));
+
+        // Cancelled drag leaves the drop target.
+        let request = drag_request(&[provider], &[DropOperation::Copy]);
+        let simulation = run_until(DragSimulation::start(IsolateId(1), request)).unwrap();
+        simulation
+            .move_to(point(10.0, 10.0), Some((drop_1.clone(), point(1.0, 1.0))))
+            .unwrap();
+        simulation.cancel().unwrap();
+        assert_eq!(
+            delegate.take_events(),
+            ["update 2 1 [Copy]", "leave 2", "ended 2"]
+        );
+        assert!(drop_1.last_operation().is_none());
+        assert!(drag_context.session_ids().is_empty());
+    }
+}
@@ -2,7 +2,12 @@ use std::{
     cell::{Cell, RefCell},
     collections::HashMap,
     rc::{Rc, Weak},
-    sync::{self, Arc, Mutex},
+    sync::{
+        self,
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
 };
 
 use async_trait::async_trait;
@@ -12,14 +17,21 @@ use irondash_message_channel::{
     IsolateId, Late, MethodCall, PlatformError, PlatformResult, RegisteredAsyncMethodHandler,
     TryFromValue, Value,
 };
-use irondash_run_loop::{util::Capsule, RunLoop, RunLoopSender};
+use irondash_run_loop::{
+    util::{Capsule, FutureCompleter},
+    RunLoop, RunLoopSender,
+};
 
 use crate::{
     context::Context,
     error::{NativeExtensionsError, NativeExtensionsResult},
+    image_codec::{
+        convert_image, image_synthesis_source, is_image_format, synthesized_image_formats,
+    },
     log::OkLog,
     platform::PlatformDataReader,
     util::{DropNotifier, NextId},
+    value_coerce::{CoerceToData, StringFormat},
 };
 
 #[derive(Debug, TryFromValue, IntoValue, Clone, Copy, PartialEq, Hash, Eq)]
@@ -38,6 +50,7 @@ pub struct DataReaderManager {
     readers: RefCell<HashMap<DataReaderId, ReaderEntry>>,
     progresses: RefCell<HashMap<(IsolateId, i64), sync::Weak<ReadProgress>>>,
     virtual_file_readers: RefCell<HashMap<(IsolateId, i64), Rc<dyn VirtualFileReader>>>,
+    item_data_streams: RefCell<HashMap<(IsolateId, i64), Rc<ItemDataStreamEntry>>>,
 }
 
 struct ReaderEntry {
@@ -45,6 +58,13 @@ struct ReaderEntry {
     _finalizable_handle: Arc<FinalizableHandle>,
 }
 
+struct ItemDataStreamEntry {
+    stream: Rc<dyn ItemDataStream>,
+    progress: Arc<ReadProgress>,
+    cancelled: Arc<AtomicBool>,
+    num_read: Cell<i64>,
+}
+
 pub trait GetDataReaderManager {
     fn data_reader_manager(&self) -> Rc<DataReaderManager>;
 }
@@ -107,6 +127,29 @@ impl ReadProgress {
             });
         }
     }
+    /// Sets cancellation handler to be invoked after the currently set one.
+    pub fn chain_cancellation_handler(self: &Arc<Self>, handler: Box<dyn FnOnce() + Send>) {
+        if self.sender.is_same_thread() {
+            let previous = {
+                let mut inner = self.inner.lock().unwrap();
+                inner.get_mut().unwrap().cancellation_handler.take()
+            };
+            let handler: Box<dyn FnOnce() + Send> = match previous {
+                Some(previous) => Box::new(move || {
+                    previous();
+                    handler();
+                }),
+                None => handler,
+            };
+            self.set_cancellation_handler(Some(handler));
+        } else {
+            let self_clone = self.clone();
+            self.sender.send(move || {
+                self_clone.chain_cancellation_handler(handler);
+            });
+        }
+    }
+
     #[allow(dead_code)]
     pub fn report_progress(self: &Arc<Self>, fraction: Option<f64>) {
         if self.sender.is_same_thread() {
@@ -148,6 +191,7 @@ impl DataReaderManager {
             readers: RefCell::new(HashMap::new()),
             progresses: RefCell::new(HashMap::new()),
             virtual_file_readers: RefCell::new(HashMap::new()),
+            item_data_streams: RefCell::new(HashMap::new()),
         }
         .register("DataReaderManager")
     }
@@ -257,13 +301,36 @@ impl DataReaderManager {
         self.get_reader(reader)?.get_items().await
     }
 
+    /// Returns formats of the item that can be used as source for image
+    /// synthesis. Virtual files and formats already synthesized by platform
+    /// reader are skipped.
+    async fn get_image_sources(
+        reader: &PlatformDataReader,
+        item: i64,
+        formats: &[String],
+    ) -> NativeExtensionsResult<Vec<String>> {
+        let mut res = Vec::new();
+        for format in formats {
+            if is_image_format(format)
+                && !reader.item_format_is_synthesized(item, format)?
+                && !reader.can_read_virtual_file_for_item(item, format).await?
+                && !reader.can_copy_virtual_file_for_item(item, format).await?
+            {
+                res.push(format.clone());
+            }
+        }
+        Ok(res)
+    }
+
     async fn get_item_formats(
         &self,
         request: ItemFormatsRequest,
     ) -> NativeExtensionsResult<Vec<String>> {
-        self.get_reader(request.reader_handle)?
-            .get_formats_for_item(request.item_handle)
-            .await
+        let reader = self.get_reader(request.reader_handle)?;
+        let mut formats = reader.get_formats_for_item(request.item_handle).await?;
+        let sources = Self::get_image_sources(&reader, request.item_handle, &formats).await?;
+        formats.extend(synthesized_image_formats(&formats, &sources));
+        Ok(formats)
     }
 
     async fn get_item_info(
@@ -274,7 +341,9 @@ impl DataReaderManager {
         let reader = self.get_reader(request.reader_handle)?;
         let start = std::time::Instant::now();
         for item_handle in request.item_handles {
-            let formats = reader.get_formats_for_item(item_handle).await?;
+            let mut formats = reader.get_formats_for_item(item_handle).await?;
+            let sources = Self::get_image_sources(&reader, item_handle, &formats).await?;
+            let image_formats = synthesized_image_formats(&formats, &sources);
             let mut synthesized_formats = Vec::new();
             let mut read_virtual_file_formats = Vec::new();
             let mut copy_virtual_file_formats = Vec::new();
@@ -295,6 +364,8 @@ impl DataReaderManager {
                     copy_virtual_file_formats.push(format.clone());
                 }
             }
+            synthesized_formats.extend(image_formats.iter().cloned());
+            formats.extend(image_formats);
             let suggested_name = reader.get_suggested_name_for_item(item_handle).await?;
             let file_uri_format =
                 if copy_virtual_file_formats.is_empty() && read_virtual_file_formats.is_empty() {
@@ -320,6 +391,30 @@ impl DataReaderManager {
         Ok(ItemInfoResponse { items: res })
     }
 
+    /// Returns item data for given format, synthesizing image formats that
+    /// the item does not provide natively.
+    async fn read_item_value(
+        reader: &PlatformDataReader,
+        item: i64,
+        format: String,
+        progress: Arc<ReadProgress>,
+    ) -> NativeExtensionsResult<Value> {
+        let formats = reader.get_formats_for_item(item).await?;
+        if !formats.contains(&format) {
+            let sources = Self::get_image_sources(reader, item, &formats).await?;
+            if let Some(source) = image_synthesis_source(&format, &sources) {
+                let data = reader
+                    .get_data_for_item(item, source.to_owned(), Some(progress))
+                    .await?;
+                return match data.coerce_to_data(StringFormat::Utf8) {
+                    Some(data) => Self::convert_image_data(data, source.to_owned(), format).await,
+                    None => Ok(Value::Null),
+                };
+            }
+        }
+        reader.get_data_for_item(item, format, Some(progress)).await
+    }
+
     async fn get_item_data(
         &self,
         isolate_id: IsolateId,
@@ -327,9 +422,120 @@ impl DataReaderManager {
     ) -> NativeExtensionsResult<Value> {
         let reader = self.get_reader(request.reader_handle)?;
         let progress = self.new_read_progress(isolate_id, request.progress_id);
-        reader
-            .get_data_for_item(request.item_handle, request.format, Some(progress))
+        Self::read_item_value(
+            &reader,
+            request.item_handle,
+            request.format.clone(),
+            progress,
+        )
+        .await
+        .map_err(|e| e.for_item_format(request.item_handle, &request.format))
+    }
+
+    /// Opens a chunked stream for item data. Returns `None` if item doesn't
+    /// have the format and [NativeExtensionsError::UnsupportedOperation] if
+    /// platform reader can not provide the data incrementally.
+    async fn get_item_data_stream(
+        &self,
+        isolate_id: IsolateId,
+        request: ItemDataRequest,
+    ) -> NativeExtensionsResult<Option<ItemDataStreamResponse>> {
+        let reader = self.get_reader(request.reader_handle)?;
+        let formats = reader.get_formats_for_item(request.item_handle).await?;
+        if !formats.contains(&request.format) {
+            return Ok(None);
+        }
+        let progress = self.new_read_progress(isolate_id, request.progress_id);
+        let stream = reader
+            .create_data_stream_for_item(request.item_handle, &request.format, progress.clone())
             .await
+            .map_err(|e| e.for_item_format(request.item_handle, &request.format))?
+            .ok_or(NativeExtensionsError::UnsupportedOperation)?;
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let cancelled_clone = cancelled.clone();
+        progress.chain_cancellation_handler(Box::new(move || {
+            cancelled_clone.store(true, Ordering::Relaxed);
+        }));
+        let stream_handle = self.next_id.next_id();
+        let length = stream.length();
+        self.item_data_streams.borrow_mut().insert(
+            (isolate_id, stream_handle),
+            Rc::new(ItemDataStreamEntry {
+                stream,
+                progress,
+                cancelled,
+                num_read: Cell::new(0),
+            }),
+        );
+        Ok(Some(ItemDataStreamResponse {
+            stream_handle,
+            length,
+        }))
+    }
+
+    /// Returns next chunk of the stream. Empty chunk means end of data, `None`
+    /// means that the stream has already been closed.
+    async fn item_data_stream_read(
+        &self,
+        isolate_id: IsolateId,
+        stream_handle: i64,
+    ) -> NativeExtensionsResult<Option<Vec<u8>>> {
+        let entry = self
+            .item_data_streams
+            .borrow()
+            .get(&(isolate_id, stream_handle))
+            .cloned();
+        let Some(entry) = entry else {
+            return Ok(None);
+        };
+        if entry.cancelled.load(Ordering::Relaxed) {
+            self.item_data_stream_close(isolate_id, stream_handle)?;
+            return Err(NativeExtensionsError::OtherError("Read cancelled".into()));
+        }
+        let data = entry.stream.read_next().await?;
+        let num_read = entry.num_read.get() + data.len() as i64;
+        entry.num_read.set(num_read);
+        if let Some(length) = entry.stream.length().filter(|l| *l > 0) {
+            entry
+                .progress
+                .report_progress(Some((num_read as f64 / length as f64).min(1.0)));
+        }
+        Ok(Some(data))
+    }
+
+    fn item_data_stream_close(
+        &self,
+        isolate_id: IsolateId,
+        stream_handle: i64,
+    ) -> NativeExtensionsResult<()> {
+        let entry = self
+            .item_data_streams
+            .borrow_mut()
+            .remove(&(isolate_id, stream_handle));
+        if let Some(entry) = entry {
+            entry.progress.set_cancellation_handler(None);
+            entry.stream.close()?;
+        }
+        Ok(())
+    }
+
+    /// Converts image data on background thread.
+    async fn convert_image_data(
+        data: Vec<u8>,
+        source: String,
+        target: String,
+    ) -> NativeExtensionsResult<Value> {
+        let (future, completer) = FutureCompleter::new();
+        let mut completer = Capsule::new(completer);
+        let sender = RunLoop::current().new_sender();
+        thread::spawn(move || {
+            let res = convert_image(&data, &source, &target).map(Value::U8List);
+            sender.send(move || {
+                let completer = completer.take().unwrap();
+                completer.complete(res);
+            });
+        });
+        future.await
     }
 
     fn cancel_progress(
@@ -434,6 +640,16 @@ pub struct RegisteredDataReader {
     finalizable_handle: Value,
 }
 
+#[cfg(test)]
+impl RegisteredDataReader {
+    pub fn new_for_tests(handle: i64) -> Self {
+        Self {
+            handle: handle.into(),
+            finalizable_handle: Value::Null,
+        }
+    }
+}
+
 #[derive(TryFromValue)]
 #[irondash(rename_all = "camelCase")]
 struct ItemFormatsRequest {
@@ -459,6 +675,14 @@ struct VirtualFileReaderRequest {
     progress_id: i64,
 }
 
+#[derive(IntoValue)]
+#[irondash(rename_all = "camelCase")]
+struct ItemDataStreamResponse {
+    stream_handle: i64,
+    /// Total length of the data, if known upfront.
+    length: Option<i64>,
+}
+
 #[derive(IntoValue)]
 #[irondash(rename_all = "camelCase")]
 struct VirtualFileReaderResponse {
@@ -518,6 +742,15 @@ pub trait VirtualFileReader {
     fn close(&self) -> NativeExtensionsResult<()>;
 }
 
+/// Incremental access to item data that may be too large to be read at once.
+#[async_trait(?Send)]
+pub trait ItemDataStream {
+    /// Returns next chunk of data. Empty chunk signals end of the stream.
+    async fn read_next(&self) -> NativeExtensionsResult<Vec<u8>>;
+    fn length(&self) -> Option<i64>;
+    fn close(&self) -> NativeExtensionsResult<()>;
+}
+
 #[async_trait(?Send)]
 impl AsyncMethodHandler for DataReaderManager {
     fn assign_weak_self(&self, weak_self: Weak<Self>) {
@@ -549,6 +782,16 @@ impl AsyncMethodHandler for DataReaderManager {
             } else {
                 true
             }
+        });
+
+        let mut streams = self.item_data_streams.borrow_mut();
+        streams.retain(|(isolate_id, _), entry| {
+            if *isolate_id == destroyed_isolate_id {
+                entry.stream.close().ok_log();
+                false
+            } else {
+                true
+            }
         })
     }
 
@@ -569,6 +812,17 @@ impl AsyncMethodHandler for DataReaderManager {
                 .get_item_data(call.isolate, call.args.try_into()?)
                 .await
                 .into_platform_result(),
+            "getItemDataStream" => self
+                .get_item_data_stream(call.isolate, call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "itemDataStreamRead" => self
+                .item_data_stream_read(call.isolate, call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "itemDataStreamClose" => self
+                .item_data_stream_close(call.isolate, call.args.try_into()?)
+                .into_platform_result(),
             "cancelProgress" => self
                 .cancel_progress(call.isolate, call.args.try_into()?)
                 .into_platform_result(),
@@ -600,85 +854,101 @@ impl AsyncMethodHandler for DataReaderManager {
     }
 }
 
-// #[cfg(test)]
-// mod tests {
-//     use super::ClipboardReaderManager;
-//     use crate::{platform::READERS, reader_manager::NewClipboardReaderResult};
-//     use nativeshell_core::{Context, FinalizableHandle, GetMessageChannel, MockIsolate, Value};
-//     use std::{sync::Arc, time::Duration};
-
-//     async fn test_dispose_main() {
-//         let _reader_manager = ClipboardReaderManager::new();
-//         let context = Context::get();
-//         let channel = "ClipboardReaderManager";
-
-//         let isolate_1 = MockIsolate::new();
-//         let isolate_1 = isolate_1.attach(&context.message_channel());
-
-//         assert_eq!(READERS.with(|c| c.borrow().len()), 0);
-
-//         //
-//         // Finalizable handle
-//         //
-
-//         let reader_id: NewClipboardReaderResult = isolate_1
-//             .call_method_async(channel, "newDefaultReader", Value::Null)
-//             .await
-//             .unwrap()
-//             .try_into()
-//             .unwrap();
-
-//         assert_eq!(READERS.with(|c| c.borrow().len()), 1);
-
-//         let handle: Arc<FinalizableHandle> = reader_id.finalizable_handle.try_into().unwrap();
-//         // Simulate finalizing handle
-//         handle.finalize();
-
-//         // wait one run loop turn
-//         context.run_loop().wait(Duration::from_secs(0)).await;
-
-//         assert_eq!(READERS.with(|c| c.borrow().len()), 0);
-
-//         //
-//         // disposeReader call
-//         //
-
-//         let reader_id: NewClipboardReaderResult = isolate_1
-//             .call_method_async(channel, "newDefaultReader", Value::Null)
-//             .await
-//             .unwrap()
-//             .try_into()
-//             .unwrap();
-
-//         assert_eq!(READERS.with(|c| c.borrow().len()), 1);
-
-//         isolate_1
-//             .call_method_async(channel, "disposeReader", reader_id.handle.into())
-//             .await
-//             .unwrap();
-
-//         assert_eq!(READERS.with(|c| c.borrow().len()), 0);
-
-//         //
-//         // Removing isolate
-//         //
-
-//         isolate_1
-//             .call_method_async(channel, "newDefaultReader", Value::Null)
-//             .await
-//             .unwrap();
-
-//         assert_eq!(READERS.with(|c| c.borrow().len()), 1);
-
-//         drop(isolate_1);
-
-//         context.run_loop().wait(Duration::from_secs(0)).await;
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use irondash_message_channel::{IsolateId, Value};
+
+    use crate::{
+        api_model::ClipboardSelection,
+        context::Context,
+        data_provider_manager::{DataProviderHandle, GetDataProviderManager},
+        error::NativeExtensionsError,
+        platform::{PlatformDataProvider, PlatformDataReader},
+        test_util::{block_on, register_text_provider},
+        util::DropNotifier,
+    };
+
+    use super::{GetDataReaderManager, ItemDataRequest, ItemFormatsRequest, ReadProgress};
+
+    #[test]
+    fn test_read_clipboard() {
+        let context = Context::new();
+        let provider_id = register_text_provider(IsolateId(1), "Hello");
+        let provider = context
+            .data_provider_manager()
+            .get_platform_data_provider(provider_id)
+            .unwrap();
+        let handle = Arc::new(DataProviderHandle::from(DropNotifier::new(|| {})));
+        block_on(PlatformDataProvider::write_to_clipboard(
+            vec![(provider, handle)],
+            ClipboardSelection::Clipboard,
+        ))
+        .unwrap();
+
+        let manager = context.data_reader_manager();
+        let reader = manager.register_platform_reader(
+            PlatformDataReader::new_clipboard_reader(ClipboardSelection::Clipboard).unwrap(),
+            IsolateId(1),
+        );
+        let reader_handle = reader.handle;
+        assert_eq!(block_on(manager.get_items(reader_handle)).unwrap(), vec![0]);
+        assert_eq!(
+            block_on(manager.get_item_formats(ItemFormatsRequest {
+                item_handle: 0,
+                reader_handle,
+            }))
+            .unwrap(),
+            vec!["text/plain".to_owned()]
+        );
+        let request = |item_handle, format: &str| ItemDataRequest {
+            item_handle,
+            reader_handle,
+            format: format.into(),
+            progress_id: 1,
+        };
+        assert_eq!(
+            block_on(manager.get_item_data(IsolateId(1), request(0, "text/plain"))).unwrap(),
+            Value::String("Hello".into())
+        );
+        assert!(matches!(
+            block_on(manager.get_item_data(IsolateId(1), request(1, "text/plain"))),
+            Err(NativeExtensionsError::ItemFormatError { item: 1, .. })
+        ));
+        // Mock reader can not stream.
+        assert!(matches!(
+            block_on(manager.get_item_data_stream(IsolateId(1), request(0, "text/plain"))),
+            Err(NativeExtensionsError::UnsupportedOperation)
+        ));
+        assert!(matches!(
+            block_on(manager.get_item_data_stream(IsolateId(1), request(0, "image/png"))),
+            Ok(None)
+        ));
 
-//         assert_eq!(READERS.with(|c| c.borrow().len()), 0);
-//     }
+        manager.dispose_reader(reader_handle).unwrap();
+        assert!(matches!(
+            block_on(manager.get_items(reader_handle)),
+            Err(NativeExtensionsError::ReaderNotFound)
+        ));
+        PlatformDataProvider::clear_clipboard(ClipboardSelection::Clipboard);
+    }
 
-//     #[test]
-//     fn test_dispose() {
-//         Context::run_test(test_dispose_main());
-//     }
-// }
+    #[test]
+    fn test_chain_cancellation_handler() {
+        let progress = Arc::new(ReadProgress::new(
+            Arc::new(DropNotifier::new(|| {})),
+            |_| {},
+            |_| {},
+        ));
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        for i in 0..2 {
+            let calls = calls.clone();
+            progress.chain_cancellation_handler(Box::new(move || {
+                calls.lock().unwrap().push(i);
+            }));
+        }
+        progress.cancel();
+        assert_eq!(*calls.lock().unwrap(), vec![0, 1]);
+    }
+}
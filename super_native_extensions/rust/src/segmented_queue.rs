@@ -2,10 +2,12 @@ use std::{
     cell::{Cell, RefCell},
     env,
     fs::{File, OpenOptions},
+    future::poll_fn,
     io,
     ops::Deref,
     path::PathBuf,
     sync::{Arc, Condvar, Mutex},
+    task::{Context, Poll, Waker},
 };
 
 use rand::{distributions::Alphanumeric, Rng};
@@ -24,16 +26,45 @@ trait Segment {
     /// or segment is completed, in which case returns empty data.
     fn read(&self, max_len: usize) -> Vec<u8>;
 
+    /// Non-blocking variant of [Segment::read]. Returns `None` if no data is
+    /// available and segment is not completed yet.
+    fn try_read(&self, max_len: usize) -> Option<Vec<u8>>;
+
     /// Returns the amount of memory used.
     fn memory_used(&self) -> usize;
 }
 
 struct MemorySegmentInner {
+    /// Data not read yet (starting at read_position). Consumed data is
+    /// discarded once reader catches up with writer.
     data: Vec<u8>,
     read_position: usize,
+    /// Total amount of data written to segment.
+    written: usize,
     completed: bool,
 }
 
+impl MemorySegmentInner {
+    fn read(&mut self, max_len: usize) -> Option<Vec<u8>> {
+        if self.read_position < self.data.len() {
+            let to_read = (self.data.len() - self.read_position).min(max_len);
+            let res = &self.data[self.read_position..self.read_position + to_read];
+            let res = res.to_owned();
+            self.read_position += to_read;
+            if self.read_position == self.data.len() {
+                self.data.clear();
+                self.read_position = 0;
+            }
+            Some(res)
+        } else if self.completed {
+            self.data.clear();
+            Some(Vec::new())
+        } else {
+            None
+        }
+    }
+}
+
 /// In memory implementation of [Segment].
 struct MemorySegment {
     inner: Mutex<MemorySegmentInner>,
@@ -47,6 +78,7 @@ impl MemorySegment {
             inner: Mutex::new(MemorySegmentInner {
                 data: Vec::new(),
                 read_position: 0,
+                written: 0,
                 completed: false,
             }),
             condition: Condvar::new(),
@@ -58,11 +90,12 @@ impl MemorySegment {
 impl Segment for MemorySegment {
     fn write(&self, data: &[u8]) -> Result<(), ()> {
         let mut inner = self.inner.lock().unwrap();
-        if inner.completed || inner.data.len() >= self.max_size {
+        if inner.completed || inner.written >= self.max_size {
             return Err(());
         }
         inner.data.extend_from_slice(data);
-        inner.completed |= inner.data.len() >= self.max_size;
+        inner.written += data.len();
+        inner.completed |= inner.written >= self.max_size;
         self.condition.notify_all();
         Ok(())
     }
@@ -76,21 +109,17 @@ impl Segment for MemorySegment {
     fn read(&self, max_len: usize) -> Vec<u8> {
         let mut inner = self.inner.lock().unwrap();
         loop {
-            if inner.read_position < inner.data.len() {
-                let to_read = (inner.data.len() - inner.read_position).min(max_len);
-                let res = &inner.data[inner.read_position..inner.read_position + to_read];
-                let res = res.to_owned();
-                inner.read_position += to_read;
-                return res;
-            } else if inner.completed {
-                inner.data.clear();
-                return Vec::new();
-            } else {
-                inner = self.condition.wait(inner).unwrap();
+            match inner.read(max_len) {
+                Some(res) => return res,
+                None => inner = self.condition.wait(inner).unwrap(),
             }
         }
     }
 
+    fn try_read(&self, max_len: usize) -> Option<Vec<u8>> {
+        self.inner.lock().unwrap().read(max_len)
+    }
+
     fn memory_used(&self) -> usize {
         self.inner.lock().unwrap().data.len()
     }
@@ -149,6 +178,30 @@ struct FileSegmentInner {
     completed: bool,
 }
 
+impl FileSegmentInner {
+    fn read(&mut self, max_len: usize) -> Option<Vec<u8>> {
+        if self.read_position < self.write_position {
+            match &self.file {
+                Some(file) => {
+                    let mut buf = vec![0u8; max_len];
+                    let res = FileSegment::read_at(file, &mut buf, self.read_position)
+                        .ok_log()
+                        .unwrap_or(0);
+                    self.read_position += res as u64;
+                    buf.resize(res, 0);
+                    Some(buf)
+                }
+                None => Some(Vec::new()),
+            }
+        } else if self.completed {
+            self.file.take();
+            Some(Vec::new())
+        } else {
+            None
+        }
+    }
+}
+
 struct FileSegment {
     max_file_length: u64,
     inner: Mutex<FileSegmentInner>,
@@ -222,28 +275,17 @@ impl Segment for FileSegment {
     fn read(&self, max_len: usize) -> Vec<u8> {
         let mut inner = self.inner.lock().unwrap();
         loop {
-            if inner.read_position < inner.write_position {
-                match &inner.file {
-                    Some(file) => {
-                        let mut buf = vec![0u8; max_len];
-                        let res = FileSegment::read_at(file, &mut buf, inner.read_position)
-                            .ok_log()
-                            .unwrap_or(0);
-                        inner.read_position += res as u64;
-                        buf.resize(res, 0);
-                        return buf;
-                    }
-                    None => return Vec::new(),
-                }
-            } else if inner.completed {
-                inner.file.take();
-                return Vec::new();
-            } else {
-                inner = self.condition.wait(inner).unwrap();
+            match inner.read(max_len) {
+                Some(res) => return res,
+                None => inner = self.condition.wait(inner).unwrap(),
             }
         }
     }
 
+    fn try_read(&self, max_len: usize) -> Option<Vec<u8>> {
+        self.inner.lock().unwrap().read(max_len)
+    }
+
     fn memory_used(&self) -> usize {
         0
     }
@@ -254,11 +296,14 @@ type BoxedSegment = Box<dyn Segment + Send + Sync>;
 struct QueueStateInner {
     segments: Vec<Arc<BoxedSegment>>,
     completed: bool,
+    reader_closed: bool,
 }
 
 struct QueueState {
     inner: Mutex<QueueStateInner>,
     condition: Condvar,
+    /// Wakers of async readers and writers waiting for the queue to change.
+    wakers: Mutex<Vec<Waker>>,
 }
 
 impl QueueState {
@@ -267,8 +312,45 @@ impl QueueState {
             inner: Mutex::new(QueueStateInner {
                 segments: Vec::new(),
                 completed: false,
+                reader_closed: false,
             }),
             condition: Condvar::new(),
+            wakers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns Ready if `ready` produces a value, otherwise registers the
+    /// waker to be woken on next change of the queue. The check is done
+    /// while holding wakers lock so that no wake-up can be missed; `ready`
+    /// must not call [QueueState::wake_all].
+    fn poll_with<T, F: FnMut() -> Option<T>>(&self, cx: &mut Context, mut ready: F) -> Poll<T> {
+        let mut wakers = self.wakers.lock().unwrap();
+        match ready() {
+            Some(value) => Poll::Ready(value),
+            None => {
+                wakers.push(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+
+    fn wake_all(&self) {
+        let wakers = std::mem::take(&mut *self.wakers.lock().unwrap());
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    /// Returns segment at given index. `None` means that the segment is not
+    /// available yet, `Some(None)` that the queue is completed.
+    fn try_get_segment_at_index(&self, index: usize) -> Option<Option<Arc<BoxedSegment>>> {
+        let inner = self.inner.lock().unwrap();
+        if index < inner.segments.len() {
+            Some(Some(inner.segments[index].clone()))
+        } else if inner.completed {
+            Some(None)
+        } else {
+            None
         }
     }
 
@@ -305,6 +387,15 @@ impl QueueState {
         self.condition.notify_all();
     }
 
+    fn close_reader(&self) {
+        self.inner.lock().unwrap().reader_closed = true;
+        self.wake_all();
+    }
+
+    fn reader_closed(&self) -> bool {
+        self.inner.lock().unwrap().reader_closed
+    }
+
     fn total_memory_usage(&self) -> usize {
         let inner = self.inner.lock().unwrap();
         let mut size = 0;
@@ -339,6 +430,8 @@ impl SegmentedQueueReader {
                     if data.is_empty() {
                         self.current_segment.replace(self.current_segment.get() + 1);
                     } else {
+                        // Reading releases memory, which may unblock async writer.
+                        self.state.wake_all();
                         return data;
                     }
                 }
@@ -347,6 +440,27 @@ impl SegmentedQueueReader {
         }
     }
 
+    /// Non-blocking variant of [SegmentedQueueReader::read_some]. Returns
+    /// `None` if no data is available yet.
+    fn try_read_some(&self, max_len: usize) -> Option<Vec<u8>> {
+        loop {
+            match self
+                .state
+                .try_get_segment_at_index(self.current_segment.get())?
+            {
+                Some(segment) => {
+                    let data = segment.try_read(max_len)?;
+                    if data.is_empty() {
+                        self.current_segment.replace(self.current_segment.get() + 1);
+                    } else {
+                        return Some(data);
+                    }
+                }
+                None => return Some(Vec::new()),
+            }
+        }
+    }
+
     /// Reads len amount of bytes from queue. Blocks until data is available.
     /// If returned amount of bytes is less than len, the queue is completed.
     pub fn read(&self, len: usize) -> Vec<u8> {
@@ -363,6 +477,47 @@ impl SegmentedQueueReader {
     }
 }
 
+impl Drop for SegmentedQueueReader {
+    fn drop(&mut self) {
+        // Unblock async writer waiting for memory to be released.
+        self.state.close_reader();
+    }
+}
+
+/// Reader that never blocks the calling thread. Futures returned from
+/// read methods are woken by the writer (on any thread) when data arrives,
+/// so the reader can be used on run loop thread, i.e. inside task spawned
+/// with [irondash_run_loop::spawn].
+pub struct AsyncSegmentedQueueReader {
+    reader: SegmentedQueueReader,
+}
+
+impl AsyncSegmentedQueueReader {
+    /// Reads up to `max_len` bytes from queue. Resolves once data is available.
+    /// Returns empty vector if queue is completed and no more data is available.
+    pub async fn read_some(&self, max_len: usize) -> Vec<u8> {
+        let state = &self.reader.state;
+        let res = poll_fn(|cx| state.poll_with(cx, || self.reader.try_read_some(max_len))).await;
+        // Reading releases memory, which may unblock async writer.
+        state.wake_all();
+        res
+    }
+
+    /// Reads len amount of bytes from queue. If returned amount of bytes is
+    /// less than len, the queue is completed.
+    pub async fn read(&self, len: usize) -> Vec<u8> {
+        let mut res = Vec::new();
+        while res.len() < len {
+            let data = self.read_some(len - res.len()).await;
+            res.extend_from_slice(&data);
+            if data.is_empty() {
+                break;
+            }
+        }
+        res
+    }
+}
+
 pub struct QueueConfiguration {
     /// Maximum size for single memory segment
     pub memory_segment_max_size: usize,
@@ -374,6 +529,9 @@ pub struct QueueConfiguration {
     /// threshold next created segment will be file segment. Otherwise (or when
     /// not set) the next created segment will be memory segment.
     /// If None all segments in queue will be memory segments.
+    ///
+    /// [AsyncSegmentedQueueWriter] never creates file segments; instead
+    /// writing waits until reader brings memory usage below this threshold.
     pub max_memory_usage: Option<usize>,
 }
 
@@ -381,10 +539,11 @@ pub struct SegmentedQueueWriter {
     configuration: QueueConfiguration,
     state: Arc<QueueState>,
     current_segment: RefCell<Arc<BoxedSegment>>,
+    spill_to_file: bool,
 }
 
 impl SegmentedQueueWriter {
-    fn new(state: Arc<QueueState>, configuration: QueueConfiguration) -> Self {
+    fn new(state: Arc<QueueState>, configuration: QueueConfiguration, spill_to_file: bool) -> Self {
         // Always start with a memory segment
         let segment: BoxedSegment =
             Box::new(MemorySegment::new(configuration.memory_segment_max_size));
@@ -394,12 +553,13 @@ impl SegmentedQueueWriter {
             configuration,
             state,
             current_segment: RefCell::new(segment),
+            spill_to_file,
         }
     }
 
     fn next_segment(&self) {
         let segment: BoxedSegment = match self.configuration.max_memory_usage {
-            Some(max_memory_usage) => {
+            Some(max_memory_usage) if self.spill_to_file => {
                 if self.state.total_memory_usage() >= max_memory_usage {
                     Box::new(FileSegment::new(self.configuration.file_segment_max_length))
                 } else {
@@ -408,7 +568,7 @@ impl SegmentedQueueWriter {
                     ))
                 }
             }
-            None => Box::new(MemorySegment::new(
+            _ => Box::new(MemorySegment::new(
                 self.configuration.memory_segment_max_size,
             )),
         };
@@ -424,11 +584,40 @@ impl SegmentedQueueWriter {
             let segment = self.current_segment.borrow().clone();
             segment.write(data).expect("Fresh segment refused data");
         }
+        self.state.wake_all();
     }
 
     pub fn close(&self) {
         self.state.complete();
         self.current_segment.borrow().complete();
+        self.state.wake_all();
+    }
+}
+
+/// Writer that applies backpressure: when memory used by the queue reaches
+/// [QueueConfiguration::max_memory_usage], writing waits until reader
+/// consumes enough data rather than spilling to file segments.
+pub struct AsyncSegmentedQueueWriter {
+    writer: SegmentedQueueWriter,
+}
+
+impl AsyncSegmentedQueueWriter {
+    pub async fn write(&self, data: &[u8]) {
+        if let Some(max_memory_usage) = self.writer.configuration.max_memory_usage {
+            let state = &self.writer.state;
+            poll_fn(|cx| {
+                state.poll_with(cx, || {
+                    (state.reader_closed() || state.total_memory_usage() < max_memory_usage)
+                        .then_some(())
+                })
+            })
+            .await;
+        }
+        self.writer.write(data);
+    }
+
+    pub fn close(&self) {
+        self.writer.close();
     }
 }
 
@@ -436,21 +625,34 @@ pub fn new_segmented_queue(
     configuration: QueueConfiguration,
 ) -> (SegmentedQueueWriter, SegmentedQueueReader) {
     let state = Arc::new(QueueState::new());
-    let writer = SegmentedQueueWriter::new(state.clone(), configuration);
+    let writer = SegmentedQueueWriter::new(state.clone(), configuration, true);
     let reader = SegmentedQueueReader::new(state);
     (writer, reader)
 }
 
+pub fn new_async_segmented_queue(
+    configuration: QueueConfiguration,
+) -> (AsyncSegmentedQueueWriter, AsyncSegmentedQueueReader) {
+    let state = Arc::new(QueueState::new());
+    let writer = SegmentedQueueWriter::new(state.clone(), configuration, false);
+    let reader = SegmentedQueueReader::new(state);
+    (
+        AsyncSegmentedQueueWriter { writer },
+        AsyncSegmentedQueueReader { reader },
+    )
+}
+
 #[cfg(test)]
 mod test {
     use std::{sync::Arc, thread, time::Duration};
 
     use crate::{
         segmented_queue::{FileSegment, MemorySegment},
+        test_util::block_on_thread,
         value_promise::Promise,
     };
 
-    use super::BoxedSegment;
+    use super::{new_async_segmented_queue, BoxedSegment, QueueConfiguration};
 
     fn read_from_segment(size: usize, segment: &Arc<BoxedSegment>) -> Arc<Promise<Vec<u8>>> {
         let promise = Arc::new(Promise::<Vec<u8>>::new());
@@ -512,4 +714,38 @@ mod test {
         test_segment(Arc::new(Box::new(FileSegment::new(8))));
         test_segment_complete(Arc::new(Box::new(FileSegment::new(8))));
     }
+
+    #[test]
+    fn test_async() {
+        let (writer, reader) = new_async_segmented_queue(QueueConfiguration {
+            memory_segment_max_size: 4,
+            file_segment_max_length: 0,
+            max_memory_usage: Some(6),
+        });
+        let writer = thread::spawn(move || {
+            block_on_thread(async {
+                for i in 0..10u8 {
+                    writer.write(&[i, i]).await;
+                    // Reader is slower than writer, so writer must wait for
+                    // memory to be released.
+                    assert!(writer.writer.state.total_memory_usage() <= 8);
+                }
+                writer.close();
+            })
+        });
+        let mut data = Vec::new();
+        block_on_thread(async {
+            loop {
+                thread::sleep(Duration::from_millis(5));
+                let chunk = reader.read_some(3).await;
+                if chunk.is_empty() {
+                    break;
+                }
+                data.extend_from_slice(&chunk);
+            }
+        });
+        writer.join().unwrap();
+        let expected: Vec<u8> = (0..10u8).flat_map(|i| [i, i]).collect();
+        assert_eq!(data, expected);
+    }
 }
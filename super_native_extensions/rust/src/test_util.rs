@@ -0,0 +1,139 @@
+use std::{
+    cell::RefCell,
+    future::Future,
+    pin::pin,
+    rc::Rc,
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+    time::Duration,
+};
+
+use irondash_run_loop::{spawn, util::FutureCompleter, RunLoop};
+
+#[cfg(feature = "mock")]
+use irondash_message_channel::{IsolateId, Value};
+
+#[cfg(feature = "mock")]
+use crate::{
+    api_model::{
+        DataProvider, DataProviderId, DataRepresentation, DragConfiguration, DragItem, DragRequest,
+        DropOperation, ImageData, Point, Rect, TargettedImage,
+    },
+    data_provider_manager::GetDataProviderManager,
+};
+
+/// Polls the future once and returns the result. Mock backend resolves
+/// everything synchronously, so the future is expected to be ready.
+#[cfg(feature = "mock")]
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    match future
+        .as_mut()
+        .poll(&mut Context::from_waker(Waker::noop()))
+    {
+        Poll::Ready(value) => value,
+        Poll::Pending => panic!("Future did not complete"),
+    }
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Parks current thread until the future completes. For futures that are
+/// woken from other threads.
+pub fn block_on_thread<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    loop {
+        match future.as_mut().poll(&mut Context::from_waker(&waker)) {
+            Poll::Ready(value) => return value,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+/// Runs the run loop until the future completes. Needed for futures that
+/// depend on run loop callbacks or timers.
+pub fn run_until<F>(future: F) -> F::Output
+where
+    F: Future + 'static,
+    F::Output: 'static,
+{
+    let result = Rc::new(RefCell::new(None));
+    let result_clone = result.clone();
+    spawn(async move {
+        result_clone.replace(Some(future.await));
+        RunLoop::current().stop();
+    });
+    RunLoop::current().run();
+    result
+        .take()
+        .expect("Run loop stopped before future completed")
+}
+
+/// Completes after given number of seconds. Use with [`run_until`].
+pub async fn sleep(seconds: f64) {
+    let (future, completer) = FutureCompleter::new();
+    RunLoop::current()
+        .schedule(Duration::from_secs_f64(seconds), move || {
+            completer.complete(());
+        })
+        .detach();
+    future.await
+}
+
+/// Registers data provider with single plain text representation in data
+/// provider manager of current context.
+#[cfg(feature = "mock")]
+pub fn register_text_provider(isolate: IsolateId, text: &str) -> DataProviderId {
+    crate::context::Context::get()
+        .data_provider_manager()
+        .register_provider(
+            DataProvider {
+                representations: vec![DataRepresentation::Simple {
+                    format: "text/plain".into(),
+                    data: Value::String(text.into()),
+                }],
+                suggested_name: None,
+            },
+            isolate,
+        )
+        .unwrap()
+}
+
+/// Drag request for items backed by given data providers. Local data of each
+/// item is its index.
+#[cfg(feature = "mock")]
+pub fn drag_request(
+    providers: &[DataProviderId],
+    allowed_operations: &[DropOperation],
+) -> DragRequest {
+    DragRequest {
+        configuration: DragConfiguration {
+            items: providers
+                .iter()
+                .enumerate()
+                .map(|(index, provider)| DragItem {
+                    data_provider_id: *provider,
+                    lift_image: None,
+                    image: TargettedImage {
+                        image_data: ImageData::default(),
+                        rect: Rect::default(),
+                    },
+                    local_data: Value::I64(index as i64),
+                })
+                .collect(),
+            allowed_operations: allowed_operations.to_vec(),
+            animates_to_starting_position_on_cancel_or_fail: false,
+            prefers_full_size_previews: false,
+        },
+        combined_drag_image: None,
+        position: Point { x: 0.0, y: 0.0 },
+    }
+}
@@ -10,9 +10,9 @@ use once_cell::sync::Lazy;
 use windows::Win32::System::Ole::OleSetClipboard;
 
 use crate::{
-    api_model::DataProvider,
+    api_model::{ClipboardSelection, DataProvider},
     data_provider_manager::{DataProviderHandle, PlatformDataProviderDelegate},
-    error::NativeExtensionsResult,
+    error::{NativeExtensionsError, NativeExtensionsResult},
     segmented_queue::SegmentedQueueWriter,
 };
 
@@ -85,7 +85,11 @@ impl PlatformDataProvider {
 
     pub async fn write_to_clipboard(
         providers: Vec<(Rc<PlatformDataProvider>, Arc<DataProviderHandle>)>,
+        selection: ClipboardSelection,
     ) -> NativeExtensionsResult<()> {
+        if selection != ClipboardSelection::Clipboard {
+            return Err(NativeExtensionsError::UnsupportedOperation);
+        }
         let data_object = DataObject::create(providers);
         unsafe {
             OleSetClipboard(&data_object)?;
@@ -29,6 +29,9 @@ use windows::{
         },
         UI::{
             Accessibility::{SetWinEventHook, UnhookWinEvent, HWINEVENTHOOK},
+            Input::KeyboardAndMouse::{
+                GetAsyncKeyState, VIRTUAL_KEY, VK_CONTROL, VK_LWIN, VK_MENU, VK_RWIN, VK_SHIFT,
+            },
             Shell::{CLSID_DragDropHelper, IDataObjectAsyncCapability, IDropTargetHelper},
             WindowsAndMessaging::{EVENT_OBJECT_DESTROY, OBJID_WINDOW, WINEVENT_INCONTEXT},
         },
@@ -36,6 +39,7 @@ use windows::{
 };
 
 use crate::{
+    accelerator::Modifiers,
     api_model::{DropOperation, Point},
     drop_manager::{
         BaseDropEvent, DropEvent, DropItem, DropSessionId, PlatformDropContextDelegate,
@@ -96,6 +100,17 @@ impl PlatformDropContext {
         })
     }
 
+    /// Keyboard modifiers currently pressed.
+    pub fn current_modifiers(&self) -> Modifiers {
+        let pressed = |key: VIRTUAL_KEY| unsafe { GetAsyncKeyState(key.0 as i32) } < 0;
+        Modifiers {
+            alt: pressed(VK_MENU),
+            meta: pressed(VK_LWIN) || pressed(VK_RWIN),
+            shift: pressed(VK_SHIFT),
+            control: pressed(VK_CONTROL),
+        }
+    }
+
     pub fn register_drop_formats(&self, _formats: &[String]) -> NativeExtensionsResult<()> {
         Ok(())
     }
@@ -6,13 +6,19 @@ use std::{
 
 use irondash_message_channel::Late;
 use windows::{
-    core::{implement, ComInterface, IUnknown},
+    core::{implement, ComInterface, IUnknown, PCWSTR},
     Win32::{
         Foundation::BOOL,
+        Globalization::{
+            GetLocaleInfoEx, LCIDToLocaleName, LOCALE_ALLOW_NEUTRAL_NAMES,
+            LOCALE_SLOCALIZEDDISPLAYNAME, LOCALE_SNAME,
+        },
+        System::SystemServices::LOCALE_NAME_MAX_LENGTH,
         UI::{
             Input::KeyboardAndMouse::{
-                GetKeyboardLayout, GetKeyboardLayoutList, MapVirtualKeyW, ToUnicodeEx,
-                MAPVK_VK_TO_VSC, MAPVK_VSC_TO_VK, VK_CONTROL, VK_MENU, VK_SHIFT, VK_SPACE,
+                ActivateKeyboardLayout, GetKeyboardLayout, GetKeyboardLayoutList, MapVirtualKeyW,
+                ToUnicodeEx, KLF_SETFORPROCESS, MAPVK_VK_TO_VSC, MAPVK_VSC_TO_VK, VK_CONTROL,
+                VK_MENU, VK_SHIFT, VK_SPACE,
             },
             TextServices::{
                 CLSID_TF_InputProcessorProfiles, ITfInputProcessorProfiles,
@@ -24,7 +30,11 @@ use windows::{
 };
 
 use crate::{
-    keyboard_layout_manager::{Key, KeyboardLayout, KeyboardLayoutDelegate},
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    keyboard_layout_manager::{
+        Key, KeyboardLayout, KeyboardLayoutDelegate, KeyboardLayoutInfo, KeymapDescription,
+    },
+    keyboard_map::{get_key_map, KeyMapEntry},
     log::OkLog,
 };
 
@@ -37,8 +47,6 @@ pub struct PlatformKeyboardLayout {
     delegate: Weak<dyn KeyboardLayoutDelegate>,
 }
 
-include!(concat!(env!("OUT_DIR"), "/generated_keyboard_map.rs"));
-
 impl PlatformKeyboardLayout {
     pub fn new(delegate: Weak<dyn KeyboardLayoutDelegate>) -> Self {
         Self {
@@ -61,9 +69,12 @@ impl PlatformKeyboardLayout {
     }
 
     fn create_keyboard_layout(&self) -> KeyboardLayout {
-        let key_map = get_key_map();
-
         let layout = unsafe { self.get_keyboard_layout() };
+        self.create_keyboard_layout_for_hkl(layout)
+    }
+
+    fn create_keyboard_layout_for_hkl(&self, layout: HKL) -> KeyboardLayout {
+        let key_map = get_key_map();
         let keys: Vec<Key> = unsafe {
             key_map
                 .iter()
@@ -71,7 +82,10 @@ impl PlatformKeyboardLayout {
                 .collect()
         };
 
-        KeyboardLayout { keys }
+        KeyboardLayout {
+            keys,
+            compose: Vec::new(),
+        }
     }
 
     unsafe fn get_keyboard_layout(&self) -> HKL {
@@ -82,9 +96,7 @@ impl PlatformKeyboardLayout {
             return current;
         }
 
-        let cnt = GetKeyboardLayoutList(Some(&mut []));
-        let mut vec: Vec<HKL> = vec![HKL(0); cnt as usize];
-        GetKeyboardLayoutList(Some(&mut vec));
+        let vec = Self::installed_layouts();
 
         // if choosing from list, prefer layout that has actual numbers
         for l in &vec {
@@ -101,6 +113,53 @@ impl PlatformKeyboardLayout {
         current
     }
 
+    unsafe fn installed_layouts() -> Vec<HKL> {
+        let cnt = GetKeyboardLayoutList(None);
+        let mut vec: Vec<HKL> = vec![HKL(0); cnt.max(0) as usize];
+        let cnt = GetKeyboardLayoutList(Some(&mut vec));
+        vec.truncate(cnt.max(0) as usize);
+        vec
+    }
+
+    /// Returns locale name (i.e. "de-CH") of the layout language, which is
+    /// the low word of the HKL.
+    unsafe fn layout_locale(hkl: HKL) -> Option<Vec<u16>> {
+        let lang_id = (hkl.0 as usize & 0xFFFF) as u32;
+        let mut name = [0u16; LOCALE_NAME_MAX_LENGTH as usize];
+        let len = LCIDToLocaleName(lang_id, Some(&mut name), LOCALE_ALLOW_NEUTRAL_NAMES);
+        // Keep the terminating null, the name is passed back to Win32.
+        (len > 0).then(|| name[..len as usize].to_vec())
+    }
+
+    unsafe fn locale_info(locale: &[u16], lc_type: u32) -> Option<String> {
+        let mut data = [0u16; 256];
+        let len = GetLocaleInfoEx(PCWSTR(locale.as_ptr()), lc_type, Some(&mut data));
+        // Length includes terminating null.
+        (len > 1).then(|| String::from_utf16_lossy(&data[..len as usize - 1]))
+    }
+
+    unsafe fn layout_info(hkl: HKL, current: HKL) -> KeyboardLayoutInfo {
+        let locale = Self::layout_locale(hkl);
+        let locale_info = |lc_type| {
+            locale
+                .as_ref()
+                .and_then(|locale| Self::locale_info(locale, lc_type))
+        };
+        KeyboardLayoutInfo {
+            id: hkl.0 as i64,
+            name: locale_info(LOCALE_SLOCALIZEDDISPLAYNAME)
+                .unwrap_or_else(|| format!("{:08X}", hkl.0 as usize & 0xFFFFFFFF)),
+            language: locale_info(LOCALE_SNAME),
+            selected: hkl == current,
+        }
+    }
+
+    fn find_layout(id: i64) -> Option<HKL> {
+        unsafe { Self::installed_layouts() }
+            .into_iter()
+            .find(|hkl| hkl.0 as i64 == id)
+    }
+
     unsafe fn is_ascii_capable(&self, hkl: HKL, including_numbers: bool) -> bool {
         // A .. Z
         for vc in 0x41..0x5A {
@@ -185,6 +244,10 @@ impl PlatformKeyboardLayout {
             logical_alt: None,
             logical_alt_shift: None,
             logical_meta: None,
+            dead: false,
+            dead_shift: false,
+            dead_alt: false,
+            dead_alt_shift: false,
         };
 
         let virtual_code = MapVirtualKeyW(entry.platform as u32, MAPVK_VSC_TO_VK);
@@ -228,6 +291,51 @@ impl PlatformKeyboardLayout {
         key
     }
 
+    /// Layouts are identified by their HKL.
+    pub fn get_layouts(&self) -> NativeExtensionsResult<Vec<KeyboardLayoutInfo>> {
+        unsafe {
+            let current = GetKeyboardLayout(0);
+            Ok(Self::installed_layouts()
+                .into_iter()
+                .map(|hkl| Self::layout_info(hkl, current))
+                .collect())
+        }
+    }
+
+    pub fn get_layout(&self, id: i64) -> NativeExtensionsResult<Option<KeyboardLayout>> {
+        let Some(hkl) = Self::find_layout(id) else {
+            return Ok(None);
+        };
+        Ok(Some(
+            self.cached_layout
+                .borrow_mut()
+                .entry(hkl.0)
+                .or_insert_with(|| self.create_keyboard_layout_for_hkl(hkl))
+                .clone(),
+        ))
+    }
+
+    pub fn get_layout_for_keymap(
+        &self,
+        _description: KeymapDescription,
+    ) -> NativeExtensionsResult<KeyboardLayout> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
+    /// Keyboard layout on Windows is per thread; this activates the layout
+    /// for the whole process, same as the language bar does for the focused
+    /// application.
+    pub fn select_layout(&self, id: i64) -> NativeExtensionsResult<()> {
+        let hkl = Self::find_layout(id).ok_or_else(|| {
+            NativeExtensionsError::OtherError(format!("invalid keyboard layout id {id}"))
+        })?;
+        let previous = unsafe { ActivateKeyboardLayout(hkl, KLF_SETFORPROCESS) }?;
+        if previous != hkl {
+            self.keyboard_layout_changed();
+        }
+        Ok(())
+    }
+
     pub fn assign_weak_self(&self, weak: Weak<PlatformKeyboardLayout>) {
         let profiles: ITfInputProcessorProfiles =
             create_instance(&CLSID_TF_InputProcessorProfiles).unwrap();
@@ -4,7 +4,7 @@ use irondash_engine_context::EngineContext;
 use irondash_message_channel::IsolateId;
 
 use crate::{
-    api_model::{ImageData, Menu, ShowContextMenuRequest, ShowContextMenuResponse},
+    api_model::{ImageData, Menu, MenuAction, ShowContextMenuRequest, ShowContextMenuResponse},
     error::{NativeExtensionsError, NativeExtensionsResult},
     menu_manager::{PlatformMenuContextDelegate, PlatformMenuContextId, PlatformMenuDelegate},
 };
@@ -30,6 +30,10 @@ impl PlatformMenu {
     ) -> NativeExtensionsResult<Rc<Self>> {
         Ok(Rc::new(Self {}))
     }
+
+    pub fn update_elements(&self, _actions: Vec<MenuAction>) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
 }
 
 impl PlatformMenuContext {
@@ -47,6 +51,19 @@ impl PlatformMenuContext {
 
     pub fn assign_weak_self(&self, _weak_self: Weak<Self>) {}
 
+    pub fn set_menu_bar(&self, _menu: Option<Rc<PlatformMenu>>) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
+    pub fn set_tray_menu(
+        &self,
+        _menu: Option<Rc<PlatformMenu>>,
+        _icon: Option<ImageData>,
+        _tooltip: Option<String>,
+    ) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
     pub fn update_preview_image(
         &self,
         _configuration_id: i64,
@@ -49,10 +49,11 @@ use windows::{
 };
 
 use crate::{
+    api_model::ClipboardSelection,
     error::{NativeExtensionsError, NativeExtensionsResult},
     log::OkLog,
-    platform_impl::platform::common::make_format_with_tymed_index,
-    reader_manager::{ReadProgress, VirtualFileReader},
+    platform_impl::platform::common::{make_format_with_tymed, make_format_with_tymed_index},
+    reader_manager::{ItemDataStream, ReadProgress, VirtualFileReader},
     util::{get_target_path, DropNotifier, Movable},
 };
 
@@ -323,7 +324,10 @@ impl PlatformDataReader {
         self.supports_async.set(true);
     }
 
-    pub fn new_clipboard_reader() -> NativeExtensionsResult<Rc<Self>> {
+    pub fn new_clipboard_reader(selection: ClipboardSelection) -> NativeExtensionsResult<Rc<Self>> {
+        if selection != ClipboardSelection::Clipboard {
+            return Err(NativeExtensionsError::UnsupportedOperation);
+        }
         let data_object = unsafe { OleGetClipboard() }?;
         Ok(Self::new_with_data_object(data_object, None))
     }
@@ -522,6 +526,37 @@ impl PlatformDataReader {
         }
     }
 
+    /// Streams data for formats that the data object provides as IStream.
+    /// Streams are only read on background thread for data objects that
+    /// support async mode, others are read eagerly by the caller.
+    pub async fn create_data_stream_for_item(
+        &self,
+        item: i64,
+        format: &str,
+        _progress: Arc<ReadProgress>,
+    ) -> NativeExtensionsResult<Option<Rc<dyn ItemDataStream>>> {
+        let format = format_from_string(format);
+        if item != 0
+            || !self.supports_async.get()
+            || format == CF_UNICODETEXT.0 as u32
+            || !self.data_object_formats_raw()?.contains(&format)
+        {
+            return Ok(None);
+        }
+        let format = make_format_with_tymed(format, TYMED_ISTREAM);
+        if !self.data_object.has_data_for_format(&format) {
+            return Ok(None);
+        }
+        let mut medium = unsafe {
+            DataObject::with_local_request(|| self.data_object.GetData(&format as *const _))?
+        };
+        let stream = Self::stream_from_medium(&medium);
+        unsafe { ReleaseStgMedium(&mut medium as *mut STGMEDIUM) };
+        let stream = unsafe { Movable::new(stream?) };
+        let reader = AsyncStreamReader::new(stream, String::new()).await?;
+        Ok(Some(Rc::new(reader)))
+    }
+
     pub async fn create_virtual_file_reader_for_item(
         &self,
         item: i64,
@@ -842,6 +877,21 @@ impl VirtualFileReader for AsyncStreamReader {
     }
 }
 
+#[async_trait(?Send)]
+impl ItemDataStream for AsyncStreamReader {
+    async fn read_next(&self) -> NativeExtensionsResult<Vec<u8>> {
+        VirtualFileReader::read_next(self).await
+    }
+
+    fn length(&self) -> Option<i64> {
+        Some(self.length as i64)
+    }
+
+    fn close(&self) -> NativeExtensionsResult<()> {
+        VirtualFileReader::close(self)
+    }
+}
+
 // Most streams in COM should be agile, also the documentation for IDataObjectAsyncCapability
 // assumes that the stream is read on background thread so we wrap it inside Movable
 // in order to be able to send it.
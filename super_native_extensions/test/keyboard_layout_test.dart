@@ -0,0 +1,65 @@
+import 'package:flutter/services.dart';
+import 'package:flutter_test/flutter_test.dart';
+import 'package:super_native_extensions/src/keyboard_layout.dart';
+import 'package:super_native_extensions/src/keyboard_layout_model.dart'
+    as model;
+
+void main() {
+  test('deserialize dead keys and compose sequences', () {
+    final layout = model.KeyboardLayout.deserialize({
+      'keys': [
+        {
+          'platform': 21,
+          'physical': PhysicalKeyboardKey.equal.usbHidUsage,
+          'logical': 0xB4,
+          'logicalShift': 0x60,
+          'dead': true,
+          'deadShift': true,
+          'deadAlt': false,
+          'deadAltShift': false,
+        },
+      ],
+      'compose': [
+        {'deadKey': 0xB4, 'base': 0x65, 'result': 'é'},
+      ],
+    })!;
+    final key = layout.keys.single;
+    expect(key.dead, isTrue);
+    expect(key.deadShift, isTrue);
+    expect(key.deadAlt, isFalse);
+    expect(layout.compose.single.result, 'é');
+
+    final keyboardLayout = KeyboardLayout(
+      {key.platform: key},
+      {key.physical: key},
+      {key.logical!: key},
+      compose: layout.compose,
+    );
+    expect(keyboardLayout.isDeadKey(PhysicalKeyboardKey.equal), isTrue);
+    expect(
+      keyboardLayout.isDeadKey(PhysicalKeyboardKey.equal, alt: true),
+      isFalse,
+    );
+    expect(keyboardLayout.isDeadKey(PhysicalKeyboardKey.keyA), isFalse);
+    expect(
+      keyboardLayout.compose(
+          const LogicalKeyboardKey(0xB4), LogicalKeyboardKey.keyE),
+      'é',
+    );
+    expect(
+      keyboardLayout.compose(
+          const LogicalKeyboardKey(0xB4), LogicalKeyboardKey.keyX),
+      isNull,
+    );
+  });
+
+  test('deserialize layout without dead keys', () {
+    final layout = model.KeyboardLayout.deserialize({
+      'keys': [
+        {'platform': 38, 'physical': 0x70004, 'logical': 0x61},
+      ],
+    })!;
+    expect(layout.keys.single.dead, isFalse);
+    expect(layout.compose, isEmpty);
+  });
+}
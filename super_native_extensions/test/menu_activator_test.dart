@@ -0,0 +1,46 @@
+import 'package:flutter/services.dart';
+import 'package:flutter_test/flutter_test.dart';
+import 'package:super_native_extensions/src/menu_model.dart';
+import 'package:super_native_extensions/src/native/menu.dart';
+
+void main() {
+  test('single activator', () {
+    const activator = SingleActivator(LogicalKeyboardKey.keyP,
+        control: true, shift: true);
+    expect(activator.serialize(), {
+      'trigger': 'P',
+      'alt': false,
+      'meta': false,
+      'shift': true,
+      'control': true,
+      'chord': null,
+    });
+  });
+
+  test('chord activator', () {
+    const activator = ChordActivator(
+      LogicalKeyboardKey.keyK,
+      control: true,
+      chord: [
+        SingleActivator(LogicalKeyboardKey.keyC, control: true),
+      ],
+    );
+    expect(activator.serialize(), {
+      'trigger': 'K',
+      'alt': false,
+      'meta': false,
+      'shift': false,
+      'control': true,
+      'chord': [
+        {
+          'trigger': 'C',
+          'alt': false,
+          'meta': false,
+          'shift': false,
+          'control': true,
+          'chord': null,
+        },
+      ],
+    });
+  });
+}